@@ -94,6 +94,14 @@ impl<S: StorageMapperApi> dex::Types for Types<S> {
     type VerifiedTokensSet = StorageSet<S, TokenId>;
     type PositionToPoolMap = StorageMap<S, PositionId, PoolId>;
     type AccountIdSet = StorageSet<S, AccountId>;
+    type PoolIdSet = StorageSet<S, PoolId>;
+    type ReferencePricesMap = StorageMap<S, PoolId, Float>;
+    type TokenDecimalsMap = StorageMap<S, TokenId, u8>;
+    type AccountedBalancesMap = StorageMap<S, TokenId, Amount>;
+    type TokenVerifiedAtMap = StorageMap<S, TokenId, u64>;
+    type PositionOwnersMap = StorageMap<S, PositionId, AccountId>;
+    type TickPositionsMap =
+        StorageMap<S, (PoolId, dex::FeeLevel, dex::Tick), Vec<(PositionId, bool)>>;
     #[cfg(feature = "smart-routing")]
     type TokenConnectionsMap = StorageMap<S, TokenId, Self::TokensSet>;
     #[cfg(feature = "smart-routing")]
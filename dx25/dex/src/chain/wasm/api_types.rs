@@ -13,7 +13,7 @@ use crate::dex::v0::NUM_FEE_LEVELS;
 use crate::dex::{self, BasisPoints, Float, PairExt, Tick};
 
 use crate::chain::{dex_types::token_id::TokenId as VmTokenId, AccountId, Amount, TokenId, VmApi};
-use crate::fp::{U128, U192X64, U256};
+use crate::fp::{U128, U128X128, U192X64, U256};
 use crate::WasmAmount;
 
 use multiversx_sc::derive::TypeAbi;
@@ -103,6 +103,14 @@ pub struct PoolInfo {
 
     /// Scale factor for the fee levels.
     pub fee_divisor: BasisPoints,
+
+    /// Block timestamp of the last swap in the pool, in seconds.
+    /// Zero if the pool has never been traded.
+    pub last_trade_ts: u64,
+
+    /// Square root of the spot price right after the last swap, as a rational fraction.
+    /// If the pool has never been traded, current spot price at fee level 0 is reported.
+    pub last_trade_sqrt_price: Fraction,
 }
 
 impl PoolInfo {
@@ -148,6 +156,8 @@ impl TryFrom<dex::PoolInfo> for PoolInfo {
             liquidities: info.liquidities.map(Into::into),
             fee_rates: info.fee_rates,
             fee_divisor: info.fee_divisor,
+            last_trade_ts: info.last_trade_ts,
+            last_trade_sqrt_price: info.last_trade_sqrtprice.try_into()?,
         })
     }
 }
@@ -272,6 +282,13 @@ impl<M: ManagedTypeApi> From<U192X64> for BigUint<M> {
     }
 }
 
+impl<M: ManagedTypeApi> From<U128X128> for BigUint<M> {
+    fn from(value: U128X128) -> Self {
+        let bytes: [u8; 32] = value.0.into();
+        Self::from_bytes_be(&bytes)
+    }
+}
+
 // We need this functions, beause we can't have generic ID's, and MultiverseX contract interface
 // parameterizes contract with VM API. So, sometimes we want to convers for an accoiated API to a concrete
 // API. Which in fact always is API for a target platform
@@ -292,7 +309,10 @@ impl TypeAbi for U256 {}
 #[must_use]
 #[derive(TypeAbi, NestedDecode, NestedEncode, TopDecode, TopEncode)]
 pub struct Withdrawal {
+    /// Account on behalf of which tokens are sent, credited back if transfer fails
     pub account_id: Address,
+    /// Account which receives tokens
+    pub recipient: Address,
     pub token_id: TokenId,
     pub amount: Amount,
     pub callback: Option<MethodCall>,
@@ -351,6 +371,8 @@ pub struct EstimateSwapExactResult {
     pub swap_price: Option<Fraction>,
     pub swap_price_worst: Option<Fraction>,
     pub fee_in_spent_tok: WasmAmount,
+    pub lp_fee_in_spent_tok: WasmAmount,
+    pub protocol_fee_in_spent_tok: WasmAmount,
     pub num_tick_crossings: u32,
 }
 
@@ -364,12 +386,51 @@ impl TryFrom<dex::EstimateSwapExactResult> for EstimateSwapExactResult {
             swap_price: res.swap_price.map(TryInto::try_into).transpose()?,
             swap_price_worst: res.swap_price_worst.map(TryInto::try_into).transpose()?,
             fee_in_spent_tok: res.fee_in_spent_tok.into(),
+            lp_fee_in_spent_tok: res.lp_fee_in_spent_tok.into(),
+            protocol_fee_in_spent_tok: res.protocol_fee_in_spent_tok.into(),
             price_impact: res.price_impact.try_into()?,
             num_tick_crossings: res.num_tick_crossings,
         })
     }
 }
 
+/// Complete fee configuration of the DEX
+#[derive(TopDecode, TopEncode, TypeAbi)]
+pub struct FeeSchedule {
+    /// Fee rate of each fee level, in ticks.
+    pub fee_rate_ticks: RawFeeLevelsArray<BasisPoints>,
+
+    /// Fee rate of each fee level, as a fraction of swapped amount.
+    pub fee_rate_fractions: RawFeeLevelsArray<Fraction>,
+
+    /// Scale factor for the protocol fee fraction.
+    pub basis_point_divisor: BasisPoints,
+
+    /// Fraction of fee which goes to DEX.
+    pub protocol_fee_fraction: BasisPoints,
+}
+
+impl TryFrom<dex::FeeSchedule> for FeeSchedule {
+    type Error = dex::Error;
+
+    fn try_from(schedule: dex::FeeSchedule) -> Result<Self, Self::Error> {
+        let mut fee_rate_fractions = RawFeeLevelsArray::<Fraction>::default();
+        for (fraction, fee_rate) in fee_rate_fractions
+            .iter_mut()
+            .zip(schedule.fee_rate_fractions)
+        {
+            *fraction = fee_rate.try_into()?;
+        }
+
+        Ok(Self {
+            fee_rate_ticks: schedule.fee_rate_ticks,
+            fee_rate_fractions,
+            basis_point_divisor: schedule.basis_point_divisor,
+            protocol_fee_fraction: schedule.protocol_fee_fraction,
+        })
+    }
+}
+
 #[derive(NestedDecode, NestedEncode, TypeAbi)]
 pub struct TxCostEstimate {
     pub gas_cost_max: WasmAmount,
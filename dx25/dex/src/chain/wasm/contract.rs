@@ -4,21 +4,24 @@ use multiversx_sc::{
     contract_base::ProxyObjBase,
     err_msg, sc_panic,
     storage::mappers::SingleValueMapper,
-    types::{ContractCall, EgldOrEsdtTokenIdentifier, ManagedAddress, ManagedAsyncCallResult},
+    types::{
+        ContractCall, EgldOrEsdtTokenIdentifier, ManagedAddress, ManagedAsyncCallResult,
+        OptionalValue,
+    },
 };
 use multiversx_sc_codec::multi_types::IgnoreValue;
 
 use crate::{
     api_types::{
         into_token_id, Action, ApiMap, ApiVec, EstimateAddLiquidityResult, EstimateSwapExactResult,
-        Fraction, MethodCall, PoolInfo, PositionInfo,
+        FeeSchedule, Fraction, MethodCall, PoolInfo, PositionInfo,
     },
     chain::{AccountId, Amount, Liquidity, TokenId, Types, VmApi},
     dex::pool::one_over_sqrt_one_minus_fee_rate,
     dex::{
-        self, latest::RawFeeLevelsArray, BasisPoints, Contract, Estimations, FeeLevel,
-        ItemFactory as _, Map, PairExt, PositionId, PositionInit, Set as _, State as _, StateMut,
-        VersionInfo,
+        self, latest::RawFeeLevelsArray, AdminConfig, BasisPoints, Contract, Estimations, FeeLevel,
+        IdempotencyKey, ItemFactory as _, Map, PairExt, PositionId, PositionInit, Set as _, Side,
+        SlippageTolerance, State as _, StateMut, VersionInfo,
     },
     dex_state::{StateMutWrapper, StateWrapper},
     error_here, Float, WasmAmount, WEGLD_DOUBLE_INIT_ERROR,
@@ -88,6 +91,12 @@ pub trait Dx25Contract {
     #[event("remove_guard_accounts")]
     fn log_remove_guard_accounts_event(&self, data: ManagedBuffer);
 
+    #[event("add_operator_accounts")]
+    fn log_add_operator_accounts_event(&self, data: ManagedBuffer);
+
+    #[event("remove_operator_accounts")]
+    fn log_remove_operator_accounts_event(&self, data: ManagedBuffer);
+
     #[event("suspend_payable_api")]
     fn log_suspend_payable_api_event(&self, data: ManagedBuffer);
 
@@ -97,6 +106,36 @@ pub trait Dx25Contract {
     #[event("tick_update")]
     fn log_tick_update_event(&self, data: ManagedBuffer);
 
+    #[event("tick_crossed")]
+    fn log_tick_crossed_event(&self, data: ManagedBuffer);
+
+    #[event("position_range_boundary_crossed")]
+    fn log_position_range_boundary_crossed_event(&self, data: ManagedBuffer);
+
+    #[event("set_protocol_fee")]
+    fn log_set_protocol_fee_event(&self, data: ManagedBuffer);
+
+    #[event("set_swap_surcharge")]
+    fn log_set_swap_surcharge_event(&self, data: ManagedBuffer);
+
+    #[event("pause_pool")]
+    fn log_pause_pool_event(&self, data: ManagedBuffer);
+
+    #[event("unpause_pool")]
+    fn log_unpause_pool_event(&self, data: ManagedBuffer);
+
+    #[event("open_position_fee")]
+    fn log_open_position_fee_event(&self, data: ManagedBuffer);
+
+    #[event("force_remove_position")]
+    fn log_force_remove_position_event(&self, data: ManagedBuffer);
+
+    #[event("sweep_untracked")]
+    fn log_sweep_untracked_event(&self, data: ManagedBuffer);
+
+    #[event("pool_created")]
+    fn log_pool_created_event(&self, data: ManagedBuffer);
+
     /// - `wegld_token_id` is wEGLD token ID, which we ask user to unwrap into
     /// EGLD to work with dx25
     #[init]
@@ -210,7 +249,8 @@ pub trait Dx25Contract {
             .contract()
             .as_ref()
             .accounts
-            .inspect(&address, |dex::Account::V0(ref account)| {
+            .inspect(&address, |account| {
+                let account = account.as_ref();
                 account
                     .token_balances
                     .iter()
@@ -227,7 +267,8 @@ pub trait Dx25Contract {
             .contract()
             .as_ref()
             .accounts
-            .inspect(&account, |dex::Account::V0(ref account)| {
+            .inspect(&account, |account| {
+                let account = account.as_ref();
                 account.token_balances.inspect(&token_id, |v| *v)
             })
             .flatten()
@@ -247,6 +288,32 @@ pub trait Dx25Contract {
             .collect()
     }
 
+    /// Get page of ordered allowed tokens list, see `get_verified_tokens`.
+    #[view]
+    fn get_verified_tokens_paged(&self, offset: usize, limit: usize) -> ApiVec<TokenId> {
+        self.as_dex()
+            .get_verified_tokens_paged(offset, limit)
+            .into()
+    }
+
+    /// Get number of allowed tokens.
+    #[view]
+    fn get_verified_tokens_count(&self) -> usize {
+        self.as_dex().get_verified_tokens_count()
+    }
+
+    /// Check whether token is in allowed tokens list.
+    #[view]
+    fn is_token_verified(&self, token_id: TokenId) -> bool {
+        self.as_dex().is_token_verified(&token_id)
+    }
+
+    /// Check whether each of tokens is in allowed tokens list.
+    #[view]
+    fn are_tokens_verified(&self, token_ids: ApiVec<TokenId>) -> ApiVec<bool> {
+        self.as_dex().are_tokens_verified(&token_ids.0).into()
+    }
+
     /// Get specific user tokens.
     #[view]
     fn get_user_tokens(&self, account_id: AccountId) -> ApiVec<TokenId> {
@@ -254,7 +321,8 @@ pub trait Dx25Contract {
             .contract()
             .as_ref()
             .accounts
-            .inspect(&account_id, |dex::Account::V0(ref account)| {
+            .inspect(&account_id, |account| {
+                let account = account.as_ref();
                 account
                     .token_balances
                     .iter()
@@ -264,6 +332,14 @@ pub trait Dx25Contract {
             .unwrap_or_default()
     }
 
+    /// Distinct pools in which account has positions
+    #[label("dx25-contract-view")]
+    #[view]
+    fn get_account_pools(&self, account_id: AccountId) -> ApiVec<(TokenId, TokenId)> {
+        self.result_unwrap(self.as_dex().get_account_pools(&account_id))
+            .into()
+    }
+
     #[view]
     fn get_pool_info(&self, tokens: (TokenId, TokenId)) -> Option<PoolInfo> {
         let result = self
@@ -282,6 +358,13 @@ pub trait Dx25Contract {
             .map(|array| array.map(|value| self.result_unwrap(value.try_into())))
     }
 
+    /// Fee levels which currently hold liquidity
+    #[view]
+    fn get_active_fee_levels(&self, tokens: (TokenId, TokenId)) -> ApiVec<FeeLevel> {
+        self.result_unwrap(self.as_dex().get_active_fee_levels(tokens))
+            .into()
+    }
+
     #[allow(unused_variables)] // Keep args names to leave API unchanged
     #[view]
     fn token_register_of(&self, account_id: AccountId, token_id: TokenId) -> bool {
@@ -298,6 +381,18 @@ pub trait Dx25Contract {
         self.as_dex().get_version()
     }
 
+    #[label("dx25-contract-view")]
+    #[view]
+    fn get_admin_config(&self) -> AdminConfig {
+        self.as_dex().get_admin_config()
+    }
+
+    #[label("dx25-contract-view")]
+    #[view]
+    fn get_fee_schedule(&self) -> FeeSchedule {
+        self.result_unwrap(self.as_dex().get_fee_schedule().try_into())
+    }
+
     #[endpoint(extendVerifiedTokens)]
     fn extend_verified_tokens(&self, token_ids: ApiVec<TokenId>) {
         self.result_unwrap(self.as_dex_mut().add_verified_tokens(token_ids.0));
@@ -331,19 +426,346 @@ pub trait Dx25Contract {
         self.set_protocol_fee_fraction(protocol_fee_fraction);
     }
 
+    #[endpoint(setSwapSurcharge)]
+    fn set_swap_surcharge(&self, surcharge_bp: BasisPoints) {
+        self.result_unwrap(self.as_dex_mut().set_swap_surcharge(surcharge_bp));
+    }
+
+    #[endpoint(set_swap_surcharge)]
+    fn set_swap_surcharge_snake_case(&self, surcharge_bp: BasisPoints) {
+        self.set_swap_surcharge(surcharge_bp);
+    }
+
+    #[endpoint(setReferencePriceSetter)]
+    fn set_reference_price_setter(&self, setter: Option<AccountId>) {
+        self.result_unwrap(self.as_dex_mut().set_reference_price_setter(setter));
+    }
+
+    #[endpoint(set_reference_price_setter)]
+    fn set_reference_price_setter_snake_case(&self, setter: Option<AccountId>) {
+        self.set_reference_price_setter(setter);
+    }
+
+    #[endpoint(setMaxOracleDeviation)]
+    fn set_max_oracle_deviation(&self, max_deviation_bp: Option<BasisPoints>) {
+        self.result_unwrap(self.as_dex_mut().set_max_oracle_deviation(max_deviation_bp));
+    }
+
+    #[endpoint(set_max_oracle_deviation)]
+    fn set_max_oracle_deviation_snake_case(&self, max_deviation_bp: Option<BasisPoints>) {
+        self.set_max_oracle_deviation(max_deviation_bp);
+    }
+
+    #[endpoint(setReferencePrice)]
+    fn set_reference_price(&self, tokens: (TokenId, TokenId), price: Option<Fraction>) {
+        self.result_unwrap(
+            self.as_dex_mut()
+                .set_reference_price(tokens, price.map(Into::into)),
+        );
+    }
+
+    #[endpoint(set_reference_price)]
+    fn set_reference_price_snake_case(&self, tokens: (TokenId, TokenId), price: Option<Fraction>) {
+        self.set_reference_price(tokens, price);
+    }
+
+    #[endpoint(setSwapCooldown)]
+    fn set_swap_cooldown(&self, swap_cooldown_secs: Option<u64>) {
+        self.result_unwrap(self.as_dex_mut().set_swap_cooldown(swap_cooldown_secs));
+    }
+
+    #[endpoint(set_swap_cooldown)]
+    fn set_swap_cooldown_snake_case(&self, swap_cooldown_secs: Option<u64>) {
+        self.set_swap_cooldown(swap_cooldown_secs);
+    }
+
+    #[endpoint(setMaxTokensPerAccount)]
+    fn set_max_tokens_per_account(&self, max_tokens: Option<u32>) {
+        self.result_unwrap(self.as_dex_mut().set_max_tokens_per_account(max_tokens));
+    }
+
+    #[endpoint(set_max_tokens_per_account)]
+    fn set_max_tokens_per_account_snake_case(&self, max_tokens: Option<u32>) {
+        self.set_max_tokens_per_account(max_tokens);
+    }
+
+    #[endpoint(setMaxSwapTickCrossings)]
+    fn set_max_swap_tick_crossings(&self, max_crossings: Option<u32>) {
+        self.result_unwrap(self.as_dex_mut().set_max_swap_tick_crossings(max_crossings));
+    }
+
+    #[endpoint(set_max_swap_tick_crossings)]
+    fn set_max_swap_tick_crossings_snake_case(&self, max_crossings: Option<u32>) {
+        self.set_max_swap_tick_crossings(max_crossings);
+    }
+
+    #[endpoint(setProtocolFeeWithdrawalDelay)]
+    fn set_protocol_fee_withdrawal_delay(&self, delay_secs: u64) {
+        self.result_unwrap(
+            self.as_dex_mut()
+                .set_protocol_fee_withdrawal_delay(delay_secs),
+        );
+    }
+
+    #[endpoint(set_protocol_fee_withdrawal_delay)]
+    fn set_protocol_fee_withdrawal_delay_snake_case(&self, delay_secs: u64) {
+        self.set_protocol_fee_withdrawal_delay(delay_secs);
+    }
+
+    /// Lock protocol fee of the pool for withdrawal, returns pending amounts and release timestamp
+    #[endpoint(requestProtocolFeeWithdrawal)]
+    fn request_protocol_fee_withdrawal(
+        &self,
+        tokens: (TokenId, TokenId),
+    ) -> (WasmAmount, WasmAmount, u64) {
+        let ((amount_a, amount_b), release_ts) =
+            self.result_unwrap(self.as_dex_mut().request_protocol_fee_withdrawal(tokens));
+
+        (amount_a.into(), amount_b.into(), release_ts)
+    }
+
+    #[endpoint(request_protocol_fee_withdrawal)]
+    fn request_protocol_fee_withdrawal_snake_case(
+        &self,
+        tokens: (TokenId, TokenId),
+    ) -> (WasmAmount, WasmAmount, u64) {
+        self.request_protocol_fee_withdrawal(tokens)
+    }
+
+    /// Credit protocol fee locked by `requestProtocolFeeWithdrawal` to owner, once timelock elapses
+    #[endpoint(executeProtocolFeeWithdrawal)]
+    fn execute_protocol_fee_withdrawal(
+        &self,
+        tokens: (TokenId, TokenId),
+    ) -> (WasmAmount, WasmAmount) {
+        let res = self.result_unwrap(self.as_dex_mut().execute_protocol_fee_withdrawal(tokens));
+
+        (res.0.into(), res.1.into())
+    }
+
+    #[endpoint(execute_protocol_fee_withdrawal)]
+    fn execute_protocol_fee_withdrawal_snake_case(
+        &self,
+        tokens: (TokenId, TokenId),
+    ) -> (WasmAmount, WasmAmount) {
+        self.execute_protocol_fee_withdrawal(tokens)
+    }
+
+    #[endpoint(setEventVerbosity)]
+    fn set_event_verbosity(&self, event_verbosity: u8) {
+        self.result_unwrap(self.as_dex_mut().set_event_verbosity(event_verbosity));
+    }
+
+    #[endpoint(set_event_verbosity)]
+    fn set_event_verbosity_snake_case(&self, event_verbosity: u8) {
+        self.set_event_verbosity(event_verbosity);
+    }
+
+    #[endpoint(setAutoUnregister)]
+    fn set_auto_unregister(&self, enabled: bool) {
+        self.result_unwrap(self.as_dex_mut().set_auto_unregister(enabled));
+    }
+
+    #[endpoint(set_auto_unregister)]
+    fn set_auto_unregister_snake_case(&self, enabled: bool) {
+        self.set_auto_unregister(enabled);
+    }
+
+    #[endpoint(importAccount)]
+    fn import_account(&self, account: AccountId, data: ManagedBuffer) {
+        self.result_unwrap(
+            self.as_dex_mut()
+                .import_account(&account, data.to_boxed_bytes().as_slice()),
+        );
+    }
+
+    #[endpoint(import_account)]
+    fn import_account_snake_case(&self, account: AccountId, data: ManagedBuffer) {
+        self.import_account(account, data);
+    }
+
+    #[endpoint(setDustGcConfig)]
+    fn set_dust_gc_config(&self, dust_liquidity_threshold: Fraction, gc_reward: WasmAmount) {
+        let dust_liquidity_threshold =
+            self.result_unwrap(Liquidity::try_from(Float::from(dust_liquidity_threshold)));
+        self.result_unwrap(
+            self.as_dex_mut()
+                .set_dust_gc_config(dust_liquidity_threshold, gc_reward.into()),
+        );
+    }
+
+    #[endpoint(set_dust_gc_config)]
+    fn set_dust_gc_config_snake_case(
+        &self,
+        dust_liquidity_threshold: Fraction,
+        gc_reward: WasmAmount,
+    ) {
+        self.set_dust_gc_config(dust_liquidity_threshold, gc_reward);
+    }
+
+    #[endpoint(setOpenPositionFee)]
+    fn set_open_position_fee(&self, token_id: TokenId, amount: WasmAmount) {
+        self.result_unwrap(
+            self.as_dex_mut()
+                .set_open_position_fee(token_id, amount.into()),
+        );
+    }
+
+    #[endpoint(set_open_position_fee)]
+    fn set_open_position_fee_snake_case(&self, token_id: TokenId, amount: WasmAmount) {
+        self.set_open_position_fee(token_id, amount);
+    }
+
+    /// Owner-only crediting of collected position opening fees to owner's deposit
+    #[endpoint(withdrawOpenPositionFees)]
+    fn withdraw_open_position_fees(&self, token_id: TokenId) -> WasmAmount {
+        self.result_unwrap(self.as_dex_mut().withdraw_open_position_fees(&token_id))
+            .into()
+    }
+
+    #[endpoint(withdraw_open_position_fees)]
+    fn withdraw_open_position_fees_snake_case(&self, token_id: TokenId) -> WasmAmount {
+        self.withdraw_open_position_fees(token_id)
+    }
+
+    /// Owner-only moving of owner's deposit to funds paying dust position GC rewards
+    #[endpoint(fundGcRewards)]
+    fn fund_gc_rewards(&self, token_id: TokenId, amount: WasmAmount) {
+        self.result_unwrap(self.as_dex_mut().fund_gc_rewards(&token_id, amount.into()));
+    }
+
+    #[endpoint(fund_gc_rewards)]
+    fn fund_gc_rewards_snake_case(&self, token_id: TokenId, amount: WasmAmount) {
+        self.fund_gc_rewards(token_id, amount);
+    }
+
+    /// Owner-only return of dust position GC reward funds to owner's deposit
+    #[endpoint(withdrawGcRewardFunds)]
+    fn withdraw_gc_reward_funds(&self, token_id: TokenId) -> WasmAmount {
+        self.result_unwrap(self.as_dex_mut().withdraw_gc_reward_funds(&token_id))
+            .into()
+    }
+
+    #[endpoint(withdraw_gc_reward_funds)]
+    fn withdraw_gc_reward_funds_snake_case(&self, token_id: TokenId) -> WasmAmount {
+        self.withdraw_gc_reward_funds(token_id)
+    }
+
+    #[endpoint(setMaxActionsPerBatch)]
+    fn set_max_actions_per_batch(&self, max_actions_per_batch: u16) {
+        self.result_unwrap(
+            self.as_dex_mut()
+                .set_max_actions_per_batch(max_actions_per_batch),
+        );
+    }
+
+    #[endpoint(set_max_actions_per_batch)]
+    fn set_max_actions_per_batch_snake_case(&self, max_actions_per_batch: u16) {
+        self.set_max_actions_per_batch(max_actions_per_batch);
+    }
+
+    #[endpoint(setSwapMaxUnderpay)]
+    fn set_swap_max_underpay(&self, max_underpay: Option<Fraction>) {
+        self.result_unwrap(
+            self.as_dex_mut()
+                .set_swap_max_underpay(max_underpay.map(Into::into)),
+        );
+    }
+
+    #[endpoint(set_swap_max_underpay)]
+    fn set_swap_max_underpay_snake_case(&self, max_underpay: Option<Fraction>) {
+        self.set_swap_max_underpay(max_underpay);
+    }
+
+    #[endpoint(setVerifiedTokenGrace)]
+    fn set_verified_token_grace(&self, grace_secs: u64) {
+        self.result_unwrap(self.as_dex_mut().set_verified_token_grace(grace_secs));
+    }
+
+    #[endpoint(set_verified_token_grace)]
+    fn set_verified_token_grace_snake_case(&self, grace_secs: u64) {
+        self.set_verified_token_grace(grace_secs);
+    }
+
+    #[endpoint(setTokenDecimals)]
+    fn set_token_decimals(&self, token_id: TokenId, decimals: Option<u8>) {
+        self.result_unwrap(self.as_dex_mut().set_token_decimals(token_id, decimals));
+    }
+
+    #[endpoint(set_token_decimals)]
+    fn set_token_decimals_snake_case(&self, token_id: TokenId, decimals: Option<u8>) {
+        self.set_token_decimals(token_id, decimals);
+    }
+
+    #[view]
+    fn get_token_decimals(&self, token_id: TokenId) -> Option<u8> {
+        self.as_dex().get_token_decimals(&token_id)
+    }
+
+    /// Spot price of `token_out` in `token_in`, scaled by registered token decimals
+    #[view]
+    fn get_spot_price_scaled(
+        &self,
+        token_in: TokenId,
+        token_out: TokenId,
+        fee_level: FeeLevel,
+    ) -> Fraction {
+        self.result_unwrap(
+            self.result_unwrap(
+                self.as_dex()
+                    .get_spot_price_scaled(&token_in, &token_out, fee_level),
+            )
+            .try_into(),
+        )
+    }
+
+    /// Raw spot sqrtprice of `token_out` in `token_in`, as fixed-point number scaled by 2^128
+    #[view]
+    fn get_spot_sqrtprice_fp(
+        &self,
+        token_in: TokenId,
+        token_out: TokenId,
+        fee_level: FeeLevel,
+    ) -> WasmAmount {
+        self.result_unwrap(
+            self.as_dex()
+                .get_spot_sqrtprice_fp(&token_in, &token_out, fee_level),
+        )
+        .into()
+    }
+
+    #[view]
+    fn get_reference_price(&self, tokens: (TokenId, TokenId)) -> Option<Fraction> {
+        self.result_unwrap(self.as_dex().get_reference_price(tokens))
+            .map(|price| self.result_unwrap(price.try_into()))
+    }
+
+    /// Execute batch of actions; batch with recently used `idempotency_key` is rejected
     #[endpoint(executeActions)]
-    fn execute_actions(&self, actions: ApiVec<Action>) {
+    fn execute_actions(
+        &self,
+        actions: ApiVec<Action>,
+        idempotency_key: OptionalValue<IdempotencyKey>,
+    ) {
         let result = self
             .as_dex_mut()
-            .execute_actions(&mut |_, _, _| Ok(()), actions.0)
+            .execute_actions(
+                &mut |_, _, _| Ok(()),
+                actions.0,
+                idempotency_key.into_option(),
+            )
             .and_then(|(outcomes, _)| SendBatch::try_handle_outcomes(self, outcomes));
 
         self.result_unwrap(result);
     }
 
     #[endpoint(execute_actions)]
-    fn execute_actions_snake_case(&self, actions: ApiVec<Action>) {
-        self.execute_actions(actions);
+    fn execute_actions_snake_case(
+        &self,
+        actions: ApiVec<Action>,
+        idempotency_key: OptionalValue<IdempotencyKey>,
+    ) {
+        self.execute_actions(actions, idempotency_key);
     }
 
     #[endpoint(swapExactIn)]
@@ -372,6 +794,70 @@ pub trait Dx25Contract {
         self.swap_exact_in(tokens, amount_in, min_amount_out)
     }
 
+    /// Swap exact amount of tokens, with minimal amount out derived from the current quote
+    /// reduced by `max_slippage_bp` basis points
+    #[endpoint(swapExactInWithSlippage)]
+    fn swap_exact_in_with_slippage(
+        &self,
+        tokens: ApiVec<TokenId>,
+        amount_in: WasmAmount,
+        max_slippage_bp: BasisPoints,
+    ) -> (WasmAmount, WasmAmount) {
+        let res = self.result_unwrap(self.as_dex_mut().swap_exact_in_with_slippage(
+            &tokens.0,
+            amount_in.into(),
+            max_slippage_bp,
+        ));
+
+        (res.0.into(), res.1.into())
+    }
+
+    #[endpoint(swap_exact_in_with_slippage)]
+    fn swap_exact_in_with_slippage_snake_case(
+        &self,
+        tokens: ApiVec<TokenId>,
+        amount_in: WasmAmount,
+        max_slippage_bp: BasisPoints,
+    ) -> (WasmAmount, WasmAmount) {
+        self.swap_exact_in_with_slippage(tokens, amount_in, max_slippage_bp)
+    }
+
+    /// Swap exact amount of tokens, sending output tokens to `recipient`
+    #[endpoint(swapAndSend)]
+    fn swap_and_send(
+        &self,
+        tokens: ApiVec<TokenId>,
+        amount_in: WasmAmount,
+        min_amount_out: WasmAmount,
+        recipient: AccountId,
+        callback: Option<MethodCall>,
+    ) {
+        let result = self
+            .as_dex_mut()
+            .swap_and_send(
+                &tokens.0,
+                amount_in.into(),
+                min_amount_out.into(),
+                &recipient,
+                (false, callback),
+            )
+            .and_then(|outcome| SendBatch::try_handle_outcomes(self, [outcome]));
+
+        self.result_unwrap(result);
+    }
+
+    #[endpoint(swap_and_send)]
+    fn swap_and_send_snake_case(
+        &self,
+        tokens: ApiVec<TokenId>,
+        amount_in: WasmAmount,
+        min_amount_out: WasmAmount,
+        recipient: AccountId,
+        callback: Option<MethodCall>,
+    ) {
+        self.swap_and_send(tokens, amount_in, min_amount_out, recipient, callback);
+    }
+
     #[endpoint(swapExactOut)]
     fn swap_exact_out(
         &self,
@@ -424,20 +910,200 @@ pub trait Dx25Contract {
         self.swap_to_price(tokens, amount_in, effective_price_limit)
     }
 
-    #[endpoint(openPosition)]
-    fn open_position(
+    #[endpoint(swapToPriceChecked)]
+    fn swap_to_price_checked(
         &self,
-        token_a: &TokenId,
-        token_b: &TokenId,
-        fee_rate: dex::BasisPoints,
-        position: PositionInit,
-    ) -> (PositionId, WasmAmount, WasmAmount, Fraction) {
-        let (position_id, amount_a, amount_b, net_liquidity) = self.result_unwrap(
-            self.as_dex_mut()
-                .open_position(token_a, token_b, fee_rate, position),
-        );
+        tokens: ApiVec<TokenId>,
+        amount_in: WasmAmount,
+        effective_price_limit: Fraction,
+    ) -> (WasmAmount, WasmAmount) {
+        let res = self.result_unwrap(self.as_dex_mut().swap_to_price_checked(
+            &tokens.0,
+            amount_in.into(),
+            effective_price_limit.into(),
+        ));
 
-        let fee_level: FeeLevel = self.result_unwrap(
+        (res.0.into(), res.1.into())
+    }
+
+    #[endpoint(swap_to_price_checked)]
+    fn swap_to_price_checked_snake_case(
+        &self,
+        tokens: ApiVec<TokenId>,
+        amount_in: WasmAmount,
+        effective_price_limit: Fraction,
+    ) -> (WasmAmount, WasmAmount) {
+        self.swap_to_price_checked(tokens, amount_in, effective_price_limit)
+    }
+
+    #[endpoint(swapToPriceLimit)]
+    fn swap_to_price_limit(
+        &self,
+        token_in: &TokenId,
+        token_out: &TokenId,
+        amount_in: WasmAmount,
+        price_limit: Fraction,
+    ) -> (WasmAmount, WasmAmount) {
+        let res = self.result_unwrap(self.as_dex_mut().swap_to_price_limit(
+            token_in,
+            token_out,
+            amount_in.into(),
+            price_limit.into(),
+        ));
+
+        (res.0.into(), res.1.into())
+    }
+
+    #[endpoint(swap_to_price_limit)]
+    fn swap_to_price_limit_snake_case(
+        &self,
+        token_in: &TokenId,
+        token_out: &TokenId,
+        amount_in: WasmAmount,
+        price_limit: Fraction,
+    ) -> (WasmAmount, WasmAmount) {
+        self.swap_to_price_limit(token_in, token_out, amount_in, price_limit)
+    }
+
+    #[endpoint(openPosition)]
+    fn open_position(
+        &self,
+        token_a: &TokenId,
+        token_b: &TokenId,
+        fee_rate: dex::BasisPoints,
+        position: PositionInit,
+    ) -> (PositionId, WasmAmount, WasmAmount, Fraction) {
+        let opened = self.result_unwrap(
+            self.as_dex_mut()
+                .open_position(token_a, token_b, fee_rate, position),
+        );
+
+        self.opened_position_result(fee_rate, opened)
+    }
+
+    #[endpoint(open_position)]
+    fn open_position_snake_case(
+        &self,
+        token_a: &TokenId,
+        token_b: &TokenId,
+        fee_rate: dex::BasisPoints,
+        position: PositionInit,
+    ) -> (PositionId, WasmAmount, WasmAmount, Fraction) {
+        self.open_position(token_a, token_b, fee_rate, position)
+    }
+
+    /// Open position which deposits only `token_a` (`Side::Left`) or only `token_b` (`Side::Right`),
+    /// narrowing position range to one side of spot price
+    #[endpoint(openPositionSingleSided)]
+    fn open_position_single_sided(
+        &self,
+        token_a: &TokenId,
+        token_b: &TokenId,
+        fee_rate: dex::BasisPoints,
+        position: PositionInit,
+        force_single_sided: Option<Side>,
+    ) -> (PositionId, WasmAmount, WasmAmount, Fraction) {
+        let opened = self.result_unwrap(self.as_dex_mut().open_position_single_sided(
+            token_a,
+            token_b,
+            fee_rate,
+            position,
+            force_single_sided,
+        ));
+
+        self.opened_position_result(fee_rate, opened)
+    }
+
+    #[endpoint(open_position_single_sided)]
+    fn open_position_single_sided_snake_case(
+        &self,
+        token_a: &TokenId,
+        token_b: &TokenId,
+        fee_rate: dex::BasisPoints,
+        position: PositionInit,
+        force_single_sided: Option<Side>,
+    ) -> (PositionId, WasmAmount, WasmAmount, Fraction) {
+        self.open_position_single_sided(token_a, token_b, fee_rate, position, force_single_sided)
+    }
+
+    /// Open position with range specified by prices of `token_b` expressed in `token_a`
+    #[allow(clippy::too_many_arguments)]
+    #[endpoint(openPositionByPrice)]
+    fn open_position_by_price(
+        &self,
+        token_a: &TokenId,
+        token_b: &TokenId,
+        fee_rate: dex::BasisPoints,
+        price_low: Fraction,
+        price_high: Fraction,
+        amount_a: WasmAmount,
+        amount_b: WasmAmount,
+    ) -> (PositionId, WasmAmount, WasmAmount, Fraction) {
+        let opened = self.result_unwrap(self.as_dex_mut().open_position_by_price(
+            token_a,
+            token_b,
+            fee_rate,
+            price_low.into(),
+            price_high.into(),
+            amount_a.into(),
+            amount_b.into(),
+        ));
+
+        self.opened_position_result(fee_rate, opened)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    #[endpoint(open_position_by_price)]
+    fn open_position_by_price_snake_case(
+        &self,
+        token_a: &TokenId,
+        token_b: &TokenId,
+        fee_rate: dex::BasisPoints,
+        price_low: Fraction,
+        price_high: Fraction,
+        amount_a: WasmAmount,
+        amount_b: WasmAmount,
+    ) -> (PositionId, WasmAmount, WasmAmount, Fraction) {
+        self.open_position_by_price(
+            token_a, token_b, fee_rate, price_low, price_high, amount_a, amount_b,
+        )
+    }
+
+    #[endpoint(openPositionFor)]
+    fn open_position_for(
+        &self,
+        owner: AccountId,
+        token_a: &TokenId,
+        token_b: &TokenId,
+        fee_rate: dex::BasisPoints,
+        position: PositionInit,
+    ) -> (PositionId, WasmAmount, WasmAmount, Fraction) {
+        let opened = self.result_unwrap(
+            self.as_dex_mut()
+                .open_position_for(&owner, token_a, token_b, fee_rate, position),
+        );
+
+        self.opened_position_result(fee_rate, opened)
+    }
+
+    #[endpoint(open_position_for)]
+    fn open_position_for_snake_case(
+        &self,
+        owner: AccountId,
+        token_a: &TokenId,
+        token_b: &TokenId,
+        fee_rate: dex::BasisPoints,
+        position: PositionInit,
+    ) -> (PositionId, WasmAmount, WasmAmount, Fraction) {
+        self.open_position_for(owner, token_a, token_b, fee_rate, position)
+    }
+
+    fn opened_position_result(
+        &self,
+        fee_rate: dex::BasisPoints,
+        (position_id, amount_a, amount_b, net_liquidity): (PositionId, Amount, Amount, Liquidity),
+    ) -> (PositionId, WasmAmount, WasmAmount, Fraction) {
+        let fee_level: FeeLevel = self.result_unwrap(
             self.as_dex()
                 .fee_rates_ticks()
                 .iter()
@@ -457,15 +1123,61 @@ pub trait Dx25Contract {
         (position_id, amount_a.into(), amount_b.into(), liquidity)
     }
 
-    #[endpoint(open_position)]
-    fn open_position_snake_case(
+    #[endpoint(gcDustPosition)]
+    fn gc_dust_position(&self, position_id: PositionId) -> (WasmAmount, WasmAmount) {
+        let (amount_a, amount_b) =
+            self.result_unwrap(self.as_dex_mut().gc_dust_position(position_id));
+        (amount_a.into(), amount_b.into())
+    }
+
+    #[endpoint(gc_dust_position)]
+    fn gc_dust_position_snake_case(&self, position_id: PositionId) -> (WasmAmount, WasmAmount) {
+        self.gc_dust_position(position_id)
+    }
+
+    /// Owner-only removal of position which can't be closed due to inconsistent state
+    #[endpoint(forceRemovePosition)]
+    fn force_remove_position(&self, position_id: PositionId) {
+        self.result_unwrap(self.as_dex_mut().force_remove_position(position_id));
+    }
+
+    #[endpoint(force_remove_position)]
+    fn force_remove_position_snake_case(&self, position_id: PositionId) {
+        self.force_remove_position(position_id);
+    }
+
+    /// Owner-only indexing of owners of positions opened before the index was introduced
+    #[endpoint(indexPositionOwners)]
+    fn index_position_owners(&self, accounts: ApiVec<AccountId>) {
+        self.result_unwrap(self.as_dex_mut().index_position_owners(accounts.0));
+    }
+
+    #[endpoint(index_position_owners)]
+    fn index_position_owners_snake_case(&self, accounts: ApiVec<AccountId>) {
+        self.index_position_owners(accounts);
+    }
+
+    /// Owner-only start of accounted balances tracking in contract upgraded from earlier version,
+    /// paged by accounts and pools; returns whether tracking has started
+    #[endpoint(initAccountedBalances)]
+    fn init_accounted_balances(
         &self,
-        token_a: &TokenId,
-        token_b: &TokenId,
-        fee_rate: dex::BasisPoints,
-        position: PositionInit,
-    ) -> (PositionId, WasmAmount, WasmAmount, Fraction) {
-        self.open_position(token_a, token_b, fee_rate, position)
+        accounts: ApiVec<AccountId>,
+        pools: ApiVec<(TokenId, TokenId)>,
+    ) -> bool {
+        self.result_unwrap(
+            self.as_dex_mut()
+                .init_accounted_balances(accounts.0, pools.0),
+        )
+    }
+
+    #[endpoint(init_accounted_balances)]
+    fn init_accounted_balances_snake_case(
+        &self,
+        accounts: ApiVec<AccountId>,
+        pools: ApiVec<(TokenId, TokenId)>,
+    ) -> bool {
+        self.init_accounted_balances(accounts, pools)
     }
 
     #[endpoint(closePosition)]
@@ -489,6 +1201,41 @@ pub trait Dx25Contract {
         self.withdraw_fee(position_id)
     }
 
+    /// Withdraw fees of caller's positions with ids after `start_after`, in bounded batches.
+    /// Returns harvested fees and id to continue from, if any positions are left
+    #[endpoint(withdrawAllFees)]
+    fn withdraw_all_fees(
+        &self,
+        start_after: Option<PositionId>,
+    ) -> (
+        ApiVec<(PositionId, WasmAmount, WasmAmount)>,
+        Option<PositionId>,
+    ) {
+        let (harvested, continuation) =
+            self.result_unwrap(self.as_dex_mut().withdraw_all_fees(start_after));
+
+        (
+            ApiVec(
+                harvested
+                    .into_iter()
+                    .map(|(position_id, fee_a, fee_b)| (position_id, fee_a.into(), fee_b.into()))
+                    .collect(),
+            ),
+            continuation,
+        )
+    }
+
+    #[endpoint(withdraw_all_fees)]
+    fn withdraw_all_fees_snake_case(
+        &self,
+        start_after: Option<PositionId>,
+    ) -> (
+        ApiVec<(PositionId, WasmAmount, WasmAmount)>,
+        Option<PositionId>,
+    ) {
+        self.withdraw_all_fees(start_after)
+    }
+
     #[view]
     fn get_position_info(&self, position_id: PositionId) -> PositionInfo {
         let position_info = self.result_unwrap(self.as_dex().get_position_info(position_id));
@@ -496,6 +1243,28 @@ pub trait Dx25Contract {
         self.result_unwrap(position_info.try_into())
     }
 
+    /// Impermanent loss of position, as a non-negative fraction of held tokens value
+    #[view]
+    fn get_position_il(&self, position_id: PositionId) -> Fraction {
+        let il = self.result_unwrap(self.as_dex().get_position_il(position_id));
+        // Without fees value of position never exceeds held value, positive IL is a rounding error
+        self.result_unwrap((-il).max(Float::zero()).try_into())
+    }
+
+    /// Value of position balance expressed in one of its pool tokens
+    #[view]
+    fn get_position_value_in(
+        &self,
+        position_id: PositionId,
+        reference_token: TokenId,
+    ) -> WasmAmount {
+        self.result_unwrap(
+            self.as_dex()
+                .get_position_value_in(position_id, &reference_token),
+        )
+        .into()
+    }
+
     #[view]
     fn get_positions_info(&self, positions_ids: Vec<PositionId>) -> Vec<Option<PositionInfo>> {
         self.as_dex()
@@ -506,9 +1275,15 @@ pub trait Dx25Contract {
     }
 
     /// Deposit tokens. Receives EGLD or single ESDT payment
+    ///
+    /// Returns `(token, deposited, consumed, remaining_balance)` for each deposited token
     #[endpoint]
     #[payable("*")]
-    fn deposit(&self, actions: ApiVec<Action>) {
+    fn deposit(
+        &self,
+        actions: ApiVec<Action>,
+        idempotency_key: OptionalValue<IdempotencyKey>,
+    ) -> Vec<(TokenId, WasmAmount, WasmAmount, WasmAmount)> {
         // Check if we have esdt payments
         let mut payments: Vec<dex::DepositPayment> = self
             .call_value()
@@ -544,6 +1319,7 @@ pub trait Dx25Contract {
 
         let actions = actions.0;
         let caller_id = self_as_dex.get_caller_id();
+        let idempotency_key = idempotency_key.into_option();
 
         let result = if actions.is_empty() {
             self_as_dex.deposit_execute_actions(
@@ -551,6 +1327,7 @@ pub trait Dx25Contract {
                 &payments,
                 &mut |_, _, _| Ok(()),
                 vec![Action::Deposit],
+                idempotency_key,
             )
         } else {
             self_as_dex.deposit_execute_actions(
@@ -558,16 +1335,31 @@ pub trait Dx25Contract {
                 &payments,
                 &mut |_, _, _| Ok(()),
                 actions,
+                idempotency_key,
             )
         }
-        .and_then(|outcomes| SendBatch::try_handle_outcomes(self, outcomes));
+        .and_then(|(outcomes, deposit_split)| {
+            SendBatch::try_handle_outcomes(self, outcomes)?;
+            Ok(deposit_split)
+        });
 
-        self.result_unwrap(result);
+        self.result_unwrap(result)
+            .into_iter()
+            .map(|(token_id, deposited, consumed, remaining)| {
+                (
+                    token_id,
+                    deposited.into(),
+                    consumed.into(),
+                    remaining.into(),
+                )
+            })
+            .collect()
     }
 
     /// Withdraw fungible tokens from specified account to their source contract
     /// Operates with ESDT tokens
     /// Client should register a callback to where reciveve the tokens to
+    /// WARNING: zero `amount` withdraws whole balance, prefer `withdrawV2` to express intent
     #[endpoint]
     fn withdraw(&self, token_id: EgldOrTokenId, amount: WasmAmount, callback: Option<MethodCall>) {
         let mut dex = self.as_dex_mut();
@@ -585,6 +1377,33 @@ pub trait Dx25Contract {
         self.result_unwrap(result);
     }
 
+    /// Withdraw tokens according to `mode`. Unlike `withdraw`, zero exact amount withdraws nothing
+    #[endpoint(withdrawV2)]
+    fn withdraw_v2(
+        &self,
+        token_id: EgldOrTokenId,
+        mode: dex::WithdrawMode,
+        callback: Option<MethodCall>,
+    ) {
+        let mut dex = self.as_dex_mut();
+
+        let result = dex
+            .withdraw_v2(&dex.get_caller_id(), &token_id, mode, false, callback)
+            .and_then(|outcome| SendBatch::try_handle_outcomes(self, outcome));
+
+        self.result_unwrap(result);
+    }
+
+    #[endpoint(withdraw_v2)]
+    fn withdraw_v2_snake_case(
+        &self,
+        token_id: EgldOrTokenId,
+        mode: dex::WithdrawMode,
+        callback: Option<MethodCall>,
+    ) {
+        self.withdraw_v2(token_id, mode, callback);
+    }
+
     #[callback]
     fn withdraw_callback(
         &self,
@@ -594,6 +1413,7 @@ pub trait Dx25Contract {
     ) {
         let Withdrawal {
             account_id,
+            recipient: _,
             token_id,
             amount,
             // FIXME: callback data should be empty here, add some debug check?
@@ -609,29 +1429,38 @@ pub trait Dx25Contract {
             item_factory: _,
         } = dex.members_mut();
         let contract = contract.latest();
+        let failed = !result.is_ok();
 
-        let result =
-            contract
-                .accounts
-                .try_update(&account_id, |dex::Account::V0(ref mut account)| {
-                    // Untrack regardless of result, transfer is finished here
-                    account.withdraw_tracker.untrack(&token_id, &amount);
-                    // If transfer succeeded, we do nothing except remove track record
-                    // If transfer failed, we return tokens back to account and write additional deposit event
-                    if !result.is_ok() {
-                        let balance = account.token_balances.update_or_insert(
-                            &token_id,
-                            || Ok(Amount::zero()),
-                            |balance, _| {
-                                *balance += amount;
-                                Ok(*balance)
-                            },
-                        )?;
-                        logger.log_deposit_event(&account_id, &token_id, &amount, &balance);
-                    }
-
+        let result = contract
+            .accounts
+            .try_update(&account_id, |account| {
+                let account = account.latest();
+                // Untrack regardless of result, transfer is finished here
+                account.withdraw_tracker.untrack(&token_id, &amount);
+                // If transfer succeeded, we do nothing except remove track record
+                // If transfer failed, we return tokens back to account and write additional deposit event
+                if failed {
+                    let balance = account.token_balances.update_or_insert(
+                        &token_id,
+                        || Ok(Amount::zero()),
+                        |balance, _| {
+                            *balance += amount;
+                            Ok(*balance)
+                        },
+                    )?;
+                    logger.log_deposit_event(&account_id, &token_id, &amount, &balance);
+                }
+
+                Ok(())
+            })
+            .and_then(|()| {
+                // Returned tokens are held by contract again
+                if failed {
+                    contract.inc_accounted_balance(&token_id, amount)
+                } else {
                     Ok(())
-                });
+                }
+            });
         // Well, we should never fail here, but just in case...
         self.result_unwrap(result);
         // Handle rest  of transfers, if there are any
@@ -658,6 +1487,55 @@ pub trait Dx25Contract {
         self.remove_guard_accounts(accounts);
     }
 
+    #[endpoint(addOperatorAccounts)]
+    fn add_operator_accounts(&self, accounts: ApiVec<AccountId>) {
+        self.result_unwrap(self.as_dex_mut().add_operator_accounts(accounts.0));
+    }
+
+    #[endpoint(add_operator_accounts)]
+    fn add_operator_accounts_snake_case(&self, accounts: ApiVec<AccountId>) {
+        self.add_operator_accounts(accounts);
+    }
+
+    #[endpoint(removeOperatorAccounts)]
+    fn remove_operator_accounts(&self, accounts: ApiVec<AccountId>) {
+        self.result_unwrap(self.as_dex_mut().remove_operator_accounts(accounts.0));
+    }
+
+    #[endpoint(remove_operator_accounts)]
+    fn remove_operator_accounts_snake_case(&self, accounts: ApiVec<AccountId>) {
+        self.remove_operator_accounts(accounts);
+    }
+
+    #[view]
+    fn is_operator(&self, account_id: AccountId) -> bool {
+        self.as_dex().is_operator(&account_id)
+    }
+
+    /// Send tokens which were transferred to contract bypassing deposit, to contract owner
+    ///
+    /// Returns swept amount
+    #[endpoint(sweepUntracked)]
+    fn sweep_untracked(&self, token_id: TokenId, callback: Option<MethodCall>) -> WasmAmount {
+        let result = self
+            .as_dex_mut()
+            .sweep_untracked(&token_id, (false, callback))
+            .and_then(|(amount, outcome)| {
+                SendBatch::try_handle_outcomes(self, outcome).map(|()| amount)
+            });
+
+        self.result_unwrap(result).into()
+    }
+
+    #[endpoint(sweep_untracked)]
+    fn sweep_untracked_snake_case(
+        &self,
+        token_id: TokenId,
+        callback: Option<MethodCall>,
+    ) -> WasmAmount {
+        self.sweep_untracked(token_id, callback)
+    }
+
     #[endpoint(suspendPayableApi)]
     fn suspend_payable_api(&self) {
         self.result_unwrap(self.as_dex_mut().suspend_payable_api());
@@ -678,6 +1556,57 @@ pub trait Dx25Contract {
         self.resume_payable_api();
     }
 
+    #[endpoint(pausePool)]
+    fn pause_pool(&self, tokens: (TokenId, TokenId)) {
+        self.result_unwrap(self.as_dex_mut().pause_pool(tokens));
+    }
+
+    #[endpoint(pause_pool)]
+    fn pause_pool_snake_case(&self, tokens: (TokenId, TokenId)) {
+        self.pause_pool(tokens);
+    }
+
+    #[endpoint(unpausePool)]
+    fn unpause_pool(&self, tokens: (TokenId, TokenId)) {
+        self.result_unwrap(self.as_dex_mut().unpause_pool(tokens));
+    }
+
+    #[endpoint(unpause_pool)]
+    fn unpause_pool_snake_case(&self, tokens: (TokenId, TokenId)) {
+        self.unpause_pool(tokens);
+    }
+
+    #[endpoint(setPoolAllowedSwapSide)]
+    fn set_pool_allowed_swap_side(&self, tokens: (TokenId, TokenId), side: Option<Side>) {
+        self.result_unwrap(self.as_dex_mut().set_pool_allowed_swap_side(tokens, side));
+    }
+
+    #[endpoint(set_pool_allowed_swap_side)]
+    fn set_pool_allowed_swap_side_snake_case(
+        &self,
+        tokens: (TokenId, TokenId),
+        side: Option<Side>,
+    ) {
+        self.set_pool_allowed_swap_side(tokens, side);
+    }
+
+    #[endpoint(setPoolObservationCardinality)]
+    fn set_pool_observation_cardinality(&self, tokens: (TokenId, TokenId), cardinality: u16) {
+        self.result_unwrap(
+            self.as_dex_mut()
+                .set_pool_observation_cardinality(tokens, cardinality),
+        );
+    }
+
+    #[endpoint(set_pool_observation_cardinality)]
+    fn set_pool_observation_cardinality_snake_case(
+        &self,
+        tokens: (TokenId, TokenId),
+        cardinality: u16,
+    ) {
+        self.set_pool_observation_cardinality(tokens, cardinality);
+    }
+
     #[label("dx25-contract-view")]
     #[view]
     fn estimate_swap_exact(
@@ -694,7 +1623,7 @@ pub trait Dx25Contract {
                 token_in,
                 token_out,
                 amount.into(),
-                slippage_tolerance_bp,
+                self.result_unwrap(SlippageTolerance::from_bp(slippage_tolerance_bp)),
             ))
             .try_into(),
         )
@@ -720,12 +1649,276 @@ pub trait Dx25Contract {
                 amount_a.map(Into::into),
                 amount_b.map(Into::into),
                 user_price.map(Into::into),
-                slippage_tolerance_bp,
+                self.result_unwrap(SlippageTolerance::from_bp(slippage_tolerance_bp)),
+            ))
+            .try_into(),
+        )
+    }
+
+    /// Amounts which would be deducted from account's deposits by opening position,
+    /// and resulting liquidity, same as returned by `openPosition`
+    #[label("dx25-contract-view")]
+    #[view]
+    fn preview_open_position(
+        &self,
+        account: AccountId,
+        token_a: TokenId,
+        token_b: TokenId,
+        fee_rate: BasisPoints,
+        position: PositionInit,
+    ) -> (WasmAmount, WasmAmount, Fraction) {
+        let (amount_a, amount_b, net_liquidity) = self.result_unwrap(
+            self.as_dex()
+                .preview_open_position(&account, &token_a, &token_b, fee_rate, position),
+        );
+        let (_, amount_a, amount_b, liquidity) =
+            self.opened_position_result(fee_rate, (0, amount_a, amount_b, net_liquidity));
+        (amount_a, amount_b, liquidity)
+    }
+
+    /// Amounts deposited into full range position and its liquidity;
+    /// missing amount is derived from pool's spot price
+    #[label("dx25-contract-view")]
+    #[view]
+    fn preview_full_range_position(
+        &self,
+        tokens: (TokenId, TokenId),
+        fee_rate: BasisPoints,
+        amount_a: Option<WasmAmount>,
+        amount_b: Option<WasmAmount>,
+    ) -> (WasmAmount, WasmAmount, Fraction) {
+        let result = self.result_unwrap(self.as_dex().preview_full_range_position(
+            tokens,
+            fee_rate,
+            amount_a.map(Into::into),
+            amount_b.map(Into::into),
+        ));
+        let (_, amount_a, amount_b, liquidity) =
+            self.opened_position_result(fee_rate, (0, result.0, result.1, result.2));
+        (amount_a, amount_b, liquidity)
+    }
+
+    /// Smallest exact-in swap amount which yields non-zero amount-out
+    #[label("dx25-contract-view")]
+    #[view]
+    fn min_effective_swap_in(&self, token_in: TokenId, token_out: TokenId) -> WasmAmount {
+        self.result_unwrap(self.as_dex().min_effective_swap_in(token_in, token_out))
+            .into()
+    }
+
+    /// Exact-in swap outputs for a series of independent input amounts
+    #[label("dx25-contract-view")]
+    #[view]
+    fn quote_ladder(
+        &self,
+        token_in: TokenId,
+        token_out: TokenId,
+        amounts: ApiVec<WasmAmount>,
+    ) -> ApiVec<WasmAmount> {
+        let amounts: Vec<Amount> = amounts.0.into_iter().map(Into::into).collect();
+        let amounts_out =
+            self.result_unwrap(self.as_dex().quote_ladder(token_in, token_out, &amounts));
+        ApiVec(amounts_out.into_iter().map(Into::into).collect())
+    }
+
+    /// Amount-in required to receive exactly `amount_out` in a swap
+    #[label("dx25-contract-view")]
+    #[view]
+    fn quote_exact_out(
+        &self,
+        token_in: TokenId,
+        token_out: TokenId,
+        amount_out: WasmAmount,
+    ) -> WasmAmount {
+        self.result_unwrap(
+            self.as_dex()
+                .quote_exact_out(token_in, token_out, amount_out.into()),
+        )
+        .into()
+    }
+
+    /// Average execution price `amount_in / amount_out` of swapping exactly `amount_in`
+    #[label("dx25-contract-view")]
+    #[view]
+    fn get_average_execution_price(
+        &self,
+        token_in: TokenId,
+        token_out: TokenId,
+        amount_in: WasmAmount,
+    ) -> Fraction {
+        self.result_unwrap(
+            self.result_unwrap(self.as_dex().get_average_execution_price(
+                token_in,
+                token_out,
+                amount_in.into(),
             ))
             .try_into(),
         )
     }
 
+    /// Spot price and active liquidity of position's pool, before and after closing the position,
+    /// as `(spot_price_before, spot_price_after, liquidity_before, liquidity_after)`
+    #[label("dx25-contract-view")]
+    #[view]
+    fn estimate_close_position_impact(
+        &self,
+        position_id: PositionId,
+    ) -> (Fraction, Fraction, Fraction, Fraction) {
+        let impact = self.result_unwrap(self.as_dex().estimate_close_position_impact(position_id));
+
+        (
+            self.result_unwrap(impact.spot_price_before.try_into()),
+            self.result_unwrap(impact.spot_price_after.try_into()),
+            self.result_unwrap(Float::from(impact.liquidity_before).try_into()),
+            self.result_unwrap(Float::from(impact.liquidity_after).try_into()),
+        )
+    }
+
+    /// Protocol fee fraction currently charged on swaps in the pool, surcharge included
+    #[label("dx25-contract-view")]
+    #[view]
+    fn get_effective_protocol_fee(&self, tokens: (TokenId, TokenId)) -> BasisPoints {
+        self.result_unwrap(self.as_dex().get_effective_protocol_fee(tokens))
+    }
+
+    #[label("dx25-contract-view")]
+    #[view]
+    fn get_pool_fee_growth(
+        &self,
+        tokens: (TokenId, TokenId),
+        fee_level: FeeLevel,
+    ) -> (Fraction, Fraction) {
+        // Accumulators are never negative, so only magnitude is reported
+        let (growth_a, growth_b) =
+            self.result_unwrap(self.as_dex().get_pool_fee_growth(tokens, fee_level));
+        (
+            self.result_unwrap(Float::from(growth_a.value).try_into()),
+            self.result_unwrap(Float::from(growth_b.value).try_into()),
+        )
+    }
+
+    #[label("dx25-contract-view")]
+    #[view]
+    fn export_account(&self, account: AccountId) -> ManagedBuffer {
+        ManagedBuffer::from(&self.result_unwrap(self.as_dex().export_account(&account))[..])
+    }
+
+    #[label("dx25-contract-view")]
+    #[view]
+    fn get_open_position_fee(&self) -> Option<(TokenId, WasmAmount)> {
+        self.as_dex()
+            .get_open_position_fee()
+            .map(|(token_id, amount)| (token_id, amount.into()))
+    }
+
+    #[label("dx25-contract-view")]
+    #[view]
+    fn get_max_actions_per_batch(&self) -> u16 {
+        self.as_dex().get_max_actions_per_batch()
+    }
+
+    #[label("dx25-contract-view")]
+    #[view]
+    fn get_max_swap_tick_crossings(&self) -> Option<u32> {
+        self.as_dex().get_max_swap_tick_crossings()
+    }
+
+    #[label("dx25-contract-view")]
+    #[view]
+    fn get_protocol_fee_withdrawal_delay(&self) -> u64 {
+        self.as_dex().get_protocol_fee_withdrawal_delay()
+    }
+
+    /// Protocol fee which can be withdrawn now, and total rounding residual
+    /// of position deposits attributed to it
+    #[label("dx25-contract-view")]
+    #[view]
+    fn get_protocol_fee(
+        &self,
+        tokens: (TokenId, TokenId),
+    ) -> (WasmAmount, WasmAmount, Fraction, Fraction) {
+        let ((accrued_a, accrued_b), (rounding_a, rounding_b)) =
+            self.result_unwrap(self.as_dex().get_protocol_fee(tokens));
+
+        (
+            accrued_a.into(),
+            accrued_b.into(),
+            self.result_unwrap(rounding_a.try_into()),
+            self.result_unwrap(rounding_b.try_into()),
+        )
+    }
+
+    #[label("dx25-contract-view")]
+    #[view]
+    fn get_pending_protocol_fee_withdrawal(
+        &self,
+        tokens: (TokenId, TokenId),
+    ) -> Option<(WasmAmount, WasmAmount, u64)> {
+        self.result_unwrap(self.as_dex().get_pending_protocol_fee_withdrawal(tokens))
+            .map(|((amount_a, amount_b), release_ts)| {
+                (amount_a.into(), amount_b.into(), release_ts)
+            })
+    }
+
+    #[label("dx25-contract-view")]
+    #[view]
+    fn get_pool_count(&self) -> u64 {
+        self.as_dex().get_pool_count()
+    }
+
+    #[label("dx25-contract-view")]
+    #[view]
+    fn get_total_position_count(&self) -> u64 {
+        self.as_dex().get_total_position_count()
+    }
+
+    #[label("dx25-contract-view")]
+    #[view]
+    fn is_suspended(&self) -> bool {
+        self.as_dex().is_suspended()
+    }
+
+    #[label("dx25-contract-view")]
+    #[view]
+    fn is_pool_paused(&self, tokens: (TokenId, TokenId)) -> bool {
+        self.result_unwrap(self.as_dex().is_pool_paused(tokens))
+    }
+
+    #[label("dx25-contract-view")]
+    #[view]
+    fn get_pool_allowed_swap_side(&self, tokens: (TokenId, TokenId)) -> Option<Side> {
+        self.result_unwrap(self.as_dex().get_pool_allowed_swap_side(tokens))
+    }
+
+    /// Canonical order of tokens in pool over `token_a` and `token_b`,
+    /// and whether it differs from the order given
+    #[label("dx25-contract-view")]
+    #[view]
+    fn canonicalize_pair(&self, token_a: TokenId, token_b: TokenId) -> (TokenId, TokenId, bool) {
+        let ((left, right), swapped) =
+            self.result_unwrap(self.as_dex().canonicalize_pair(&token_a, &token_b));
+        (left, right, swapped)
+    }
+
+    #[label("dx25-contract-view")]
+    #[view]
+    fn get_pool_observation_cardinality(&self, tokens: (TokenId, TokenId)) -> u16 {
+        self.result_unwrap(self.as_dex().get_pool_observation_cardinality(tokens))
+    }
+
+    /// Time-weighted geometric average price of `token_in` in `token_out` over last `window_secs`
+    #[label("dx25-contract-view")]
+    #[view]
+    fn observe_twap(&self, token_in: TokenId, token_out: TokenId, window_secs: u64) -> Fraction {
+        self.result_unwrap(
+            self.result_unwrap(
+                self.as_dex()
+                    .observe_twap(&token_in, &token_out, window_secs),
+            )
+            .try_into(),
+        )
+    }
+
     fn as_dex(&self) -> dex::Dex<Types<Self::Api>, StateWrapper<Self>, StateWrapper<Self>> {
         dex::Dex::new(StateWrapper::new(self))
     }
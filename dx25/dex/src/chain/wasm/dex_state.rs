@@ -1,6 +1,6 @@
 use multiversx_sc::{
     contract_base::ErrorHelper,
-    types::{ContractCall, EsdtTokenPayment},
+    types::{ContractCall, EgldOrEsdtTokenIdentifier, EsdtTokenPayment},
 };
 use multiversx_sc_codec::multi_types::IgnoreValue;
 use multiversx_wegld_swap_sc::ProxyTrait;
@@ -82,6 +82,7 @@ impl<'a, C: Dx25Contract> dex::StateMut<Types<C::Api>> for StateMutWrapper<'a, C
     fn send_tokens(
         &mut self,
         account_id: &AccountId,
+        recipient: &AccountId,
         token_id: &TokenId,
         amount: Amount,
         _unregister: bool,
@@ -115,6 +116,7 @@ impl<'a, C: Dx25Contract> dex::StateMut<Types<C::Api>> for StateMutWrapper<'a, C
         SendBatch::send_sync_or_return_withdrawal(
             self.contract,
             account_id,
+            recipient,
             token_id,
             amount,
             unwrapper,
@@ -130,6 +132,18 @@ impl<'a, C: Dx25Contract> dex::StateMut<Types<C::Api>> for StateMutWrapper<'a, C
     fn get_caller_id(&self) -> AccountId {
         into_account_id(&self.contract.blockchain().get_caller())
     }
+
+    fn get_block_timestamp(&self) -> u64 {
+        self.contract.blockchain().get_block_timestamp()
+    }
+
+    fn get_contract_balance(&self, token_id: &TokenId) -> Amount {
+        let token_id = EgldOrEsdtTokenIdentifier::esdt(token_id.native().clone());
+        self.contract
+            .blockchain()
+            .get_sc_balance(&token_id, 0)
+            .into()
+    }
 }
 
 /// Save changed value of a mutable reference
@@ -98,10 +98,19 @@ impl<'a, C: Dx25Contract> DexWrapper<'a, C> {
         deposit_data: &[dex::DepositPayment],
         register_account_cb: dex::AccountCallbackType<'_, Types<C::Api>>,
         actions: Vec<Action>,
-    ) -> Result<Vec<Result<Option<Withdrawal>>>> {
+        idempotency_key: Option<dex::IdempotencyKey>,
+    ) -> Result<(
+        Vec<Result<Option<Withdrawal>>>,
+        Vec<(TokenId, Amount, Amount, Amount)>,
+    )> {
         let actions = self.map_actions(actions);
-        self.dex
-            .deposit_execute_actions(account_id, deposit_data, register_account_cb, actions)
+        self.dex.deposit_execute_actions(
+            account_id,
+            deposit_data,
+            register_account_cb,
+            actions,
+            idempotency_key,
+        )
     }
 
     pub fn withdraw(
@@ -123,6 +132,25 @@ impl<'a, C: Dx25Contract> DexWrapper<'a, C> {
         )
     }
 
+    pub fn withdraw_v2(
+        &mut self,
+        account_id: &AccountId,
+        token_id: &EgldOrEsdtTokenIdentifier<VmApi>,
+        mode: dex::WithdrawMode,
+        unregister: bool,
+        method_call: Option<MethodCall>,
+    ) -> Result<Option<Result<Option<Withdrawal>>>> {
+        let (token_id, unwrap) =
+            map_token_id::<C>(token_id.clone(), self.wegld().map(|(_, id)| id));
+        self.dex.withdraw_v2(
+            account_id,
+            &token_id,
+            mode,
+            unregister,
+            (unwrap, method_call),
+        )
+    }
+
     pub fn owner_withdraw(
         &mut self,
         token_id: &EgldOrEsdtTokenIdentifier<VmApi>,
@@ -139,8 +167,14 @@ impl<'a, C: Dx25Contract> DexWrapper<'a, C> {
         &mut self,
         register_account_cb: dex::AccountCallbackType<'_, Types<C::Api>>,
         actions: Vec<Action>,
+        idempotency_key: Option<dex::IdempotencyKey>,
     ) -> Result<(Vec<Result<Option<Withdrawal>>>, Option<Amount>)> {
-        self.execute_actions_for(&self.dex.get_caller_id(), register_account_cb, actions)
+        self.execute_actions_for(
+            &self.dex.get_caller_id(),
+            register_account_cb,
+            actions,
+            idempotency_key,
+        )
     }
 
     #[allow(clippy::type_complexity)]
@@ -149,9 +183,10 @@ impl<'a, C: Dx25Contract> DexWrapper<'a, C> {
         account_id: &AccountId,
         register_account_cb: dex::AccountCallbackType<'_, Types<C::Api>>,
         actions: Vec<Action>,
+        idempotency_key: Option<dex::IdempotencyKey>,
     ) -> Result<(Vec<Result<Option<Withdrawal>>>, Option<Amount>)> {
         let actions = self.map_actions(actions);
         self.dex
-            .execute_actions_for(account_id, register_account_cb, actions)
+            .execute_actions_for(account_id, register_account_cb, actions, idempotency_key)
     }
 }
@@ -7,7 +7,7 @@ use crate::{
     chain::{AccountId, Amount, TokenId},
     dex::{
         self, latest::RawFeeLevelsArray, BasisPoints, FeeLevel, Float, PoolUpdateReason,
-        PositionId, Tick,
+        PositionId, Side, Tick,
     },
     Dx25Contract,
 };
@@ -178,6 +178,22 @@ impl<'a, C: Dx25Contract> dex::Logger for Logger<'a, C> {
         self.contract.log_remove_guard_accounts_event(data);
     }
 
+    fn log_add_operator_accounts_event(&mut self, accounts: &[AccountId]) {
+        let data = log_util::serialize_log_data(event::AddOperatorAccounts {
+            accounts: ApiVec(accounts.to_vec()),
+        });
+
+        self.contract.log_add_operator_accounts_event(data);
+    }
+
+    fn log_remove_operator_accounts_event(&mut self, accounts: &[AccountId]) {
+        let data = log_util::serialize_log_data(event::RemoveOperatorAccounts {
+            accounts: ApiVec(accounts.to_vec()),
+        });
+
+        self.contract.log_remove_operator_accounts_event(data);
+    }
+
     fn log_suspend_payable_api_event(&mut self, account: &AccountId) {
         let data = log_util::serialize_log_data(event::SuspendPayableAPI {
             account: account.clone(),
@@ -194,6 +210,110 @@ impl<'a, C: Dx25Contract> dex::Logger for Logger<'a, C> {
         self.contract.log_resume_payable_api_event(data);
     }
 
+    fn log_set_protocol_fee_event(&mut self, old: BasisPoints, new: BasisPoints, by: &AccountId) {
+        let data = log_util::serialize_log_data(event::SetProtocolFee {
+            old,
+            new,
+            account: by.clone(),
+        });
+
+        self.contract.log_set_protocol_fee_event(data);
+    }
+
+    fn log_set_swap_surcharge_event(&mut self, old: BasisPoints, new: BasisPoints, by: &AccountId) {
+        let data = log_util::serialize_log_data(event::SetSwapSurcharge {
+            old,
+            new,
+            account: by.clone(),
+        });
+
+        self.contract.log_set_swap_surcharge_event(data);
+    }
+
+    fn log_pause_pool_event(&mut self, pool: (&TokenId, &TokenId), by: &AccountId) {
+        let data = log_util::serialize_log_data(event::PausePool {
+            pool: (pool.0.native().clone(), pool.1.native().clone()),
+            account: by.clone(),
+        });
+
+        self.contract.log_pause_pool_event(data);
+    }
+
+    fn log_unpause_pool_event(&mut self, pool: (&TokenId, &TokenId), by: &AccountId) {
+        let data = log_util::serialize_log_data(event::UnpausePool {
+            pool: (pool.0.native().clone(), pool.1.native().clone()),
+            account: by.clone(),
+        });
+
+        self.contract.log_unpause_pool_event(data);
+    }
+
+    fn log_open_position_fee_event(
+        &mut self,
+        user: &AccountId,
+        position_id: PositionId,
+        token: &TokenId,
+        amount: &Amount,
+    ) {
+        let data = log_util::serialize_log_data(event::OpenPositionFee {
+            user: user.clone(),
+            position_id,
+            token_id: token.native().clone(),
+            amount: (*amount).into(),
+        });
+
+        self.contract.log_open_position_fee_event(data);
+    }
+
+    fn log_force_remove_position_event(
+        &mut self,
+        position_id: PositionId,
+        pool: (&TokenId, &TokenId),
+        owner: Option<&AccountId>,
+        by: &AccountId,
+    ) {
+        let data = log_util::serialize_log_data(event::ForceRemovePosition {
+            position_id,
+            pool: (pool.0.native().clone(), pool.1.native().clone()),
+            owner: owner.cloned(),
+            account: by.clone(),
+        });
+
+        self.contract.log_force_remove_position_event(data);
+    }
+
+    fn log_sweep_untracked_event(
+        &mut self,
+        recipient: &AccountId,
+        token: &TokenId,
+        amount: &Amount,
+    ) {
+        let data = log_util::serialize_log_data(event::SweepUntracked {
+            recipient: recipient.clone(),
+            token_id: token.native().clone(),
+            amount: (*amount).into(),
+        });
+
+        self.contract.log_sweep_untracked_event(data);
+    }
+
+    fn log_pool_created_event(
+        &mut self,
+        pool: (&TokenId, &TokenId),
+        fee_level: FeeLevel,
+        initial_sqrtprice: Float,
+        by: &AccountId,
+    ) {
+        let data = log_util::serialize_log_data(event::PoolCreated {
+            pool: (pool.0.native().clone(), pool.1.native().clone()),
+            fee_level,
+            initial_sqrtprice,
+            account: by.clone(),
+        });
+
+        self.contract.log_pool_created_event(data);
+    }
+
     fn log_tick_update_event(
         &mut self,
         pool: (&TokenId, &TokenId),
@@ -210,13 +330,44 @@ impl<'a, C: Dx25Contract> dex::Logger for Logger<'a, C> {
 
         self.contract.log_tick_update_event(data);
     }
+
+    fn log_tick_crossed_event(
+        &mut self,
+        pool: (&TokenId, &TokenId),
+        fee_level: FeeLevel,
+        tick: Tick,
+        side: Side,
+    ) {
+        let data = log_util::serialize_log_data(event::TickCrossed {
+            pool: (pool.0.native().clone(), pool.1.native().clone()),
+            fee_level,
+            tick: tick.index(),
+            side,
+        });
+
+        self.contract.log_tick_crossed_event(data);
+    }
+
+    fn log_position_range_boundary_crossed_event(
+        &mut self,
+        position_id: PositionId,
+        entered: bool,
+    ) {
+        let data = log_util::serialize_log_data(event::PositionRangeBoundaryCrossed {
+            position_id,
+            entered,
+        });
+
+        self.contract
+            .log_position_range_boundary_crossed_event(data);
+    }
 }
 
 pub mod event {
     use crate::{
         api_types::ApiVec,
         chain::{AccountId, VmApi},
-        dex::{latest::RawFeeLevelsArray, BasisPoints, Float, PoolUpdateReason, PositionId},
+        dex::{latest::RawFeeLevelsArray, BasisPoints, Float, PoolUpdateReason, PositionId, Side},
         WasmAmount,
     };
     use multiversx_sc::types::TokenIdentifier;
@@ -303,6 +454,16 @@ pub mod event {
         pub accounts: ApiVec<AccountId>,
     }
 
+    #[derive(TopEncode)]
+    pub struct AddOperatorAccounts {
+        pub accounts: ApiVec<AccountId>,
+    }
+
+    #[derive(TopEncode)]
+    pub struct RemoveOperatorAccounts {
+        pub accounts: ApiVec<AccountId>,
+    }
+
     #[derive(TopEncode)]
     pub struct SuspendPayableAPI {
         pub account: AccountId,
@@ -313,6 +474,63 @@ pub mod event {
         pub account: AccountId,
     }
 
+    #[derive(TopEncode)]
+    pub struct SetProtocolFee {
+        pub old: BasisPoints,
+        pub new: BasisPoints,
+        pub account: AccountId,
+    }
+
+    #[derive(TopEncode)]
+    pub struct SetSwapSurcharge {
+        pub old: BasisPoints,
+        pub new: BasisPoints,
+        pub account: AccountId,
+    }
+
+    #[derive(TopEncode)]
+    pub struct PausePool {
+        pub pool: (NativeTokenId, NativeTokenId),
+        pub account: AccountId,
+    }
+
+    #[derive(TopEncode)]
+    pub struct UnpausePool {
+        pub pool: (NativeTokenId, NativeTokenId),
+        pub account: AccountId,
+    }
+
+    #[derive(TopEncode)]
+    pub struct OpenPositionFee {
+        pub user: AccountId,
+        pub position_id: PositionId,
+        pub token_id: NativeTokenId,
+        pub amount: WasmAmount,
+    }
+
+    #[derive(TopEncode)]
+    pub struct ForceRemovePosition {
+        pub position_id: PositionId,
+        pub pool: (NativeTokenId, NativeTokenId),
+        pub owner: Option<AccountId>,
+        pub account: AccountId,
+    }
+
+    #[derive(TopEncode, TopDecode)]
+    pub struct SweepUntracked {
+        pub recipient: AccountId,
+        pub token_id: NativeTokenId,
+        pub amount: WasmAmount,
+    }
+
+    #[derive(TopEncode)]
+    pub struct PoolCreated {
+        pub pool: (NativeTokenId, NativeTokenId),
+        pub fee_level: u8,
+        pub initial_sqrtprice: Float,
+        pub account: AccountId,
+    }
+
     #[derive(TopEncode)]
     pub struct TickUpdate {
         pub pool: (NativeTokenId, NativeTokenId),
@@ -320,4 +538,18 @@ pub mod event {
         pub tick: i32,
         pub liquidity_change: Float,
     }
+
+    #[derive(TopEncode)]
+    pub struct TickCrossed {
+        pub pool: (NativeTokenId, NativeTokenId),
+        pub fee_level: u8,
+        pub tick: i32,
+        pub side: Side,
+    }
+
+    #[derive(TopEncode, TopDecode)]
+    pub struct PositionRangeBoundaryCrossed {
+        pub position_id: PositionId,
+        pub entered: bool,
+    }
 }
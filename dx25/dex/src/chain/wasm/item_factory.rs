@@ -70,4 +70,54 @@ impl<S: StorageMapperApi> dex::ItemFactory<Types<S>> for ItemFactory<S> {
     fn new_guards(&mut self) -> <Types<S> as dex::Types>::AccountIdSet {
         StorageSet::new(self.next_unique_id())
     }
+
+    fn new_reference_prices_map(&mut self) -> <Types<S> as dex::Types>::ReferencePricesMap {
+        StorageMap::new(self.next_unique_id())
+    }
+
+    fn new_token_decimals_map(&mut self) -> <Types<S> as dex::Types>::TokenDecimalsMap {
+        StorageMap::new(self.next_unique_id())
+    }
+
+    fn new_token_verified_at_map(&mut self) -> <Types<S> as dex::Types>::TokenVerifiedAtMap {
+        StorageMap::new(self.next_unique_id())
+    }
+
+    fn new_operators(&mut self) -> <Types<S> as dex::Types>::AccountIdSet {
+        StorageSet::new(self.next_unique_id())
+    }
+
+    fn new_accounted_balances_map(&mut self) -> <Types<S> as dex::Types>::AccountedBalancesMap {
+        StorageMap::new(self.next_unique_id())
+    }
+
+    fn new_paused_pools(&mut self) -> <Types<S> as dex::Types>::PoolIdSet {
+        StorageSet::new(self.next_unique_id())
+    }
+
+    fn new_position_owners_map(&mut self) -> <Types<S> as dex::Types>::PositionOwnersMap {
+        StorageMap::new(self.next_unique_id())
+    }
+
+    fn new_tick_positions_map(&mut self) -> <Types<S> as dex::Types>::TickPositionsMap {
+        StorageMap::new(self.next_unique_id())
+    }
+
+    fn new_accounted_balances_init_accounts(&mut self) -> <Types<S> as dex::Types>::AccountIdSet {
+        StorageSet::new(self.next_unique_id())
+    }
+
+    fn new_accounted_balances_init_pools(&mut self) -> <Types<S> as dex::Types>::PoolIdSet {
+        StorageSet::new(self.next_unique_id())
+    }
+
+    fn new_collected_open_position_fees_map(
+        &mut self,
+    ) -> <Types<S> as dex::Types>::AccountedBalancesMap {
+        StorageMap::new(self.next_unique_id())
+    }
+
+    fn new_gc_reward_funds_map(&mut self) -> <Types<S> as dex::Types>::AccountedBalancesMap {
+        StorageMap::new(self.next_unique_id())
+    }
 }
@@ -65,7 +65,9 @@ impl SendBatch {
     ///
     /// # Parameters
     /// * `contract` - reference to contract instance
-    /// * `account_id` - target account
+    /// * `account_id` - account on behalf of which tokens are sent; asynchronous transfer
+    ///     is tracked on it, and tokens are returned to its deposit if transfer fails
+    /// * `recipient` - account which receives tokens, usually same as `account_id`
     /// * `token_id` - identifier of token to send
     /// * `amount` - amount of token to send
     /// * `unwrap` - `true` if token in question is a wrapped eGld token which must be unwrapped before send
@@ -79,6 +81,7 @@ impl SendBatch {
     pub fn send_sync_or_return_withdrawal<C: Dx25Contract, F: FnOnce(Amount)>(
         contract: &C,
         account_id: &AccountId,
+        recipient: &AccountId,
         token_id: &TokenId,
         amount: Amount,
         unwrapper: Option<F>,
@@ -96,7 +99,7 @@ impl SendBatch {
         //          If
         //
 
-        let dx25_address = account_id.to_address().into();
+        let dx25_address = recipient.to_address().into();
         let is_contract = contract.blockchain().is_smart_contract(&dx25_address);
         let dx25_contract = contract;
         // Receiver is either just user or there's no callback to invoke -> perform direct
@@ -152,7 +155,7 @@ impl SendBatch {
             } else {
                 Self::prepare_esdt_transfer(
                     dx25_contract,
-                    account_id,
+                    recipient,
                     token_id,
                     amount,
                     entrypoint,
@@ -168,19 +171,19 @@ impl SendBatch {
             let mut dex = dx25_contract.as_dex_mut();
             let contract = dex.contract_mut().latest();
 
-            contract
-                .accounts
-                .try_update(account_id, |dex::Account::V0(ref mut acc)| {
-                    // Track transfer
-                    acc.withdraw_tracker.track(token_id.clone(), amount);
-                    // Finally, return withdraw payload
-                    Ok(Some(Withdrawal {
-                        account_id: account_id.to_address(),
-                        token_id: token_id.clone(),
-                        amount,
-                        callback: Some(callback),
-                    }))
-                })
+            contract.accounts.try_update(account_id, |acc| {
+                let acc = acc.latest();
+                // Track transfer
+                acc.withdraw_tracker.track(token_id.clone(), amount);
+                // Finally, return withdraw payload
+                Ok(Some(Withdrawal {
+                    account_id: account_id.to_address(),
+                    recipient: recipient.to_address(),
+                    token_id: token_id.clone(),
+                    amount,
+                    callback: Some(callback),
+                }))
+            })
         }
     }
 
@@ -239,7 +242,7 @@ impl SendBatch {
         // Create async call
         contract
             .send()
-            .contract_call::<()>(head.account_id.clone().into(), "ESDTTransfer".into())
+            .contract_call::<()>(head.recipient.clone().into(), "ESDTTransfer".into())
             .with_raw_arguments(args)
             .async_call()
             .with_callback(contract.callbacks().withdraw_callback(head, tail))
@@ -14,18 +14,20 @@ use crate::{
         latest::{EffSqrtprices, RawFeeLevelsArray},
         pool::{
             self, eff_sqrtprice_from_spot_sqrtprice, eval_initial_eff_sqrtprice, fee_rate_ticks,
-            fee_rates_ticks, find_pivot, Pool as _,
+            fee_rates_ticks, find_pivot, Pool as _, PoolImpl as _,
         },
         traits::{ItemFactory as _, Map as _},
         utils::{next_down, next_up, swap_if, MinSome},
         v0::{position_state_ex::eval_position_balance_ufp, FeeLevelsArray, NUM_FEE_LEVELS},
-        BasisPoints, EffTick, ErrorKind, EstimateAddLiquidityResult, EstimateRemoveLiquidityResult,
-        EstimateSwapExactResult, FeeLevel, ItemFactory as _, Pool, PoolId, PositionId,
-        PositionInit, PositionOpenedInfo, Range, Side, State, Tick, TxCostEstimate, Types,
-        BASIS_POINT_DIVISOR, MAX_NET_LIQUIDITY, MIN_NET_LIQUIDITY,
+        Action, BasisPoints, ClosePositionImpact, EffTick, Error, ErrorKind,
+        EstimateAddLiquidityResult, EstimateRemoveLiquidityResult, EstimateSwapExactResult,
+        FeeLevel, ItemFactory as _, PoolId, PositionClosedInfo, PositionId, PositionInit,
+        PositionOpenedInfo, Range, Side, SimOp, SimOpResult, SimResult, SlippageTolerance, State,
+        Tick, TxCostEstimate, Types, WasmApi, ZapEstimate, BASIS_POINT_DIVISOR, MAX_NET_LIQUIDITY,
+        MAX_QUOTE_LADDER_LEN, MIN_NET_LIQUIDITY,
     },
     ensure, ensure_here, error_here, AccountId, Amount, AmountSFP, AmountUFP, Float, Liquidity,
-    LiquiditySFP, NetLiquidityUFP, TokenId,
+    LiquiditySFP, LongestUFP, NetLiquidityUFP, TokenId,
 };
 
 use self::overlay_factory::OverlayItemFactory;
@@ -65,7 +67,7 @@ pub trait Estimations {
         token_in: TokenId,
         token_out: TokenId,
         amount: Amount,
-        slippage_tolerance_bp: BasisPoints,
+        slippage_tolerance: SlippageTolerance,
     ) -> Result<EstimateSwapExactResult>;
 
     #[allow(clippy::too_many_arguments)]
@@ -77,10 +79,126 @@ pub trait Estimations {
         amount_a: Option<Amount>,
         amount_b: Option<Amount>,
         user_price: Option<Float>,
-        slippage_tolerance_bp: BasisPoints,
+        slippage_tolerance: SlippageTolerance,
     ) -> Result<EstimateAddLiquidityResult>;
 
     fn estimate_liq_remove(&self, position_id: u64) -> Result<EstimateRemoveLiquidityResult>;
+
+    /// Estimate gas needed to execute batch of `actions`, as sum of per-action costs.
+    ///
+    /// Only actions with measured cost constants can be estimated: position opening
+    /// and, on NEAR, position closing. Fails with `GasCostUnknown` if batch contains
+    /// any other action.
+    fn estimate_actions_gas<E: WasmApi>(&self, actions: &[Action<E>]) -> Result<TxCostEstimate>;
+
+    fn min_effective_swap_in(&self, token_in: TokenId, token_out: TokenId) -> Result<Amount>;
+
+    fn quote_ladder(
+        &self,
+        token_in: TokenId,
+        token_out: TokenId,
+        amounts: &[Amount],
+    ) -> Result<Vec<Amount>>;
+
+    fn quote_exact_out(
+        &self,
+        token_in: TokenId,
+        token_out: TokenId,
+        amount_out: Amount,
+    ) -> Result<Amount>;
+
+    fn get_average_execution_price(
+        &self,
+        token_in: TokenId,
+        token_out: TokenId,
+        amount_in: Amount,
+    ) -> Result<Float>;
+
+    fn liquidity_for_target_price(
+        &self,
+        tokens: (TokenId, TokenId),
+        fee_rate: BasisPoints,
+        ticks_range: (Option<i32>, Option<i32>),
+        target_price: Float,
+        side: Side,
+        amount: Amount,
+    ) -> Result<(Amount, Amount, Liquidity)>;
+
+    fn simulate(&self, tokens: (TokenId, TokenId), ops: &[SimOp]) -> Result<SimResult>;
+
+    fn estimate_zap_in(
+        &self,
+        token_have: TokenId,
+        token_other: TokenId,
+        fee_rate: BasisPoints,
+        ticks_range: (Option<i32>, Option<i32>),
+        amount: Amount,
+    ) -> Result<ZapEstimate>;
+
+    /// Preview opening of position by `account` without committing it.
+    ///
+    /// Returns amounts of `token_a` and `token_b` which would be deducted from account's
+    /// deposits, including position opening fee if it's charged in one of these tokens,
+    /// along with net liquidity of position.
+    /// Fails with `NotEnoughTokens` if account's deposits don't cover deducted amounts.
+    fn preview_open_position(
+        &self,
+        account: &AccountId,
+        token_a: &TokenId,
+        token_b: &TokenId,
+        fee_rate: BasisPoints,
+        position: PositionInit,
+    ) -> Result<(Amount, Amount, Liquidity)>;
+
+    /// Amounts deposited into full range position, along with its net liquidity.
+    ///
+    /// Specialization of `estimate_liq_add` for full range. If only one amount is specified,
+    /// the other one is derived from pool's spot price, so pool must have liquidity.
+    fn preview_full_range_position(
+        &self,
+        tokens: (TokenId, TokenId),
+        fee_rate: BasisPoints,
+        amount_a: Option<Amount>,
+        amount_b: Option<Amount>,
+    ) -> Result<(Amount, Amount, Liquidity)>;
+
+    /// Simulate closing of position, reporting spot price and active liquidity
+    /// of its pool before and after. Position removal changes depth of the pool,
+    /// but is not expected to move its spot price.
+    fn estimate_close_position_impact(
+        &self,
+        position_id: PositionId,
+    ) -> Result<ClosePositionImpact>;
+}
+
+impl<T: Types, S: State<T>, SS: Borrow<S>> Dex<T, S, SS> {
+    /// Gas cost of opening position in pool over `tokens` at `fee_level`,
+    /// grows with number of initialized ticks on that level
+    #[allow(clippy::useless_conversion)]
+    fn open_position_gas_cost(
+        &self,
+        tokens: (TokenId, TokenId),
+        fee_level: FeeLevel,
+    ) -> Result<Amount> {
+        let ticks_len_log2 = match self.get_pool_ticks(tokens, fee_level)? {
+            Some(ticks_len) if ticks_len > 0 => u128::from(ticks_len.ilog2()),
+            _ => 0,
+        };
+        Ok(Amount::from(
+            OPEN_POSITION_COST_PER_TICK_LOG * ticks_len_log2 + OPEN_POSITION_COST_BASE,
+        ))
+    }
+
+    /// Gas cost of closing position, grows with number of initialized ticks on its fee level
+    #[cfg(feature = "near")]
+    fn close_position_gas_cost(&self, position_id: PositionId) -> Result<Amount> {
+        let pos_info = self.get_position_info(position_id)?;
+        let ticks_len_log2 = match self.get_pool_ticks(pos_info.tokens_ids, pos_info.fee_level)? {
+            Some(ticks_len) if ticks_len > 0 => u128::from(ticks_len.ilog2()),
+            _ => 0,
+        };
+        Ok(CLOSE_POSITION_COST_PER_TICK_LOG * ticks_len_log2 + CLOSE_POSITION_COST_BASE)
+    }
 }
 
 impl<T: Types, S: State<T>, SS: Borrow<S>> Estimations for Dex<T, S, SS> {
@@ -90,7 +208,7 @@ impl<T: Types, S: State<T>, SS: Borrow<S>> Estimations for Dex<T, S, SS> {
         token_in: TokenId,
         token_out: TokenId,
         amount: Amount,
-        slippage_tolerance_bp: BasisPoints,
+        slippage_tolerance: SlippageTolerance,
     ) -> Result<EstimateSwapExactResult> {
         let (pool_id, swapped) =
             PoolId::try_from_pair((token_in, token_out)).map_err(|e| error_here!(e))?;
@@ -98,7 +216,7 @@ impl<T: Types, S: State<T>, SS: Borrow<S>> Estimations for Dex<T, S, SS> {
 
         let contract = self.contract().as_ref();
 
-        contract.pools.try_inspect(&pool_id, |Pool::V0(ref pool)| {
+        contract.pools.try_inspect(&pool_id, |pool| {
             let init_eff_sqrtprice = pool.eff_sqrtprice(0, direction);
 
             let mut pool = PoolStateOverlay::<T>::from(pool);
@@ -109,10 +227,20 @@ impl<T: Types, S: State<T>, SS: Borrow<S>> Estimations for Dex<T, S, SS> {
                 .map(|position_reserves_at_level| position_reserves_at_level[direction])
                 .sum();
 
-            let (amount_in, amount_out, num_tick_crossings) = if is_exact_in {
-                pool.swap_exact_in(direction, amount, contract.protocol_fee_fraction)?
+            let (amount_in, amount_out, crossed_ticks) = if is_exact_in {
+                pool.swap_exact_in(
+                    direction,
+                    amount,
+                    contract.swap_protocol_fee_fraction(),
+                    contract.swap_max_underpay,
+                )?
             } else {
-                pool.swap_exact_out(direction, amount, contract.protocol_fee_fraction)?
+                pool.swap_exact_out(
+                    direction,
+                    amount,
+                    contract.swap_protocol_fee_fraction(),
+                    contract.swap_max_underpay,
+                )?
             };
 
             let position_reserves_after: AmountUFP = pool
@@ -124,14 +252,20 @@ impl<T: Types, S: State<T>, SS: Borrow<S>> Estimations for Dex<T, S, SS> {
                 AmountUFP::from(amount_in) - (position_reserves_after - position_reserves_before),
             )
             .map_err(|_| error_here!(ErrorKind::InternalLogicError))?;
+            // Same factor as the one used when accumulating LP fees on-chain
+            let lp_fee_factor = LongestUFP::from(u128::from(
+                BASIS_POINT_DIVISOR - contract.swap_protocol_fee_fraction(),
+            )) / LongestUFP::from(u128::from(BASIS_POINT_DIVISOR));
+            let lp_fee_in_spent_tok =
+                Amount::try_from(LongestUFP::from(fee_in_spent_tok) * lp_fee_factor)
+                    .map_err(|e| error_here!(e))?;
+            let protocol_fee_in_spent_tok = fee_in_spent_tok - lp_fee_in_spent_tok;
 
             let amount_in_float = Float::from(amount_in);
             let amount_out_float = Float::from(amount_out);
 
             let result = if is_exact_in { amount_out } else { amount_in };
-            let slippage_tolerance =
-                Float::from(slippage_tolerance_bp) / Float::from(BASIS_POINT_DIVISOR);
-            let slippage_factor = Float::one() - slippage_tolerance;
+            let slippage_factor = Float::one() - slippage_tolerance.fraction();
             let result_bound_float = if is_exact_in {
                 amount_out_float * slippage_factor
             } else {
@@ -169,7 +303,9 @@ impl<T: Types, S: State<T>, SS: Borrow<S>> Estimations for Dex<T, S, SS> {
                 swap_price,
                 swap_price_worst,
                 fee_in_spent_tok,
-                num_tick_crossings,
+                lp_fee_in_spent_tok,
+                protocol_fee_in_spent_tok,
+                num_tick_crossings: crossed_ticks.len() as u32,
             })
         })?
     }
@@ -206,7 +342,7 @@ impl<T: Types, S: State<T>, SS: Borrow<S>> Estimations for Dex<T, S, SS> {
         amount_a: Option<Amount>,
         amount_b: Option<Amount>,
         user_price: Option<Float>,
-        slippage_tolerance_bp: BasisPoints,
+        slippage_tolerance: SlippageTolerance,
     ) -> Result<EstimateAddLiquidityResult> {
         let tokens = (token_a, token_b);
 
@@ -225,6 +361,15 @@ impl<T: Types, S: State<T>, SS: Borrow<S>> Estimations for Dex<T, S, SS> {
             .as_ref()
             .map(|pool_info| pool_info.spot_sqrtprices[fee_level as usize].powi(2))
             .filter(|price| !price.is_zero());
+        // Error for estimations which need current spot price, when it's absent
+        let pool_exists = pool_info.is_some();
+        let no_spot_price_error = || {
+            if pool_exists {
+                error_here!(ErrorKind::PoolHasNoLiquidity)
+            } else {
+                error_here!(ErrorKind::PoolNotRegistered)
+            }
+        };
 
         ensure!(
             amount_a.is_some() || amount_b.is_some(),
@@ -267,10 +412,8 @@ impl<T: Types, S: State<T>, SS: Borrow<S>> Estimations for Dex<T, S, SS> {
             let max_amont_b = amount_b.ok_or_else(|| error_here!(ErrorKind::SwapAmountTooSmall))?;
 
             let max_amount_a = if let Some(user_price) = user_price {
-                ensure!(
-                    spot_price.is_none(),
-                    error_here!(ErrorKind::PoolNotRegistered)
-                );
+                // User price is applicable only to pools without liquidity
+                ensure!(spot_price.is_none(), error_here!(ErrorKind::InvalidParams));
 
                 let user_eff_sqrtprices = EffSqrtprices::from_value(
                     eff_sqrtprice_from_spot_sqrtprice(user_price.sqrt(), fee_level),
@@ -289,10 +432,7 @@ impl<T: Types, S: State<T>, SS: Borrow<S>> Estimations for Dex<T, S, SS> {
                 )?
                 .0
             } else {
-                ensure!(
-                    spot_price.is_some(),
-                    error_here!(ErrorKind::PoolNotRegistered)
-                );
+                ensure!(spot_price.is_some(), no_spot_price_error());
                 self.evaluate_open_position(
                     &tokens,
                     fee_rate,
@@ -308,10 +448,8 @@ impl<T: Types, S: State<T>, SS: Borrow<S>> Estimations for Dex<T, S, SS> {
                 amount_a.ok_or_else(|| error_here!(ErrorKind::SwapAmountTooSmall))?;
 
             let max_amont_b = if let Some(user_price) = user_price {
-                ensure!(
-                    spot_price.is_none(),
-                    error_here!(ErrorKind::PoolNotRegistered)
-                );
+                // User price is applicable only to pools without liquidity
+                ensure!(spot_price.is_none(), error_here!(ErrorKind::InvalidParams));
 
                 let user_eff_sqrtprices = EffSqrtprices::from_value(
                     eff_sqrtprice_from_spot_sqrtprice(user_price.sqrt(), fee_level),
@@ -330,10 +468,7 @@ impl<T: Types, S: State<T>, SS: Borrow<S>> Estimations for Dex<T, S, SS> {
                 )?
                 .1
             } else {
-                ensure!(
-                    spot_price.is_some(),
-                    error_here!(ErrorKind::PoolNotRegistered)
-                );
+                ensure!(spot_price.is_some(), no_spot_price_error());
                 self.evaluate_open_position(
                     &tokens,
                     fee_rate,
@@ -362,9 +497,7 @@ impl<T: Types, S: State<T>, SS: Borrow<S>> Estimations for Dex<T, S, SS> {
                 max_amount_b,
             )?;
 
-        let slippage_tolerance =
-            Float::from(slippage_tolerance_bp) / Float::from(BASIS_POINT_DIVISOR);
-        let slippage_factor = (Float::one() - slippage_tolerance).sqrt();
+        let slippage_factor = (Float::one() - slippage_tolerance.fraction()).sqrt();
 
         let min_a_eff_sqrtprices = {
             let mut min_a_eff_sqrtprices = EffSqrtprices::from_value(
@@ -426,30 +559,11 @@ impl<T: Types, S: State<T>, SS: Borrow<S>> Estimations for Dex<T, S, SS> {
             error_here!(ErrorKind::InternalLogicError)
         );
 
-        #[allow(clippy::useless_conversion)]
-        let mut tx_cost = TxCostEstimate {
-            gas_cost_max: Amount::from(OPEN_POSITION_COST_BASE),
+        let tx_cost = TxCostEstimate {
+            gas_cost_max: self.open_position_gas_cost(tokens, fee_level)?,
             storage_fee_max: Amount::from(0u16),
         };
 
-        if let Some(ticks_len) = self.get_pool_ticks(tokens, fee_level) {
-            if ticks_len > 0 {
-                #[cfg(feature = "near")]
-                {
-                    let ticks_len_log2 = u128::from(ticks_len.ilog2());
-                    tx_cost.gas_cost_max =
-                        OPEN_POSITION_COST_PER_TICK_LOG * ticks_len_log2 + OPEN_POSITION_COST_BASE;
-                }
-                #[cfg(any(feature = "multiversx", feature = "concordium"))]
-                {
-                    let ticks_len_log2 = u128::from(ticks_len.ilog2());
-                    tx_cost.gas_cost_max = Amount::from(
-                        OPEN_POSITION_COST_PER_TICK_LOG * ticks_len_log2 + OPEN_POSITION_COST_BASE,
-                    );
-                }
-            }
-        }
-
         Ok(EstimateAddLiquidityResult {
             min_a: min_amount_a,
             max_a: max_amount_a,
@@ -472,27 +586,655 @@ impl<T: Types, S: State<T>, SS: Borrow<S>> Estimations for Dex<T, S, SS> {
 
         #[cfg(feature = "near")]
         {
-            let mut tx_cost = TxCostEstimate {
-                gas_cost_max: CLOSE_POSITION_COST_BASE,
+            let tx_cost = TxCostEstimate {
+                gas_cost_max: self.close_position_gas_cost(position_id)?,
                 storage_fee_max: 0,
             };
 
-            let pos_info = self.get_position_info(position_id)?;
+            Ok(EstimateRemoveLiquidityResult { tx_cost })
+        }
+    }
+
+    fn estimate_actions_gas<E: WasmApi>(&self, actions: &[Action<E>]) -> Result<TxCostEstimate> {
+        let mut gas_cost_max = Amount::from(0u16);
+
+        for action in actions {
+            let action_gas_cost = match action {
+                Action::OpenPosition {
+                    tokens, fee_rate, ..
+                } => {
+                    #[allow(clippy::cast_possible_truncation)]
+                    let fee_level: FeeLevel = fee_rates_ticks()
+                        .iter()
+                        .position(|r| r == fee_rate)
+                        .ok_or_else(|| error_here!(ErrorKind::IllegalFee))?
+                        as FeeLevel;
+                    self.open_position_gas_cost(tokens.clone(), fee_level)?
+                }
+                #[cfg(feature = "near")]
+                Action::ClosePosition(position_id) => self.close_position_gas_cost(*position_id)?,
+                // No cost constants were measured for other actions yet
+                _ => return Err(error_here!(ErrorKind::GasCostUnknown)),
+            };
+            gas_cost_max = gas_cost_max
+                .checked_add(action_gas_cost)
+                .ok_or_else(|| error_here!(ErrorKind::ConvOverflow))?;
+        }
+
+        Ok(TxCostEstimate {
+            gas_cost_max,
+            storage_fee_max: Amount::from(0u16),
+        })
+    }
+
+    /// Evaluate the smallest exact-in swap amount which yields non-zero amount-out,
+    /// given the current state of the pool.
+    ///
+    /// Smaller amounts are rejected by exact-in swap with `SwapAmountTooSmall`.
+    fn min_effective_swap_in(&self, token_in: TokenId, token_out: TokenId) -> Result<Amount> {
+        let (pool_id, swapped) =
+            PoolId::try_from_pair((token_in, token_out)).map_err(|e| error_here!(e))?;
+        let direction = if swapped { Side::Right } else { Side::Left };
+
+        let contract = self.contract().as_ref();
 
-            if let Some(ticks_len) = self.get_pool_ticks(pos_info.tokens_ids, pos_info.fee_level) {
-                if ticks_len > 0 {
-                    let ticks_len_log2 = u128::from(ticks_len.ilog2());
+        contract.pools.try_inspect(&pool_id, |pool| {
+            // Simulate swap on a throw-away overlay; `Ok(false)` if amount-out rounds to zero
+            let yields_amount_out = |amount_in: Amount| -> Result<bool> {
+                let mut pool = PoolStateOverlay::<T>::from(pool);
+                match pool.swap_exact_in(
+                    direction,
+                    amount_in,
+                    contract.swap_protocol_fee_fraction(),
+                    contract.swap_max_underpay,
+                ) {
+                    Ok(_) => Ok(true),
+                    Err(e) if matches!(e.kind, ErrorKind::SwapAmountTooSmall) => Ok(false),
+                    Err(e) => Err(e),
+                }
+            };
 
-                    tx_cost.gas_cost_max = CLOSE_POSITION_COST_PER_TICK_LOG * ticks_len_log2
-                        + CLOSE_POSITION_COST_BASE;
+            // Find upper bound by doubling the amount; `lower` always fails, `upper` always succeeds
+            let mut lower = Amount::from(0u16);
+            let mut upper = Amount::from(1u16);
+            while !yields_amount_out(upper)? {
+                lower = upper;
+                upper = upper
+                    .checked_add(upper)
+                    .ok_or_else(|| error_here!(ErrorKind::SwapAmountTooLarge))?;
+            }
+            // Bisect
+            while upper - lower > Amount::from(1u16) {
+                let middle = lower + (upper - lower) / Amount::from(2u16);
+                if yields_amount_out(middle)? {
+                    upper = middle;
+                } else {
+                    lower = middle;
                 }
             }
+            Ok(upper)
+        })?
+    }
 
-            Ok(EstimateRemoveLiquidityResult { tx_cost })
+    /// Evaluate exact-in swap outputs for a series of input amounts.
+    ///
+    /// Each amount is swapped independently against the current state of the pool.
+    /// Number of amounts is limited by `MAX_QUOTE_LADDER_LEN`.
+    fn quote_ladder(
+        &self,
+        token_in: TokenId,
+        token_out: TokenId,
+        amounts: &[Amount],
+    ) -> Result<Vec<Amount>> {
+        ensure_here!(
+            amounts.len() <= MAX_QUOTE_LADDER_LEN,
+            ErrorKind::InvalidParams
+        );
+        let (pool_id, swapped) =
+            PoolId::try_from_pair((token_in, token_out)).map_err(|e| error_here!(e))?;
+        let direction = if swapped { Side::Right } else { Side::Left };
+
+        let contract = self.contract().as_ref();
+
+        contract.pools.try_inspect(&pool_id, |pool| {
+            amounts
+                .iter()
+                .map(|&amount_in| {
+                    let mut pool = PoolStateOverlay::<T>::from(pool);
+                    let (_, amount_out, _) = pool.swap_exact_in(
+                        direction,
+                        amount_in,
+                        contract.swap_protocol_fee_fraction(),
+                        contract.swap_max_underpay,
+                    )?;
+                    Ok(amount_out)
+                })
+                .collect()
+        })?
+    }
+
+    /// Evaluate amount-in required to receive exactly `amount_out` in a swap,
+    /// without modifying the pool.
+    fn quote_exact_out(
+        &self,
+        token_in: TokenId,
+        token_out: TokenId,
+        amount_out: Amount,
+    ) -> Result<Amount> {
+        let (pool_id, swapped) =
+            PoolId::try_from_pair((token_in, token_out)).map_err(|e| error_here!(e))?;
+        let direction = if swapped { Side::Right } else { Side::Left };
+
+        let contract = self.contract().as_ref();
+
+        contract.pools.try_inspect(&pool_id, |pool| {
+            ensure_here!(pool.is_spot_price_set(), ErrorKind::PoolHasNoLiquidity);
+            let available_out: AmountUFP = pool
+                .position_reserves()
+                .into_iter()
+                .map(|position_reserves_at_level| position_reserves_at_level[direction.opposite()])
+                .sum();
+            ensure_here!(
+                AmountUFP::from(amount_out) < available_out,
+                ErrorKind::InsufficientLiquidity
+            );
+
+            let mut pool = PoolStateOverlay::<T>::from(pool);
+            let (amount_in, _, _) = pool
+                .swap_exact_out(
+                    direction,
+                    amount_out,
+                    contract.swap_protocol_fee_fraction(),
+                    contract.swap_max_underpay,
+                )
+                .map_err(|e| match e.kind {
+                    // Swap reaches the price extreme before `amount_out` is collected
+                    ErrorKind::PriceTickOutOfBounds | ErrorKind::PriceTickOutOfBoundsAt(_) => {
+                        error_here!(ErrorKind::InsufficientLiquidity)
+                    }
+                    _ => e,
+                })?;
+            Ok(amount_in)
+        })?
+    }
+
+    /// Evaluate average execution price of swapping exactly `amount_in`, without modifying the pool.
+    /// Price is expressed as `amount_in / amount_out`, same as `swap_price` of `estimate_swap_exact`.
+    /// Fails with `InsufficientLiquidity` if pool can't complete the swap.
+    fn get_average_execution_price(
+        &self,
+        token_in: TokenId,
+        token_out: TokenId,
+        amount_in: Amount,
+    ) -> Result<Float> {
+        let (pool_id, swapped) =
+            PoolId::try_from_pair((token_in, token_out)).map_err(|e| error_here!(e))?;
+        let direction = if swapped { Side::Right } else { Side::Left };
+
+        let contract = self.contract().as_ref();
+
+        contract.pools.try_inspect(&pool_id, |pool| {
+            let mut pool = PoolStateOverlay::<T>::from(pool);
+            let (amount_in, amount_out, _) = pool
+                .swap_exact_in(
+                    direction,
+                    amount_in,
+                    contract.swap_protocol_fee_fraction(),
+                    contract.swap_max_underpay,
+                )
+                .map_err(|e| match e.kind {
+                    // Swap reaches the price extreme before `amount_in` is spent
+                    ErrorKind::PriceTickOutOfBounds | ErrorKind::PriceTickOutOfBoundsAt(_) => {
+                        error_here!(ErrorKind::InsufficientLiquidity)
+                    }
+                    _ => e,
+                })?;
+            ensure_here!(!amount_out.is_zero(), ErrorKind::InsufficientLiquidity);
+            Ok(Float::from(amount_in) / Float::from(amount_out))
+        })?
+    }
+
+    /// Evaluate the position which would set the spot price of a new pool to `target_price`.
+    ///
+    /// `target_price` follows the same convention as `user_price` in `estimate_liq_add`,
+    /// and must lie strictly within `ticks_range`. `amount` is the amount of the token
+    /// on `side` to be deposited, while the amount of the other token is evaluated.
+    ///
+    /// Returns amounts of both tokens and net liquidity of the position.
+    fn liquidity_for_target_price(
+        &self,
+        tokens: (TokenId, TokenId),
+        fee_rate: BasisPoints,
+        ticks_range: (Option<i32>, Option<i32>),
+        target_price: Float,
+        side: Side,
+        amount: Amount,
+    ) -> Result<(Amount, Amount, Liquidity)> {
+        PoolId::try_from_pair(tokens).map_err(|e| error_here!(e))?;
+
+        #[allow(clippy::cast_possible_truncation)]
+        let fee_level: FeeLevel = fee_rates_ticks()
+            .iter()
+            .position(|&r| r == fee_rate)
+            .ok_or_else(|| error_here!(ErrorKind::IllegalFee))?
+            as FeeLevel;
+
+        let ticks_range = Tick::unwrap_range(ticks_range).map_err(|e| error_here!(e))?;
+
+        // Also rejects NaN and negative prices
+        let target_sqrtprice = target_price.sqrt();
+        ensure_here!(
+            ticks_range.0.spot_sqrtprice() < target_sqrtprice
+                && target_sqrtprice < ticks_range.1.spot_sqrtprice(),
+            ErrorKind::InvalidParams
+        );
+
+        let target_eff_sqrtprices = EffSqrtprices::from_value(
+            eff_sqrtprice_from_spot_sqrtprice(target_sqrtprice, fee_level),
+            Side::Left,
+            fee_level,
+            None,
+        )
+        .map_err(|e| error_here!(e))?;
+
+        let max_amounts = match side {
+            Side::Left => (amount, Amount::MAX),
+            Side::Right => (Amount::MAX, amount),
+        };
+
+        let (amount_a, amount_b, _, net_liquidity) = self
+            .evaluate_open_position_at_eff_sqrtprices(
+                fee_rate,
+                ticks_range,
+                max_amounts.0,
+                max_amounts.1,
+                target_eff_sqrtprices,
+            )?;
+
+        Ok((amount_a, amount_b, net_liquidity))
+    }
+
+    /// Apply a sequence of hypothetical operations to a snapshot of the pool,
+    /// without modifying the actual state. If pool doesn't exist, an empty one is used.
+    ///
+    /// Positions opened during simulation get identifiers starting from the next free one,
+    /// so they may be referred to by subsequent `SimOp::Close`.
+    /// Ownership of closed positions is not checked.
+    fn simulate(&self, tokens: (TokenId, TokenId), ops: &[SimOp]) -> Result<SimResult> {
+        let (pool_id, swapped) = PoolId::try_from_pair(tokens).map_err(|e| error_here!(e))?;
+
+        let contract = self.contract().as_ref();
+        let (protocol_fee_fraction, max_underpay, next_position_id) = (
+            contract.swap_protocol_fee_fraction(),
+            contract.swap_max_underpay,
+            contract.next_free_position_id,
+        );
+
+        if let Ok(result) = contract.pools.try_inspect(&pool_id, |pool| {
+            simulate_ops(
+                &mut PoolStateOverlay::from(pool),
+                ops,
+                swapped,
+                protocol_fee_fraction,
+                max_underpay,
+                next_position_id,
+            )
+        }) {
+            result
+        } else {
+            simulate_ops(
+                &mut PoolStateOverlay::<T>::default(),
+                ops,
+                swapped,
+                protocol_fee_fraction,
+                max_underpay,
+                next_position_id,
+            )
+        }
+    }
+
+    /// Estimate zapping `amount` of `token_have` into a position, i.e. swapping part of it
+    /// into `token_other` and opening a position with both tokens.
+    ///
+    /// Swap amount is chosen as the largest one whose output is fully deposited.
+    /// If the range lies entirely on one side of the current price, either nothing
+    /// or everything is swapped. Pool must exist.
+    ///
+    /// Swap output which isn't deposited due to rounding is not included into `leftover`.
+    fn estimate_zap_in(
+        &self,
+        token_have: TokenId,
+        token_other: TokenId,
+        fee_rate: BasisPoints,
+        ticks_range: (Option<i32>, Option<i32>),
+        amount: Amount,
+    ) -> Result<ZapEstimate> {
+        let (pool_id, swapped) =
+            PoolId::try_from_pair((token_have, token_other)).map_err(|e| error_here!(e))?;
+        let direction = if swapped { Side::Right } else { Side::Left };
+
+        #[allow(clippy::cast_possible_truncation)]
+        let fee_level: FeeLevel = fee_rates_ticks()
+            .iter()
+            .position(|&r| r == fee_rate)
+            .ok_or_else(|| error_here!(ErrorKind::IllegalFee))?
+            as FeeLevel;
+
+        let contract = self.contract().as_ref();
+        let (protocol_fee_fraction, max_underpay, position_id) = (
+            contract.swap_protocol_fee_fraction(),
+            contract.swap_max_underpay,
+            contract.next_free_position_id,
+        );
+
+        contract.pools.try_inspect(&pool_id, |pool| {
+            // Swap `swap_amount` and open position with everything at hand, on a fresh overlay.
+            // Returns estimate along with the swap output.
+            let evaluate = |swap_amount: Amount| -> Result<(ZapEstimate, Amount)> {
+                let mut pool = PoolStateOverlay::<T>::from(pool);
+                let amount_out = if swap_amount.is_zero() {
+                    Amount::zero()
+                } else {
+                    pool.swap_exact_in(direction, swap_amount, protocol_fee_fraction, max_underpay)?
+                        .1
+                };
+                let position = PositionInit {
+                    amount_ranges: (
+                        Range {
+                            min: Amount::zero().into(),
+                            max: (amount - swap_amount).into(),
+                        },
+                        Range {
+                            min: Amount::zero().into(),
+                            max: amount_out.into(),
+                        },
+                    ),
+                    ticks_range,
+                };
+                let PositionOpenedInfo {
+                    deposited_amounts,
+                    net_liquidity,
+                    ..
+                } = pool.open_position(
+                    position.transpose_if(swapped),
+                    fee_level,
+                    position_id,
+                    &mut OverlayItemFactory::new(),
+                )?;
+                let deposited = swap_if(swapped, deposited_amounts);
+                Ok((
+                    ZapEstimate {
+                        swap_amount,
+                        position_net_liquidity: Float::from(net_liquidity),
+                        deposited,
+                        leftover: amount - swap_amount - deposited.0,
+                    },
+                    amount_out,
+                ))
+            };
+
+            // Range lies on the `token_have` side of the price
+            if let Ok((estimate, _)) = evaluate(Amount::zero()) {
+                return Ok(estimate);
+            }
+            // Range lies on the `token_other` side of the price
+            if let Ok((estimate, _)) = evaluate(amount) {
+                return Ok(estimate);
+            }
+
+            // Range spans the price: bisect for the largest swap amount
+            // whose output is deposited up to rounding
+            let one = Amount::from(1u16);
+            let (mut low, mut high) = (Amount::zero(), amount);
+            let mut best = None;
+            while high - low > one {
+                let mid = low + (high - low) / Amount::from(2u16);
+                match evaluate(mid) {
+                    Ok((estimate, amount_out)) => {
+                        if amount_out - estimate.deposited.1 > one {
+                            // `token_have` is exhausted first, too much was swapped
+                            high = mid;
+                        } else {
+                            low = mid;
+                            best = Some(estimate);
+                        }
+                    }
+                    // Too little was swapped to deposit anything
+                    Err(Error {
+                        kind: ErrorKind::Slippage,
+                        ..
+                    }) => low = mid,
+                    Err(e) => return Err(e),
+                }
+            }
+            best.ok_or_else(|| error_here!(ErrorKind::SwapAmountTooSmall))
+        })?
+    }
+
+    fn preview_open_position(
+        &self,
+        account: &AccountId,
+        token_a: &TokenId,
+        token_b: &TokenId,
+        fee_rate: BasisPoints,
+        position: PositionInit,
+    ) -> Result<(Amount, Amount, Liquidity)> {
+        let (pool_id, swapped) = PoolId::try_from_pair((token_a.clone(), token_b.clone()))
+            .map_err(|e| error_here!(e))?;
+
+        #[allow(clippy::cast_possible_truncation)]
+        let fee_level: FeeLevel = fee_rates_ticks()
+            .iter()
+            .position(|&r| r == fee_rate)
+            .ok_or_else(|| error_here!(ErrorKind::IllegalFee))?
+            as FeeLevel;
+
+        let contract = self.contract().as_ref();
+        let position = position.transpose_if(swapped);
+        let position_id = contract.next_free_position_id;
+        let open = |pool: &mut PoolStateOverlay<'_, T>| {
+            pool.open_position(
+                position.clone(),
+                fee_level,
+                position_id,
+                &mut OverlayItemFactory::new(),
+            )
+        };
+        let PositionOpenedInfo {
+            deposited_amounts,
+            net_liquidity,
+            ..
+        } = match contract
+            .pools
+            .inspect(&pool_id, |pool| open(&mut PoolStateOverlay::from(pool)))
+        {
+            Some(opened) => opened?,
+            None => open(&mut PoolStateOverlay::default())?,
+        };
+
+        // Opening fee is charged in configured token, which may be one of pool tokens
+        let mut deducted = (deposited_amounts.0, deposited_amounts.1);
+        let mut fee_deducted = None;
+        if let Some((fee_token, fee_amount)) = contract.open_position_fee {
+            if fee_token == &pool_id.0 {
+                deducted.0 += *fee_amount;
+            } else if fee_token == &pool_id.1 {
+                deducted.1 += *fee_amount;
+            } else {
+                fee_deducted = Some((fee_token, *fee_amount));
+            }
         }
+        contract.accounts.try_inspect(account, |acc| {
+            let acc = acc.as_ref();
+            for (token_id, amount) in [(&pool_id.0, deducted.0), (&pool_id.1, deducted.1)]
+                .into_iter()
+                .chain(fee_deducted)
+            {
+                let balance = acc
+                    .token_balances
+                    .inspect(token_id, |balance| *balance)
+                    .unwrap_or_default();
+                ensure_here!(balance >= amount, ErrorKind::NotEnoughTokens);
+            }
+            Ok(())
+        })??;
+
+        let deducted = swap_if(swapped, deducted);
+        Ok((deducted.0, deducted.1, net_liquidity))
+    }
+
+    fn preview_full_range_position(
+        &self,
+        tokens: (TokenId, TokenId),
+        fee_rate: BasisPoints,
+        amount_a: Option<Amount>,
+        amount_b: Option<Amount>,
+    ) -> Result<(Amount, Amount, Liquidity)> {
+        const FULL_RANGE: (Option<i32>, Option<i32>) = (None, None);
+
+        #[allow(clippy::cast_possible_truncation)]
+        let fee_level: FeeLevel = fee_rates_ticks()
+            .iter()
+            .position(|&r| r == fee_rate)
+            .ok_or_else(|| error_here!(ErrorKind::IllegalFee))?
+            as FeeLevel;
+
+        let (max_amount_a, max_amount_b) = match (amount_a, amount_b) {
+            (Some(amount_a), Some(amount_b)) => (amount_a, amount_b),
+            (None, None) => return Err(error_here!(ErrorKind::SwapAmountTooSmall)),
+            (amount_a, amount_b) => {
+                let pool_info = self
+                    .get_pool_info(tokens.clone())?
+                    .ok_or_else(|| error_here!(ErrorKind::PoolNotRegistered))?;
+                ensure_here!(
+                    !pool_info.spot_sqrtprices[fee_level as usize].is_zero(),
+                    ErrorKind::PoolHasNoLiquidity
+                );
+                // Missing amount is limited only by the specified one, through spot price
+                let (amount_a, amount_b, ..) = self.evaluate_open_position(
+                    &tokens,
+                    fee_rate,
+                    FULL_RANGE,
+                    amount_a.unwrap_or(Amount::MAX),
+                    amount_b.unwrap_or(Amount::MAX),
+                )?;
+                (amount_a, amount_b)
+            }
+        };
+
+        let (amount_a, amount_b, _, net_liquidity, _) =
+            self.evaluate_open_position(&tokens, fee_rate, FULL_RANGE, max_amount_a, max_amount_b)?;
+        Ok((amount_a, amount_b, net_liquidity))
+    }
+
+    fn estimate_close_position_impact(
+        &self,
+        position_id: PositionId,
+    ) -> Result<ClosePositionImpact> {
+        let contract = self.contract().as_ref();
+        let pool_id = contract
+            .position_to_pool_id
+            .try_inspect(&position_id, Clone::clone)?;
+
+        contract.pools.try_inspect_or(
+            &pool_id,
+            // Inconsistent state: position is present in `position_to_pool_id`,
+            // but the pool doesn't exist
+            ErrorKind::InternalLogicError,
+            |pool| {
+                let fee_level = pool.get_position_info(&pool_id, position_id)?.fee_level;
+                let mut pool = PoolStateOverlay::<T>::from(pool);
+
+                let spot_price_before = pool.spot_price(Side::Left, fee_level);
+                let liquidity_before = pool.liquidity(fee_level);
+                pool.withdraw_fee_and_close_position(position_id)?;
+
+                Ok(ClosePositionImpact {
+                    spot_price_before,
+                    spot_price_after: pool.spot_price(Side::Left, fee_level),
+                    liquidity_before,
+                    liquidity_after: pool.liquidity(fee_level),
+                })
+            },
+        )?
     }
 }
 
+/// Apply operations to pool overlay; see `Estimations::simulate`
+fn simulate_ops<T: Types>(
+    pool: &mut PoolStateOverlay<'_, T>,
+    ops: &[SimOp],
+    swapped: bool,
+    protocol_fee_fraction: BasisPoints,
+    max_underpay: Float,
+    mut next_position_id: PositionId,
+) -> Result<SimResult> {
+    let mut factory = OverlayItemFactory::new();
+    let mut outputs = Vec::with_capacity(ops.len());
+
+    for op in ops {
+        let output = match op {
+            SimOp::Open { fee_rate, position } => {
+                #[allow(clippy::cast_possible_truncation)]
+                let fee_level: FeeLevel = fee_rates_ticks()
+                    .iter()
+                    .position(|r| r == fee_rate)
+                    .ok_or_else(|| error_here!(ErrorKind::IllegalFee))?
+                    as FeeLevel;
+
+                let position_id = next_position_id;
+                next_position_id += 1;
+
+                let PositionOpenedInfo {
+                    deposited_amounts,
+                    net_liquidity,
+                    ..
+                } = pool.open_position(
+                    position.clone().transpose_if(swapped),
+                    fee_level,
+                    position_id,
+                    &mut factory,
+                )?;
+                SimOpResult::Open {
+                    position_id,
+                    deposited_amounts: swap_if(swapped, deposited_amounts),
+                    net_liquidity: Float::from(net_liquidity),
+                }
+            }
+            SimOp::Swap { side, kind, amount } => {
+                let side = if swapped { side.opposite() } else { *side };
+                let (amount_in, amount_out, _) = pool.swap(
+                    side,
+                    *kind,
+                    *amount,
+                    protocol_fee_fraction,
+                    max_underpay,
+                    None,
+                )?;
+                SimOpResult::Swap {
+                    amount_in,
+                    amount_out,
+                }
+            }
+            SimOp::Close { position_id } => {
+                let PositionClosedInfo { balance, fees, .. } =
+                    pool.withdraw_fee_and_close_position(*position_id)?;
+                SimOpResult::Close {
+                    balance: swap_if(swapped, balance),
+                    fees: swap_if(swapped, fees),
+                }
+            }
+        };
+        outputs.push(output);
+    }
+
+    let side = if swapped { Side::Right } else { Side::Left };
+    Ok(SimResult {
+        pool_info: pool.pool_info(side)?,
+        outputs,
+    })
+}
+
 // Utility methods mixins
 trait EstimationUtils {
     fn evaluate_open_position_at_eff_sqrtprices(
@@ -502,7 +1244,7 @@ trait EstimationUtils {
         max_amount_a: Amount,
         max_amount_b: Amount,
         eff_sqrtprices: EffSqrtprices,
-    ) -> Result<(Amount, Amount, Float)>;
+    ) -> Result<(Amount, Amount, Float, Liquidity)>;
 
     fn evaluate_open_position(
         &self,
@@ -523,7 +1265,7 @@ impl<T: Types, S: State<T>, SS: Borrow<S>> EstimationUtils for Dex<T, S, SS> {
         max_amount_a: Amount,
         max_amount_b: Amount,
         eff_sqrtprices: EffSqrtprices,
-    ) -> Result<(Amount, Amount, Float)> {
+    ) -> Result<(Amount, Amount, Float, Liquidity)> {
         let mut factory = OverlayItemFactory::new();
 
         let fee_rates = fee_rates_ticks();
@@ -564,7 +1306,9 @@ impl<T: Types, S: State<T>, SS: Borrow<S>> EstimationUtils for Dex<T, S, SS> {
         pool::PoolState::set_active_side(&mut pool, Side::Left);
 
         let PositionOpenedInfo {
-            deposited_amounts, ..
+            deposited_amounts,
+            net_liquidity,
+            ..
         } = pool.open_position(
             PositionInit {
                 amount_ranges: (
@@ -584,7 +1328,12 @@ impl<T: Types, S: State<T>, SS: Borrow<S>> EstimationUtils for Dex<T, S, SS> {
             &mut factory,
         )?;
         let spot_price = pool.spot_price(Side::Left, fee_level);
-        Ok((deposited_amounts.0, deposited_amounts.1, spot_price))
+        Ok((
+            deposited_amounts.0,
+            deposited_amounts.1,
+            spot_price,
+            net_liquidity,
+        ))
     }
 
     #[allow(clippy::too_many_lines)]
@@ -643,7 +1392,7 @@ impl<T: Types, S: State<T>, SS: Borrow<S>> EstimationUtils for Dex<T, S, SS> {
                 },
                 spot_price,
                 expected_eff_sqrtprices,
-            ) = if let Ok(result) = contract.pools.try_inspect(&pool_id, |Pool::V0(ref pool)| {
+            ) = if let Ok(result) = contract.pools.try_inspect(&pool_id, |pool| {
                 let mut pool = PoolStateOverlay::from(pool);
 
                 let result = pool.open_position(pos_clone, fee_level, position_id, &mut factory)?;
@@ -54,4 +54,52 @@ impl<T: Types> dex::ItemFactory<T> for OverlayItemFactory {
     fn new_guards(&mut self) -> T::AccountIdSet {
         unimplemented!()
     }
+
+    fn new_reference_prices_map(&mut self) -> T::ReferencePricesMap {
+        unimplemented!()
+    }
+
+    fn new_token_decimals_map(&mut self) -> T::TokenDecimalsMap {
+        unimplemented!()
+    }
+
+    fn new_token_verified_at_map(&mut self) -> T::TokenVerifiedAtMap {
+        unimplemented!()
+    }
+
+    fn new_operators(&mut self) -> T::AccountIdSet {
+        unimplemented!()
+    }
+
+    fn new_accounted_balances_map(&mut self) -> T::AccountedBalancesMap {
+        unimplemented!()
+    }
+
+    fn new_paused_pools(&mut self) -> T::PoolIdSet {
+        unimplemented!()
+    }
+
+    fn new_position_owners_map(&mut self) -> T::PositionOwnersMap {
+        unimplemented!()
+    }
+
+    fn new_tick_positions_map(&mut self) -> T::TickPositionsMap {
+        unimplemented!()
+    }
+
+    fn new_accounted_balances_init_accounts(&mut self) -> T::AccountIdSet {
+        unimplemented!()
+    }
+
+    fn new_accounted_balances_init_pools(&mut self) -> T::PoolIdSet {
+        unimplemented!()
+    }
+
+    fn new_collected_open_position_fees_map(&mut self) -> T::AccountedBalancesMap {
+        unimplemented!()
+    }
+
+    fn new_gc_reward_funds_map(&mut self) -> T::AccountedBalancesMap {
+        unimplemented!()
+    }
 }
@@ -8,7 +8,7 @@ use crate::{
         pool::{as_fee_level, one_over_sqrt_one_minus_fee_rate, PoolState},
         traits,
         v0::{EffSqrtprices, RawFeeLevelsArray},
-        EffTick, ErrorKind, FeeLevel, PoolV0, Position, PositionId, Result, Side, Tick, TickState,
+        EffTick, ErrorKind, FeeLevel, Pool, Position, PositionId, Result, Side, Tick, TickState,
     },
     ensure_here, error_here, Amount, AmountUFP, Float, LPFeePerFeeLiquidity, Liquidity,
     NetLiquiditySFP, NetLiquidityUFP,
@@ -58,6 +58,10 @@ where
     /// effective sqrtprice in the opposite direction.
     /// See `eff_sqrtprice_opposite_side` for details.
     pub pivot: EffTick,
+    /// Block timestamp of the last swap in this pool, in seconds
+    pub last_trade_ts: u64,
+    /// Square root of spot price, as seen from left side, right after the last swap
+    pub last_trade_sqrtprice: Float,
 }
 
 impl<'a, T: traits::Types> Default for PoolStateOverlay<'a, T> {
@@ -76,38 +80,50 @@ impl<'a, T: traits::Types> Default for PoolStateOverlay<'a, T> {
             top_active_level: FeeLevel::default(),
             active_side: Side::default(),
             pivot: EffTick::default(),
+            last_trade_ts: 0,
+            last_trade_sqrtprice: Float::zero(),
         }
     }
 }
 
-impl<'a, T: traits::Types> From<&'a PoolV0<T>> for PoolStateOverlay<'a, T> {
-    fn from(pool: &'a PoolV0<T>) -> Self {
-        let positions: &T::PoolPositionsMap = &pool.positions;
+impl<'a, T: traits::Types> From<&'a Pool<T>> for PoolStateOverlay<'a, T> {
+    fn from(pool: &'a Pool<T>) -> Self {
+        let positions: &T::PoolPositionsMap = pool.positions();
+        let tick_states = pool.tick_states();
         let tick_states_refs: RawFeeLevelsArray<&T::TickStatesMap> = [
-            &pool.tick_states[0],
-            &pool.tick_states[1],
-            &pool.tick_states[2],
-            &pool.tick_states[3],
-            &pool.tick_states[4],
-            &pool.tick_states[5],
-            &pool.tick_states[6],
-            &pool.tick_states[7],
+            &tick_states[0],
+            &tick_states[1],
+            &tick_states[2],
+            &tick_states[3],
+            &tick_states[4],
+            &tick_states[5],
+            &tick_states[6],
+            &tick_states[7],
         ];
+        let (last_trade_ts, last_trade_sqrtprice) = pool.last_trade();
 
         Self {
             positions: OrderedOverlayMap::new(positions),
             tick_states: tick_states_refs.map(OrderedOverlayMap::new),
-            acc_lp_fee: pool.acc_lp_fee,
-            total_reserves: pool.total_reserves,
-            acc_lp_fees_per_fee_liquidity: pool.acc_lp_fees_per_fee_liquidity.into(),
-            active_side: pool.active_side,
-            eff_sqrtprices: pool.eff_sqrtprices.into(),
-            net_liquidities: pool.net_liquidities.into(),
-            next_active_ticks_left: pool.next_active_ticks_left.into(),
-            next_active_ticks_right: pool.next_active_ticks_right.into(),
-            pivot: pool.pivot,
-            position_reserves: pool.position_reserves.into(),
-            top_active_level: pool.top_active_level,
+            acc_lp_fee: pool.acc_lp_fees(),
+            total_reserves: pool.total_reserves(),
+            acc_lp_fees_per_fee_liquidity: array_init(|level| {
+                pool.acc_lp_fees_per_fee_liquidity_at(as_fee_level(level))
+            }),
+            active_side: pool.active_side(),
+            eff_sqrtprices: pool.eff_sqrtprices(),
+            net_liquidities: array_init(|level| pool.net_liquidity_at(as_fee_level(level))),
+            next_active_ticks_left: array_init(|level| {
+                pool.next_active_tick(as_fee_level(level), Side::Left)
+            }),
+            next_active_ticks_right: array_init(|level| {
+                pool.next_active_tick(as_fee_level(level), Side::Right)
+            }),
+            pivot: pool.pivot(),
+            position_reserves: pool.position_reserves(),
+            top_active_level: pool.top_active_level(),
+            last_trade_ts,
+            last_trade_sqrtprice,
         }
     }
 }
@@ -183,6 +199,10 @@ impl<'a, T: traits::Types> PoolState<T> for PoolStateOverlay<'a, T> {
         self.pivot = pivot;
     }
 
+    fn last_trade(&self) -> (u64, Float) {
+        (self.last_trade_ts, self.last_trade_sqrtprice)
+    }
+
     fn position_reserves(&self) -> RawFeeLevelsArray<(AmountUFP, AmountUFP)> {
         self.position_reserves
     }
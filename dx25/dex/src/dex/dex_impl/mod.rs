@@ -4,29 +4,41 @@ pub mod estimations;
 use super::errors::{ErrorKind, Result};
 use super::traits::AccountExtra;
 use super::util_types::{PoolId, Side};
-use super::utils::swap_if;
+use super::utils::{checked_sum, swap_if};
 use super::{
-    state_types, Account, AccountLatest, AccountV0, AccountWithdrawTracker, Action, BasisPoints,
-    DepositPayment, EstimateSwapExactResult, FeeLevel, ItemFactory, Logger, Map, MapRemoveKey,
-    Pool, PoolInfo, PoolV0, PositionClosedInfo, PositionId, PositionInfo, PositionInit,
-    PositionOpenedInfo, Range, Set, State, StateMembersMut, StateMut, SwapAction, SwapKind,
-    SwapToPriceAction, Tick, Types, VersionInfo, BASIS_POINT_DIVISOR,
+    state_types, Account, AccountLatest, AccountWithdrawTracker, Action, AdminConfig, BasisPoints,
+    DepositPayment, EstimateSwapExactResult, FeeLevel, FeeSchedule, IdempotencyKey, ItemFactory,
+    Logger, Map, MapRemoveKey, PoolInfo, PoolLatest, PositionClosedInfo, PositionId, PositionInfo,
+    PositionInit, PositionOpenedInfo, Range, Set, SlippageTolerance, State, StateMembersMut,
+    StateMut, SwapAction, SwapKind, SwapToPriceAction, Tick, Types, VersionInfo, WithdrawMode,
+    ACCOUNT_EXPORT_VERSION, BASIS_POINT_DIVISOR, DEFAULT_OBSERVATION_CARDINALITY,
+    EVENTS_POOL_STATE, EVENTS_SWAP, MAX_ADMIN_CONFIG_GUARDS, MAX_FEE_HARVEST_BATCH,
+    MAX_FEE_RANKING_SCAN, MAX_IDEMPOTENCY_KEYS, MAX_NOTIFIED_POSITIONS_PER_TICK,
+    MAX_OBSERVATION_CARDINALITY, MAX_PROTOCOL_FEE_FRACTION, MAX_TICKS_LIQUIDITY_PAGE,
+    MAX_TOP_POOLS_SCAN,
+};
+#[cfg(feature = "smart-routing")]
+use super::{MAX_ROUTE_HOPS, MAX_ROUTE_POOLS_SCAN, MAX_TOKEN_CONNECTIONS_SCAN};
+use crate::chain::wasm::WasmAmount;
+use crate::chain::{
+    AccountId, Amount, AmountUFP, LPFeePerFeeLiquidity, Liquidity, SqrtpriceUFP, TokenId,
 };
-use crate::chain::{AccountId, Amount, AmountUFP, Liquidity, TokenId};
 use crate::{dex, fp};
 use crate::{ensure_here, error_here, Float};
 use dex::latest::{FeeLevelsArray, RawFeeLevelsArray, NUM_FEE_LEVELS};
 use dex::map_with_context::MapWithContext;
-use dex::pool::pool_impl::{fee_rate_ticks, fee_rates_ticks, PoolImpl};
-use dex::pool::Pool as _;
-use dex::{validate_protocol_fee_fraction, PairExt, PoolUpdateReason};
+use dex::pool::pool_impl::{as_fee_level, fee_rate, fee_rate_ticks, fee_rates_ticks, PoolImpl};
+use dex::pool::{CrossedTicks, Pool as _, PoolState, SWAP_MAX_UNDERPAY_LIMIT};
+use dex::{validate_fee_level, validate_protocol_fee_fraction, PairExt, PoolUpdateReason};
 
 use array_init::array_init;
 use itertools::Itertools;
+use multiversx_sc_codec::{top_encode_to_vec_u8, TopDecode};
 #[allow(unused)] // Some impls use it, some don't
 use num_traits::{One, Zero};
 use std::borrow::{Borrow, BorrowMut};
 use std::cell::RefCell;
+use std::cmp::Ordering;
 use std::marker::PhantomData;
 use std::ops::{Deref, DerefMut};
 
@@ -35,6 +47,9 @@ use std::ops::{Deref, DerefMut};
 use super::Path;
 #[cfg(feature = "smart-routing")]
 use crate::chain::FixedPointBig;
+use estimations::Estimations as _;
+#[cfg(feature = "smart-routing")]
+use std::collections::HashMap;
 
 #[cfg(test)]
 mod tests;
@@ -94,9 +109,108 @@ struct AccountViewMut<'a, T: Types> {
     pool_count: &'a mut u64,
     next_free_position_id: &'a mut u64,
     position_to_pool_id: &'a mut state_types::PositionToPoolMap<T>,
+    position_owners: &'a mut Option<T::PositionOwnersMap>,
+    tick_positions: &'a mut Option<T::TickPositionsMap>,
+
+    oracle_guard: OracleGuard<'a, T>,
+    grace_guard: GraceGuard<'a, T>,
+    pause_guard: PauseGuard<'a, T>,
+    open_position_fee: Option<(TokenId, Amount)>,
+    collected_open_position_fees: &'a mut Option<T::AccountedBalancesMap>,
 
     item_factory: &'a mut dyn ItemFactory<T>,
     logger: &'a mut dyn Logger,
+    event_verbosity: u8,
+}
+
+/// Rejects swaps which leave pool's spot price too far from reference price,
+/// unless they move the price closer to reference
+struct OracleGuard<'a, T: Types> {
+    reference_prices: Option<&'a T::ReferencePricesMap>,
+    max_deviation_bp: Option<BasisPoints>,
+}
+
+impl<'a, T: Types> OracleGuard<'a, T> {
+    /// Relative deviation of pool's spot price from reference price,
+    /// or `None` if pool isn't subject to the check
+    fn price_deviation(&self, pool_id: &PoolId, pool: &PoolLatest<T>) -> Option<Float> {
+        let (Some(reference_prices), Some(_)) = (self.reference_prices, self.max_deviation_bp) else {
+            return None;
+        };
+        let reference_price = reference_prices.inspect(pool_id, |price| *price)?;
+        let spot_price = pool.spot_price(Side::Left, 0);
+        // Pool without liquidity has no meaningful price yet
+        if spot_price.is_zero() {
+            return None;
+        }
+        Some((spot_price / reference_price - Float::one()).abs())
+    }
+
+    /// Check pool's state after swap, given price deviation evaluated before it
+    fn ensure_within_deviation(
+        &self,
+        pool_id: &PoolId,
+        pool: &PoolLatest<T>,
+        deviation_before: Option<Float>,
+    ) -> Result<()> {
+        let (Some(max_deviation_bp), Some(deviation)) =
+            (self.max_deviation_bp, self.price_deviation(pool_id, pool)) else {
+            return Ok(());
+        };
+        let max_deviation = Float::from(max_deviation_bp) / Float::from(BASIS_POINT_DIVISOR);
+        let moved_toward_reference = deviation_before.map_or(false, |before| deviation < before);
+        ensure_here!(
+            deviation <= max_deviation || moved_toward_reference,
+            ErrorKind::OracleDeviationExceeded
+        );
+        Ok(())
+    }
+}
+
+/// Rejects swaps in pairs with tokens verified less than grace period ago
+struct GraceGuard<'a, T: Types> {
+    token_verified_at: Option<&'a T::TokenVerifiedAtMap>,
+    grace_secs: u64,
+    now: u64,
+}
+
+impl<'a, T: Types> GraceGuard<'a, T> {
+    fn ensure_out_of_grace(&self, pool_id: &PoolId) -> Result<()> {
+        let Some(token_verified_at) = self.token_verified_at else {
+            return Ok(());
+        };
+        if self.grace_secs == 0 {
+            return Ok(());
+        }
+        let (token_a, token_b) = pool_id.as_refs();
+        for token in [token_a, token_b] {
+            // Tokens verified before timestamps were tracked are never in grace period
+            if let Some(verified_at) = token_verified_at.inspect(token, |ts| *ts) {
+                ensure_here!(
+                    self.now.saturating_sub(verified_at) >= self.grace_secs,
+                    ErrorKind::TokenInGracePeriod
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Rejects swaps in pools paused by guards
+struct PauseGuard<'a, T: Types> {
+    paused_pools: Option<&'a T::PoolIdSet>,
+}
+
+impl<'a, T: Types> PauseGuard<'a, T> {
+    fn ensure_not_paused(&self, pool_id: &PoolId) -> Result<()> {
+        ensure_here!(
+            !self
+                .paused_pools
+                .map_or(false, |paused_pools| paused_pools.contains_item(pool_id)),
+            ErrorKind::PoolPaused
+        );
+        Ok(())
+    }
 }
 
 impl<T: Types, S: State<T>, SS: Borrow<S>> Dex<T, S, SS> {
@@ -104,11 +218,136 @@ impl<T: Types, S: State<T>, SS: Borrow<S>> Dex<T, S, SS> {
         self.contract()
             .as_ref()
             .accounts
-            .try_inspect(account, |Account::V0(ref acc)| {
+            .try_inspect(account, |acc| {
+                let acc = acc.as_ref();
                 acc.token_balances.try_inspect(token, |balance| *balance)
             })?
     }
 
+    /// Returns total amount of token accounted by contract state, i.e. sum of all account deposits
+    /// and reserves of all pools with this token, including collected fees
+    ///
+    /// Running total is kept per token, updated when tokens are deposited or sent out.
+    /// Contracts upgraded from earlier versions must call `init_accounted_balances` first,
+    /// otherwise fails with `AccountedBalancesNotTracked`
+    pub fn get_accounted_balance(&self, token_id: &TokenId) -> Result<Amount> {
+        let accounted_balances = self
+            .contract()
+            .as_ref()
+            .accounted_balances
+            .ok_or_else(|| error_here!(ErrorKind::AccountedBalancesNotTracked))?;
+        Ok(accounted_balances
+            .inspect(token_id, |balance| *balance)
+            .unwrap_or_default())
+    }
+
+    /// Returns ticks range `(tick_low, tick_high)` which spans approximately `band_bp`
+    /// below and above current spot price of the pool on the specified fee level.
+    ///
+    /// Ticks are oriented according to order of `tokens`, same as for `open_position`.
+    /// Prices of returned ticks are nearest to `spot * (1 - band)` and `spot * (1 + band)`.
+    pub fn ticks_for_price_band(
+        &self,
+        tokens: (TokenId, TokenId),
+        fee_level: FeeLevel,
+        band_bp: BasisPoints,
+    ) -> Result<(i32, i32)> {
+        validate_fee_level(fee_level).map_err(|e| error_here!(e))?;
+        ensure_here!(
+            0 < band_bp && band_bp < BASIS_POINT_DIVISOR,
+            ErrorKind::InvalidParams
+        );
+        let (pool_id, swapped) = PoolId::try_from_pair(tokens).map_err(|e| error_here!(e))?;
+        let side = if swapped { Side::Right } else { Side::Left };
+
+        let spot_sqrtprice = self
+            .contract()
+            .as_ref()
+            .pools
+            .try_inspect(&pool_id, |pool| {
+                ensure_here!(pool.is_spot_price_set(), ErrorKind::PoolHasNoLiquidity);
+                Ok(pool.spot_sqrtprice(side, fee_level))
+            })??;
+
+        let band = Float::from(band_bp) / Float::from(BASIS_POINT_DIVISOR);
+        let tick_low =
+            Tick::nearest_to_spot_sqrtprice(spot_sqrtprice * (Float::one() - band).sqrt());
+        let tick_high =
+            Tick::nearest_to_spot_sqrtprice(spot_sqrtprice * (Float::one() + band).sqrt());
+        ensure_here!(tick_low < tick_high, ErrorKind::InvalidParams);
+
+        Ok((tick_low.index(), tick_high.index()))
+    }
+
+    /// Returns whether account has any open positions; `false` for unregistered account
+    pub fn account_has_positions(&self, account: &AccountId) -> Result<bool> {
+        Ok(self.account_position_count(account)? > 0)
+    }
+
+    /// Returns number of created pools, including ones without liquidity left
+    pub fn get_pool_count(&self) -> u64 {
+        self.contract().as_ref().pool_count
+    }
+
+    /// Serialize whole state of `account_id` into versioned blob,
+    /// which can be restored with `import_account`.
+    ///
+    /// Blob contains `ACCOUNT_EXPORT_VERSION`, token balances, position identifiers,
+    /// withdraw tracker and remaining account fields. Positions themselves
+    /// are stored in pools and aren't exported.
+    pub fn export_account(&self, account_id: &AccountId) -> Result<Vec<u8>> {
+        self.contract()
+            .as_ref()
+            .accounts
+            .try_inspect(account_id, |account| {
+                let account = account.as_ref();
+                let token_balances = account
+                    .token_balances
+                    .iter()
+                    .map(|(token_id, amount)| (token_id.clone(), *amount))
+                    .collect_vec();
+                let position_ids = account
+                    .positions
+                    .iter()
+                    .map(|id| *id)
+                    .sorted()
+                    .collect_vec();
+
+                top_encode_to_vec_u8(&(
+                    ACCOUNT_EXPORT_VERSION,
+                    token_balances,
+                    position_ids,
+                    account.withdraw_tracker,
+                    account.extra,
+                    account.last_swap_ts,
+                    account.idempotency_keys,
+                    account.auto_unregister_empty,
+                ))
+                .map_err(|_| error_here!(ErrorKind::InvalidAccountExport))
+            })?
+    }
+
+    /// Returns flat fee charged when opening position, as token and amount,
+    /// see `set_open_position_fee`
+    pub fn get_open_position_fee(&self) -> Option<(TokenId, Amount)> {
+        self.contract().as_ref().open_position_fee.cloned()
+    }
+
+    /// Returns number of open positions over all pools
+    pub fn get_total_position_count(&self) -> u64 {
+        u64::try_from(self.contract().as_ref().position_to_pool_id.len()).unwrap_or(u64::MAX)
+    }
+
+    /// Returns number of account's open positions; zero for unregistered account
+    pub fn account_position_count(&self, account: &AccountId) -> Result<usize> {
+        Ok(self
+            .contract()
+            .as_ref()
+            .accounts
+            .inspect(account, |acc| acc.as_ref().positions.len())
+            .unwrap_or(0))
+    }
+
     pub fn get_pool_info(&self, tokens: (TokenId, TokenId)) -> Result<Option<PoolInfo>> {
         let (pool_id, swapped) = PoolId::try_from_pair(tokens).map_err(|e| error_here!(e))?;
         let side = if swapped { Side::Right } else { Side::Left };
@@ -116,7 +355,7 @@ impl<T: Types, S: State<T>, SS: Borrow<S>> Dex<T, S, SS> {
             .contract()
             .as_ref()
             .pools
-            .inspect(&pool_id, |Pool::V0(ref pool)| pool.pool_info(side))
+            .inspect(&pool_id, |pool| pool.pool_info(side))
             .transpose()?;
         Ok(result)
     }
@@ -124,520 +363,2193 @@ impl<T: Types, S: State<T>, SS: Borrow<S>> Dex<T, S, SS> {
     pub fn get_pool_infos(&self) -> Result<Vec<(PoolId, PoolInfo)>> {
         let mut infos = Vec::new();
         for (pool_id, pool) in self.contract().as_ref().pools.iter() {
-            let Pool::V0(ref pool) = &*pool;
+            let pool = &*pool;
             infos.push((pool_id.clone(), pool.pool_info(Side::Left)?));
         }
         Ok(infos)
     }
 
-    pub fn get_position_info(&self, position_id: u64) -> Result<PositionInfo> {
-        let contract = self.contract().as_ref();
-        contract
-            .position_to_pool_id
-            .try_inspect(&position_id, |pool_id| {
-                contract.pools.try_inspect(pool_id, |Pool::V0(ref pool)| {
-                    pool.get_position_info(pool_id, position_id)
-                })
-            })??
+    /// Returns up to `limit` pools with the largest total liquidity over all fee levels,
+    /// in descending order of liquidity.
+    ///
+    /// To bound the cost, only first `MAX_TOP_POOLS_SCAN` pools in storage order are inspected,
+    /// so with more pools the result may be incomplete.
+    pub fn get_top_pools_by_liquidity(&self, limit: usize) -> Result<Vec<(PoolId, Liquidity)>> {
+        let mut pools: Vec<(PoolId, Liquidity)> = self
+            .contract()
+            .as_ref()
+            .pools
+            .iter()
+            .take(MAX_TOP_POOLS_SCAN)
+            .map(|(pool_id, pool)| {
+                let pool = &*pool;
+                (pool_id.clone(), pool.liquidities().into_iter().sum())
+            })
+            .collect();
+        pools.sort_by(|(_, a), (_, b)| b.cmp(a));
+        pools.truncate(limit);
+        Ok(pools)
     }
 
-    pub fn get_positions_info(&self, position_ids: &[u64]) -> Vec<Option<PositionInfo>> {
+    /// Returns distinct pools in which `account` has positions, as pairs of tokens in pool order
+    pub fn get_account_pools(&self, account: &AccountId) -> Result<Vec<(TokenId, TokenId)>> {
         let contract = self.contract().as_ref();
 
-        position_ids
-            .iter()
-            .map(|position_id| {
-                contract
-                    .position_to_pool_id
-                    .try_inspect(position_id, |pool_id| {
-                        contract.pools.try_inspect(pool_id, |Pool::V0(ref pool)| {
-                            pool.get_position_info(pool_id, *position_id)
-                        })
-                    })
-                    .and_then(|position| position.and_then(|pool| pool))
-                    .ok()
-            })
-            .collect()
-    }
+        let mut position_ids: Vec<PositionId> = contract.accounts.try_inspect(account, |acc| {
+            let acc = acc.as_ref();
+            acc.positions.iter().map(|id| *id).collect()
+        })?;
+        position_ids.sort_unstable();
 
-    pub fn get_version(&self) -> VersionInfo {
-        VersionInfo {
-            version: env!("DEX_CORE_VERSION").to_string(),
+        let mut pool_ids = Vec::<PoolId>::new();
+        for position_id in position_ids {
+            if let Some(pool_id) = contract
+                .position_to_pool_id
+                .inspect(&position_id, Clone::clone)
+            {
+                if !pool_ids.contains(&pool_id) {
+                    pool_ids.push(pool_id);
+                }
+            }
         }
+        Ok(pool_ids
+            .into_iter()
+            .map(|pool_id| (pool_id.0.clone(), pool_id.1.clone()))
+            .collect())
     }
 
-    pub fn fee_rate_ticks(&self, fee_level: FeeLevel) -> BasisPoints {
-        fee_rate_ticks(fee_level)
-    }
+    /// Returns up to `limit` positions of `account` in pool over `tokens`, with their fees
+    /// accrued since last withdrawal, in descending order of fees value.
+    ///
+    /// Value of fees sums both tokens at current spot price of position's fee level.
+    /// Fee amounts are in order of `tokens`. Since fees are computed for each position,
+    /// only first `MAX_FEE_RANKING_SCAN` positions of account in id order are inspected,
+    /// so with more positions the result may be incomplete.
+    pub fn get_positions_by_fees(
+        &self,
+        account: &AccountId,
+        tokens: (TokenId, TokenId),
+        limit: usize,
+    ) -> Result<Vec<(PositionId, Amount, Amount)>> {
+        let (pool_id, swapped) = PoolId::try_from_pair(tokens).map_err(|e| error_here!(e))?;
+        let contract = self.contract().as_ref();
 
-    pub fn fee_rates_ticks(&self) -> [BasisPoints; NUM_FEE_LEVELS as usize] {
-        fee_rates_ticks()
+        let mut position_ids: Vec<PositionId> = contract.accounts.try_inspect(account, |acc| {
+            let acc = acc.as_ref();
+            acc.positions.iter().map(|id| *id).collect()
+        })?;
+        position_ids.sort_unstable();
+        position_ids.truncate(MAX_FEE_RANKING_SCAN);
+
+        let mut ranked = contract.pools.try_inspect(&pool_id, |pool| {
+            position_ids
+                .into_iter()
+                .filter(|position_id| {
+                    contract
+                        .position_to_pool_id
+                        .inspect(position_id, |id| *id == pool_id)
+                        .unwrap_or(false)
+                })
+                .map(|position_id| {
+                    let PositionInfo {
+                        fee_level,
+                        reward_since_last_withdraw: fees,
+                        ..
+                    } = pool.get_position_info(&pool_id, position_id)?;
+                    // Price of right token expressed in left token
+                    let price = pool.spot_price(Side::Left, fee_level);
+                    let value = Float::from(fees.0) + Float::from(fees.1) * price;
+                    Ok((position_id, swap_if(swapped, fees), value))
+                })
+                .collect::<Result<Vec<_>>>()
+        })??;
+
+        ranked.sort_by(|(_, _, a), (_, _, b)| b.partial_cmp(a).unwrap_or(Ordering::Equal));
+        Ok(ranked
+            .into_iter()
+            .take(limit)
+            .map(|(position_id, fees, _)| (position_id, fees.0, fees.1))
+            .collect())
     }
 
-    pub fn get_liqudity_fee_level_distribution(
-        &self,
-        tokens: (TokenId, TokenId),
-    ) -> Result<Option<[Float; NUM_FEE_LEVELS as usize]>> {
-        match self.get_pool_info(tokens)? {
-            None => Ok(None),
-            Some(PoolInfo { liquidities, .. }) => {
-                let total_liquidity = liquidities
-                    .iter()
-                    .fold(Float::zero(), |total_liquidity, liquidity| {
-                        total_liquidity + (*liquidity).into()
-                    });
+    /// Returns reference price of pool, as price of first token expressed in second one
+    pub fn get_reference_price(&self, tokens: (TokenId, TokenId)) -> Result<Option<Float>> {
+        let (pool_id, swapped) = PoolId::try_from_pair(tokens).map_err(|e| error_here!(e))?;
+        Ok(self
+            .contract()
+            .as_ref()
+            .reference_prices
+            .and_then(|prices| prices.inspect(&pool_id, |price| *price))
+            .map(|price| if swapped { price.recip() } else { price }))
+    }
 
-                if total_liquidity.is_zero() {
-                    return Ok(None);
-                }
+    pub fn get_token_decimals(&self, token_id: &TokenId) -> Option<u8> {
+        self.contract()
+            .as_ref()
+            .token_decimals
+            .and_then(|decimals| decimals.inspect(token_id, |decimals| *decimals))
+    }
 
-                Ok(Some(liquidities.map(|liquidity| {
-                    Float::from(liquidity) * 100.into() / total_liquidity
-                })))
+    /// Returns spot price of `token_out` expressed in `token_in`, at specified fee level,
+    /// scaled to whole tokens if decimals of both tokens are registered.
+    /// Otherwise returns raw price, i.e. ratio of smallest units.
+    pub fn get_spot_price_scaled(
+        &self,
+        token_in: &TokenId,
+        token_out: &TokenId,
+        fee_level: FeeLevel,
+    ) -> Result<Float> {
+        validate_fee_level(fee_level).map_err(|e| error_here!(e))?;
+        let (pool_id, swapped) = PoolId::try_from_pair((token_in.clone(), token_out.clone()))
+            .map_err(|e| error_here!(e))?;
+        let side = if swapped { Side::Right } else { Side::Left };
+        let raw_price = self
+            .contract()
+            .as_ref()
+            .pools
+            .try_inspect(&pool_id, |pool| pool.spot_price(side, fee_level))?;
+
+        match (
+            self.get_token_decimals(token_in),
+            self.get_token_decimals(token_out),
+        ) {
+            (Some(decimals_in), Some(decimals_out)) => {
+                let exponent = i32::from(decimals_out) - i32::from(decimals_in);
+                Ok(raw_price * Float::from(10.0).powi(exponent))
             }
+            _ => Ok(raw_price),
         }
     }
 
-    pub fn protocol_fee_fraction(&self) -> BasisPoints {
-        self.contract().as_ref().protocol_fee_fraction
-    }
+    /// Returns raw spot price at specified fee level for each of `pairs`, in input order,
+    /// as price of second token expressed in first one, same as `get_spot_price_scaled`.
+    /// `None` is returned for pairs which have no pool.
+    pub fn get_spot_prices(
+        &self,
+        pairs: &[(TokenId, TokenId)],
+        fee_level: FeeLevel,
+    ) -> Result<Vec<Option<Float>>> {
+        validate_fee_level(fee_level).map_err(|e| error_here!(e))?;
+        let pools = &self.contract().as_ref().pools;
 
-    pub fn get_pool_ticks(&self, pool: (TokenId, TokenId), fee_level: u8) -> Option<usize> {
-        let (pool_id, _swapped) = PoolId::try_from_pair(pool).ok()?;
+        Ok(pairs
+            .iter()
+            .map(|pair| {
+                let (pool_id, swapped) = PoolId::try_from_pair(pair.clone()).ok()?;
+                let side = if swapped { Side::Right } else { Side::Left };
+                pools.inspect(&pool_id, |pool| pool.spot_price(side, fee_level))
+            })
+            .collect())
+    }
 
-        let contract = self.contract().as_ref();
+    /// Returns raw spot sqrtprice of `token_out` expressed in `token_in`, at specified fee level,
+    /// as fixed-point number. Squaring it yields the same price as `get_spot_prices`,
+    /// without loss of precision for extreme prices on the client side.
+    pub fn get_spot_sqrtprice_fp(
+        &self,
+        token_in: &TokenId,
+        token_out: &TokenId,
+        fee_level: FeeLevel,
+    ) -> Result<SqrtpriceUFP> {
+        validate_fee_level(fee_level).map_err(|e| error_here!(e))?;
+        let (pool_id, swapped) = PoolId::try_from_pair((token_in.clone(), token_out.clone()))
+            .map_err(|e| error_here!(e))?;
+        let side = if swapped { Side::Right } else { Side::Left };
+        let sqrtprice = self
+            .contract()
+            .as_ref()
+            .pools
+            .try_inspect(&pool_id, |pool| pool.spot_sqrtprice(side, fee_level))?;
+        SqrtpriceUFP::try_from(sqrtprice).map_err(|e| error_here!(e))
+    }
 
-        contract
+    /// Maximal number of price observations kept by the pool over `tokens`
+    pub fn get_pool_observation_cardinality(&self, tokens: (TokenId, TokenId)) -> Result<u16> {
+        let (pool_id, _) = PoolId::try_from_pair(tokens).map_err(|e| error_here!(e))?;
+        self.contract()
+            .as_ref()
             .pools
-            .try_inspect(&pool_id, |Pool::V0(ref pool)| {
-                Some(pool.tick_states[fee_level].len())
+            .try_inspect(&pool_id, |pool| {
+                pool.as_latest()
+                    .map_or(DEFAULT_OBSERVATION_CARDINALITY, |pool| {
+                        pool.observation_cardinality
+                    })
             })
-            .unwrap_or(None)
     }
 
-    #[cfg(feature = "test-utils")]
-    pub fn eff_sqrtprices(
+    /// Time-weighted geometric average price of `token_in` in `token_out`
+    /// over the last `window_secs` seconds, based on pool price observations.
+    /// Fails with `ObservationTooOld` if the window starts before the oldest observation.
+    pub fn observe_twap(
+        &self,
+        token_in: &TokenId,
+        token_out: &TokenId,
+        window_secs: u64,
+    ) -> Result<Float> {
+        ensure_here!(window_secs > 0, ErrorKind::InvalidParams);
+        let (pool_id, swapped) = PoolId::try_from_pair((token_in.clone(), token_out.clone()))
+            .map_err(|e| error_here!(e))?;
+        let now = self.get_block_timestamp();
+        let since = now
+            .checked_sub(window_secs)
+            .ok_or_else(|| error_here!(ErrorKind::ObservationTooOld))?;
+
+        let (cumulative_now, cumulative_since) =
+            self.contract()
+                .as_ref()
+                .pools
+                .try_inspect(&pool_id, |pool| -> Result<_> {
+                    let pool = pool
+                        .as_latest()
+                        .ok_or_else(|| error_here!(ErrorKind::ObservationTooOld))?;
+                    Ok((
+                        pool.tick_cumulative_at(now)?,
+                        pool.tick_cumulative_at(since)?,
+                    ))
+                })??;
+
+        let window =
+            i64::try_from(window_secs).map_err(|_| error_here!(ErrorKind::ConvOverflow))?;
+        let average_tick = i32::try_from(
+            cumulative_now
+                .checked_sub(cumulative_since)
+                .ok_or_else(|| error_here!(ErrorKind::ConvOverflow))?
+                .div_euclid(window),
+        )
+        .map_err(|_| error_here!(ErrorKind::ConvOverflow))?;
+        let sqrtprice = Tick::new(average_tick)
+            .map_err(|e| error_here!(e))?
+            .spot_sqrtprice();
+        let price = sqrtprice * sqrtprice;
+        Ok(if swapped { price.recip() } else { price })
+    }
+
+    /// Returns page of all initialized ticks of pool at specified fee level,
+    /// as `(tick, net_liquidity_change)` in ascending tick order, for `tokens` order.
+    /// At most `MAX_TICKS_LIQUIDITY_PAGE` ticks are returned regardless of `limit`.
+    pub fn get_all_ticks_liquidity(
         &self,
         tokens: (TokenId, TokenId),
-        direction: Side,
-    ) -> Result<RawFeeLevelsArray<Float>> {
+        fee_level: FeeLevel,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<(i32, Float)>> {
+        validate_fee_level(fee_level).map_err(|e| error_here!(e))?;
         let (pool_id, swapped) = PoolId::try_from_pair(tokens).map_err(|e| error_here!(e))?;
-        let side = direction.opposite_if(swapped);
+        let side = if swapped { Side::Right } else { Side::Left };
+
         self.contract()
             .as_ref()
             .pools
-            .try_inspect(&pool_id, |Pool::V0(ref pool)| {
-                [0, 1, 2, 3, 4, 5, 6, 7].map(|level| pool.eff_sqrtprice(level, side))
+            .try_inspect(&pool_id, |pool| {
+                pool.get_all_ticks_liquidity_change(fee_level, side)
+                    .into_iter()
+                    .skip(offset)
+                    .take(limit.min(MAX_TICKS_LIQUIDITY_PAGE))
+                    .map(|(tick, liquidity_change)| (tick.index(), liquidity_change))
+                    .collect()
             })
     }
 
-    #[cfg(feature = "smart-routing")]
-    pub fn calculate_path_liquidity(&self, token_id_vec: &[TokenId]) -> Result<Liquidity> {
-        match token_id_vec.len() {
-            2 => Ok(self
-                .total_liquidity_of_pair(token_id_vec[0].clone(), token_id_vec[1].clone())
-                .map_err(|e| error_here!(e))?),
-            3 => {
-                let x = token_id_vec[0].clone();
-                let a = token_id_vec[1].clone();
-                let y = token_id_vec[2].clone();
-                let liqiudity_xa = self
-                    .total_liquidity_of_pair(x.clone(), a.clone())
-                    .map_err(|e| error_here!(e))?;
-                let liqiudity_ay = self
-                    .total_liquidity_of_pair(a.clone(), y.clone())
-                    .map_err(|e| error_here!(e))?;
-                let price_xa = self
-                    .price_of_pair(x, a.clone())
-                    .map_err(|e| error_here!(e))?;
-                let price_ay = self.price_of_pair(a, y).map_err(|e| error_here!(e))?;
-                Ok(
-                    ((liqiudity_xa * liqiudity_ay) / (price_xa * price_xa * price_ay * price_ay))
-                        .integer_sqrt(),
-                )
-            }
-            4 => {
-                let x = token_id_vec[0].clone();
-                let a = token_id_vec[1].clone();
-                let b = token_id_vec[2].clone();
-                let y = token_id_vec[3].clone();
-                let liqiudity_xa = self
-                    .total_liquidity_of_pair(x.clone(), a.clone())
-                    .map_err(|e| error_here!(e))?;
-                let liqiudity_ab = self
-                    .total_liquidity_of_pair(a.clone(), b.clone())
-                    .map_err(|e| error_here!(e))?;
-                let liqiudity_by = self
-                    .total_liquidity_of_pair(b.clone(), y.clone())
-                    .map_err(|e| error_here!(e))?;
-                let price_xa = self.price_of_pair(x, a).map_err(|e| error_here!(e))?;
-                let price_by = self.price_of_pair(b, y).map_err(|e| error_here!(e))?;
-                let fixed_point_big: FixedPointBig = ((liqiudity_xa * liqiudity_ab * liqiudity_by)
-                    / (price_xa * price_xa * price_by * price_by))
-                    .into();
+    pub fn get_position_info(&self, position_id: u64) -> Result<PositionInfo> {
+        let contract = self.contract().as_ref();
+        contract
+            .position_to_pool_id
+            .try_inspect(&position_id, |pool_id| {
+                contract
+                    .pools
+                    .try_inspect(pool_id, |pool| pool.get_position_info(pool_id, position_id))
+            })??
+    }
 
-                Ok(Liquidity::try_from(fixed_point_big.integer_cbrt())
-                    .map_err(|e| error_here!(e))?)
+    /// Value of position balance expressed in `reference_token`, which must be one of
+    /// position's pool tokens. Other token is converted at current spot price of position's fee level.
+    pub fn get_position_value_in(
+        &self,
+        position_id: PositionId,
+        reference_token: &TokenId,
+    ) -> Result<Amount> {
+        let PositionInfo {
+            tokens_ids,
+            fee_level,
+            balance,
+            ..
+        } = self.get_position_info(position_id)?;
+
+        // Price of right token expressed in left token
+        let price = self
+            .get_spot_prices(&[tokens_ids.clone()], fee_level)?
+            .pop()
+            .flatten()
+            .ok_or_else(|| error_here!(ErrorKind::PoolNotRegistered))?;
+
+        let value = if *reference_token == tokens_ids.0 {
+            Float::from(balance.0) + Float::from(balance.1) * price
+        } else if *reference_token == tokens_ids.1 {
+            Float::from(balance.1) + Float::from(balance.0) / price
+        } else {
+            return Err(error_here!(ErrorKind::InvalidParams));
+        };
+
+        Amount::try_from(value.floor()).map_err(|e| error_here!(e))
+    }
+
+    /// Impermanent loss of position versus holding initially deposited tokens,
+    /// as a fraction of held tokens value. Negative value means loss.
+    pub fn get_position_il(&self, position_id: PositionId) -> Result<Float> {
+        let contract = self.contract().as_ref();
+        contract
+            .position_to_pool_id
+            .try_inspect(&position_id, |pool_id| {
+                contract
+                    .pools
+                    .try_inspect(pool_id, |pool| pool.get_position_il(position_id))
+            })??
+    }
+
+    /// Spot price, of right token expressed in left token, at which position value
+    /// with accrued fees equals value of initially deposited tokens if they were just held.
+    /// See `Pool::get_position_breakeven_price` for details.
+    pub fn get_position_breakeven_price(&self, position_id: PositionId) -> Result<Option<Float>> {
+        let contract = self.contract().as_ref();
+        contract
+            .position_to_pool_id
+            .try_inspect(&position_id, |pool_id| {
+                contract.pools.try_inspect(pool_id, |pool| {
+                    pool.get_position_breakeven_price(position_id)
+                })
+            })??
+    }
+
+    /// Returns account's positions whose range doesn't include current spot price
+    /// of their fee level, so they earn no fees.
+    /// Account's positions are paged by `offset` and `limit` in id order before filtering.
+    pub fn get_out_of_range_positions(
+        &self,
+        account: &AccountId,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<PositionId>> {
+        let contract = self.contract().as_ref();
+
+        let mut position_ids: Vec<PositionId> = contract
+            .accounts
+            .inspect(account, |acc| {
+                let acc = acc.as_ref();
+                acc.positions.iter().map(|id| *id).collect()
+            })
+            .unwrap_or_default();
+        position_ids.sort_unstable();
+
+        position_ids
+            .into_iter()
+            .skip(offset)
+            .take(limit)
+            .map(|position_id| {
+                contract
+                    .position_to_pool_id
+                    .try_inspect(&position_id, |pool_id| {
+                        contract.pools.try_inspect(pool_id, |pool| {
+                            let pos = PoolState::get_position(pool, position_id)
+                                .ok_or(error_here!(ErrorKind::PositionDoesNotExist))?
+                                .into_latest();
+                            pool.cmp_spot_price_to_position_range(pos.fee_level, pos.tick_bounds)
+                        })
+                    })??
+                    .map(|ordering| (position_id, ordering))
+            })
+            .filter_ok(|(_, ordering)| ordering.is_ne())
+            .map_ok(|(position_id, _)| position_id)
+            .collect()
+    }
+
+    /// Returns pairs of account's positions in the same pool and fee level
+    /// which tick ranges overlap. Positions in each pair, and pairs themselves,
+    /// are ordered by position identifier.
+    pub fn get_overlapping_positions(
+        &self,
+        account: &AccountId,
+        tokens: (TokenId, TokenId),
+        fee_rate: BasisPoints,
+    ) -> Result<Vec<(PositionId, PositionId)>> {
+        let fee_level: FeeLevel = fee_rates_ticks()
+            .iter()
+            .position(|&r| r == fee_rate)
+            .ok_or_else(|| error_here!(ErrorKind::IllegalFee))?
+            .try_into()
+            .map_err(|_| error_here!(ErrorKind::ConvOverflow))?;
+        let (pool_id, _) = PoolId::try_from_pair(tokens).map_err(|e| error_here!(e))?;
+        let contract = self.contract().as_ref();
+
+        let mut position_ids: Vec<PositionId> = contract
+            .accounts
+            .inspect(account, |acc| {
+                let acc = acc.as_ref();
+                acc.positions.iter().map(|id| *id).collect()
+            })
+            .unwrap_or_default();
+        position_ids.sort_unstable();
+
+        let ranges = contract
+            .pools
+            .inspect(&pool_id, |pool| {
+                position_ids
+                    .into_iter()
+                    .filter(|position_id| {
+                        contract
+                            .position_to_pool_id
+                            .inspect(position_id, |position_pool_id| *position_pool_id == pool_id)
+                            .unwrap_or(false)
+                    })
+                    .map(|position_id| {
+                        let info = pool.get_position_info(&pool_id, position_id)?;
+                        Ok((position_id, info.fee_level, info.range_ticks))
+                    })
+                    .filter_ok(|(_, level, _)| *level == fee_level)
+                    .map_ok(|(position_id, _, range)| (position_id, range))
+                    .collect::<Result<Vec<_>>>()
+            })
+            .transpose()?
+            .unwrap_or_default();
+
+        Ok(ranges
+            .iter()
+            .tuple_combinations()
+            .filter(|((_, (low_a, high_a)), (_, (low_b, high_b)))| low_a < high_b && low_b < high_a)
+            .map(|((id_a, _), (id_b, _))| (*id_a, *id_b))
+            .collect())
+    }
+
+    /// Returns fee accumulator baselines stored in position, as pairs for left and right tokens:
+    /// first at position creation, second at last fee withdrawal.
+    #[allow(clippy::type_complexity)]
+    pub fn get_position_fee_accumulators(
+        &self,
+        position_id: PositionId,
+    ) -> Result<(
+        (LPFeePerFeeLiquidity, LPFeePerFeeLiquidity),
+        (LPFeePerFeeLiquidity, LPFeePerFeeLiquidity),
+    )> {
+        let contract = self.contract().as_ref();
+        contract
+            .position_to_pool_id
+            .try_inspect(&position_id, |pool_id| {
+                contract.pools.try_inspect(pool_id, |pool| {
+                    let pos = PoolState::get_position(pool, position_id)
+                        .ok_or(error_here!(ErrorKind::PositionDoesNotExist))?
+                        .into_latest();
+                    Ok((
+                        pos.init_acc_lp_fees_per_fee_liquidity,
+                        pos.unwithdrawn_acc_lp_fees_per_fee_liquidity,
+                    ))
+                })
+            })??
+    }
+
+    pub fn get_positions_info(&self, position_ids: &[u64]) -> Vec<Option<PositionInfo>> {
+        let contract = self.contract().as_ref();
+
+        position_ids
+            .iter()
+            .map(|position_id| {
+                contract
+                    .position_to_pool_id
+                    .try_inspect(position_id, |pool_id| {
+                        contract.pools.try_inspect(pool_id, |pool| {
+                            pool.get_position_info(pool_id, *position_id)
+                        })
+                    })
+                    .and_then(|position| position.and_then(|pool| pool))
+                    .ok()
+            })
+            .collect()
+    }
+
+    pub fn get_version(&self) -> VersionInfo {
+        VersionInfo {
+            version: env!("DEX_CORE_VERSION").to_string(),
+        }
+    }
+
+    /// Snapshot of owner, guards and other administrative settings.
+    /// Lists at most `MAX_ADMIN_CONFIG_GUARDS` guards
+    #[allow(clippy::clone_on_copy)] // Some blockchains have address copyable, some don't
+    pub fn get_admin_config(&self) -> AdminConfig {
+        let contract = self.contract().as_ref();
+        AdminConfig {
+            owner: contract.owner_id.clone(),
+            guards: contract
+                .guards
+                .iter()
+                .take(MAX_ADMIN_CONFIG_GUARDS)
+                .map(|guard| guard.clone())
+                .collect(),
+            guard_count: contract.guards.len() as u64,
+            suspended: contract.suspended,
+            protocol_fee_fraction: contract.protocol_fee_fraction,
+        }
+    }
+
+    pub fn fee_rate_ticks(&self, fee_level: FeeLevel) -> Result<BasisPoints> {
+        validate_fee_level(fee_level).map_err(|e| error_here!(e))?;
+        Ok(fee_rate_ticks(fee_level))
+    }
+
+    pub fn fee_rates_ticks(&self) -> [BasisPoints; NUM_FEE_LEVELS as usize] {
+        fee_rates_ticks()
+    }
+
+    /// Complete fee configuration: fee rates of all levels and protocol fee fraction
+    pub fn get_fee_schedule(&self) -> FeeSchedule {
+        FeeSchedule {
+            fee_rate_ticks: fee_rates_ticks(),
+            fee_rate_fractions: array_init(|level| fee_rate(as_fee_level(level))),
+            basis_point_divisor: BASIS_POINT_DIVISOR,
+            protocol_fee_fraction: self.contract().as_ref().protocol_fee_fraction,
+        }
+    }
+
+    pub fn get_liqudity_fee_level_distribution(
+        &self,
+        tokens: (TokenId, TokenId),
+    ) -> Result<Option<[Float; NUM_FEE_LEVELS as usize]>> {
+        match self.get_pool_info(tokens)? {
+            None => Ok(None),
+            Some(PoolInfo { liquidities, .. }) => {
+                let total_liquidity = liquidities
+                    .iter()
+                    .fold(Float::zero(), |total_liquidity, liquidity| {
+                        total_liquidity + (*liquidity).into()
+                    });
+
+                if total_liquidity.is_zero() {
+                    return Ok(None);
+                }
+
+                Ok(Some(liquidities.map(|liquidity| {
+                    Float::from(liquidity) * 100.into() / total_liquidity
+                })))
             }
-            _ => Err(error_here!(ErrorKind::InvalidParams)),
         }
     }
 
-    #[cfg(feature = "smart-routing")]
-    fn price_of_pair(&self, token_a: TokenId, token_b: TokenId) -> Result<Liquidity, ErrorKind> {
+    /// Returns fee levels which currently hold non-zero liquidity, in ascending order.
+    /// Empty for nonexistent pool.
+    pub fn get_active_fee_levels(&self, tokens: (TokenId, TokenId)) -> Result<Vec<FeeLevel>> {
+        Ok(self
+            .get_pool_info(tokens)?
+            .map(|PoolInfo { liquidities, .. }| {
+                (0..NUM_FEE_LEVELS)
+                    .filter(|&level| !liquidities[level as usize].is_zero())
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    pub fn protocol_fee_fraction(&self) -> BasisPoints {
+        self.contract().as_ref().protocol_fee_fraction
+    }
+
+    /// Protocol fee fraction applied to swaps, including surcharge
+    pub fn swap_protocol_fee_fraction(&self) -> BasisPoints {
+        self.contract().as_ref().swap_protocol_fee_fraction()
+    }
+
+    /// Fraction of amount-in which may be underpaid by trader in a swap due to numeric errors
+    pub fn swap_max_underpay(&self) -> Float {
+        self.contract().as_ref().swap_max_underpay
+    }
+
+    /// Number of initialized ticks on specified fee level of the pool.
+    /// `None` if there's no such pool.
+    pub fn get_pool_ticks(
+        &self,
+        pool: (TokenId, TokenId),
+        fee_level: FeeLevel,
+    ) -> Result<Option<usize>> {
+        validate_fee_level(fee_level).map_err(|e| error_here!(e))?;
+        let Ok((pool_id, _swapped)) = PoolId::try_from_pair(pool) else {
+            return Ok(None);
+        };
+
         let contract = self.contract().as_ref();
-        let (pool_id, swapped) = PoolId::try_from_pair((token_a, token_b))?;
-        let price = contract
+
+        Ok(contract
             .pools
-            .try_inspect(&pool_id, |Pool::V0(ref pool)| pool.reserves_ratio())
-            .map_err(|e| e.kind)?;
-        if swapped {
-            Ok(price.recip())
-        } else {
-            Ok(price)
-        }
+            .try_inspect(&pool_id, |pool| Some(pool.tick_states()[fee_level].len()))
+            .unwrap_or(None))
     }
 
-    #[cfg(feature = "smart-routing")]
-    fn total_liquidity_of_pair(
+    /// Global LP fee per fee-liquidity accumulated in the pool over `pool` at `fee_level`,
+    /// for both tokens, in order of tokens in `pool`.
+    ///
+    /// Accumulators only grow, and reward of position spanning whole price range
+    /// equals its fee-liquidity times accumulators increase.
+    pub fn get_pool_fee_growth(
         &self,
-        token_a: TokenId,
-        token_b: TokenId,
-    ) -> Result<Liquidity, ErrorKind> {
-        let (pool_id, _) = PoolId::try_from_pair((token_a, token_b))?;
+        pool: (TokenId, TokenId),
+        fee_level: FeeLevel,
+    ) -> Result<(LPFeePerFeeLiquidity, LPFeePerFeeLiquidity)> {
+        validate_fee_level(fee_level).map_err(|e| error_here!(e))?;
+        let (pool_id, swapped) = PoolId::try_from_pair(pool).map_err(|e| error_here!(e))?;
+
+        let fee_growth = self
+            .contract()
+            .as_ref()
+            .pools
+            .try_inspect(&pool_id, |pool| {
+                (
+                    pool.acc_lp_fee_per_fee_liquidity(fee_level, Side::Left),
+                    pool.acc_lp_fee_per_fee_liquidity(fee_level, Side::Right),
+                )
+            })?;
+        Ok(swap_if(swapped, fee_growth))
+    }
+
+    #[cfg(feature = "test-utils")]
+    pub fn eff_sqrtprices(
+        &self,
+        tokens: (TokenId, TokenId),
+        direction: Side,
+    ) -> Result<RawFeeLevelsArray<Float>> {
+        let (pool_id, swapped) = PoolId::try_from_pair(tokens).map_err(|e| error_here!(e))?;
+        let side = direction.opposite_if(swapped);
         self.contract()
             .as_ref()
             .pools
-            .try_inspect(&pool_id, |Pool::V0(ref pool)| pool.total_liquidity())
-            .map_err(|e| e.kind)
+            .try_inspect(&pool_id, |pool| {
+                [0, 1, 2, 3, 4, 5, 6, 7].map(|level| pool.eff_sqrtprice(level, side))
+            })
+    }
+
+    /// Effective sqrtprices `(left, right)` on each fee level, as stored in pool,
+    /// i.e. in canonical order of pool tokens regardless of order of `tokens`
+    pub fn get_eff_sqrtprices(
+        &self,
+        tokens: (TokenId, TokenId),
+    ) -> Result<RawFeeLevelsArray<(Float, Float)>> {
+        let (pool_id, _) = PoolId::try_from_pair(tokens).map_err(|e| error_here!(e))?;
+        self.contract()
+            .as_ref()
+            .pools
+            .try_inspect(&pool_id, |pool| {
+                pool.eff_sqrtprices()
+                    .map(|eff_sqrtprices| eff_sqrtprices.as_tuple())
+            })
+    }
+
+    /// Number of ticks crossed by the last swap in the pool over `tokens`, over all fee levels
+    pub fn get_last_swap_crossings(&self, tokens: (TokenId, TokenId)) -> Result<u32> {
+        let (pool_id, _) = PoolId::try_from_pair(tokens).map_err(|e| error_here!(e))?;
+        self.contract()
+            .as_ref()
+            .pools
+            .try_inspect(&pool_id, |pool| {
+                pool.as_latest()
+                    .map_or(0, |pool| pool.last_swap_tick_crossings)
+            })
+    }
+
+    /// Whether payable API, i.e. swaps, deposits and liquidity management, is suspended by guards
+    pub fn is_suspended(&self) -> bool {
+        self.contract().as_ref().suspended
+    }
+
+    /// Whether swaps in the pool over `tokens` are paused by guards
+    pub fn is_pool_paused(&self, tokens: (TokenId, TokenId)) -> Result<bool> {
+        let (pool_id, _) = PoolId::try_from_pair(tokens).map_err(|e| error_here!(e))?;
+        Ok(self
+            .contract()
+            .as_ref()
+            .paused_pools
+            .map_or(false, |paused_pools| paused_pools.contains_item(&pool_id)))
+    }
+
+    /// Side of `tokens` from which swaps into the pool are allowed, if restricted;
+    /// `Side::Left` means only swaps with first token as input are allowed
+    pub fn get_pool_allowed_swap_side(&self, tokens: (TokenId, TokenId)) -> Result<Option<Side>> {
+        let (pool_id, swapped) = PoolId::try_from_pair(tokens).map_err(|e| error_here!(e))?;
+        self.contract()
+            .as_ref()
+            .pools
+            .try_inspect(&pool_id, |pool| {
+                pool.as_latest()
+                    .and_then(|pool| pool.allowed_swap_side)
+                    .map(|side| side.opposite_if(swapped))
+            })
+    }
+
+    /// Whether opening a position over `tokens` would create a new pool.
+    /// Returns `false` for pairs which can't form a pool, e.g. identical tokens.
+    pub fn would_create_pool(&self, tokens: (TokenId, TokenId)) -> bool {
+        PoolId::try_from_pair(tokens).map_or(false, |(pool_id, _)| {
+            !self.contract().as_ref().pools.contains_key(&pool_id)
+        })
+    }
+
+    /// Canonical `(left, right)` order in which pool over `token_a` and `token_b` stores tokens,
+    /// and whether it differs from `(token_a, token_b)`
+    pub fn canonicalize_pair(
+        &self,
+        token_a: &TokenId,
+        token_b: &TokenId,
+    ) -> Result<((TokenId, TokenId), bool)> {
+        let (pool_id, swapped) = PoolId::try_from_pair((token_a.clone(), token_b.clone()))
+            .map_err(|e| error_here!(e))?;
+        Ok((pool_id.into(), swapped))
+    }
+
+    /// Protocol fee fraction currently charged on swaps in the pool over `tokens`:
+    /// global protocol fee fraction with active surcharge added, capped by `MAX_PROTOCOL_FEE_FRACTION`
+    pub fn get_effective_protocol_fee(&self, tokens: (TokenId, TokenId)) -> Result<BasisPoints> {
+        let (pool_id, _) = PoolId::try_from_pair(tokens).map_err(|e| error_here!(e))?;
+        let contract = self.contract().as_ref();
+        contract.pools.try_inspect(&pool_id, |_| ())?;
+        Ok(contract.swap_protocol_fee_fraction())
+    }
+
+    /// Returns tokens which have a pool with `token`, paged by `offset` and `limit`,
+    /// in pools storage order. Empty if `token` has no pools.
+    ///
+    /// To bound the cost, only first `MAX_TOKEN_CONNECTIONS_SCAN` pools in storage order
+    /// are inspected, so with more pools the result may be incomplete.
+    #[cfg(feature = "smart-routing")]
+    pub fn get_token_connections(
+        &self,
+        token: &TokenId,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<TokenId>> {
+        Ok(self
+            .contract()
+            .as_ref()
+            .pools
+            .iter()
+            .take(MAX_TOKEN_CONNECTIONS_SCAN)
+            .filter_map(|(pool_id, _)| {
+                if &pool_id.0 == token {
+                    Some(pool_id.1.clone())
+                } else if &pool_id.1 == token {
+                    Some(pool_id.0.clone())
+                } else {
+                    None
+                }
+            })
+            .skip(offset)
+            .take(limit)
+            .collect())
+    }
+
+    /// Find path from `token_in` to `token_out` of at most `max_hops` swaps,
+    /// which yields the largest amount out for `amount_in`.
+    ///
+    /// Candidate paths are discovered by inspecting first `MAX_ROUTE_POOLS_SCAN` pools
+    /// in storage order, and each one is quoted, so cost grows quickly with `max_hops`.
+    /// Paths which can't be quoted, e.g. due to insufficient liquidity, are skipped.
+    ///
+    /// # Returns
+    /// (Path, including both `token_in` and `token_out`, Amount out).
+    /// If no path could be quoted, fails with error of the last failed quote,
+    /// or with `NoRouteFound` if there's no path at all
+    #[cfg(feature = "smart-routing")]
+    pub fn find_best_route(
+        &self,
+        token_in: &TokenId,
+        token_out: &TokenId,
+        amount_in: Amount,
+        max_hops: usize,
+    ) -> Result<(Vec<TokenId>, Amount)> {
+        ensure_here!(token_in != token_out, ErrorKind::IdenticalTokens);
+        ensure_here!(
+            (1..=MAX_ROUTE_HOPS).contains(&max_hops),
+            ErrorKind::InvalidParams
+        );
+
+        let mut connections: HashMap<TokenId, Vec<TokenId>> = HashMap::new();
+        for (pool_id, _) in self
+            .contract()
+            .as_ref()
+            .pools
+            .iter()
+            .take(MAX_ROUTE_POOLS_SCAN)
+        {
+            let (token_a, token_b) = pool_id.as_refs();
+            connections
+                .entry(token_a.clone())
+                .or_default()
+                .push(token_b.clone());
+            connections
+                .entry(token_b.clone())
+                .or_default()
+                .push(token_a.clone());
+        }
+
+        let quote_path = |path: &[TokenId]| -> Result<Amount> {
+            path.iter()
+                .tuple_windows()
+                .try_fold(amount_in, |amount, (token_a, token_b)| {
+                    Ok(self
+                        .estimate_swap_exact(
+                            true,
+                            token_a.clone(),
+                            token_b.clone(),
+                            amount,
+                            SlippageTolerance::default(),
+                        )?
+                        .result)
+                })
+        };
+
+        let mut best_route: Option<(Vec<TokenId>, Amount)> = None;
+        let mut last_error = None;
+        // Depth-first enumeration of paths without repeated tokens
+        let mut paths = vec![vec![token_in.clone()]];
+        while let Some(path) = paths.pop() {
+            let Some(last_token) = path.last() else {
+                return Err(error_here!(ErrorKind::InternalLogicError));
+            };
+            if last_token == token_out {
+                match quote_path(&path) {
+                    Ok(amount_out) => {
+                        if best_route
+                            .as_ref()
+                            .map_or(true, |(_, best_amount_out)| amount_out > *best_amount_out)
+                        {
+                            best_route = Some((path, amount_out));
+                        }
+                    }
+                    Err(error) => last_error = Some(error),
+                }
+                continue;
+            }
+            if path.len() > max_hops {
+                continue;
+            }
+            for next_token in connections.get(last_token).into_iter().flatten() {
+                if !path.contains(next_token) {
+                    let mut next_path = path.clone();
+                    next_path.push(next_token.clone());
+                    paths.push(next_path);
+                }
+            }
+        }
+
+        best_route.ok_or_else(|| last_error.unwrap_or_else(|| error_here!(ErrorKind::NoRouteFound)))
+    }
+
+    #[cfg(feature = "smart-routing")]
+    pub fn calculate_path_liquidity(&self, token_id_vec: &[TokenId]) -> Result<Liquidity> {
+        match token_id_vec.len() {
+            2 => Ok(self
+                .total_liquidity_of_pair(token_id_vec[0].clone(), token_id_vec[1].clone())
+                .map_err(|e| error_here!(e))?),
+            3 => {
+                let x = token_id_vec[0].clone();
+                let a = token_id_vec[1].clone();
+                let y = token_id_vec[2].clone();
+                let liqiudity_xa = self
+                    .total_liquidity_of_pair(x.clone(), a.clone())
+                    .map_err(|e| error_here!(e))?;
+                let liqiudity_ay = self
+                    .total_liquidity_of_pair(a.clone(), y.clone())
+                    .map_err(|e| error_here!(e))?;
+                let price_xa = self
+                    .price_of_pair(x, a.clone())
+                    .map_err(|e| error_here!(e))?;
+                let price_ay = self.price_of_pair(a, y).map_err(|e| error_here!(e))?;
+                Ok(
+                    ((liqiudity_xa * liqiudity_ay) / (price_xa * price_xa * price_ay * price_ay))
+                        .integer_sqrt(),
+                )
+            }
+            4 => {
+                let x = token_id_vec[0].clone();
+                let a = token_id_vec[1].clone();
+                let b = token_id_vec[2].clone();
+                let y = token_id_vec[3].clone();
+                let liqiudity_xa = self
+                    .total_liquidity_of_pair(x.clone(), a.clone())
+                    .map_err(|e| error_here!(e))?;
+                let liqiudity_ab = self
+                    .total_liquidity_of_pair(a.clone(), b.clone())
+                    .map_err(|e| error_here!(e))?;
+                let liqiudity_by = self
+                    .total_liquidity_of_pair(b.clone(), y.clone())
+                    .map_err(|e| error_here!(e))?;
+                let price_xa = self.price_of_pair(x, a).map_err(|e| error_here!(e))?;
+                let price_by = self.price_of_pair(b, y).map_err(|e| error_here!(e))?;
+                let fixed_point_big: FixedPointBig = ((liqiudity_xa * liqiudity_ab * liqiudity_by)
+                    / (price_xa * price_xa * price_by * price_by))
+                    .into();
+
+                Ok(Liquidity::try_from(fixed_point_big.integer_cbrt())
+                    .map_err(|e| error_here!(e))?)
+            }
+            _ => Err(error_here!(ErrorKind::InvalidParams)),
+        }
+    }
+
+    #[cfg(feature = "smart-routing")]
+    fn price_of_pair(&self, token_a: TokenId, token_b: TokenId) -> Result<Liquidity, ErrorKind> {
+        let contract = self.contract().as_ref();
+        let (pool_id, swapped) = PoolId::try_from_pair((token_a, token_b))?;
+        let price = contract
+            .pools
+            .try_inspect(&pool_id, |pool| pool.reserves_ratio())
+            .map_err(|e| e.kind)?;
+        if swapped {
+            Ok(price.recip())
+        } else {
+            Ok(price)
+        }
+    }
+
+    #[cfg(feature = "smart-routing")]
+    fn total_liquidity_of_pair(
+        &self,
+        token_a: TokenId,
+        token_b: TokenId,
+    ) -> Result<Liquidity, ErrorKind> {
+        let (pool_id, _) = PoolId::try_from_pair((token_a, token_b))?;
+        self.contract()
+            .as_ref()
+            .pools
+            .try_inspect(&pool_id, |pool| pool.total_liquidity())
+            .map_err(|e| e.kind)
+    }
+}
+
+impl<T: Types, S: StateMut<T>, SS: BorrowMut<S>> Dex<T, S, SS> {
+    fn with_account_mut<R>(
+        &mut self,
+        account_id: &AccountId,
+        cb: impl FnOnce(AccountViewMut<'_, T>) -> Result<R>,
+    ) -> Result<R> {
+        let now = self.get_block_timestamp();
+        let StateMembersMut {
+            contract,
+            item_factory,
+            logger,
+        } = self.members_mut();
+        let contract = contract.latest();
+
+        contract.accounts.try_update(account_id, |account| {
+            let account = account.latest();
+            cb(AccountViewMut {
+                account_id,
+                account,
+                pools: &mut contract.pools,
+                pool_count: &mut contract.pool_count,
+                next_free_position_id: &mut contract.next_free_position_id,
+                position_to_pool_id: &mut contract.position_to_pool_id,
+                position_owners: &mut contract.position_owners,
+                tick_positions: &mut contract.tick_positions,
+                oracle_guard: OracleGuard {
+                    reference_prices: contract.reference_prices.as_ref(),
+                    max_deviation_bp: contract.max_oracle_deviation_bp,
+                },
+                grace_guard: GraceGuard {
+                    token_verified_at: contract.token_verified_at.as_ref(),
+                    grace_secs: contract.verified_token_grace_secs,
+                    now,
+                },
+                pause_guard: PauseGuard {
+                    paused_pools: contract.paused_pools.as_ref(),
+                },
+                open_position_fee: contract.open_position_fee.clone(),
+                collected_open_position_fees: &mut contract.collected_open_position_fees,
+                item_factory,
+                logger,
+                event_verbosity: contract.event_verbosity,
+            })
+        })
+    }
+
+    fn with_caller_account_mut<R>(
+        &mut self,
+        cb: impl FnOnce(AccountViewMut<'_, T>) -> Result<R>,
+    ) -> Result<R> {
+        let account_id = self.get_caller_id();
+        self.with_account_mut(&account_id, cb)
+    }
+    /// Register caller's account in smart contract storage
+    ///
+    /// Same as `register_account_and_then` with `account_id: None` and no-op callback which always succeeds
+    ///
+    /// # Returns
+    /// * `Ok(())` if succeeds, `Err(_)` if fails, for some reason
+    pub fn register_account(&mut self) -> Result<()> {
+        self.register_account_and_then(None, |_, _, _| Ok(()))
+    }
+
+    /// Register account and tokens if they weren't registered before.
+    /// Uses a caller ID as an account ID to register.
+    /// Used by platforms where we don't need to register account and tokens manually, during ops (cdex and dx25).
+    ///
+    /// # Returns
+    /// * `Ok(())` if succeeds, `Err(_)` if fails, for some reason
+    #[cfg(not(feature = "near"))]
+    fn register_account_and_tokens(
+        &mut self,
+        account_id: Option<AccountId>,
+        tokens: &[TokenId],
+    ) -> Result<()> {
+        let max_tokens = self.contract().as_ref().max_tokens_per_account;
+        self.register_account_and_then(account_id, |_, account, _| {
+            let account = account.latest();
+            account.register_tokens_capped(tokens, max_tokens)
+        })
+    }
+    /// Register token holder account and invoke provided callback over it
+    ///
+    /// # Arguments
+    /// * `account_id` - either identifier of new account or `None`, in which case caller id is used
+    /// * `register_cb` - callback which receives account identifier, mutable reference to account
+    ///     data structure, and boolean flag which is `true` if account already exists; if returns `Err(_)`,
+    ///     whole account registration is deemed failure
+    ///
+    /// # Returns
+    /// Either success result of `register_cb` or failure, either caused by account registration itself
+    /// or callback
+    pub fn register_account_and_then<R>(
+        &mut self,
+        account_id: impl Into<Option<AccountId>>,
+        register_cb: impl FnOnce(&AccountId, &mut Account<T>, bool /*exists*/) -> Result<R>,
+    ) -> Result<R> {
+        self.ensure_payable_api_resumed()?;
+
+        let account_id: Option<AccountId> = account_id.into();
+        let account_id = account_id.unwrap_or_else(|| self.get_caller_id());
+        let StateMembersMut {
+            contract,
+            item_factory,
+            ..
+        } = self.members_mut();
+        let contract = contract.latest();
+        contract.accounts.update_or_insert(
+            &account_id,
+            || item_factory.new_account(),
+            |account, exists| register_cb(&account_id, account, exists),
+        )
+    }
+
+    /// Register several accounts at once, on behalf of owner or guard account
+    ///
+    /// # Returns
+    /// For each account, `true` if account was created and `false` if it already existed
+    pub fn register_accounts(&mut self, account_ids: &[AccountId]) -> Result<Vec<bool>> {
+        self.ensure_caller_is_guard()?;
+
+        account_ids
+            .iter()
+            .map(|account_id| {
+                self.register_account_and_then(account_id.clone(), |_, _, exists| Ok(!exists))
+            })
+            .collect()
+    }
+
+    /// Try unregister caller account, if one's found
+    ///
+    /// Equivalent to `unregister_account_with_cb(None, |_, _| Ok(()))`
+    pub fn unregister_account(&mut self) -> Result<Option<()>> {
+        self.unregister_account_with_cb(None, |_, _| Ok(()))
+    }
+
+    /// Try unregister user account from Dex
+    ///
+    /// If account identified by `account_id` parameter is found, it's first checked to not
+    /// track any withdrawals and have no tokens in store; `unregister_cb` is called as last check;
+    /// if all checks succeed, account is unregistered
+    ///
+    /// # Parameters
+    /// * `account_id` - either identifier of new account or `None`, in which case caller id is used
+    /// * `unregister_cb` - callback usable for additional checks before actually unregistering account;
+    ///     can cancel unregistration by returning error
+    ///
+    /// # Returns
+    /// * `Ok(None)` - if account isn't registered
+    /// * `Ok(Some(result))` - return value of unregister callback, if all checks succeeded
+    ///     and account was unregistered
+    /// * `Err(error)` - if any error occured, including one returned by unregister callback
+    pub fn unregister_account_with_cb<R>(
+        &mut self,
+        account_id: impl Into<Option<AccountId>>,
+        unregister_cb: impl FnOnce(&AccountId, &Account<T>) -> Result<R>,
+    ) -> Result<Option<R>> {
+        self.ensure_payable_api_resumed()?;
+
+        let account_id: Option<AccountId> = account_id.into();
+        let account_id = account_id.unwrap_or_else(|| self.get_caller_id());
+
+        let contract = self.contract_mut().latest();
+
+        contract
+            .accounts
+            .inspect(&account_id, |account| {
+                let acc = account.as_ref();
+                ensure_here!(
+                    acc.token_balances.is_empty(),
+                    ErrorKind::TokensStorageNotEmpty
+                );
+                ensure_here!(acc.positions.is_empty(), ErrorKind::UserHasPositions);
+                ensure_here!(
+                    !acc.withdraw_tracker.is_any_withdraw_in_progress(),
+                    ErrorKind::WithdrawInProgress
+                );
+                unregister_cb(&account_id, account)
+            })
+            .map(|r| {
+                if r.is_ok() {
+                    contract.accounts.remove(&account_id);
+                }
+                r
+            })
+            .transpose()
+    }
+
+    fn ensure_caller_is_owner(&self) -> Result<()> {
+        ensure_here!(
+            self.contract().as_ref().owner_id == &self.get_caller_id(),
+            ErrorKind::PermissionDenied
+        );
+        Ok(())
+    }
+
+    fn ensure_suspended(&self) -> Result<()> {
+        ensure_here!(
+            self.contract().as_ref().suspended,
+            ErrorKind::GuardChangeStateDenied
+        );
+        Ok(())
+    }
+
+    fn ensure_resumed(&self) -> Result<()> {
+        ensure_here!(
+            !self.contract().as_ref().suspended,
+            ErrorKind::GuardChangeStateDenied
+        );
+        Ok(())
+    }
+
+    fn ensure_caller_is_guard(&self) -> Result<()> {
+        let caller = self.get_caller_id();
+        let contract = self.contract().as_ref();
+        ensure_here!(
+            contract.owner_id == &caller || contract.guards.contains_item(&caller),
+            ErrorKind::PermissionDenied
+        );
+        Ok(())
+    }
+
+    fn ensure_caller_is_operator(&self) -> Result<()> {
+        let caller = self.get_caller_id();
+        let contract = self.contract().as_ref();
+        ensure_here!(
+            contract
+                .operators
+                .map_or(false, |operators| operators.contains_item(&caller)),
+            ErrorKind::PermissionDenied
+        );
+        Ok(())
+    }
+
+    pub(crate) fn ensure_payable_api_resumed(&self) -> Result<()> {
+        ensure_here!(
+            !self.contract().as_ref().suspended,
+            ErrorKind::PayableAPISuspended
+        );
+        Ok(())
+    }
+
+    pub fn add_verified_tokens(&mut self, tokens: impl IntoIterator<Item = TokenId>) -> Result<()> {
+        self.ensure_payable_api_resumed()?;
+        self.ensure_caller_is_owner()?;
+        let now = self.get_block_timestamp();
+        let StateMembersMut {
+            contract,
+            item_factory,
+            ..
+        } = self.members_mut();
+        let contract = contract.latest();
+        let mut new_tokens = Vec::new();
+
+        for token in tokens {
+            if !contract.verified_tokens.contains_item(&token) {
+                contract.verified_tokens.add_item(token.clone());
+                contract
+                    .token_verified_at
+                    .get_or_insert_with(|| item_factory.new_token_verified_at_map())
+                    .insert(token.clone(), now);
+                new_tokens.push(token);
+            }
+        }
+
+        self.logger_mut().log_add_verified_tokens_event(&new_tokens);
+
+        Ok(())
+    }
+
+    pub fn remove_verified_tokens(
+        &mut self,
+        tokens: impl IntoIterator<Item = TokenId>,
+    ) -> Result<()> {
+        self.ensure_payable_api_resumed()?;
+        self.ensure_caller_is_owner()?;
+        let contract = self.contract_mut().latest();
+        let mut removed_tokens = Vec::new();
+        for token in tokens {
+            if contract.verified_tokens.contains_item(&token) {
+                contract.verified_tokens.remove_item(&token);
+                if let Some(token_verified_at) = contract.token_verified_at.as_mut() {
+                    token_verified_at.remove(&token);
+                }
+                removed_tokens.push(token);
+            }
+        }
+
+        self.logger_mut()
+            .log_remove_verified_tokens_event(&removed_tokens);
+
+        Ok(())
+    }
+
+    pub fn get_verified_tokens(&self) -> Vec<TokenId> {
+        self.contract()
+            .as_ref()
+            .verified_tokens
+            .iter()
+            .map(|token| token.clone())
+            .collect()
+    }
+
+    /// Returns up to `limit` verified tokens, skipping first `offset` ones.
+    ///
+    /// Order is the same as in `get_verified_tokens`, and is stable unless the set is modified.
+    pub fn get_verified_tokens_paged(&self, offset: usize, limit: usize) -> Vec<TokenId> {
+        self.contract()
+            .as_ref()
+            .verified_tokens
+            .iter()
+            .skip(offset)
+            .take(limit)
+            .map(|token| token.clone())
+            .collect()
+    }
+
+    pub fn get_verified_tokens_count(&self) -> usize {
+        self.contract().as_ref().verified_tokens.len()
+    }
+
+    pub fn is_token_verified(&self, token: &TokenId) -> bool {
+        self.contract()
+            .as_ref()
+            .verified_tokens
+            .contains_item(token)
+    }
+
+    /// Returns verification status of each of specified tokens, in the same order
+    pub fn are_tokens_verified(&self, tokens: &[TokenId]) -> Vec<bool> {
+        let verified_tokens = self.contract().as_ref().verified_tokens;
+        tokens
+            .iter()
+            .map(|token| verified_tokens.contains_item(token))
+            .collect()
+    }
+
+    #[allow(clippy::clone_on_copy)]
+    pub fn add_guard_accounts(
+        &mut self,
+        guard_accounts: impl IntoIterator<Item = AccountId>,
+    ) -> Result<()> {
+        self.ensure_payable_api_resumed()?;
+        self.ensure_caller_is_owner()?;
+        let contract = self.contract_mut().latest();
+        let guards = &mut contract.guards;
+        let mut new_guards = Vec::new();
+
+        for guard_account in guard_accounts {
+            if !guards.contains_item(&guard_account) {
+                guards.add_item(guard_account.clone());
+                new_guards.push(guard_account);
+            }
+        }
+
+        self.logger_mut().log_add_guard_accounts_event(&new_guards);
+
+        Ok(())
+    }
+
+    pub fn remove_guard_accounts(
+        &mut self,
+        guard_accounts: impl IntoIterator<Item = AccountId>,
+    ) -> Result<()> {
+        self.ensure_payable_api_resumed()?;
+        self.ensure_caller_is_owner()?;
+        let contract = self.contract_mut().latest();
+        let guards = &mut contract.guards;
+        let mut removed_guards = Vec::new();
+
+        for guard_account in guard_accounts {
+            if guards.contains_item(&guard_account) {
+                guards.remove_item(&guard_account);
+                removed_guards.push(guard_account);
+            }
+        }
+
+        self.logger_mut()
+            .log_remove_guard_accounts_event(&removed_guards);
+
+        Ok(())
+    }
+
+    /// Allow accounts to open positions on behalf of other accounts, see `open_position_for`
+    #[allow(clippy::clone_on_copy)]
+    pub fn add_operator_accounts(
+        &mut self,
+        operator_accounts: impl IntoIterator<Item = AccountId>,
+    ) -> Result<()> {
+        self.ensure_payable_api_resumed()?;
+        self.ensure_caller_is_owner()?;
+        let StateMembersMut {
+            contract,
+            item_factory,
+            ..
+        } = self.members_mut();
+        let operators = contract
+            .latest()
+            .operators
+            .get_or_insert_with(|| item_factory.new_operators());
+        let mut new_operators = Vec::new();
+
+        for operator_account in operator_accounts {
+            if !operators.contains_item(&operator_account) {
+                operators.add_item(operator_account.clone());
+                new_operators.push(operator_account);
+            }
+        }
+
+        self.logger_mut()
+            .log_add_operator_accounts_event(&new_operators);
+
+        Ok(())
+    }
+
+    pub fn remove_operator_accounts(
+        &mut self,
+        operator_accounts: impl IntoIterator<Item = AccountId>,
+    ) -> Result<()> {
+        self.ensure_payable_api_resumed()?;
+        self.ensure_caller_is_owner()?;
+        let mut removed_operators = Vec::new();
+
+        if let Some(operators) = self.contract_mut().latest().operators.as_mut() {
+            for operator_account in operator_accounts {
+                if operators.contains_item(&operator_account) {
+                    operators.remove_item(&operator_account);
+                    removed_operators.push(operator_account);
+                }
+            }
+        }
+
+        self.logger_mut()
+            .log_remove_operator_accounts_event(&removed_operators);
+
+        Ok(())
+    }
+
+    /// Send tokens which are held by contract, but aren't accounted in any deposit
+    /// or pool reserves, e.g. transferred directly to contract by mistake, to contract owner.
+    /// Can be called only by owner.
+    ///
+    /// # Returns
+    /// Swept amount along with send result; if there's no surplus, nothing is sent
+    pub fn sweep_untracked(
+        &mut self,
+        token_id: &TokenId,
+        extra: S::SendTokensExtraParam,
+    ) -> Result<(Amount, Option<S::SendTokensResult>)> {
+        self.ensure_payable_api_resumed()?;
+        self.ensure_caller_is_owner()?;
+
+        let (accounted, actual) = self.solvency_report(token_id)?;
+        // Contract holding less than accounted is abnormal, but in any case
+        // we must not touch accounted funds
+        if actual <= accounted {
+            return Ok((Amount::zero(), None));
+        }
+        let surplus = actual - accounted;
+
+        let owner_id = self.contract().as_ref().owner_id.clone();
+        self.logger_mut()
+            .log_sweep_untracked_event(&owner_id, token_id, &surplus);
+
+        let result = self.send_tokens(&owner_id, &owner_id, token_id, surplus, false, extra);
+        Ok((surplus, Some(result)))
+    }
+
+    /// Compare amount of token accounted by contract state with amount actually held by contract
+    ///
+    /// # Returns
+    /// `(accounted, actual)`, see `get_accounted_balance` and `StateMut::get_contract_balance`.
+    /// Healthy contract always has `actual >= accounted`
+    pub fn solvency_report(&self, token_id: &TokenId) -> Result<(Amount, Amount)> {
+        Ok((
+            self.get_accounted_balance(token_id)?,
+            self.get_contract_balance(token_id),
+        ))
+    }
+
+    /// Start tracking running totals of accounted tokens in contract upgraded from
+    /// earlier version. Owner only; payable API must be suspended, so that balances
+    /// don't change while totals are collected.
+    ///
+    /// Each call counts deposits of `accounts` and reserves of `pools`, skipping already
+    /// counted ones, so large contracts are processed in several calls. Tracking starts
+    /// once all registered accounts and existing pools are counted; resuming payable API
+    /// before that discards collected totals.
+    ///
+    /// Returns whether totals are tracked
+    pub fn init_accounted_balances(
+        &mut self,
+        accounts: impl IntoIterator<Item = AccountId>,
+        pools: impl IntoIterator<Item = (TokenId, TokenId)>,
+    ) -> Result<bool> {
+        self.ensure_caller_is_owner()?;
+        ensure_here!(
+            self.contract().as_ref().suspended,
+            ErrorKind::PayableAPINotSuspended
+        );
+        let StateMembersMut {
+            contract,
+            item_factory,
+            ..
+        } = self.members_mut();
+        let contract = contract.latest();
+        if contract.accounted_balances.is_some() {
+            return Ok(true);
+        }
+
+        let totals = contract
+            .accounted_balances_init
+            .get_or_insert_with(|| item_factory.new_accounted_balances_map());
+        let counted_accounts = contract
+            .accounted_balances_init_accounts
+            .get_or_insert_with(|| item_factory.new_accounted_balances_init_accounts());
+        let counted_pools = contract
+            .accounted_balances_init_pools
+            .get_or_insert_with(|| item_factory.new_accounted_balances_init_pools());
+        let mut add = |token_id: &TokenId, amount: Amount| {
+            totals.update_or_insert(
+                token_id,
+                || Ok(Amount::zero()),
+                |total, _| {
+                    *total = total
+                        .checked_add(amount)
+                        .ok_or_else(|| error_here!(ErrorKind::DepositWouldOverflow))?;
+                    Ok(())
+                },
+            )
+        };
+
+        for account_id in accounts {
+            if counted_accounts.contains_item(&account_id) {
+                continue;
+            }
+            contract
+                .accounts
+                .try_inspect(&account_id, |account| -> Result<()> {
+                    let account = account.as_ref();
+                    for (token_id, balance) in account.token_balances.iter() {
+                        add(&*token_id, *balance)?;
+                    }
+                    Ok(())
+                })??;
+            counted_accounts.add_item(account_id);
+        }
+
+        for tokens in pools {
+            let (pool_id, _) = PoolId::try_from_pair(tokens).map_err(|e| error_here!(e))?;
+            if counted_pools.contains_item(&pool_id) {
+                continue;
+            }
+            let (total_reserves, pending) = contract.pools.try_inspect(&pool_id, |pool| {
+                let (pending, _) = pool
+                    .as_latest()
+                    .and_then(|pool| pool.pending_protocol_fee)
+                    .unwrap_or_default();
+                (pool.total_reserves(), pending)
+            })?;
+            let (token_a, token_b) = pool_id.as_refs();
+            add(token_a, total_reserves.0)?;
+            add(token_a, pending.0)?;
+            add(token_b, total_reserves.1)?;
+            add(token_b, pending.1)?;
+            counted_pools.add_item(pool_id);
+        }
+
+        if counted_accounts.len() < contract.accounts.len()
+            || counted_pools.len() < contract.pools.len()
+        {
+            return Ok(false);
+        }
+        // Can't change while payable API is suspended, so are counted once all else is
+        for held in [
+            contract.collected_open_position_fees.as_ref(),
+            contract.gc_reward_funds.as_ref(),
+        ]
+        .into_iter()
+        .flatten()
+        {
+            for (token_id, amount) in held.iter() {
+                add(&*token_id, *amount)?;
+            }
+        }
+
+        contract.accounted_balances = contract.accounted_balances_init.take();
+        contract.accounted_balances_init_accounts = None;
+        contract.accounted_balances_init_pools = None;
+        Ok(true)
+    }
+
+    /// Send tokens out of contract, deducting them from accounted balance.
+    /// Fails with `AccountedBalanceUnderflow` if accounted balance is less than `amount`
+    fn send_accounted_tokens(
+        &mut self,
+        account_id: &AccountId,
+        recipient: &AccountId,
+        token_id: &TokenId,
+        amount: Amount,
+        unregister: bool,
+        extra: S::SendTokensExtraParam,
+    ) -> Result<S::SendTokensResult> {
+        self.contract_mut()
+            .latest()
+            .dec_accounted_balance(token_id, amount)?;
+        Ok(self.send_tokens(account_id, recipient, token_id, amount, unregister, extra))
+    }
+
+    pub fn is_operator(&self, account_id: &AccountId) -> bool {
+        self.contract()
+            .as_ref()
+            .operators
+            .map_or(false, |operators| operators.contains_item(account_id))
+    }
+
+    pub fn suspend_payable_api(&mut self) -> Result<()> {
+        self.ensure_caller_is_guard()?;
+        self.ensure_resumed()?;
+
+        let contract = self.contract_mut().latest();
+        contract.suspended = true;
+
+        let caller_id = self.get_caller_id();
+        self.logger_mut().log_suspend_payable_api_event(&caller_id);
+
+        Ok(())
+    }
+
+    pub fn resume_payable_api(&mut self) -> Result<()> {
+        self.ensure_caller_is_guard()?;
+        self.ensure_suspended()?;
+
+        let contract = self.contract_mut().latest();
+        contract.suspended = false;
+        // Balances may change from now on, so partially collected totals are stale
+        contract.accounted_balances_init = None;
+        contract.accounted_balances_init_accounts = None;
+        contract.accounted_balances_init_pools = None;
+
+        let caller_id = self.get_caller_id();
+        self.logger_mut().log_resume_payable_api_event(&caller_id);
+
+        Ok(())
+    }
+
+    /// Pause swaps in the pool over `tokens`, on behalf of owner or guard account.
+    /// Positions in paused pool can still be closed and their fees withdrawn.
+    pub fn pause_pool(&mut self, tokens: (TokenId, TokenId)) -> Result<()> {
+        self.ensure_caller_is_guard()?;
+        let (pool_id, _) = PoolId::try_from_pair(tokens).map_err(|e| error_here!(e))?;
+
+        let StateMembersMut {
+            contract,
+            item_factory,
+            ..
+        } = self.members_mut();
+        let contract = contract.latest();
+        contract.pools.try_inspect(&pool_id, |_| ())?;
+        contract
+            .paused_pools
+            .get_or_insert_with(|| item_factory.new_paused_pools())
+            .add_item(pool_id.clone());
+
+        let caller_id = self.get_caller_id();
+        self.logger_mut()
+            .log_pause_pool_event(pool_id.as_refs(), &caller_id);
+
+        Ok(())
+    }
+
+    /// Resume swaps in the pool over `tokens`, on behalf of owner or guard account
+    pub fn unpause_pool(&mut self, tokens: (TokenId, TokenId)) -> Result<()> {
+        self.ensure_caller_is_guard()?;
+        let (pool_id, _) = PoolId::try_from_pair(tokens).map_err(|e| error_here!(e))?;
+
+        if let Some(paused_pools) = self.contract_mut().latest().paused_pools.as_mut() {
+            paused_pools.remove_item(&pool_id);
+        }
+
+        let caller_id = self.get_caller_id();
+        self.logger_mut()
+            .log_unpause_pool_event(pool_id.as_refs(), &caller_id);
+
+        Ok(())
+    }
+
+    /// Restrict swaps in the pool over `tokens` to a single direction, or lift restriction
+    /// if `side` is `None`. `Side::Left` allows only swaps with first token as input.
+    /// Adding and removing liquidity isn't affected.
+    pub fn set_pool_allowed_swap_side(
+        &mut self,
+        tokens: (TokenId, TokenId),
+        side: Option<Side>,
+    ) -> Result<()> {
+        self.ensure_payable_api_resumed()?;
+        self.ensure_caller_is_owner()?;
+        let (pool_id, swapped) = PoolId::try_from_pair(tokens).map_err(|e| error_here!(e))?;
+
+        self.contract_mut()
+            .latest()
+            .pools
+            .try_update(&pool_id, |pool| {
+                let pool = pool.latest();
+                pool.allowed_swap_side = side.map(|side| side.opposite_if(swapped));
+                Ok(())
+            })
     }
-}
 
-impl<T: Types, S: StateMut<T>, SS: BorrowMut<S>> Dex<T, S, SS> {
-    fn with_account_mut<R>(
+    /// Set maximal number of price observations kept by the pool over `tokens`.
+    /// When reduced, oldest observations are dropped.
+    pub fn set_pool_observation_cardinality(
         &mut self,
-        account_id: &AccountId,
-        cb: impl FnOnce(AccountViewMut<'_, T>) -> Result<R>,
-    ) -> Result<R> {
-        let StateMembersMut {
-            contract,
-            item_factory,
-            logger,
-        } = self.members_mut();
-        let contract = contract.latest();
+        tokens: (TokenId, TokenId),
+        cardinality: u16,
+    ) -> Result<()> {
+        self.ensure_payable_api_resumed()?;
+        self.ensure_caller_is_owner()?;
+        ensure_here!(
+            (1..=MAX_OBSERVATION_CARDINALITY).contains(&cardinality),
+            ErrorKind::InvalidParams
+        );
+        let (pool_id, _) = PoolId::try_from_pair(tokens).map_err(|e| error_here!(e))?;
 
-        contract
-            .accounts
-            .try_update(account_id, |Account::V0(ref mut account)| {
-                cb(AccountViewMut {
-                    account_id,
-                    account,
-                    pools: &mut contract.pools,
-                    pool_count: &mut contract.pool_count,
-                    next_free_position_id: &mut contract.next_free_position_id,
-                    position_to_pool_id: &mut contract.position_to_pool_id,
-                    item_factory,
-                    logger,
-                })
+        self.contract_mut()
+            .latest()
+            .pools
+            .try_update(&pool_id, |pool| {
+                let pool = pool.latest();
+                pool.set_observation_cardinality(cardinality);
+                Ok(())
             })
     }
 
-    fn with_caller_account_mut<R>(
-        &mut self,
-        cb: impl FnOnce(AccountViewMut<'_, T>) -> Result<R>,
-    ) -> Result<R> {
-        let account_id = self.get_caller_id();
-        self.with_account_mut(&account_id, cb)
+    pub fn set_protocol_fee_fraction(&mut self, protocol_fee_fraction: BasisPoints) -> Result<()> {
+        self.ensure_payable_api_resumed()?;
+        self.ensure_caller_is_owner()?;
+        let protocol_fee_fraction =
+            validate_protocol_fee_fraction(protocol_fee_fraction).map_err(|e| error_here!(e))?;
+        let contract = self.contract_mut().latest();
+        let old_protocol_fee_fraction =
+            std::mem::replace(&mut contract.protocol_fee_fraction, protocol_fee_fraction);
+
+        let caller_id = self.get_caller_id();
+        self.logger_mut().log_set_protocol_fee_event(
+            old_protocol_fee_fraction,
+            protocol_fee_fraction,
+            &caller_id,
+        );
+
+        Ok(())
     }
-    /// Register caller's account in smart contract storage
-    ///
-    /// Same as `register_account_and_then` with `account_id: None` and no-op callback which always succeeds
-    ///
-    /// # Returns
-    /// * `Ok(())` if succeeds, `Err(_)` if fails, for some reason
-    pub fn register_account(&mut self) -> Result<()> {
-        self.register_account_and_then(None, |_, _, _| Ok(()))
+
+    /// Set extra protocol fee fraction applied to swaps, on behalf of owner or guard account.
+    /// Total fraction applied to swaps never exceeds `MAX_PROTOCOL_FEE_FRACTION`
+    pub fn set_swap_surcharge(&mut self, surcharge_bp: BasisPoints) -> Result<()> {
+        self.ensure_payable_api_resumed()?;
+        self.ensure_caller_is_guard()?;
+        ensure_here!(
+            surcharge_bp <= MAX_PROTOCOL_FEE_FRACTION,
+            ErrorKind::InvalidParams
+        );
+        let contract = self.contract_mut().latest();
+        let old_surcharge_bp = std::mem::replace(&mut contract.swap_surcharge_bp, surcharge_bp);
+
+        let caller_id = self.get_caller_id();
+        self.logger_mut()
+            .log_set_swap_surcharge_event(old_surcharge_bp, surcharge_bp, &caller_id);
+
+        Ok(())
     }
 
-    /// Register account and tokens if they weren't registered before.
-    /// Uses a caller ID as an account ID to register.
-    /// Used by platforms where we don't need to register account and tokens manually, during ops (cdex and dx25).
+    /// Set account which is allowed to push reference prices; `None` disallows everyone
+    pub fn set_reference_price_setter(&mut self, setter: Option<AccountId>) -> Result<()> {
+        self.ensure_payable_api_resumed()?;
+        self.ensure_caller_is_owner()?;
+        self.contract_mut().latest().reference_price_setter = setter;
+        Ok(())
+    }
+
+    /// Set maximal allowed deviation of pool spot price from reference price, in basis points;
+    /// `None` disables the check.
     ///
-    /// # Returns
-    /// * `Ok(())` if succeeds, `Err(_)` if fails, for some reason
-    #[cfg(not(feature = "near"))]
-    fn register_account_and_tokens(
+    /// Swaps are rejected if they leave the price outside of allowed band,
+    /// unless they move it closer to reference price.
+    pub fn set_max_oracle_deviation(
         &mut self,
-        account_id: Option<AccountId>,
-        tokens: &[TokenId],
+        max_deviation_bp: Option<BasisPoints>,
     ) -> Result<()> {
-        self.register_account_and_then(account_id, |_, &mut Account::V0(ref mut account), _| {
-            account.register_tokens(tokens);
-            Ok(())
-        })
+        self.ensure_payable_api_resumed()?;
+        self.ensure_caller_is_owner()?;
+        self.contract_mut().latest().max_oracle_deviation_bp = max_deviation_bp;
+        Ok(())
     }
-    /// Register token holder account and invoke provided callback over it
-    ///
-    /// # Arguments
-    /// * `account_id` - either identifier of new account or `None`, in which case caller id is used
-    /// * `register_cb` - callback which receives account identifier, mutable reference to account
-    ///     data structure, and boolean flag which is `true` if account already exists; if returns `Err(_)`,
-    ///     whole account registration is deemed failure
+
+    /// Set reference price of pool, as price of first token expressed in second one;
+    /// `None` removes reference price, which disables the check for this pool.
     ///
-    /// # Returns
-    /// Either success result of `register_cb` or failure, either caused by account registration itself
-    /// or callback
-    pub fn register_account_and_then<R>(
+    /// Can only be called by reference price setter.
+    pub fn set_reference_price(
         &mut self,
-        account_id: impl Into<Option<AccountId>>,
-        register_cb: impl FnOnce(&AccountId, &mut Account<T>, bool /*exists*/) -> Result<R>,
-    ) -> Result<R> {
+        tokens: (TokenId, TokenId),
+        price: Option<Float>,
+    ) -> Result<()> {
         self.ensure_payable_api_resumed()?;
+        let caller_id = self.get_caller_id();
+        let (pool_id, swapped) = PoolId::try_from_pair(tokens).map_err(|e| error_here!(e))?;
 
-        let account_id: Option<AccountId> = account_id.into();
-        let account_id = account_id.unwrap_or_else(|| self.get_caller_id());
         let StateMembersMut {
             contract,
             item_factory,
             ..
         } = self.members_mut();
         let contract = contract.latest();
-        contract.accounts.update_or_insert(
-            &account_id,
-            || item_factory.new_account(),
-            |account, exists| register_cb(&account_id, account, exists),
-        )
-    }
-
-    /// Try unregister caller account, if one's found
-    ///
-    /// Equivalent to `unregister_account_with_cb(None, |_, _| Ok(()))`
-    pub fn unregister_account(&mut self) -> Result<Option<()>> {
-        self.unregister_account_with_cb(None, |_, _| Ok(()))
-    }
-
-    /// Try unregister user account from Dex
-    ///
-    /// If account identified by `account_id` parameter is found, it's first checked to not
-    /// track any withdrawals and have no tokens in store; `unregister_cb` is called as last check;
-    /// if all checks succeed, account is unregistered
-    ///
-    /// # Parameters
-    /// * `account_id` - either identifier of new account or `None`, in which case caller id is used
-    /// * `unregister_cb` - callback usable for additional checks before actually unregistering account;
-    ///     can cancel unregistration by returning error
-    ///
-    /// # Returns
-    /// * `Ok(None)` - if account isn't registered
-    /// * `Ok(Some(result))` - return value of unregister callback, if all checks succeeded
-    ///     and account was unregistered
-    /// * `Err(error)` - if any error occured, including one returned by unregister callback
-    pub fn unregister_account_with_cb<R>(
-        &mut self,
-        account_id: impl Into<Option<AccountId>>,
-        unregister_cb: impl FnOnce(&AccountId, &Account<T>) -> Result<R>,
-    ) -> Result<Option<R>> {
-        self.ensure_payable_api_resumed()?;
-
-        let account_id: Option<AccountId> = account_id.into();
-        let account_id = account_id.unwrap_or_else(|| self.get_caller_id());
-
-        let contract = self.contract_mut().latest();
+        ensure_here!(
+            contract.reference_price_setter.as_ref() == Some(&caller_id),
+            ErrorKind::PermissionDenied
+        );
 
-        contract
-            .accounts
-            .inspect(&account_id, |account| {
-                let Account::V0(ref acc) = account;
-                ensure_here!(
-                    acc.token_balances.is_empty(),
-                    ErrorKind::TokensStorageNotEmpty
-                );
-                ensure_here!(acc.positions.is_empty(), ErrorKind::UserHasPositions);
+        match price {
+            Some(price) => {
                 ensure_here!(
-                    !acc.withdraw_tracker.is_any_withdraw_in_progress(),
-                    ErrorKind::WithdrawInProgress
+                    price.is_normal() && price > Float::zero(),
+                    ErrorKind::InvalidParams
                 );
-                unregister_cb(&account_id, account)
-            })
-            .map(|r| {
-                if r.is_ok() {
-                    contract.accounts.remove(&account_id);
+                let price = if swapped { price.recip() } else { price };
+                contract
+                    .reference_prices
+                    .get_or_insert_with(|| item_factory.new_reference_prices_map())
+                    .insert(pool_id, price);
+            }
+            None => {
+                if let Some(reference_prices) = contract.reference_prices.as_mut() {
+                    reference_prices.remove(&pool_id);
                 }
-                r
-            })
-            .transpose()
+            }
+        }
+        Ok(())
     }
 
-    fn ensure_caller_is_owner(&self) -> Result<()> {
-        ensure_here!(
-            self.contract().as_ref().owner_id == &self.get_caller_id(),
-            ErrorKind::PermissionDenied
-        );
+    /// Set minimal time between swaps of the same account, in seconds; `None` disables the check
+    pub fn set_swap_cooldown(&mut self, swap_cooldown_secs: Option<u64>) -> Result<()> {
+        self.ensure_payable_api_resumed()?;
+        self.ensure_caller_is_owner()?;
+        self.contract_mut().latest().swap_cooldown_secs = swap_cooldown_secs;
         Ok(())
     }
 
-    fn ensure_suspended(&self) -> Result<()> {
-        ensure_here!(
-            self.contract().as_ref().suspended,
-            ErrorKind::GuardChangeStateDenied
-        );
+    /// Set maximal number of distinct tokens single account may have registered;
+    /// `None` removes the limit. Accounts already above the limit keep their tokens,
+    /// but can't register new ones until they unregister enough
+    pub fn set_max_tokens_per_account(&mut self, max_tokens: Option<u32>) -> Result<()> {
+        self.ensure_payable_api_resumed()?;
+        self.ensure_caller_is_owner()?;
+        self.contract_mut().latest().max_tokens_per_account = max_tokens;
         Ok(())
     }
 
-    fn ensure_resumed(&self) -> Result<()> {
-        ensure_here!(
-            !self.contract().as_ref().suspended,
-            ErrorKind::GuardChangeStateDenied
-        );
+    /// Set maximal number of ticks single swap may cross; `None` removes the limit
+    pub fn set_max_swap_tick_crossings(&mut self, max_crossings: Option<u32>) -> Result<()> {
+        self.ensure_payable_api_resumed()?;
+        self.ensure_caller_is_owner()?;
+        self.contract_mut().latest().max_swap_tick_crossings = max_crossings;
         Ok(())
     }
 
-    fn ensure_caller_is_guard(&self) -> Result<()> {
-        let caller = self.get_caller_id();
-        let contract = self.contract().as_ref();
-        ensure_here!(
-            contract.owner_id == &caller || contract.guards.contains_item(&caller),
-            ErrorKind::PermissionDenied
-        );
+    pub fn get_max_swap_tick_crossings(&self) -> Option<u32> {
+        self.contract().as_ref().max_swap_tick_crossings
+    }
+
+    /// Set delay between request and payout of protocol fee withdrawal, in seconds.
+    /// With non-zero delay, protocol fee can only be withdrawn via
+    /// `request_protocol_fee_withdrawal` and `execute_protocol_fee_withdrawal`
+    pub fn set_protocol_fee_withdrawal_delay(&mut self, delay_secs: u64) -> Result<()> {
+        self.ensure_payable_api_resumed()?;
+        self.ensure_caller_is_owner()?;
+        self.contract_mut()
+            .latest()
+            .protocol_fee_withdrawal_delay_secs = delay_secs;
         Ok(())
     }
 
-    pub(crate) fn ensure_payable_api_resumed(&self) -> Result<()> {
-        ensure_here!(
-            !self.contract().as_ref().suspended,
-            ErrorKind::PayableAPISuspended
-        );
+    pub fn get_protocol_fee_withdrawal_delay(&self) -> u64 {
+        self.contract().as_ref().protocol_fee_withdrawal_delay_secs
+    }
+
+    /// Set bitmask of event categories which are emitted, see `EVENTS_*` constants;
+    /// events not belonging to any category are always emitted
+    pub fn set_event_verbosity(&mut self, event_verbosity: u8) -> Result<()> {
+        self.ensure_payable_api_resumed()?;
+        self.ensure_caller_is_owner()?;
+        self.contract_mut().latest().event_verbosity = event_verbosity;
         Ok(())
     }
 
-    pub fn add_verified_tokens(&mut self, tokens: impl IntoIterator<Item = TokenId>) -> Result<()> {
+    /// Set flat fee charged from deposit of account opening position, as `amount` of `token_id`,
+    /// regardless of pool tokens. Fee is kept apart from pools' reserves and protocol fees,
+    /// and is collected with `withdraw_open_position_fees`. Zero `amount` disables the fee
+    pub fn set_open_position_fee(&mut self, token_id: TokenId, amount: Amount) -> Result<()> {
+        self.ensure_payable_api_resumed()?;
+        self.ensure_caller_is_owner()?;
+        self.contract_mut().latest().open_position_fee =
+            (!amount.is_zero()).then_some((token_id, amount));
+        Ok(())
+    }
+
+    /// Credit position opening fees collected in `token_id` to owner's deposit.
+    ///
+    /// # Returns
+    /// Credited amount
+    pub fn withdraw_open_position_fees(&mut self, token_id: &TokenId) -> Result<Amount> {
         self.ensure_payable_api_resumed()?;
         self.ensure_caller_is_owner()?;
         let contract = self.contract_mut().latest();
-        let verified_tokens = &mut contract.verified_tokens;
-        let mut new_tokens = Vec::new();
 
-        for token in tokens {
-            if !verified_tokens.contains_item(&token) {
-                verified_tokens.add_item(token.clone());
-                new_tokens.push(token);
-            }
+        let Some(collected) = contract.collected_open_position_fees.as_mut() else {
+            return Ok(Amount::zero());
+        };
+        let amount = collected
+            .inspect(token_id, |amount| *amount)
+            .unwrap_or_default();
+        if amount.is_zero() {
+            return Ok(amount);
+        }
+        collected.remove(token_id);
+        contract
+            .accounts
+            .try_update(&contract.owner_id, |account| {
+                account
+                    .latest()
+                    .deposit(token_id, amount)
+                    .map_err(|e| error_here!(e))?;
+                Ok(())
+            })?;
+        Ok(amount)
+    }
+
+    /// Restore account from blob produced by `export_account`, e.g. when migrating
+    /// to new contract instance. Only owner may do this, while payable API is suspended,
+    /// and `account_id` must not be registered.
+    ///
+    /// Imported deposits must be already transferred to contract: contract's balance of each
+    /// token must cover its accounted balance along with imported deposit, otherwise fails
+    /// with `ImportedDepositNotFunded`.
+    ///
+    /// Positions are attached to account as is; pools which hold them must be restored separately.
+    /// Fails with `PositionAlreadyExists` if any of positions is already known to contract.
+    pub fn import_account(&mut self, account_id: &AccountId, data: &[u8]) -> Result<()> {
+        self.ensure_caller_is_owner()?;
+        ensure_here!(
+            self.contract().as_ref().suspended,
+            ErrorKind::PayableAPINotSuspended
+        );
+
+        #[allow(clippy::type_complexity)]
+        let (
+            version,
+            token_balances,
+            position_ids,
+            withdraw_tracker,
+            extra,
+            last_swap_ts,
+            idempotency_keys,
+            auto_unregister_empty,
+        ): (
+            u16,
+            Vec<(TokenId, Amount)>,
+            Vec<PositionId>,
+            T::AccountWithdrawTracker,
+            T::AccountExtra,
+            u64,
+            Vec<IdempotencyKey>,
+            bool,
+        ) = TopDecode::top_decode(data)
+            .map_err(|_| error_here!(ErrorKind::InvalidAccountExport))?;
+        ensure_here!(
+            version == ACCOUNT_EXPORT_VERSION,
+            ErrorKind::InvalidAccountExport
+        );
+        ensure_here!(
+            idempotency_keys.len() <= MAX_IDEMPOTENCY_KEYS,
+            ErrorKind::InvalidAccountExport
+        );
+        ensure_here!(
+            token_balances
+                .iter()
+                .map(|(token_id, _)| token_id)
+                .all_unique(),
+            ErrorKind::InvalidAccountExport
+        );
+
+        // Imported deposits must be backed by tokens actually received by contract
+        for (token_id, amount) in &token_balances {
+            let required = self
+                .get_accounted_balance(token_id)?
+                .checked_add(*amount)
+                .ok_or_else(|| error_here!(ErrorKind::DepositWouldOverflow))?;
+            ensure_here!(
+                self.get_contract_balance(token_id) >= required,
+                ErrorKind::ImportedDepositNotFunded
+            );
+        }
+
+        let StateMembersMut {
+            contract,
+            item_factory,
+            ..
+        } = self.members_mut();
+        let contract = contract.latest();
+        for position_id in &position_ids {
+            ensure_here!(
+                !contract.position_to_pool_id.contains_key(position_id)
+                    && !contract
+                        .position_owners
+                        .as_ref()
+                        .map_or(false, |owners| owners.contains_key(position_id)),
+                ErrorKind::PositionAlreadyExists
+            );
         }
 
-        self.logger_mut().log_add_verified_tokens_event(&new_tokens);
-
-        Ok(())
+        contract.accounts.update_or_insert(
+            account_id,
+            || item_factory.new_account(),
+            |account, exists| {
+                ensure_here!(!exists, ErrorKind::AccountAlreadyRegistered);
+                let account = account.latest();
+                for (token_id, amount) in &token_balances {
+                    account.token_balances.insert(token_id.clone(), *amount);
+                }
+                for position_id in &position_ids {
+                    account.positions.add_item(*position_id);
+                }
+                account.withdraw_tracker = withdraw_tracker;
+                account.extra = extra;
+                account.last_swap_ts = last_swap_ts;
+                account.idempotency_keys = idempotency_keys;
+                account.auto_unregister_empty = auto_unregister_empty;
+                Ok(())
+            },
+        )?;
+        // New positions must not reuse imported ids
+        if let Some(max_position_id) = position_ids.iter().max() {
+            contract.next_free_position_id =
+                contract.next_free_position_id.max(max_position_id + 1);
+        }
+        // Imported deposits are held by contract from now on
+        for (token_id, amount) in &token_balances {
+            contract.inc_accounted_balance(token_id, *amount)?;
+        }
+        self.index_position_owners([account_id.clone()])
     }
 
-    pub fn remove_verified_tokens(
+    /// Record owners of all positions of `accounts` in position owners index.
+    /// Needed for positions opened before the index was introduced. Owner only.
+    pub fn index_position_owners(
         &mut self,
-        tokens: impl IntoIterator<Item = TokenId>,
+        accounts: impl IntoIterator<Item = AccountId>,
     ) -> Result<()> {
-        self.ensure_payable_api_resumed()?;
         self.ensure_caller_is_owner()?;
-        let contract = self.contract_mut().latest();
-        let verified_tokens = &mut contract.verified_tokens;
-        let mut removed_tokens = Vec::new();
-        for token in tokens {
-            if verified_tokens.contains_item(&token) {
-                verified_tokens.remove_item(&token);
-                removed_tokens.push(token);
-            }
+        let StateMembersMut {
+            contract,
+            item_factory,
+            ..
+        } = self.members_mut();
+        let contract = contract.latest();
+        let position_owners = contract
+            .position_owners
+            .get_or_insert_with(|| item_factory.new_position_owners_map());
+
+        for account_id in accounts {
+            contract.accounts.try_inspect(&account_id, |account| {
+                let account = account.as_ref();
+                for position_id in account.positions.iter() {
+                    position_owners.insert(*position_id, account_id.clone());
+                }
+            })?;
         }
-
-        self.logger_mut()
-            .log_remove_verified_tokens_event(&removed_tokens);
-
         Ok(())
     }
 
-    pub fn get_verified_tokens(&self) -> Vec<TokenId> {
-        self.contract()
-            .as_ref()
-            .verified_tokens
-            .iter()
-            .map(|token| token.clone())
-            .collect()
-    }
-
-    #[allow(clippy::clone_on_copy)]
-    pub fn add_guard_accounts(
+    /// Configure closing of dust positions by third parties, see `gc_dust_position`.
+    /// Zero `dust_liquidity_threshold` disables it.
+    pub fn set_dust_gc_config(
         &mut self,
-        guard_accounts: impl IntoIterator<Item = AccountId>,
+        dust_liquidity_threshold: Liquidity,
+        gc_reward: Amount,
     ) -> Result<()> {
         self.ensure_payable_api_resumed()?;
         self.ensure_caller_is_owner()?;
         let contract = self.contract_mut().latest();
-        let guards = &mut contract.guards;
-        let mut new_guards = Vec::new();
-
-        for guard_account in guard_accounts {
-            if !guards.contains_item(&guard_account) {
-                guards.add_item(guard_account.clone());
-                new_guards.push(guard_account);
-            }
-        }
+        contract.dust_liquidity_threshold = dust_liquidity_threshold;
+        contract.gc_reward = gc_reward;
+        Ok(())
+    }
 
-        self.logger_mut().log_add_guard_accounts_event(&new_guards);
+    /// Move `amount` of token from owner's deposit to `gc_reward_funds`,
+    /// out of which `gc_dust_position` rewards are paid. Owner only
+    pub fn fund_gc_rewards(&mut self, token_id: &TokenId, amount: Amount) -> Result<()> {
+        self.ensure_payable_api_resumed()?;
+        self.ensure_caller_is_owner()?;
+        let StateMembersMut {
+            contract,
+            item_factory,
+            ..
+        } = self.members_mut();
+        let contract = contract.latest();
 
-        Ok(())
+        contract
+            .accounts
+            .try_update(&contract.owner_id, |account| {
+                account
+                    .latest()
+                    .withdraw(token_id, amount)
+                    .map_err(|e| error_here!(e))?;
+                Ok(())
+            })?;
+        contract
+            .gc_reward_funds
+            .get_or_insert_with(|| item_factory.new_gc_reward_funds_map())
+            .update_or_insert(
+                token_id,
+                || Ok(Amount::zero()),
+                |fund, _| {
+                    *fund = fund
+                        .checked_add(amount)
+                        .ok_or_else(|| error_here!(ErrorKind::DepositWouldOverflow))?;
+                    Ok(())
+                },
+            )
     }
 
-    pub fn remove_guard_accounts(
-        &mut self,
-        guard_accounts: impl IntoIterator<Item = AccountId>,
-    ) -> Result<()> {
+    /// Return whole fund of token in `gc_reward_funds` to owner's deposit. Owner only
+    ///
+    /// # Returns
+    /// Returned amount
+    pub fn withdraw_gc_reward_funds(&mut self, token_id: &TokenId) -> Result<Amount> {
         self.ensure_payable_api_resumed()?;
         self.ensure_caller_is_owner()?;
         let contract = self.contract_mut().latest();
-        let guards = &mut contract.guards;
-        let mut removed_guards = Vec::new();
 
-        for guard_account in guard_accounts {
-            if guards.contains_item(&guard_account) {
-                guards.remove_item(&guard_account);
-                removed_guards.push(guard_account);
-            }
+        let Some(funds) = contract.gc_reward_funds.as_mut() else {
+            return Ok(Amount::zero());
+        };
+        let amount = funds.inspect(token_id, |fund| *fund).unwrap_or_default();
+        if amount.is_zero() {
+            return Ok(amount);
         }
+        funds.remove(token_id);
+        contract
+            .accounts
+            .try_update(&contract.owner_id, |account| {
+                account
+                    .latest()
+                    .deposit(token_id, amount)
+                    .map_err(|e| error_here!(e))?;
+                Ok(())
+            })?;
+        Ok(amount)
+    }
 
-        self.logger_mut()
-            .log_remove_guard_accounts_event(&removed_guards);
-
+    /// Set maximal number of actions accepted in a single batch.
+    /// Zero is rejected, as it would block all batches, including deposits
+    pub fn set_max_actions_per_batch(&mut self, max_actions_per_batch: u16) -> Result<()> {
+        self.ensure_payable_api_resumed()?;
+        self.ensure_caller_is_owner()?;
+        ensure_here!(max_actions_per_batch > 0, ErrorKind::InvalidParams);
+        self.contract_mut().latest().max_actions_per_batch = max_actions_per_batch;
         Ok(())
     }
 
-    pub fn suspend_payable_api(&mut self) -> Result<()> {
-        self.ensure_caller_is_guard()?;
-        self.ensure_resumed()?;
-
-        let contract = self.contract_mut().latest();
-        contract.suspended = true;
-
-        let caller_id = self.get_caller_id();
-        self.logger_mut().log_suspend_payable_api_event(&caller_id);
+    pub fn get_max_actions_per_batch(&self) -> u16 {
+        self.contract().as_ref().max_actions_per_batch
+    }
 
+    fn ensure_batch_size_allowed<A>(&self, actions: &[A]) -> Result<()> {
+        ensure_here!(
+            actions.len() <= usize::from(self.get_max_actions_per_batch()),
+            ErrorKind::BatchTooLarge
+        );
         Ok(())
     }
 
-    pub fn resume_payable_api(&mut self) -> Result<()> {
-        self.ensure_caller_is_guard()?;
-        self.ensure_suspended()?;
-
-        let contract = self.contract_mut().latest();
-        contract.suspended = false;
-
-        let caller_id = self.get_caller_id();
-        self.logger_mut().log_resume_payable_api_event(&caller_id);
+    /// Set fraction of amount-in which may be underpaid by trader in a swap due to numeric errors;
+    /// `None` restores default `SWAP_MAX_UNDERPAY`. Must not exceed `SWAP_MAX_UNDERPAY_LIMIT`
+    pub fn set_swap_max_underpay(&mut self, max_underpay: Option<Float>) -> Result<()> {
+        self.ensure_payable_api_resumed()?;
+        self.ensure_caller_is_owner()?;
+        if let Some(max_underpay) = max_underpay {
+            // Also rejects NaN
+            ensure_here!(
+                max_underpay >= Float::zero() && max_underpay <= SWAP_MAX_UNDERPAY_LIMIT,
+                ErrorKind::InvalidParams
+            );
+        }
+        self.contract_mut().latest().swap_max_underpay = max_underpay;
+        Ok(())
+    }
 
+    /// Set time after token verification during which swaps in pairs with it are rejected,
+    /// in seconds; zero disables the check
+    pub fn set_verified_token_grace(&mut self, grace_secs: u64) -> Result<()> {
+        self.ensure_payable_api_resumed()?;
+        self.ensure_caller_is_owner()?;
+        self.contract_mut().latest().verified_token_grace_secs = grace_secs;
         Ok(())
     }
 
-    pub fn set_protocol_fee_fraction(&mut self, protocol_fee_fraction: BasisPoints) -> Result<()> {
+    /// Register number of decimals of token, used to scale prices in views;
+    /// `None` removes registered value
+    pub fn set_token_decimals(&mut self, token_id: TokenId, decimals: Option<u8>) -> Result<()> {
         self.ensure_payable_api_resumed()?;
         self.ensure_caller_is_owner()?;
-        let contract = self.contract_mut().latest();
-        contract.protocol_fee_fraction =
-            validate_protocol_fee_fraction(protocol_fee_fraction).map_err(|e| error_here!(e))?;
+
+        let StateMembersMut {
+            contract,
+            item_factory,
+            ..
+        } = self.members_mut();
+        let contract = contract.latest();
+
+        match decimals {
+            Some(decimals) => contract
+                .token_decimals
+                .get_or_insert_with(|| item_factory.new_token_decimals_map())
+                .insert(token_id, decimals),
+            None => {
+                if let Some(token_decimals) = contract.token_decimals.as_mut() {
+                    token_decimals.remove(&token_id);
+                }
+            }
+        }
         Ok(())
     }
 
@@ -654,7 +2566,8 @@ impl<T: Types, S: StateMut<T>, SS: BorrowMut<S>> Dex<T, S, SS> {
         let contract = self.contract_mut().latest();
         contract
             .accounts
-            .try_update(&contract.owner_id, |Account::V0(ref mut account)| {
+            .try_update(&contract.owner_id, |account| {
+                let account = account.latest();
                 // Note: subtraction and deregistration will be reverted if the promise fails.
                 account
                     .withdraw(token_id, amount)
@@ -663,7 +2576,7 @@ impl<T: Types, S: StateMut<T>, SS: BorrowMut<S>> Dex<T, S, SS> {
         #[allow(clippy::clone_on_copy)] // Some blockchains have address copyable, some don't
         let owner_id = contract.owner_id.clone();
 
-        Ok(self.send_tokens(&owner_id, token_id, amount, false, extra))
+        self.send_accounted_tokens(&owner_id, &owner_id, token_id, amount, false, extra)
     }
 
     pub fn register_tokens<'a>(
@@ -673,12 +2586,11 @@ impl<T: Types, S: StateMut<T>, SS: BorrowMut<S>> Dex<T, S, SS> {
     ) -> Result<()> {
         self.ensure_payable_api_resumed()?;
         let contract = self.contract_mut().latest();
-        contract
-            .accounts
-            .try_update(account_id, |Account::V0(ref mut account)| {
-                account.register_tokens(tokens);
-                Ok(())
-            })
+        let max_tokens = contract.max_tokens_per_account;
+        contract.accounts.try_update(account_id, |account| {
+            let account = account.latest();
+            account.register_tokens_capped(tokens, max_tokens)
+        })
     }
 
     pub fn unregister_tokens<'a>(
@@ -688,10 +2600,24 @@ impl<T: Types, S: StateMut<T>, SS: BorrowMut<S>> Dex<T, S, SS> {
     ) -> Result<()> {
         self.ensure_payable_api_resumed()?;
         let contract = self.contract_mut().latest();
-        contract
+        contract.accounts.try_update(account_id, |account| {
+            let account = account.latest();
+            account.unregister_tokens(tokens)
+        })
+    }
+
+    /// Set caller's preference to unregister tokens automatically once their balance
+    /// becomes zero due to withdrawal or swap
+    pub fn set_auto_unregister(&mut self, enabled: bool) -> Result<()> {
+        self.ensure_payable_api_resumed()?;
+        let caller_id = self.get_caller_id();
+        self.contract_mut()
+            .latest()
             .accounts
-            .try_update(account_id, |Account::V0(ref mut account)| {
-                account.unregister_tokens(tokens)
+            .try_update(&caller_id, |account| {
+                let account = account.latest();
+                account.auto_unregister_empty = enabled;
+                Ok(())
             })
     }
 
@@ -713,16 +2639,17 @@ impl<T: Types, S: StateMut<T>, SS: BorrowMut<S>> Dex<T, S, SS> {
             contract, logger, ..
         } = self.members_mut();
         let contract = contract.latest();
-        contract
-            .accounts
-            .try_update(account_id, |Account::V0(ref mut account)| {
-                Self::deposit_impl(account_id, account, token_id, amount, logger)
-            })
+        let balance = contract.accounts.try_update(account_id, |account| {
+            let account = account.latest();
+            Self::deposit_impl(account_id, account, token_id, amount, logger)
+        })?;
+        contract.inc_accounted_balance(token_id, amount)?;
+        Ok(balance)
     }
 
     fn deposit_impl(
         account_id: &AccountId,
-        account: &mut AccountV0<T>,
+        account: &mut AccountLatest<T>,
         token_id: &TokenId,
         amount: Amount,
         logger: &mut dyn Logger,
@@ -734,6 +2661,10 @@ impl<T: Types, S: StateMut<T>, SS: BorrowMut<S>> Dex<T, S, SS> {
         Ok(balance)
     }
 
+    /// Withdraw `amount` of token from account.
+    ///
+    /// WARNING: zero `amount` withdraws whole balance rather than nothing.
+    /// Kept for compatibility, prefer `withdraw_v2` which makes intent explicit.
     pub fn withdraw(
         &mut self,
         account_id: &AccountId,
@@ -748,16 +2679,44 @@ impl<T: Types, S: StateMut<T>, SS: BorrowMut<S>> Dex<T, S, SS> {
         } = self.members_mut();
         let contract = contract.latest();
 
-        let sender = contract
-            .accounts
-            .try_update(account_id, |Account::V0(ref mut account)| {
-                Self::withdraw_impl(
-                    account_id, account, token_id, amount, unregister, extra, logger,
-                )
-            })?;
+        let sender = contract.accounts.try_update(account_id, |account| {
+            let account = account.latest();
+            Self::withdraw_impl(
+                account_id, account, token_id, amount, unregister, extra, logger,
+            )
+        })?;
 
-        Ok(sender.map(|func| func(self)))
+        sender.map(|func| func(self)).transpose()
+    }
+
+    /// Withdraw token from account according to `mode`.
+    ///
+    /// Unlike `withdraw`, `WithdrawMode::Exact` with zero amount and `WithdrawMode::None`
+    /// do nothing at all, and return `None`.
+    pub fn withdraw_v2(
+        &mut self,
+        account_id: &AccountId,
+        token_id: &TokenId,
+        mode: WithdrawMode,
+        unregister: bool,
+        extra: S::SendTokensExtraParam,
+    ) -> Result<Option<S::SendTokensResult>> {
+        self.ensure_payable_api_resumed()?;
+        let amount = match mode {
+            WithdrawMode::Exact(amount) => {
+                let amount: Amount = amount.into();
+                if amount.is_zero() {
+                    return Ok(None);
+                }
+                amount
+            }
+            // Zero amount means whole balance for `withdraw`
+            WithdrawMode::All => Amount::zero(),
+            WithdrawMode::None => return Ok(None),
+        };
+        self.withdraw(account_id, token_id, amount, unregister, extra)
     }
+
     /// Internal implementation of token withdrawal, including event logging
     /// and sending tokens to new owner
     ///
@@ -774,7 +2733,7 @@ impl<T: Types, S: StateMut<T>, SS: BorrowMut<S>> Dex<T, S, SS> {
     /// * `Ok(None)` - if requested amount was zero, and balance was zero too or token wasn't registered,
     /// * `Ok(Some(closure))` - if nonzero amount was withdrawn. `closure` will perform
     ///     actual tokens send and return that send result. So function callers should call it like
-    ///     `Self::withdraw_impl(...)?.map(|func| func(self))`
+    ///     `Self::withdraw_impl(...)?.map(|func| func(self)).transpose()`
     /// * `Err(_)` if any error happens on the way
     fn withdraw_impl(
         account_id: &AccountId,
@@ -784,7 +2743,7 @@ impl<T: Types, S: StateMut<T>, SS: BorrowMut<S>> Dex<T, S, SS> {
         unregister: bool,
         extra: S::SendTokensExtraParam,
         logger: &mut dyn Logger,
-    ) -> Result<Option<impl FnOnce(&mut Self) -> S::SendTokensResult>> {
+    ) -> Result<Option<impl FnOnce(&mut Self) -> Result<S::SendTokensResult>>> {
         // If amount is zero, we try withdraw all what remains
         let amount = if amount.is_zero() {
             // First, fetch balance
@@ -795,7 +2754,7 @@ impl<T: Types, S: StateMut<T>, SS: BorrowMut<S>> Dex<T, S, SS> {
                 // Balance is zero, unregister if requested and bail out
                 {
                     if balance == Amount::zero() {
-                        if unregister {
+                        if unregister || account.auto_unregister_empty {
                             account.unregister_tokens([token_id])?;
                         }
                         return Ok(None);
@@ -818,11 +2777,21 @@ impl<T: Types, S: StateMut<T>, SS: BorrowMut<S>> Dex<T, S, SS> {
         // Log event, happens regardless of transfer mode
         logger.log_withdraw_event(account_id, token_id, &amount, &new_balance);
 
+        // Emptied token is unregistered along with send, so it's restored if send fails
+        let unregister = unregister || (account.auto_unregister_empty && new_balance.is_zero());
+
         #[allow(clippy::clone_on_copy)] // not all account ids are copyable
         let account_id = account_id.clone();
         let token_id = token_id.clone();
         let sender = move |dex: &mut Self| {
-            dex.send_tokens(&account_id, &token_id, amount, unregister, extra)
+            dex.send_accounted_tokens(
+                &account_id,
+                &account_id,
+                &token_id,
+                amount,
+                unregister,
+                extra,
+            )
         };
         Ok(Some(sender))
     }
@@ -839,16 +2808,186 @@ impl<T: Types, S: StateMut<T>, SS: BorrowMut<S>> Dex<T, S, SS> {
         fee_rate: BasisPoints,
         position: PositionInit,
     ) -> Result<(PositionId, Amount, Amount, Liquidity)> {
-        self.ensure_payable_api_resumed()?;
-
-        // We need manual token registration for NEAR to supply storage maintanance fee
-        // Add other dex'es register account and tokens automatically
-        #[cfg(not(feature = "near"))]
-        self.register_account_and_tokens(None, &[token_a.clone(), token_b.clone()])?;
+        self.ensure_payable_api_resumed()?;
+
+        // We need manual token registration for NEAR to supply storage maintanance fee
+        // Add other dex'es register account and tokens automatically
+        #[cfg(not(feature = "near"))]
+        self.register_account_and_tokens(None, &[token_a.clone(), token_b.clone()])?;
+
+        self.with_caller_account_mut(|mut account_view| {
+            Self::open_position_impl(token_a, token_b, fee_rate, position, &mut account_view)
+        })
+    }
+
+    /// Open position on behalf of `owner`. Only operator accounts are allowed to do so.
+    ///
+    /// Tokens are taken from deposits of `owner`, and position is owned by `owner`.
+    /// Unlike `open_position`, neither account nor its tokens are registered automatically.
+    pub fn open_position_for(
+        &mut self,
+        owner: &AccountId,
+        token_a: &TokenId,
+        token_b: &TokenId,
+        fee_rate: BasisPoints,
+        position: PositionInit,
+    ) -> Result<(PositionId, Amount, Amount, Liquidity)> {
+        self.ensure_payable_api_resumed()?;
+        self.ensure_caller_is_operator()?;
+
+        self.with_account_mut(owner, |mut account_view| {
+            Self::open_position_impl(token_a, token_b, fee_rate, position, &mut account_view)
+        })
+    }
+
+    /// Same as `open_position`, but fails with `Slippage` if accounted net liquidity
+    /// is below `min_liquidity_out`, e.g. because price moved since liquidity was estimated.
+    /// Liquidity is checked with `preview_open_position` before opening, so nothing is changed
+    /// on failure.
+    pub fn open_position_with_min_liquidity(
+        &mut self,
+        token_a: &TokenId,
+        token_b: &TokenId,
+        fee_rate: BasisPoints,
+        position: PositionInit,
+        min_liquidity_out: Liquidity,
+    ) -> Result<(PositionId, Amount, Amount, Liquidity)> {
+        let caller_id = self.get_caller_id();
+        let (_, _, liquidity) =
+            self.preview_open_position(&caller_id, token_a, token_b, fee_rate, position.clone())?;
+        ensure_here!(liquidity >= min_liquidity_out, ErrorKind::Slippage);
+        self.open_position(token_a, token_b, fee_rate, position)
+    }
+
+    /// Same as `open_position`, but with `force_single_sided` set position range is narrowed
+    /// to lie entirely on one side of current spot price, so that only one token is deposited:
+    /// `token_a` for `Side::Left`, `token_b` for `Side::Right`. Minimal amount of the other token
+    /// is ignored. Fails with `SingleSidedRangeConflict` if range lies entirely on the wrong side.
+    pub fn open_position_single_sided(
+        &mut self,
+        token_a: &TokenId,
+        token_b: &TokenId,
+        fee_rate: BasisPoints,
+        mut position: PositionInit,
+        force_single_sided: Option<Side>,
+    ) -> Result<(PositionId, Amount, Amount, Liquidity)> {
+        let Some(side) = force_single_sided else {
+            return self.open_position(token_a, token_b, fee_rate, position);
+        };
+        let fee_level = as_fee_level(
+            fee_rates_ticks()
+                .iter()
+                .position(|r| *r == fee_rate)
+                .ok_or_else(|| error_here!(ErrorKind::IllegalFee))?,
+        );
+        let (pool_id, swapped) = PoolId::try_from_pair((token_a.clone(), token_b.clone()))
+            .map_err(|e| error_here!(e))?;
+        let spot_sqrtprice = self
+            .contract()
+            .as_ref()
+            .pools
+            .try_inspect(&pool_id, |pool| {
+                pool.spot_sqrtprice(if swapped { Side::Right } else { Side::Left }, fee_level)
+            })?;
+
+        let (tick_low, tick_high) =
+            Tick::unwrap_range(position.ticks_range).map_err(|e| error_here!(e))?;
+        let nearest = Tick::nearest_to_spot_sqrtprice(spot_sqrtprice);
+        let (tick_low, tick_high, other_range) = match side {
+            // Range at or below spot price holds only `token_a`
+            Side::Left => {
+                let below = if nearest.spot_sqrtprice() <= spot_sqrtprice {
+                    nearest
+                } else {
+                    Tick::new(nearest.index() - 1).map_err(|e| error_here!(e))?
+                };
+                (
+                    tick_low,
+                    tick_high.min(below),
+                    &mut position.amount_ranges.1,
+                )
+            }
+            // Range at or above spot price holds only `token_b`
+            Side::Right => {
+                let above = if nearest.spot_sqrtprice() >= spot_sqrtprice {
+                    nearest
+                } else {
+                    Tick::new(nearest.index() + 1).map_err(|e| error_here!(e))?
+                };
+                (
+                    tick_low.max(above),
+                    tick_high,
+                    &mut position.amount_ranges.0,
+                )
+            }
+        };
+        ensure_here!(tick_low < tick_high, ErrorKind::SingleSidedRangeConflict);
+        other_range.min = Amount::zero().into();
+        position.ticks_range = (Some(tick_low.index()), Some(tick_high.index()));
+
+        let opened = self.open_position(token_a, token_b, fee_rate, position)?;
+        let other_deposit = match side {
+            Side::Left => opened.2,
+            Side::Right => opened.1,
+        };
+        ensure_here!(other_deposit.is_zero(), ErrorKind::InternalLogicError);
+        Ok(opened)
+    }
+
+    /// Same as `open_position`, but position range is specified by prices of `token_b`
+    /// expressed in `token_a`, same as `get_spot_price_scaled`: in whole tokens
+    /// if decimals of both tokens are registered, otherwise raw, i.e. ratio of smallest units.
+    /// Prices are converted to the nearest ticks; up to `amount_a` and `amount_b` are deposited.
+    #[allow(clippy::too_many_arguments)]
+    pub fn open_position_by_price(
+        &mut self,
+        token_a: &TokenId,
+        token_b: &TokenId,
+        fee_rate: BasisPoints,
+        price_low: Float,
+        price_high: Float,
+        amount_a: Amount,
+        amount_b: Amount,
+    ) -> Result<(PositionId, Amount, Amount, Liquidity)> {
+        ensure_here!(
+            price_low.is_normal()
+                && price_high.is_normal()
+                && price_low > Float::zero()
+                && price_low < price_high,
+            ErrorKind::InvalidParams
+        );
+        // Inverse of `get_spot_price_scaled` scaling
+        let raw_scale = match (
+            self.get_token_decimals(token_a),
+            self.get_token_decimals(token_b),
+        ) {
+            (Some(decimals_a), Some(decimals_b)) => {
+                Float::from(10.0).powi(i32::from(decimals_a) - i32::from(decimals_b))
+            }
+            _ => Float::one(),
+        };
+        let tick_low = Tick::nearest_to_spot_sqrtprice((price_low * raw_scale).sqrt());
+        let tick_high = Tick::nearest_to_spot_sqrtprice((price_high * raw_scale).sqrt());
+        ensure_here!(tick_low < tick_high, ErrorKind::InvalidParams);
 
-        self.with_caller_account_mut(|mut account_view| {
-            Self::open_position_impl(token_a, token_b, fee_rate, position, &mut account_view)
-        })
+        self.open_position(
+            token_a,
+            token_b,
+            fee_rate,
+            PositionInit {
+                amount_ranges: (
+                    Range {
+                        min: Amount::zero().into(),
+                        max: amount_a.into(),
+                    },
+                    Range {
+                        min: Amount::zero().into(),
+                        max: amount_b.into(),
+                    },
+                ),
+                ticks_range: Tick::wrap_range((tick_low, tick_high)),
+            },
+        )
     }
 
     #[allow(clippy::too_many_lines)] // FIXME: refactor
@@ -864,7 +3003,8 @@ impl<T: Types, S: StateMut<T>, SS: BorrowMut<S>> Dex<T, S, SS> {
         let (pool_id, transposed) = PoolId::try_from_pair((token_a.clone(), token_b.clone()))
             .map_err(|e| error_here!(e))?;
 
-        if !account_view.pools.contains_key(&pool_id) {
+        let is_new_pool = !account_view.pools.contains_key(&pool_id);
+        if is_new_pool {
             account_view.account.extra.on_pool_created()?;
         }
 
@@ -884,20 +3024,24 @@ impl<T: Types, S: StateMut<T>, SS: BorrowMut<S>> Dex<T, S, SS> {
             .try_into()
             .map_err(|_| error_here!(ErrorKind::ConvOverflow))?;
 
-        let (deposited_amounts, accounted_net_liquidity) = account_view.pools.update_or_insert(
+        let (deposited_amounts, net_liquidity, tick_bounds) = account_view.pools.update_or_insert(
             &pool_id,
             || {
                 *account_view.pool_count += 1;
                 let pool = factory.borrow_mut().new_pool()?;
                 Ok(pool)
             },
-            |Pool::V0(ref mut pool), _| {
+            |pool, _| {
+                let pool = pool.latest();
                 let PositionOpenedInfo {
                     deposited_amounts,
+                    rounding_residual,
                     net_liquidity,
                     low_tick_liquidity_change,
                     high_tick_liquidity_change,
                 } = pool.open_position(position, fee_level, position_id, *factory.borrow_mut())?;
+                pool.acc_position_rounding_residual.0 += rounding_residual.0;
+                pool.acc_position_rounding_residual.1 += rounding_residual.1;
 
                 ensure_here!(
                     !account_view.account.positions.contains_item(&position_id),
@@ -915,12 +3059,45 @@ impl<T: Types, S: StateMut<T>, SS: BorrowMut<S>> Dex<T, S, SS> {
                     .withdraw(&pool_id.1, deposited_amounts.1)
                     .map_err(|e| error_here!(e))?;
 
+                if let Some((fee_token, fee_amount)) = &account_view.open_position_fee {
+                    account_view
+                        .account
+                        .withdraw(fee_token, *fee_amount)
+                        .map_err(|e| error_here!(e))?;
+                    account_view
+                        .collected_open_position_fees
+                        .get_or_insert_with(|| {
+                            account_view
+                                .item_factory
+                                .new_collected_open_position_fees_map()
+                        })
+                        .update_or_insert(
+                            fee_token,
+                            || Ok(Amount::zero()),
+                            |collected, _| {
+                                *collected = collected
+                                    .checked_add(*fee_amount)
+                                    .ok_or_else(|| error_here!(ErrorKind::DepositWouldOverflow))?;
+                                Ok(())
+                            },
+                        )?;
+                }
+
                 account_view.account.positions.add_item(position_id);
 
                 account_view
                     .position_to_pool_id
                     .insert(position_id, pool_id.clone());
 
+                if is_new_pool {
+                    account_view.logger.log_pool_created_event(
+                        pool_id.as_refs(),
+                        fee_level,
+                        pool.spot_sqrtprice(Side::Left, fee_level),
+                        account_view.account_id,
+                    );
+                }
+
                 for (tick, liquidity_change) in
                     [low_tick_liquidity_change, high_tick_liquidity_change]
                 {
@@ -944,24 +3121,46 @@ impl<T: Types, S: StateMut<T>, SS: BorrowMut<S>> Dex<T, S, SS> {
                     position_id,
                     (tick_low, tick_high),
                 );
+                if let Some((fee_token, fee_amount)) = &account_view.open_position_fee {
+                    account_view.logger.log_open_position_fee_event(
+                        account_view.account_id,
+                        position_id,
+                        fee_token,
+                        fee_amount,
+                    );
+                }
 
                 Self::log_pool_v0_state(
                     &pool_id,
                     pool,
                     account_view.logger,
+                    account_view.event_verbosity,
                     PoolUpdateReason::AddLiquidity,
                 );
 
-                Ok((deposited_amounts, net_liquidity))
+                Ok((deposited_amounts, net_liquidity, (tick_low, tick_high)))
             },
         )?;
 
+        Self::register_position_bounds(
+            account_view.tick_positions,
+            account_view.item_factory,
+            &pool_id,
+            fee_level,
+            tick_bounds,
+            position_id,
+        )?;
+        account_view
+            .position_owners
+            .get_or_insert_with(|| account_view.item_factory.new_position_owners_map())
+            .insert(position_id, account_view.account_id.clone());
+
         let deposited_amounts_in_user_order = swap_if(transposed, deposited_amounts);
         Ok((
             position_id,
             deposited_amounts_in_user_order.0,
             deposited_amounts_in_user_order.1,
-            accounted_net_liquidity,
+            net_liquidity,
         ))
     }
 
@@ -1001,14 +3200,96 @@ impl<T: Types, S: StateMut<T>, SS: BorrowMut<S>> Dex<T, S, SS> {
     pub fn close_position(&mut self, position_id: PositionId) -> Result<()> {
         self.ensure_payable_api_resumed()?;
         self.with_caller_account_mut(|mut account_view| {
-            Self::close_position_impl(position_id, &mut account_view)
+            Self::close_position_impl(position_id, &mut account_view).map(|_| ())
+        })
+    }
+
+    /// Close caller's position and withdraw resulting balances of both pool tokens to caller's wallet
+    ///
+    /// Whole balances of the pool tokens are withdrawn, including ones held before closing.
+    /// Each withdrawal is performed as by `withdraw`, so failed sends are rolled back the same way.
+    ///
+    /// # Returns
+    /// * vector of `(token_id, amount, send_result)` for each pool token with nonzero balance
+    pub fn close_position_and_withdraw(
+        &mut self,
+        position_id: PositionId,
+        unregister_tokens: bool,
+        extra: S::SendTokensExtraParam,
+    ) -> Result<Vec<(TokenId, Amount, S::SendTokensResult)>>
+    where
+        S::SendTokensExtraParam: Clone,
+    {
+        self.ensure_payable_api_resumed()?;
+        let pool_id = self
+            .contract()
+            .as_ref()
+            .position_to_pool_id
+            .try_inspect(&position_id, Clone::clone)?;
+
+        self.close_position(position_id)?;
+
+        let account_id = self.get_caller_id();
+        let mut sends = Vec::new();
+        for token_id in [&pool_id.0, &pool_id.1] {
+            let amount = self.get_deposit(&account_id, token_id)?;
+            if let Some(send_result) = self.withdraw(
+                &account_id,
+                token_id,
+                amount,
+                unregister_tokens,
+                extra.clone(),
+            )? {
+                sends.push((token_id.clone(), amount, send_result));
+            }
+        }
+        Ok(sends)
+    }
+
+    /// Close all caller's positions in the pool with specified tokens, harvesting their fees
+    ///
+    /// # Returns
+    /// * vector of `(position_id, amount_a, amount_b)`, where amounts are total payouts
+    ///   (position balance plus fees) in the order of `tokens`;
+    ///   empty if caller has no positions in the pool
+    pub fn close_positions_in_pool(
+        &mut self,
+        tokens: (TokenId, TokenId),
+    ) -> Result<Vec<(PositionId, Amount, Amount)>> {
+        self.ensure_payable_api_resumed()?;
+        let (pool_id, swapped) = PoolId::try_from_pair(tokens).map_err(|e| error_here!(e))?;
+
+        self.with_caller_account_mut(|mut account_view| {
+            let position_ids: Vec<PositionId> = account_view
+                .account
+                .positions
+                .iter()
+                .map(|position_id| *position_id)
+                .filter(|position_id| {
+                    account_view
+                        .position_to_pool_id
+                        .inspect(position_id, |position_pool_id| *position_pool_id == pool_id)
+                        .unwrap_or(false)
+                })
+                .collect();
+
+            position_ids
+                .into_iter()
+                .map(|position_id| {
+                    let payout = Self::close_position_impl(position_id, &mut account_view)?;
+                    let payout = swap_if(swapped, payout);
+                    Ok((position_id, payout.0, payout.1))
+                })
+                .collect()
         })
     }
 
+    /// Returns total payout of the closed position, i.e. position balance plus fees,
+    /// in the pool's order of tokens
     fn close_position_impl(
         position_id: PositionId,
         account_view: &mut AccountViewMut<'_, T>,
-    ) -> Result<()> {
+    ) -> Result<(Amount, Amount)> {
         // Get pool_id and at the same time check if position exists
         let (pool_id, fees, amounts, tick_updates, fee_level) =
             account_view
@@ -1030,7 +3311,8 @@ impl<T: Types, S: StateMut<T>, SS: BorrowMut<S>> Dex<T, S, SS> {
                         // Inconsistent state: position is present in `position_to_pool_id`,
                         // but the pool doesn't exist
                         ErrorKind::InternalLogicError,
-                        |Pool::V0(ref mut pool)| {
+                        |pool| {
+                            let pool = pool.latest();
                             let PositionClosedInfo {
                                 fees,
                                 balance: amounts,
@@ -1059,6 +3341,17 @@ impl<T: Types, S: StateMut<T>, SS: BorrowMut<S>> Dex<T, S, SS> {
                 })??;
 
         account_view.position_to_pool_id.remove(&position_id);
+        if let Some(position_owners) = account_view.position_owners.as_mut() {
+            position_owners.remove(&position_id);
+        }
+
+        Self::unregister_position_bounds(
+            account_view.tick_positions.as_mut(),
+            &pool_id,
+            fee_level,
+            (tick_updates[0].0, tick_updates[1].0),
+            position_id,
+        )?;
 
         for (tick, liquidity_change) in tick_updates {
             account_view.logger.log_tick_update_event(
@@ -1075,15 +3368,129 @@ impl<T: Types, S: StateMut<T>, SS: BorrowMut<S>> Dex<T, S, SS> {
             .logger
             .log_close_position_event(position_id, amounts);
 
-        account_view.pools.inspect(&pool_id, |Pool::V0(ref pool)| {
+        account_view.pools.inspect(&pool_id, |pool| {
             Self::log_pool_v0_state(
                 &pool_id,
                 pool,
                 account_view.logger,
+                account_view.event_verbosity,
                 PoolUpdateReason::RemoveLiquidity,
             );
         });
 
+        Ok((amounts.0 + fees.0, amounts.1 + fees.1))
+    }
+
+    /// Close position which net liquidity is below `dust_liquidity_threshold`, on behalf of its owner.
+    /// May be called by anyone; position's balance and fees are deposited to its owner.
+    ///
+    /// Caller must be registered, and is paid `gc_reward` of left pool token out of
+    /// `gc_reward_funds`, see `fund_gc_rewards`, or whole fund of that token if it's smaller;
+    /// if reward is due, caller must have left pool token registered.
+    /// Pools' protocol fees aren't touched.
+    /// Fails with `PositionOwnerNotIndexed` for position opened before position owners
+    /// were indexed, until `index_position_owners` is called for its owner.
+    ///
+    /// # Returns
+    /// Amounts deposited to position's owner, in order of tokens in pool
+    pub fn gc_dust_position(&mut self, position_id: PositionId) -> Result<(Amount, Amount)> {
+        self.ensure_payable_api_resumed()?;
+
+        let caller_id = self.get_caller_id();
+        let contract = self.contract().as_ref();
+        contract.accounts.try_inspect(&caller_id, |_| ())?;
+        let pool_id = contract
+            .position_to_pool_id
+            .try_inspect(&position_id, Clone::clone)?;
+        let net_liquidity = contract.pools.try_inspect(&pool_id, |pool| {
+            PoolState::get_position(pool, position_id)
+                .map(|position| position.into_latest().net_liquidity)
+                .ok_or(error_here!(ErrorKind::PositionDoesNotExist))
+        })??;
+        ensure_here!(
+            net_liquidity < contract.dust_liquidity_threshold,
+            ErrorKind::PositionNotDust
+        );
+        let gc_reward = contract.gc_reward;
+        let owner = contract
+            .position_owners
+            .and_then(|position_owners| position_owners.inspect(&position_id, Clone::clone))
+            .ok_or(error_here!(ErrorKind::PositionOwnerNotIndexed))?;
+
+        let amounts = self.with_account_mut(&owner, |mut account_view| {
+            Self::close_position_impl(position_id, &mut account_view)
+        })?;
+
+        let contract = self.contract_mut().latest();
+        let reward = contract
+            .gc_reward_funds
+            .as_mut()
+            .and_then(|funds| {
+                funds.update(&pool_id.0, |fund| {
+                    let reward = (*fund).min(gc_reward);
+                    *fund = *fund - reward;
+                    Ok(reward)
+                })
+            })
+            .transpose()?
+            .unwrap_or_default();
+        if !reward.is_zero() {
+            contract.accounts.try_update(&caller_id, |account| {
+                let account = account.latest();
+                account
+                    .deposit(&pool_id.0, reward)
+                    .map_err(|e| error_here!(e))
+            })?;
+        }
+
+        Ok(amounts)
+    }
+
+    /// Last-resort removal of position which can't be closed because of inconsistent state,
+    /// i.e. its pool doesn't exist or doesn't know the position. Only bookkeeping entries
+    /// of position are removed, pool state isn't touched and no tokens are paid out.
+    /// Position is detached from its owner only if owner is indexed, see `index_position_owners`.
+    /// Owner only.
+    pub fn force_remove_position(&mut self, position_id: PositionId) -> Result<()> {
+        self.ensure_caller_is_owner()?;
+
+        let contract = self.contract().as_ref();
+        let pool_id = contract
+            .position_to_pool_id
+            .try_inspect(&position_id, Clone::clone)?;
+        let stuck = contract
+            .pools
+            .inspect(&pool_id, |pool| {
+                PoolState::get_position(pool, position_id).is_none()
+            })
+            .unwrap_or(true);
+        ensure_here!(stuck, ErrorKind::PositionNotStuck);
+
+        let owner = contract
+            .position_owners
+            .and_then(|position_owners| position_owners.inspect(&position_id, Clone::clone));
+
+        let contract = self.contract_mut().latest();
+        contract.position_to_pool_id.remove(&position_id);
+        if let Some(position_owners) = contract.position_owners.as_mut() {
+            position_owners.remove(&position_id);
+        }
+        if let Some(ref owner) = owner {
+            contract.accounts.try_update(owner, |account| {
+                let account = account.latest();
+                account.positions.remove_item(&position_id);
+                Ok(())
+            })?;
+        }
+
+        let caller_id = self.get_caller_id();
+        self.logger_mut().log_force_remove_position_event(
+            position_id,
+            pool_id.as_refs(),
+            owner.as_ref(),
+            &caller_id,
+        );
+
         Ok(())
     }
 
@@ -1094,6 +3501,57 @@ impl<T: Types, S: StateMut<T>, SS: BorrowMut<S>> Dex<T, S, SS> {
         })
     }
 
+    /// Withdraw fees of all caller's positions, in ascending order of ids after `start_after`.
+    ///
+    /// At most `MAX_FEE_HARVEST_BATCH` positions are processed per call. Positions without
+    /// accrued fees are skipped and not included in result.
+    ///
+    /// # Returns
+    /// Harvested `(position_id, fee_a, fee_b)`, with fees in order of pool tokens,
+    /// and id of last processed position if there are more positions left,
+    /// to be passed as `start_after` to the next call
+    #[allow(clippy::type_complexity)]
+    pub fn withdraw_all_fees(
+        &mut self,
+        start_after: Option<PositionId>,
+    ) -> Result<(Vec<(PositionId, Amount, Amount)>, Option<PositionId>)> {
+        self.ensure_payable_api_resumed()?;
+        self.with_caller_account_mut(|mut account_view| {
+            let mut position_ids = account_view
+                .account
+                .positions
+                .iter()
+                .map(|id| *id)
+                .filter(|id| start_after.map_or(true, |after| *id > after))
+                .collect_vec();
+            position_ids.sort_unstable();
+            let continuation = (position_ids.len() > MAX_FEE_HARVEST_BATCH)
+                .then(|| position_ids[MAX_FEE_HARVEST_BATCH - 1]);
+            position_ids.truncate(MAX_FEE_HARVEST_BATCH);
+
+            let mut harvested = Vec::new();
+            for position_id in position_ids {
+                let pool_id = account_view
+                    .position_to_pool_id
+                    .try_inspect(&position_id, Clone::clone)?;
+                let PositionInfo {
+                    reward_since_last_withdraw: fees,
+                    ..
+                } = account_view.pools.try_inspect_or(
+                    &pool_id,
+                    ErrorKind::InternalLogicError,
+                    |pool| pool.get_position_info(&pool_id, position_id),
+                )??;
+                if fees.0.is_zero() && fees.1.is_zero() {
+                    continue;
+                }
+                let (fee_a, fee_b) = Self::withdraw_fee_impl(position_id, &mut account_view)?;
+                harvested.push((position_id, fee_a, fee_b));
+            }
+            Ok((harvested, continuation))
+        })
+    }
+
     fn withdraw_fee_impl(
         position_id: PositionId,
         account_view: &mut AccountViewMut<'_, T>,
@@ -1114,7 +3572,8 @@ impl<T: Types, S: StateMut<T>, SS: BorrowMut<S>> Dex<T, S, SS> {
                     // Inconsistent state: position is present in `position_to_pool_id`,
                     // but the pool doesn't exist
                     ErrorKind::InternalLogicError,
-                    |Pool::V0(ref mut pool)| {
+                    |pool| {
+                        let pool = pool.latest();
                         let fees = pool.withdraw_fee(position_id)?;
                         account_view
                             .account
@@ -1144,31 +3603,194 @@ impl<T: Types, S: StateMut<T>, SS: BorrowMut<S>> Dex<T, S, SS> {
         let sender_id = self.get_caller_id();
         let contract = self.contract_mut().latest();
         ensure_here!(contract.owner_id == sender_id, ErrorKind::PermissionDenied);
+        ensure_here!(
+            contract.protocol_fee_withdrawal_delay_secs == 0,
+            ErrorKind::WithdrawalTimelockActive
+        );
 
         let (pool_id, swapped) = PoolId::try_from_pair(pool_id).map_err(|e| error_here!(e))?;
-        let protocol_fees = contract
-            .pools
-            .try_update(&pool_id, |Pool::V0(ref mut pool)| {
-                let protocol_fees = pool.withdraw_protocol_fee()?;
-
-                contract
-                    .accounts
-                    .try_update(&sender_id, |Account::V0(ref mut account)| {
-                        account
-                            .deposit(&pool_id.0, protocol_fees.0)
-                            .map_err(|e| error_here!(e))?;
-                        account
-                            .deposit(&pool_id.1, protocol_fees.1)
-                            .map_err(|e| error_here!(e))?;
+        let protocol_fees = contract.pools.try_update(&pool_id, |pool| {
+            let pool = pool.latest();
+            let protocol_fees = pool.withdraw_protocol_fee()?;
 
-                        Ok(())
-                    })?;
+            contract.accounts.try_update(&sender_id, |account| {
+                let account = account.latest();
+                account
+                    .deposit(&pool_id.0, protocol_fees.0)
+                    .map_err(|e| error_here!(e))?;
+                account
+                    .deposit(&pool_id.1, protocol_fees.1)
+                    .map_err(|e| error_here!(e))?;
 
-                Ok(protocol_fees)
+                Ok(())
             })?;
+
+            Ok(protocol_fees)
+        })?;
         Ok(swap_if(swapped, protocol_fees))
     }
 
+    /// Withdraw protocol fees from several pools at once, crediting them to owner's deposit.
+    /// Like `withdraw_protocol_fee`, requires `protocol_fee_withdrawal_delay_secs` to be zero;
+    /// with timelock set, use `request_protocol_fee_withdrawal` per pool instead.
+    ///
+    /// Returns withdrawn amounts per pool, in order of tokens in pool identifiers.
+    /// Invalid pairs, missing pools and pools without accrued protocol fee are skipped
+    /// and omitted from result.
+    #[allow(clippy::type_complexity)]
+    pub fn withdraw_protocol_fees(
+        &mut self,
+        pools: &[(TokenId, TokenId)],
+    ) -> Result<Vec<((TokenId, TokenId), Amount, Amount)>> {
+        self.ensure_payable_api_resumed()?;
+        self.ensure_caller_is_owner()?;
+        ensure_here!(
+            self.contract().as_ref().protocol_fee_withdrawal_delay_secs == 0,
+            ErrorKind::WithdrawalTimelockActive
+        );
+
+        let mut withdrawn = Vec::new();
+        for tokens in pools {
+            let Ok((pool_id, _)) = PoolId::try_from_pair(tokens.clone()) else {
+                continue;
+            };
+            let Some(protocol_fee) = self
+                .contract()
+                .as_ref()
+                .pools
+                .inspect(&pool_id, |pool| pool.protocol_fee())
+            else {
+                continue;
+            };
+            let (fee_a, fee_b) = protocol_fee?;
+            if fee_a.is_zero() && fee_b.is_zero() {
+                continue;
+            }
+
+            let (amount_a, amount_b) = self.withdraw_protocol_fee(tokens.clone())?;
+            withdrawn.push((tokens.clone(), amount_a, amount_b));
+        }
+        Ok(withdrawn)
+    }
+
+    /// Take protocol fee accrued in the pool out of its reserves, and lock it
+    /// for `protocol_fee_withdrawal_delay_secs`, see `execute_protocol_fee_withdrawal`.
+    /// If some fee is already pending in the pool, new fee is added to it and timelock restarts.
+    ///
+    /// # Returns
+    /// `(amounts, release_ts)` of pending withdrawal, amounts in order of `tokens`
+    pub fn request_protocol_fee_withdrawal(
+        &mut self,
+        tokens: (TokenId, TokenId),
+    ) -> Result<((Amount, Amount), u64)> {
+        self.ensure_payable_api_resumed()?;
+        self.ensure_caller_is_owner()?;
+
+        let (pool_id, swapped) = PoolId::try_from_pair(tokens).map_err(|e| error_here!(e))?;
+        let now = self.get_block_timestamp();
+        let contract = self.contract_mut().latest();
+        let release_ts = now.saturating_add(contract.protocol_fee_withdrawal_delay_secs);
+
+        let amounts = contract.pools.try_update(&pool_id, |pool| {
+            let pool = pool.latest();
+            let protocol_fees = pool.withdraw_protocol_fee()?;
+            let (pending, _) = pool.pending_protocol_fee.unwrap_or_default();
+            let amounts = (
+                checked_sum([pending.0, protocol_fees.0]).map_err(|e| error_here!(e))?,
+                checked_sum([pending.1, protocol_fees.1]).map_err(|e| error_here!(e))?,
+            );
+            pool.pending_protocol_fee = Some((amounts, release_ts));
+            Ok(amounts)
+        })?;
+        Ok((swap_if(swapped, amounts), release_ts))
+    }
+
+    /// Credit protocol fee withdrawn by `request_protocol_fee_withdrawal` to owner's deposit,
+    /// once its timelock elapses. Suspending payable API postpones payout beyond timelock.
+    ///
+    /// # Returns
+    /// Paid out amounts, in order of `tokens`
+    pub fn execute_protocol_fee_withdrawal(
+        &mut self,
+        tokens: (TokenId, TokenId),
+    ) -> Result<(Amount, Amount)> {
+        self.ensure_payable_api_resumed()?;
+        self.ensure_caller_is_owner()?;
+
+        let (pool_id, swapped) = PoolId::try_from_pair(tokens).map_err(|e| error_here!(e))?;
+        let now = self.get_block_timestamp();
+        let owner_id = self.get_caller_id();
+        let contract = self.contract_mut().latest();
+
+        let amounts = contract.pools.try_update(&pool_id, |pool| {
+            let pool = pool.latest();
+            let (amounts, release_ts) = pool
+                .pending_protocol_fee
+                .ok_or_else(|| error_here!(ErrorKind::NoPendingWithdrawal))?;
+            ensure_here!(now >= release_ts, ErrorKind::WithdrawalTimelockActive);
+
+            contract.accounts.try_update(&owner_id, |account| {
+                let account = account.latest();
+                account
+                    .deposit(&pool_id.0, amounts.0)
+                    .map_err(|e| error_here!(e))?;
+                account
+                    .deposit(&pool_id.1, amounts.1)
+                    .map_err(|e| error_here!(e))?;
+
+                Ok(())
+            })?;
+
+            pool.pending_protocol_fee = None;
+            Ok(amounts)
+        })?;
+        Ok(swap_if(swapped, amounts))
+    }
+
+    /// Returns protocol fee accrued in the pool, as `(accrued, position_rounding)`,
+    /// both in order of `tokens`. `accrued` is amount which can be withdrawn now,
+    /// `position_rounding` is total rounding residual of position deposits since pool creation,
+    /// which was attributed to protocol fee
+    pub fn get_protocol_fee(
+        &self,
+        tokens: (TokenId, TokenId),
+    ) -> Result<((Amount, Amount), (Float, Float))> {
+        let (pool_id, swapped) = PoolId::try_from_pair(tokens).map_err(|e| error_here!(e))?;
+        self.contract()
+            .as_ref()
+            .pools
+            .try_inspect(&pool_id, |pool| {
+                let accrued = pool.protocol_fee()?;
+                let position_rounding = pool
+                    .as_latest()
+                    .map_or((AmountUFP::zero(), AmountUFP::zero()), |pool| {
+                        pool.acc_position_rounding_residual
+                    })
+                    .map_into::<Float>();
+                Ok((
+                    swap_if(swapped, accrued),
+                    swap_if(swapped, position_rounding),
+                ))
+            })?
+    }
+
+    /// Returns protocol fee pending withdrawal in the pool, as `(amounts, release_ts)`,
+    /// amounts in order of `tokens`
+    pub fn get_pending_protocol_fee_withdrawal(
+        &self,
+        tokens: (TokenId, TokenId),
+    ) -> Result<Option<((Amount, Amount), u64)>> {
+        let (pool_id, swapped) = PoolId::try_from_pair(tokens).map_err(|e| error_here!(e))?;
+        self.contract()
+            .as_ref()
+            .pools
+            .try_inspect(&pool_id, |pool| {
+                pool.as_latest()
+                    .and_then(|pool| pool.pending_protocol_fee)
+                    .map(|(amounts, release_ts)| (swap_if(swapped, amounts), release_ts))
+            })
+    }
+
     /// Common implementation of `execute_actions` and `deposit_execute_actions`, handles all actions
     /// with respect to execution context
     #[allow(clippy::too_many_lines)] // Because of lengthy worker functions invocations. Relatively simple otherwise
@@ -1181,6 +3803,8 @@ impl<T: Types, S: StateMut<T>, SS: BorrowMut<S>> Dex<T, S, SS> {
     ) -> Result<Vec<ActionResult<S::SendTokensResult>>> {
         // Var to allow deposit only once
         let mut deposit_handled = false;
+        // Kept to account deposited tokens after successful batch execution
+        let deposited = deposit_data;
         // First, we use peeking to process possible register account request
         // before we visit account
         let mut actions = actions.into_iter().peekable();
@@ -1204,7 +3828,11 @@ impl<T: Types, S: StateMut<T>, SS: BorrowMut<S>> Dex<T, S, SS> {
             self.register_account_and_then(account_id.clone(), register_account_cb)?;
         }
 
-        let protocol_fee_fraction = self.protocol_fee_fraction();
+        let protocol_fee_fraction = self.swap_protocol_fee_fraction();
+        let swap_max_underpay = self.swap_max_underpay();
+        let now = self.get_block_timestamp();
+        let swap_cooldown = self.contract().as_ref().swap_cooldown_secs;
+        let max_tick_crossings = self.get_max_swap_tick_crossings();
 
         // Process rest of actions
         self.with_account_mut(account_id, |mut account_view| {
@@ -1228,11 +3856,18 @@ impl<T: Types, S: StateMut<T>, SS: BorrowMut<S>> Dex<T, S, SS> {
                             account_id,
                             account_view.account,
                             account_view.pools,
+                            &account_view.oracle_guard,
+                            &account_view.grace_guard,
+                            &account_view.pause_guard,
+                            account_view.tick_positions.as_ref(),
                             account_view.logger,
+                            account_view.event_verbosity,
                             &prev_swap_action,
                             SwapKind::ExactIn,
                             action,
                             protocol_fee_fraction,
+                            swap_max_underpay,
+                            max_tick_crossings,
                         )?;
                         let swap_amount = swap_result.2;
                         prev_swap_action = Some(swap_result);
@@ -1249,11 +3884,18 @@ impl<T: Types, S: StateMut<T>, SS: BorrowMut<S>> Dex<T, S, SS> {
                             account_id,
                             account_view.account,
                             account_view.pools,
+                            &account_view.oracle_guard,
+                            &account_view.grace_guard,
+                            &account_view.pause_guard,
+                            account_view.tick_positions.as_ref(),
                             account_view.logger,
+                            account_view.event_verbosity,
                             &prev_swap_action,
                             SwapKind::ExactOut,
                             action,
                             protocol_fee_fraction,
+                            swap_max_underpay,
+                            max_tick_crossings,
                         )?;
                         let swap_amount = swap_result.2;
                         prev_swap_action = Some(swap_result);
@@ -1270,10 +3912,17 @@ impl<T: Types, S: StateMut<T>, SS: BorrowMut<S>> Dex<T, S, SS> {
                             account_id,
                             account_view.account,
                             account_view.pools,
+                            &account_view.oracle_guard,
+                            &account_view.grace_guard,
+                            &account_view.pause_guard,
+                            account_view.tick_positions.as_ref(),
                             account_view.logger,
+                            account_view.event_verbosity,
                             &prev_swap_action,
                             action,
                             protocol_fee_fraction,
+                            swap_max_underpay,
+                            max_tick_crossings,
                         )?;
                         let swap_amount = swap_result.2;
                         prev_swap_action = Some(swap_result);
@@ -1350,32 +3999,43 @@ impl<T: Types, S: StateMut<T>, SS: BorrowMut<S>> Dex<T, S, SS> {
                 };
                 results.push(result);
             }
+            // Chain of swaps in single batch counts as one swap
+            if prev_swap_action.is_some() {
+                account_view.account.record_swap(now, swap_cooldown)?;
+            }
             Ok(())
         })?;
 
         // Deposit must be handled if requested
         ensure_here!(deposit_data.is_empty(), ErrorKind::DepositNotHandled);
 
+        let contract = self.contract_mut().latest();
+        for payment in deposited {
+            contract.inc_accounted_balance(&payment.token_id, payment.amount)?;
+        }
+
         // Transform inner result into outer one
-        let results = results
+        results
             .into_iter()
-            .map(|r| match r {
-                // Only withdrawal needs actual transformation
-                ActionResult::Withdraw(r) => ActionResult::Withdraw(r.map(|func| func(self))),
-                // Rest is just transformed as-is
-                ActionResult::RegisterAccount => ActionResult::RegisterAccount,
-                ActionResult::RegisterTokens => ActionResult::RegisterTokens,
-                ActionResult::SwapExactIn(amount) => ActionResult::SwapExactIn(amount),
-                ActionResult::SwapExactOut(amount) => ActionResult::SwapExactOut(amount),
-                ActionResult::SwapToPrice(amount) => ActionResult::SwapToPrice(amount),
-                ActionResult::Deposit => ActionResult::Deposit,
-                ActionResult::OpenPosition => ActionResult::OpenPosition,
-                ActionResult::ClosePosition => ActionResult::ClosePosition,
-                ActionResult::WithdrawFee => ActionResult::WithdrawFee,
+            .map(|r| {
+                Ok(match r {
+                    // Only withdrawal needs actual transformation
+                    ActionResult::Withdraw(r) => {
+                        ActionResult::Withdraw(r.map(|func| func(self)).transpose()?)
+                    }
+                    // Rest is just transformed as-is
+                    ActionResult::RegisterAccount => ActionResult::RegisterAccount,
+                    ActionResult::RegisterTokens => ActionResult::RegisterTokens,
+                    ActionResult::SwapExactIn(amount) => ActionResult::SwapExactIn(amount),
+                    ActionResult::SwapExactOut(amount) => ActionResult::SwapExactOut(amount),
+                    ActionResult::SwapToPrice(amount) => ActionResult::SwapToPrice(amount),
+                    ActionResult::Deposit => ActionResult::Deposit,
+                    ActionResult::OpenPosition => ActionResult::OpenPosition,
+                    ActionResult::ClosePosition => ActionResult::ClosePosition,
+                    ActionResult::WithdrawFee => ActionResult::WithdrawFee,
+                })
             })
-            .collect();
-
-        Ok(results)
+            .collect()
     }
     /// Execute batch of actions passed as additional payload during extrnal deposit operation
     ///
@@ -1389,12 +4049,20 @@ impl<T: Types, S: StateMut<T>, SS: BorrowMut<S>> Dex<T, S, SS> {
     /// * `deposit_amount` - token amount to deposit
     /// * `register_account_cb` - callback which is called if account registration is requested
     /// * `actions` - list of actions to actually execute
+    /// * `idempotency_key` - optional key of batch; batch with key which was recently used
+    ///     by the same account fails with `DuplicateRequest` and isn't executed
     ///
     /// # Returns
     /// * if operation succeeds, vector of `(usize, TokenId, Amount, S::SendTokensResult)`, where
     ///     * `usize` is the index of `Withdraw` operation in batch
     ///     * `TokenId` and `Amount` describe withdrawal request parameters
     ///     * `S::SendTokensResult` is the actual result of `send_tokens` call
+    ///
+    ///   along with vector of `(TokenId, Amount, Amount, Amount)`, one per deposited token, where
+    ///     * `TokenId` is the deposited token
+    ///     * first `Amount` is total deposited amount of this token
+    ///     * second `Amount` is part of deposited amount consumed by batch actions
+    ///     * third `Amount` is account's balance of this token after batch execution
     /// * If it fails, failure reason is returned
     pub fn deposit_execute_actions(
         &mut self,
@@ -1402,13 +4070,32 @@ impl<T: Types, S: StateMut<T>, SS: BorrowMut<S>> Dex<T, S, SS> {
         deposit_data: &[DepositPayment],
         register_account_cb: AccountCallbackType<'_, T>,
         actions: Vec<Action<S::SendTokensExtraParam>>,
-    ) -> Result<Vec<S::SendTokensResult>> {
+        idempotency_key: Option<IdempotencyKey>,
+    ) -> Result<(
+        Vec<S::SendTokensResult>,
+        Vec<(TokenId, Amount, Amount, Amount)>,
+    )> {
         self.ensure_payable_api_resumed()?;
 
         ensure_here!(
             account_id == &self.get_initiator_id(),
             ErrorKind::DepositSenderMustBeSigner
         );
+        self.ensure_batch_size_allowed(&actions)?;
+        self.ensure_idempotency_key_unused(account_id, idempotency_key.as_ref())?;
+
+        // Deposited amounts per token, in order of first appearance, along with balances before batch
+        let mut deposits: Vec<(TokenId, Amount, Amount)> = Vec::new();
+        for payment in deposit_data {
+            if let Some((_, amount, _)) = deposits.iter_mut().find(|d| d.0 == payment.token_id) {
+                *amount += payment.amount;
+            } else {
+                let balance_before = self
+                    .get_deposit(account_id, &payment.token_id)
+                    .unwrap_or_default();
+                deposits.push((payment.token_id.clone(), payment.amount, balance_before));
+            }
+        }
 
         let results = self
             .execute_actions_impl(account_id, deposit_data, register_account_cb, actions)?
@@ -1420,18 +4107,74 @@ impl<T: Types, S: StateMut<T>, SS: BorrowMut<S>> Dex<T, S, SS> {
                     None
                 }
             })
-            .collect();
+            .collect();
+
+        self.record_idempotency_key(account_id, idempotency_key)?;
+
+        let deposit_split = deposits
+            .into_iter()
+            .map(|(token_id, deposited, balance_before)| {
+                let balance_after = self.get_deposit(account_id, &token_id).unwrap_or_default();
+                // Net decrease of balance, bounded by deposited amount
+                let consumed = (balance_before + deposited)
+                    .saturating_sub(balance_after)
+                    .min(deposited);
+                (token_id, deposited, consumed, balance_after)
+            })
+            .collect();
+
+        Ok((results, deposit_split))
+    }
+
+    fn ensure_idempotency_key_unused(
+        &self,
+        account_id: &AccountId,
+        idempotency_key: Option<&IdempotencyKey>,
+    ) -> Result<()> {
+        let Some(key) = idempotency_key else { return Ok(()) };
+        let seen = self
+            .contract()
+            .as_ref()
+            .accounts
+            .inspect(account_id, |account| {
+                let account = account.as_ref();
+                account.idempotency_keys.contains(key)
+            })
+            .unwrap_or(false);
+        ensure_here!(!seen, ErrorKind::DuplicateRequest);
+        Ok(())
+    }
 
-        Ok(results)
+    fn record_idempotency_key(
+        &mut self,
+        account_id: &AccountId,
+        idempotency_key: Option<IdempotencyKey>,
+    ) -> Result<()> {
+        let Some(key) = idempotency_key else { return Ok(()) };
+        self.contract_mut()
+            .latest()
+            .accounts
+            .try_update(account_id, |account| {
+                let account = account.latest();
+                account.record_idempotency_key(key)
+            })
     }
 
     /// Execute batch of actions passed as normal request
+    ///
+    /// See `deposit_execute_actions` regarding `idempotency_key`
     pub fn execute_actions(
         &mut self,
         register_account_cb: AccountCallbackType<'_, T>,
         actions: Vec<Action<S::SendTokensExtraParam>>,
+        idempotency_key: Option<IdempotencyKey>,
     ) -> Result<(Vec<S::SendTokensResult>, Option<Amount>)> {
-        self.execute_actions_for(&self.get_caller_id(), register_account_cb, actions)
+        self.execute_actions_for(
+            &self.get_caller_id(),
+            register_account_cb,
+            actions,
+            idempotency_key,
+        )
     }
 
     pub fn execute_actions_for(
@@ -1439,8 +4182,11 @@ impl<T: Types, S: StateMut<T>, SS: BorrowMut<S>> Dex<T, S, SS> {
         account_id: &AccountId,
         register_account_cb: AccountCallbackType<T>,
         actions: Vec<Action<S::SendTokensExtraParam>>,
+        idempotency_key: Option<IdempotencyKey>,
     ) -> Result<(Vec<S::SendTokensResult>, Option<Amount>)> {
         self.ensure_payable_api_resumed()?;
+        self.ensure_batch_size_allowed(&actions)?;
+        self.ensure_idempotency_key_unused(account_id, idempotency_key.as_ref())?;
 
         let mut out_amount = None;
 
@@ -1459,6 +4205,8 @@ impl<T: Types, S: StateMut<T>, SS: BorrowMut<S>> Dex<T, S, SS> {
             })
             .collect();
 
+        self.record_idempotency_key(account_id, idempotency_key)?;
+
         Ok((results, out_amount))
     }
 
@@ -1470,11 +4218,23 @@ impl<T: Types, S: StateMut<T>, SS: BorrowMut<S>> Dex<T, S, SS> {
     ) -> Result<(Amount, Amount)> {
         ensure_here!(tokens.len() >= 2, ErrorKind::AtLeastOneSwap);
 
+        let last_hop = tokens.len() - 2;
         let mut amount_out = amount_in;
-        for (token_in, token_out) in tokens.iter().tuple_windows() {
-            amount_out = self
-                .swap(token_in, token_out, SwapKind::ExactIn, None, amount_out)?
+        for (hop, (token_in, token_out)) in tokens.iter().tuple_windows().enumerate() {
+            // Report intermediate hop which rounds output to zero explicitly,
+            // so that router may pick another path or larger amount
+            amount_out =
+                match self.swap(token_in, token_out, SwapKind::ExactIn, None, amount_out) {
+                    Err(e) if hop < last_hop && matches!(e.kind, ErrorKind::SwapAmountTooSmall) => {
+                        Err(error_here!(ErrorKind::IntermediateSwapRoundedToZero(hop)))
+                    }
+                    result => result,
+                }?
                 .1;
+            ensure_here!(
+                hop == last_hop || !amount_out.is_zero(),
+                ErrorKind::IntermediateSwapRoundedToZero(hop)
+            );
         }
 
         ensure_here!(amount_out >= min_amount_out, ErrorKind::Slippage);
@@ -1484,6 +4244,139 @@ impl<T: Types, S: StateMut<T>, SS: BorrowMut<S>> Dex<T, S, SS> {
         Ok((amount_in, amount_out))
     }
 
+    /// Swap exact `amount_in` of `token_in` to `token_out` along the best path
+    /// of at most `max_hops` swaps, see `find_best_route`
+    ///
+    /// Fails with `NoRouteFound` if tokens aren't connected by such path,
+    /// or with error of the last failed quote if no such path could be quoted.
+    ///
+    /// # Returns
+    /// (Chosen path, Amount in, Amount out)
+    #[cfg(feature = "smart-routing")]
+    pub fn swap_auto_route(
+        &mut self,
+        token_in: &TokenId,
+        token_out: &TokenId,
+        amount_in: Amount,
+        min_amount_out: Amount,
+        max_hops: usize,
+    ) -> Result<(Vec<TokenId>, Amount, Amount)> {
+        let (path, _) = self.find_best_route(token_in, token_out, amount_in, max_hops)?;
+        let (amount_in, amount_out) = self.swap_exact_in(&path, amount_in, min_amount_out)?;
+        Ok((path, amount_in, amount_out))
+    }
+
+    /// Same as `swap_exact_in`, but minimal amount out is derived from the quote
+    /// against current state, reduced by `max_slippage_bp` basis points.
+    /// Fails with `IllegalSlippageTolerance` if `max_slippage_bp` exceeds `BASIS_POINT_DIVISOR`.
+    pub fn swap_exact_in_with_slippage(
+        &mut self,
+        tokens: &[TokenId],
+        amount_in: Amount,
+        max_slippage_bp: BasisPoints,
+    ) -> Result<(Amount, Amount)> {
+        let min_amount_out = self.quote_min_amount_out(tokens, amount_in, max_slippage_bp)?;
+        self.swap_exact_in(tokens, amount_in, min_amount_out)
+    }
+
+    /// Quoted amount out of swapping exactly `amount_in` along `tokens`,
+    /// reduced by `max_slippage_bp` basis points, see `swap_exact_in_with_slippage`
+    pub fn quote_min_amount_out(
+        &self,
+        tokens: &[TokenId],
+        amount_in: Amount,
+        max_slippage_bp: BasisPoints,
+    ) -> Result<Amount> {
+        let slippage_tolerance =
+            SlippageTolerance::from_bp(max_slippage_bp).map_err(|e| error_here!(e))?;
+        ensure_here!(tokens.len() >= 2, ErrorKind::AtLeastOneSwap);
+
+        let last_hop = tokens.len() - 2;
+        let mut amount_out = amount_in;
+        for (hop, (token_in, token_out)) in tokens.iter().tuple_windows().enumerate() {
+            let estimate = self.estimate_swap_exact(
+                true,
+                token_in.clone(),
+                token_out.clone(),
+                amount_out,
+                slippage_tolerance,
+            )?;
+            // Slippage applies to the final amount only
+            amount_out = if hop == last_hop {
+                estimate.result_bound
+            } else {
+                estimate.result
+            };
+        }
+        Ok(amount_out)
+    }
+
+    /// Same as `swap_exact_in`, but amounts are passed as `WasmAmount`.
+    ///
+    /// Both amounts are validated to fit into `Amount` before any swap is performed,
+    /// otherwise `ConvOverflow` is returned.
+    pub fn swap_exact_in_wasm(
+        &mut self,
+        tokens: &[TokenId],
+        amount_in: &WasmAmount,
+        min_amount_out: &WasmAmount,
+    ) -> Result<(Amount, Amount)> {
+        let amount_in = wasm_amount_to_amount(amount_in)?;
+        let min_amount_out = wasm_amount_to_amount(min_amount_out)?;
+        self.swap_exact_in(tokens, amount_in, min_amount_out)
+    }
+
+    /// Same as `swap_exact_in`, but also returns pools swapped through, in order of swaps
+    ///
+    /// # Returns
+    /// `(amount_in, amount_out, trace)`, where each `trace` entry is a pool identifier
+    /// along with side from which tokens were put into that pool
+    pub fn swap_exact_in_traced(
+        &mut self,
+        tokens: &[TokenId],
+        amount_in: Amount,
+        min_amount_out: Amount,
+    ) -> Result<(Amount, Amount, Vec<(PoolId, Side)>)> {
+        let (amount_in, amount_out) = self.swap_exact_in(tokens, amount_in, min_amount_out)?;
+        Ok((amount_in, amount_out, Self::swap_trace(tokens)?))
+    }
+
+    /// Same as `swap_exact_in`, but output tokens are sent to `recipient`
+    /// instead of being credited to caller's deposit.
+    ///
+    /// Output is sent the same way as by `withdraw`, so in case of failed transfer
+    /// tokens are returned to caller's deposit, as `recipient` may be not registered.
+    pub fn swap_and_send(
+        &mut self,
+        tokens: &[TokenId],
+        amount_in: Amount,
+        min_amount_out: Amount,
+        recipient: &AccountId,
+        extra: S::SendTokensExtraParam,
+    ) -> Result<S::SendTokensResult> {
+        let (_, amount_out) = self.swap_exact_in(tokens, amount_in, min_amount_out)?;
+        // Path length is already checked by swap
+        let token_out = &tokens[tokens.len() - 1];
+
+        let caller_id = self.get_caller_id();
+        let StateMembersMut {
+            contract, logger, ..
+        } = self.members_mut();
+        contract
+            .latest()
+            .accounts
+            .try_update(&caller_id, |account| {
+                let account = account.latest();
+                let new_balance = account
+                    .withdraw(token_out, amount_out)
+                    .map_err(|e| error_here!(e))?;
+                logger.log_withdraw_event(&caller_id, token_out, &amount_out, &new_balance);
+                Ok(())
+            })?;
+
+        self.send_accounted_tokens(&caller_id, recipient, token_out, amount_out, false, extra)
+    }
+
     pub fn swap_exact_out(
         &mut self,
         tokens: &[TokenId],
@@ -1506,6 +4399,42 @@ impl<T: Types, S: StateMut<T>, SS: BorrowMut<S>> Dex<T, S, SS> {
         Ok((amount_in, amount_out))
     }
 
+    /// Same as `swap_exact_out`, but also returns pools swapped through, in order of swaps.
+    /// See `swap_exact_in_traced` for details
+    pub fn swap_exact_out_traced(
+        &mut self,
+        tokens: &[TokenId],
+        amount_out: Amount,
+        max_amount_in: Amount,
+    ) -> Result<(Amount, Amount, Vec<(PoolId, Side)>)> {
+        let (amount_in, amount_out) = self.swap_exact_out(tokens, amount_out, max_amount_in)?;
+        Ok((amount_in, amount_out, Self::swap_trace(tokens)?))
+    }
+
+    /// Pools which swap over `tokens` path goes through, with sides from which they're entered
+    fn swap_trace(tokens: &[TokenId]) -> Result<Vec<(PoolId, Side)>> {
+        tokens
+            .iter()
+            .tuple_windows()
+            .map(|(token_in, token_out)| {
+                let (pool_id, swapped) =
+                    PoolId::try_from_pair((token_in.clone(), token_out.clone()))
+                        .map_err(|e| error_here!(e))?;
+                let side = if swapped { Side::Right } else { Side::Left };
+                Ok((pool_id, side))
+            })
+            .collect()
+    }
+
+    /// Swap up to `amount_in` of `tokens[0]` to `tokens[1]` until effective price
+    /// reaches `effective_price_limit`
+    ///
+    /// If the limit is already reached, i.e. it lies on the wrong side of the current
+    /// effective price, nothing is traded and `(0, 0)` is returned without an error.
+    /// Use `swap_to_price_checked` to get `PriceLimitNotCrossable` error instead.
+    ///
+    /// # Returns
+    /// (Amount in, Amount out)
     pub fn swap_to_price(
         &mut self,
         tokens: &[TokenId],
@@ -1527,6 +4456,72 @@ impl<T: Types, S: StateMut<T>, SS: BorrowMut<S>> Dex<T, S, SS> {
         Ok((amount_in, amount_out))
     }
 
+    /// Same as `swap_to_price`, but fails with `PriceLimitNotCrossable`
+    /// if the limit is already reached, instead of silently trading nothing
+    ///
+    /// # Returns
+    /// (Amount in, Amount out)
+    pub fn swap_to_price_checked(
+        &mut self,
+        tokens: &[TokenId],
+        amount_in: Amount,
+        effective_price_limit: Float,
+    ) -> Result<(Amount, Amount)> {
+        ensure_here!(tokens.len() == 2, ErrorKind::ExactOneSwap);
+
+        let (pool_id, swapped) = PoolId::try_from_pair((tokens[0].clone(), tokens[1].clone()))
+            .map_err(|e| error_here!(e))?;
+        let side = if swapped { Side::Right } else { Side::Left };
+        let eff_sqrtprice = self
+            .contract()
+            .as_ref()
+            .pools
+            .try_inspect(&pool_id, |pool| pool.eff_sqrtprice(0, side))?;
+        // Same condition as pool uses to skip swap to price
+        ensure_here!(
+            effective_price_limit.sqrt() > eff_sqrtprice,
+            ErrorKind::PriceLimitNotCrossable
+        );
+
+        self.swap_to_price(tokens, amount_in, effective_price_limit)
+    }
+
+    /// Swap up to `amount_in` of `token_in` to `token_out`, stopping once the marginal rate,
+    /// with fees included, drops to `price_limit`
+    ///
+    /// Unlike `swap_to_price`, `price_limit` is a plain price, expressed as amount of `token_out`
+    /// received per unit of `token_in`, so no part of the swap is executed at a worse rate.
+    /// Conversion to effective sqrtprice the pool operates on is done internally.
+    ///
+    /// # Returns
+    /// (Amount in, Amount out)
+    pub fn swap_to_price_limit(
+        &mut self,
+        token_in: &TokenId,
+        token_out: &TokenId,
+        amount_in: Amount,
+        price_limit: Float,
+    ) -> Result<(Amount, Amount)> {
+        ensure_here!(price_limit > Float::zero(), ErrorKind::InvalidParams);
+
+        // Effective price is amount of `token_in` paid per unit of `token_out`
+        let (amount_in, amount_out) = self.swap(
+            token_in,
+            token_out,
+            SwapKind::ToPrice,
+            Some(price_limit.recip()),
+            amount_in,
+        )?;
+
+        self.post_swap_update(
+            &[token_in.clone(), token_out.clone()],
+            amount_in,
+            amount_out,
+        )?;
+
+        Ok((amount_in, amount_out))
+    }
+
     /// Returns (Amount in, Amount out)
     // XXX: Don't switch `effective_price_limit` and `amount` order. There's a bug when `amount` just dissapears
     // from parameters if it goes before `Option<Float>` in MX. If you do this, check if it still works by calling
@@ -1549,24 +4544,61 @@ impl<T: Types, S: StateMut<T>, SS: BorrowMut<S>> Dex<T, S, SS> {
         let (pool_id, swapped) = PoolId::try_from_pair((token_in.clone(), token_out.clone()))
             .map_err(|e| error_here!(e))?;
         let direction = if swapped { Side::Right } else { Side::Left };
+        let now = self.get_block_timestamp();
+        let max_underpay = self.swap_max_underpay();
+        let protocol_fee_fraction = self.swap_protocol_fee_fraction();
+        let max_tick_crossings = self.get_max_swap_tick_crossings();
 
         let contract = self.contract_mut().latest();
         // Pool uses square effective price. Need to convert here
         let max_eff_sqrtprice_limit = effective_price_limit.map(|limit| limit.sqrt());
+        let oracle_guard = OracleGuard::<T> {
+            reference_prices: contract.reference_prices.as_ref(),
+            max_deviation_bp: contract.max_oracle_deviation_bp,
+        };
+        GraceGuard::<T> {
+            token_verified_at: contract.token_verified_at.as_ref(),
+            grace_secs: contract.verified_token_grace_secs,
+            now,
+        }
+        .ensure_out_of_grace(&pool_id)?;
+        PauseGuard::<T> {
+            paused_pools: contract.paused_pools.as_ref(),
+        }
+        .ensure_not_paused(&pool_id)?;
+
+        let (amount_in, amount_out, crossed_ticks) =
+            contract.pools.try_update(&pool_id, |pool| {
+                let pool = pool.latest();
+                let deviation_before = oracle_guard.price_deviation(&pool_id, pool);
+                Self::ensure_swap_side_allowed(pool, direction)?;
+                pool.write_observation(now)?;
+                let result = pool.swap(
+                    direction,
+                    swap_type,
+                    amount,
+                    protocol_fee_fraction,
+                    max_underpay,
+                    max_eff_sqrtprice_limit,
+                )?;
+                Self::ensure_tick_crossings_allowed(max_tick_crossings, &result.2)?;
+                oracle_guard.ensure_within_deviation(&pool_id, pool, deviation_before)?;
+                pool.last_trade_ts = now;
+                pool.last_trade_sqrtprice = pool.spot_sqrtprice(Side::Left, 0);
+                pool.last_swap_tick_crossings = u32::try_from(result.2.len()).unwrap_or(u32::MAX);
+                Ok(result)
+            })?;
 
-        let (amount_in, amount_out, _num_tick_crossings) =
-            contract
-                .pools
-                .try_update(&pool_id, |Pool::V0(ref mut pool)| {
-                    pool.swap(
-                        direction,
-                        swap_type,
-                        amount,
-                        contract.protocol_fee_fraction,
-                        max_eff_sqrtprice_limit,
-                    )
-                })?;
-
+        let StateMembersMut {
+            contract, logger, ..
+        } = self.members_mut();
+        Self::log_crossed_ticks(
+            &pool_id,
+            direction,
+            &crossed_ticks,
+            contract.latest().tick_positions.as_ref(),
+            logger,
+        );
         self.log_pool_state(&pool_id, PoolUpdateReason::Swap)?;
 
         Ok((amount_in, amount_out))
@@ -1584,25 +4616,32 @@ impl<T: Types, S: StateMut<T>, SS: BorrowMut<S>> Dex<T, S, SS> {
         };
 
         let caller_id = &self.get_caller_id();
+        let now = self.get_block_timestamp();
 
         let contract = self.contract_mut().latest();
-        contract
-            .accounts
-            .try_update(caller_id, |Account::V0(ref mut account)| {
-                account
-                    .withdraw(first_token, amount_in)
-                    .map_err(|e| error_here!(e))?;
-                account
-                    .deposit(last_token, amount_out)
-                    .map_err(|e| error_here!(e))
-            })?;
+        let swap_cooldown = contract.swap_cooldown_secs;
+        let event_verbosity = contract.event_verbosity;
+        contract.accounts.try_update(caller_id, |account| {
+            let account = account.latest();
+            account.record_swap(now, swap_cooldown)?;
+            account
+                .withdraw(first_token, amount_in)
+                .map_err(|e| error_here!(e))?;
+            account
+                .deposit(last_token, amount_out)
+                .map_err(|e| error_here!(e))?;
+            account.auto_unregister_if_empty(first_token);
+            Ok(())
+        })?;
 
-        self.logger_mut().log_swap_event(
-            caller_id,
-            (first_token, last_token),
-            (&amount_in, &amount_out),
-            &[], // TODO: add fees into swap event
-        );
+        if event_verbosity & EVENTS_SWAP != 0 {
+            self.logger_mut().log_swap_event(
+                caller_id,
+                (first_token, last_token),
+                (&amount_in, &amount_out),
+                &[], // TODO: add fees into swap event
+            );
+        }
 
         Ok(())
     }
@@ -1616,28 +4655,32 @@ impl<T: Types, S: StateMut<T>, SS: BorrowMut<S>> Dex<T, S, SS> {
 
         let amount_pairs = self.multiple_path_swap(paths, SwapKind::ExactIn)?;
 
-        let mut sum = Amount::zero();
-        for (_, amount_out) in amount_pairs.clone() {
-            sum += amount_out;
-        }
+        let sum = checked_sum(amount_pairs.iter().map(|(_, amount_out)| *amount_out))
+            .map_err(|e| error_here!(e))?;
         ensure_here!(sum >= min_amount_out, ErrorKind::Slippage);
 
         let caller_id = &self.get_caller_id();
+        let now = self.get_block_timestamp();
         let contract = self.contract_mut().latest();
 
+        let swap_cooldown = contract.swap_cooldown_secs;
+        contract.accounts.try_update(caller_id, |account| {
+            let account = account.latest();
+            account.record_swap(now, swap_cooldown)
+        })?;
+
         for (i, path) in paths.iter().enumerate() {
             //unfallible unwrap as the length of `amount_pairs` is same as the length of `paths`
             let (amount_in, amount_out) = amount_pairs.get(i).unwrap();
-            contract
-                .accounts
-                .try_update(caller_id, |Account::V0(ref mut account)| {
-                    account
-                        .withdraw(&path.tokens[0], *amount_in)
-                        .map_err(|e| error_here!(e))?;
-                    account
-                        .deposit(&path.tokens[path.tokens.len() - 1], *amount_out)
-                        .map_err(|e| error_here!(e))
-                })?;
+            contract.accounts.try_update(caller_id, |account| {
+                let account = account.latest();
+                account
+                    .withdraw(&path.tokens[0], *amount_in)
+                    .map_err(|e| error_here!(e))?;
+                account
+                    .deposit(&path.tokens[path.tokens.len() - 1], *amount_out)
+                    .map_err(|e| error_here!(e))
+            })?;
         }
 
         Ok(amount_pairs)
@@ -1652,28 +4695,32 @@ impl<T: Types, S: StateMut<T>, SS: BorrowMut<S>> Dex<T, S, SS> {
 
         let amount_pairs = self.multiple_path_swap(paths, SwapKind::ExactOut)?;
 
-        let mut sum = Amount::zero();
-        for (amount_in, _) in amount_pairs.clone() {
-            sum += amount_in;
-        }
+        let sum = checked_sum(amount_pairs.iter().map(|(amount_in, _)| *amount_in))
+            .map_err(|e| error_here!(e))?;
         ensure_here!(sum >= max_amount_in, ErrorKind::Slippage);
 
         let caller_id = &self.get_caller_id();
+        let now = self.get_block_timestamp();
         let contract = self.contract_mut().latest();
 
+        let swap_cooldown = contract.swap_cooldown_secs;
+        contract.accounts.try_update(caller_id, |account| {
+            let account = account.latest();
+            account.record_swap(now, swap_cooldown)
+        })?;
+
         for (i, path) in paths.iter().enumerate() {
             //unfallible unwrap as the length of `amount_pairs` is same as the length of `paths`
             let (amount_in, amount_out) = amount_pairs.get(i).unwrap();
-            contract
-                .accounts
-                .try_update(caller_id, |Account::V0(ref mut account)| {
-                    account
-                        .withdraw(path.tokens.first().unwrap(), *amount_in)
-                        .map_err(|e| error_here!(e))?;
-                    account
-                        .deposit(path.tokens.last().unwrap(), *amount_out)
-                        .map_err(|e| error_here!(e))
-                })?;
+            contract.accounts.try_update(caller_id, |account| {
+                let account = account.latest();
+                account
+                    .withdraw(path.tokens.first().unwrap(), *amount_in)
+                    .map_err(|e| error_here!(e))?;
+                account
+                    .deposit(path.tokens.last().unwrap(), *amount_out)
+                    .map_err(|e| error_here!(e))
+            })?;
         }
 
         Ok(amount_pairs)
@@ -1717,13 +4764,20 @@ impl<T: Types, S: StateMut<T>, SS: BorrowMut<S>> Dex<T, S, SS> {
     #[allow(clippy::too_many_arguments)]
     fn execute_swap_action(
         account_id: &AccountId,
-        account: &mut AccountV0<T>,
+        account: &mut AccountLatest<T>,
         pools: &mut state_types::PoolsMap<T>,
+        oracle_guard: &OracleGuard<'_, T>,
+        grace_guard: &GraceGuard<'_, T>,
+        pause_guard: &PauseGuard<'_, T>,
+        tick_positions: Option<&T::TickPositionsMap>,
         logger: &mut dyn Logger,
+        event_verbosity: u8,
         prev_swap_result: &Option<(TokenId, SwapKind, Amount)>,
         swap_type: SwapKind,
         action: SwapAction,
         protocol_fee_fraction: BasisPoints,
+        max_underpay: Float,
+        max_tick_crossings: Option<u32>,
     ) -> Result<(TokenId, SwapKind, Amount)> {
         let SwapAction {
             token_in,
@@ -1777,39 +4831,60 @@ impl<T: Types, S: StateMut<T>, SS: BorrowMut<S>> Dex<T, S, SS> {
         let (pool_id, swapped) = PoolId::try_from_pair((token_in.clone(), token_out.clone()))
             .map_err(|e| error_here!(e))?;
 
-        let (amount_in, amount_out) = pools.try_update(&pool_id, |Pool::V0(ref mut pool)| {
+        grace_guard.ensure_out_of_grace(&pool_id)?;
+        pause_guard.ensure_not_paused(&pool_id)?;
+        let (amount_in, amount_out) = pools.try_update(&pool_id, |pool| {
+            let pool = pool.latest();
+            let deviation_before = oracle_guard.price_deviation(&pool_id, pool);
             let side = if swapped { Side::Right } else { Side::Left };
+            Self::ensure_swap_side_allowed(pool, side)?;
+            pool.write_observation(grace_guard.now)?;
 
-            let (amount_in, amount_out) = match swap_type {
+            let (amount_in, amount_out, crossed_ticks) = match swap_type {
                 SwapKind::ExactIn => {
-                    let (amount_in, amount_out, _num_tick_crossings) =
-                        pool.swap_exact_in(side, amount, protocol_fee_fraction)?;
+                    let (amount_in, amount_out, crossed_ticks) =
+                        pool.swap_exact_in(side, amount, protocol_fee_fraction, max_underpay)?;
                     ensure_here!(amount_out >= amount_limit, ErrorKind::Slippage);
-                    (amount_in, amount_out)
+                    (amount_in, amount_out, crossed_ticks)
                 }
                 SwapKind::ExactOut => {
-                    let (amount_in, amount_out, _num_tick_crossings) =
-                        pool.swap_exact_out(side, amount, protocol_fee_fraction)?;
+                    let (amount_in, amount_out, crossed_ticks) =
+                        pool.swap_exact_out(side, amount, protocol_fee_fraction, max_underpay)?;
                     ensure_here!(amount_in <= amount_limit, ErrorKind::Slippage);
-                    (amount_in, amount_out)
+                    (amount_in, amount_out, crossed_ticks)
                 }
                 SwapKind::ToPrice => unreachable!("Should never happen"),
             };
+            Self::ensure_tick_crossings_allowed(max_tick_crossings, &crossed_ticks)?;
+            oracle_guard.ensure_within_deviation(&pool_id, pool, deviation_before)?;
             account
                 .withdraw(&token_in, amount_in)
                 .map_err(|e| error_here!(e))?;
             account
                 .deposit(&token_out, amount_out)
                 .map_err(|e| error_here!(e))?;
+            account.auto_unregister_if_empty(&token_in);
+            pool.last_trade_ts = grace_guard.now;
+            pool.last_trade_sqrtprice = pool.spot_sqrtprice(Side::Left, 0);
+            pool.last_swap_tick_crossings = u32::try_from(crossed_ticks.len()).unwrap_or(u32::MAX);
 
             // Log swap event and pool state
-            logger.log_swap_event(
-                account_id,
-                (&token_in, &token_out),
-                (&amount_in, &amount_out),
-                &[], // TODO: add fees into swap event
+            if event_verbosity & EVENTS_SWAP != 0 {
+                logger.log_swap_event(
+                    account_id,
+                    (&token_in, &token_out),
+                    (&amount_in, &amount_out),
+                    &[], // TODO: add fees into swap event
+                );
+            }
+            Self::log_crossed_ticks(&pool_id, side, &crossed_ticks, tick_positions, logger);
+            Self::log_pool_v0_state(
+                &pool_id,
+                pool,
+                logger,
+                event_verbosity,
+                PoolUpdateReason::Swap,
             );
-            Self::log_pool_v0_state(&pool_id, pool, logger, PoolUpdateReason::Swap);
 
             Ok((amount_in, amount_out))
         })?;
@@ -1827,12 +4902,19 @@ impl<T: Types, S: StateMut<T>, SS: BorrowMut<S>> Dex<T, S, SS> {
     #[allow(clippy::too_many_arguments)]
     fn execute_swap_to_price_action(
         account_id: &AccountId,
-        account: &mut AccountV0<T>,
+        account: &mut AccountLatest<T>,
         pools: &mut state_types::PoolsMap<T>,
+        oracle_guard: &OracleGuard<'_, T>,
+        grace_guard: &GraceGuard<'_, T>,
+        pause_guard: &PauseGuard<'_, T>,
+        tick_positions: Option<&T::TickPositionsMap>,
         logger: &mut dyn Logger,
+        event_verbosity: u8,
         prev_swap_result: &Option<(TokenId, SwapKind, Amount)>,
         action: SwapToPriceAction,
         protocol_fee_fraction: BasisPoints,
+        max_underpay: Float,
+        max_tick_crossings: Option<u32>,
     ) -> Result<(TokenId, SwapKind, Amount)> {
         let SwapToPriceAction {
             token_in,
@@ -1861,11 +4943,24 @@ impl<T: Types, S: StateMut<T>, SS: BorrowMut<S>> Dex<T, S, SS> {
         let (pool_id, swapped) = PoolId::try_from_pair((token_in.clone(), token_out.clone()))
             .map_err(|e| error_here!(e))?;
 
-        let (_, amount_out) = pools.try_update(&pool_id, |Pool::V0(ref mut pool)| {
+        grace_guard.ensure_out_of_grace(&pool_id)?;
+        pause_guard.ensure_not_paused(&pool_id)?;
+        let (_, amount_out) = pools.try_update(&pool_id, |pool| {
+            let pool = pool.latest();
+            let deviation_before = oracle_guard.price_deviation(&pool_id, pool);
             let side = if swapped { Side::Right } else { Side::Left };
-
-            let (amount_in, amount_out, _num_tick_crossings) =
-                pool.swap_to_price(side, amount, max_eff_sqrtprice, protocol_fee_fraction)?;
+            Self::ensure_swap_side_allowed(pool, side)?;
+            pool.write_observation(grace_guard.now)?;
+
+            let (amount_in, amount_out, crossed_ticks) = pool.swap_to_price(
+                side,
+                amount,
+                max_eff_sqrtprice,
+                protocol_fee_fraction,
+                max_underpay,
+            )?;
+            Self::ensure_tick_crossings_allowed(max_tick_crossings, &crossed_ticks)?;
+            oracle_guard.ensure_within_deviation(&pool_id, pool, deviation_before)?;
 
             account
                 .withdraw(&token_in, amount_in)
@@ -1873,15 +4968,28 @@ impl<T: Types, S: StateMut<T>, SS: BorrowMut<S>> Dex<T, S, SS> {
             account
                 .deposit(&token_out, amount_out)
                 .map_err(|e| error_here!(e))?;
+            account.auto_unregister_if_empty(&token_in);
+            pool.last_trade_ts = grace_guard.now;
+            pool.last_trade_sqrtprice = pool.spot_sqrtprice(Side::Left, 0);
+            pool.last_swap_tick_crossings = u32::try_from(crossed_ticks.len()).unwrap_or(u32::MAX);
 
             // Log swap event and pool state
-            logger.log_swap_event(
-                account_id,
-                (&token_in, &token_out),
-                (&amount_in, &amount_out),
-                &[], // TODO: add fees into swap event
+            if event_verbosity & EVENTS_SWAP != 0 {
+                logger.log_swap_event(
+                    account_id,
+                    (&token_in, &token_out),
+                    (&amount_in, &amount_out),
+                    &[], // TODO: add fees into swap event
+                );
+            }
+            Self::log_crossed_ticks(&pool_id, side, &crossed_ticks, tick_positions, logger);
+            Self::log_pool_v0_state(
+                &pool_id,
+                pool,
+                logger,
+                event_verbosity,
+                PoolUpdateReason::Swap,
             );
-            Self::log_pool_v0_state(&pool_id, pool, logger, PoolUpdateReason::Swap);
 
             Ok((amount_in, amount_out))
         })?;
@@ -1895,18 +5003,129 @@ impl<T: Types, S: StateMut<T>, SS: BorrowMut<S>> Dex<T, S, SS> {
             contract, logger, ..
         } = self.members_mut();
         let contract = contract.latest();
+        let event_verbosity = contract.event_verbosity;
 
-        contract.pools.try_inspect(pool_id, |Pool::V0(ref pool)| {
-            Self::log_pool_v0_state(pool_id, pool, logger, reason);
+        contract.pools.try_inspect(pool_id, |pool| {
+            Self::log_pool_v0_state(pool_id, pool, logger, event_verbosity, reason);
         })
     }
 
+    /// Rejects swap into the pool from `side` if pool is restricted to the other direction
+    fn ensure_swap_side_allowed(pool: &PoolLatest<T>, side: Side) -> Result<()> {
+        ensure_here!(
+            pool.allowed_swap_side
+                .map_or(true, |allowed| allowed == side),
+            ErrorKind::SwapDirectionNotAllowed
+        );
+        Ok(())
+    }
+
+    /// Rejects swap which crossed more ticks than allowed by `max_tick_crossings`
+    fn ensure_tick_crossings_allowed(
+        max_tick_crossings: Option<u32>,
+        crossed_ticks: &CrossedTicks,
+    ) -> Result<()> {
+        if let Some(max_tick_crossings) = max_tick_crossings {
+            ensure_here!(
+                u32::try_from(crossed_ticks.len()).map_or(false, |num| num <= max_tick_crossings),
+                ErrorKind::TooManyTickCrossings
+            );
+        }
+        Ok(())
+    }
+
+    /// Logs crossed ticks, along with range boundary crossings of positions registered at them
+    fn log_crossed_ticks(
+        pool_id: &PoolId,
+        side: Side,
+        crossed_ticks: &CrossedTicks,
+        tick_positions: Option<&T::TickPositionsMap>,
+        logger: &mut dyn Logger,
+    ) {
+        for &(fee_level, tick) in crossed_ticks {
+            logger.log_tick_crossed_event(pool_id.as_refs(), fee_level, tick, side);
+
+            let Some(tick_positions) = tick_positions else {
+                continue;
+            };
+            tick_positions.inspect(&(pool_id.clone(), fee_level, tick), |positions| {
+                for &(position_id, is_low_bound) in positions {
+                    // Swap from left side moves price up, i.e. towards higher ticks
+                    let entered = is_low_bound == (side == Side::Left);
+                    logger.log_position_range_boundary_crossed_event(position_id, entered);
+                }
+            });
+        }
+    }
+
+    /// Registers position at both of its range bounds, in pool order of ticks.
+    /// Full range bounds can't be crossed and aren't registered.
+    /// Ticks which already have `MAX_NOTIFIED_POSITIONS_PER_TICK` positions are skipped
+    fn register_position_bounds(
+        tick_positions: &mut Option<T::TickPositionsMap>,
+        item_factory: &mut dyn ItemFactory<T>,
+        pool_id: &PoolId,
+        fee_level: FeeLevel,
+        (tick_low, tick_high): (Tick, Tick),
+        position_id: PositionId,
+    ) -> Result<()> {
+        let tick_positions =
+            tick_positions.get_or_insert_with(|| item_factory.new_tick_positions_map());
+        for (tick, is_low_bound) in [(tick_low, true), (tick_high, false)] {
+            if tick == Tick::MIN || tick == Tick::MAX {
+                continue;
+            }
+            tick_positions.update_or_insert(
+                &(pool_id.clone(), fee_level, tick),
+                || Ok(Vec::new()),
+                |positions, _| {
+                    if positions.len() < MAX_NOTIFIED_POSITIONS_PER_TICK {
+                        positions.push((position_id, is_low_bound));
+                    }
+                    Ok(())
+                },
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Removes position from registry at both of its range bounds, dropping emptied entries
+    fn unregister_position_bounds(
+        tick_positions: Option<&mut T::TickPositionsMap>,
+        pool_id: &PoolId,
+        fee_level: FeeLevel,
+        (tick_low, tick_high): (Tick, Tick),
+        position_id: PositionId,
+    ) -> Result<()> {
+        let Some(tick_positions) = tick_positions else {
+            return Ok(());
+        };
+        for tick in [tick_low, tick_high] {
+            let key = (pool_id.clone(), fee_level, tick);
+            let emptied = tick_positions
+                .update(&key, |positions| {
+                    positions.retain(|&(id, _)| id != position_id);
+                    Ok(positions.is_empty())
+                })
+                .transpose()?
+                .unwrap_or(false);
+            if emptied {
+                tick_positions.remove(&key);
+            }
+        }
+        Ok(())
+    }
+
     fn log_pool_v0_state(
         pool_id: &PoolId,
-        pool: &PoolV0<T>,
+        pool: &impl PoolState<T>,
         logger: &mut dyn Logger,
+        event_verbosity: u8,
         reason: PoolUpdateReason,
     ) {
+        if event_verbosity & EVENTS_POOL_STATE == 0 {
+            return;
+        }
         let position_reserves = pool.position_reserves();
         let amounts_a = position_reserves.map(|(left, _right)| Amount::try_from(left).unwrap());
         let amounts_b = position_reserves.map(|(_left, right)| Amount::try_from(right).unwrap());
@@ -1932,6 +5151,7 @@ impl<T: Types, S: StateMut<T>, SS: BorrowMut<S>> Dex<T, S, SS> {
         start_tick: i32,
         number: u8,
     ) -> Result<i32> {
+        validate_fee_level(fee_level).map_err(|e| error_here!(e))?;
         self.with_caller_account_mut(|mut account_view| {
             Self::log_ticks_liquidity_change_impl(
                 pool,
@@ -1953,12 +5173,9 @@ impl<T: Types, S: StateMut<T>, SS: BorrowMut<S>> Dex<T, S, SS> {
         let (pool_id, _) = PoolId::try_from_pair(pool).map_err(|e| error_here!(e))?;
         let start_tick = Tick::new(start_tick).map_err(|e| error_here!(e))?;
 
-        let liquiduty_changes =
-            account_view
-                .pools
-                .try_inspect(&pool_id, |Pool::V0(ref pool)| {
-                    pool.get_ticks_liquidity_change(fee_level, Side::Left, start_tick, number)
-                })?;
+        let liquiduty_changes = account_view.pools.try_inspect(&pool_id, |pool| {
+            pool.get_ticks_liquidity_change(fee_level, Side::Left, start_tick, number)
+        })?;
 
         for (tick, liquidity_change) in &liquiduty_changes {
             account_view.logger.log_tick_update_event(
@@ -1975,3 +5192,13 @@ impl<T: Types, S: StateMut<T>, SS: BorrowMut<S>> Dex<T, S, SS> {
             .ok_or(error_here!(ErrorKind::InternalTickNotFound))
     }
 }
+
+/// Converts `WasmAmount` into `Amount`, failing with `ConvOverflow` if value doesn't fit
+fn wasm_amount_to_amount(value: &WasmAmount) -> Result<Amount> {
+    let bytes = value.to_bytes_be();
+    ensure_here!(
+        bytes.len() <= std::mem::size_of::<Amount>(),
+        ErrorKind::ConvOverflow
+    );
+    Ok(Amount::from(bytes.as_slice()))
+}
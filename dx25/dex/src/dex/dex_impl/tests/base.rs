@@ -6,17 +6,29 @@
 // Won't be fixed - `|x| x.do_something()` is usually more readable
 #![allow(clippy::redundant_closure_for_method_calls)]
 
-use crate::chain::{Amount, TokenId};
+use crate::chain::{AccountId, Amount, Liquidity, TokenId};
+use crate::dex::latest::NUM_FEE_LEVELS;
+use crate::dex::pool::{
+    fee_liquidity_from_net_liquidity, SWAP_MAX_UNDERPAY, SWAP_MAX_UNDERPAY_LIMIT,
+};
 use crate::dex::test_utils::{
     amount_as_u128, new_account_id, new_amount, new_token_id, Event, Sandbox, SwapTestContext,
 };
 use crate::dex::tick::Tick;
+use crate::dex::utils::checked_sum;
+#[cfg(feature = "smart-routing")]
+use crate::dex::MAX_ROUTE_HOPS;
 use crate::dex::{
-    BasisPoints, Error, ErrorKind, PairExt, PoolId, PositionInit, Range, Side, State as _,
+    Account, AccountV0, BasisPoints, Error, ErrorKind, Estimations as _, Map as _, PairExt, PoolId,
+    PositionInfo, PositionInit, Range, Set as _, Side, SlippageTolerance, State as _,
+    StateMembersMut, StateMut as _, WithdrawMode, BASIS_POINT_DIVISOR, EVENTS_ALL,
+    EVENTS_POOL_STATE, EVENTS_SWAP, MAX_PROTOCOL_FEE_FRACTION,
 };
 use crate::Float;
 use crate::{assert_any_matches, assert_eq_rel_tol};
 use assert_matches::assert_matches;
+use itertools::Itertools;
+use num_traits::Zero;
 use rand::Rng;
 use rstest::rstest;
 
@@ -136,6 +148,46 @@ fn add_remove_guards() {
     );
 }
 
+#[test]
+fn set_protocol_fee_fraction_logs_event() {
+    let acc = new_account_id();
+    let mut sandbox = Sandbox::new_default(acc.clone());
+    let old = sandbox.call(|dex| dex.protocol_fee_fraction());
+
+    sandbox
+        .call_mut(|dex| dex.set_protocol_fee_fraction(old + 10))
+        .unwrap();
+
+    assert_eq!(sandbox.call(|dex| dex.protocol_fee_fraction()), old + 10);
+    assert_eq!(
+        sandbox.latest_logs(),
+        &[Event::SetProtocolFee {
+            old,
+            new: old + 10,
+            account: acc,
+        }]
+    );
+
+    // Rejected change must not be logged
+    let other = new_account_id();
+    sandbox.set_initiator_caller_ids(other);
+    assert_matches!(
+        sandbox.call_mut(|dex| dex.set_protocol_fee_fraction(old)),
+        Err(Error {
+            kind: ErrorKind::PermissionDenied,
+            ..
+        })
+    );
+    assert_eq!(
+        sandbox
+            .logs()
+            .iter()
+            .filter(|event| matches!(event, Event::SetProtocolFee { .. }))
+            .count(),
+        1
+    );
+}
+
 #[test]
 fn open_close_position() {
     let acc = new_account_id();
@@ -321,6 +373,153 @@ fn open_two_positions() {
     assert_eq!(pos_id, 1);
 }
 
+#[test]
+fn close_positions_in_pool() {
+    let acc = new_account_id();
+    let mut sandbox = Sandbox::new_default(acc.clone());
+    sandbox.call_mut(|dex| dex.register_account()).unwrap();
+
+    let token_0 = new_token_id();
+    let token_1 = new_token_id();
+    let token_2 = new_token_id();
+
+    sandbox
+        .call_mut(|dex| dex.register_tokens(&acc, [&token_0, &token_1, &token_2]))
+        .unwrap();
+    for token in [&token_0, &token_1, &token_2] {
+        sandbox
+            .call_mut(|dex| dex.deposit(&acc, token, new_amount(5_000_000)))
+            .unwrap();
+    }
+    //
+    // Open two positions in the first pool, and one in the second
+    //
+    let amounts = (new_amount(100_000), new_amount(100_000));
+    let (pos_id_0, _, _, _) = sandbox
+        .call_mut(|dex| dex.open_position_full(&token_0, &token_1, 1, amounts.0, amounts.1))
+        .unwrap();
+    let (pos_id_1, _, _, _) = sandbox
+        .call_mut(|dex| dex.open_position_full(&token_0, &token_1, 1, amounts.0, amounts.1))
+        .unwrap();
+    let (pos_id_2, _, _, _) = sandbox
+        .call_mut(|dex| dex.open_position_full(&token_0, &token_2, 1, amounts.0, amounts.1))
+        .unwrap();
+    //
+    // Close positions in the first pool only; tokens are passed in reverse order
+    //
+    let closed = sandbox
+        .call_mut(|dex| dex.close_positions_in_pool((token_1.clone(), token_0.clone())))
+        .unwrap();
+
+    let mut closed_ids: Vec<_> = closed.iter().map(|(id, _, _)| *id).collect();
+    closed_ids.sort_unstable();
+    assert_eq!(closed_ids, vec![pos_id_0, pos_id_1]);
+    for (_, amount_1, amount_0) in &closed {
+        assert!(*amount_0 <= amounts.0 && amounts.0 - *amount_0 <= new_amount(1));
+        assert!(*amount_1 <= amounts.1 && amounts.1 - *amount_1 <= new_amount(1));
+    }
+
+    assert_matches!(
+        sandbox.call(|dex| dex.get_position_info(pos_id_0)),
+        Err(Error {
+            kind: ErrorKind::PositionDoesNotExist,
+            ..
+        })
+    );
+    assert_matches!(
+        sandbox.call(|dex| dex.get_position_info(pos_id_1)),
+        Err(Error {
+            kind: ErrorKind::PositionDoesNotExist,
+            ..
+        })
+    );
+    sandbox.call(|dex| dex.get_position_info(pos_id_2)).unwrap();
+    //
+    // Repeated call is a no-op
+    //
+    let closed = sandbox
+        .call_mut(|dex| dex.close_positions_in_pool((token_0.clone(), token_1.clone())))
+        .unwrap();
+    assert!(closed.is_empty());
+    sandbox.call(|dex| dex.get_position_info(pos_id_2)).unwrap();
+}
+
+#[test]
+fn pool_created_event_logged_once_per_pool() {
+    let acc = new_account_id();
+    let mut sandbox = Sandbox::new_default(acc.clone());
+    sandbox.call_mut(|dex| dex.register_account()).unwrap();
+
+    let token_0 = new_token_id();
+    let token_1 = new_token_id();
+    let token_2 = new_token_id();
+
+    sandbox
+        .call_mut(|dex| dex.register_tokens(&acc, [&token_0, &token_1, &token_2]))
+        .unwrap();
+    for token in [&token_0, &token_1, &token_2] {
+        sandbox
+            .call_mut(|dex| dex.deposit(&acc, token, new_amount(5_000_000)))
+            .unwrap();
+    }
+
+    // Pool tokens are logged in canonical order, which may differ from the call order
+    let same_pair = |pool: &(TokenId, TokenId), tokens: (&TokenId, &TokenId)| {
+        (&pool.0, &pool.1) == tokens || (&pool.1, &pool.0) == tokens
+    };
+
+    let amounts = (new_amount(100_000), new_amount(100_000));
+    //
+    // First position creates the pool; event must precede position opening
+    //
+    sandbox
+        .call_mut(|dex| dex.open_position_full(&token_0, &token_1, 1, amounts.0, amounts.1))
+        .unwrap();
+    let logs = sandbox.latest_logs();
+    let created_idx = logs
+        .iter()
+        .position(|event| matches!(event, Event::PoolCreated { .. }))
+        .unwrap();
+    let opened_idx = logs
+        .iter()
+        .position(|event| matches!(event, Event::OpenPosition { .. }))
+        .unwrap();
+    assert!(created_idx < opened_idx);
+    assert_matches!(
+        &logs[created_idx],
+        Event::PoolCreated { pool, fee_level: 1, account, .. }
+            if same_pair(pool, (&token_0, &token_1)) && *account == acc
+    );
+    //
+    // Subsequent positions in the same pool don't emit the event
+    //
+    sandbox
+        .call_mut(|dex| dex.open_position_full(&token_0, &token_1, 1, amounts.0, amounts.1))
+        .unwrap();
+    assert!(!sandbox
+        .latest_logs()
+        .iter()
+        .any(|event| matches!(event, Event::PoolCreated { .. })));
+    //
+    // Another pool gets its own event
+    //
+    sandbox
+        .call_mut(|dex| dex.open_position_full(&token_0, &token_2, 1, amounts.0, amounts.1))
+        .unwrap();
+
+    let created_pools: Vec<_> = sandbox
+        .logs()
+        .iter()
+        .filter_map(|event| match event {
+            Event::PoolCreated { pool, .. } => Some(pool.clone()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(created_pools.len(), 2);
+    assert!(same_pair(&created_pools[0], (&token_0, &token_1)));
+    assert!(same_pair(&created_pools[1], (&token_0, &token_2)));
+}
+
 #[test]
 fn get_positions_infos() {
     let acc = new_account_id();
@@ -1366,6 +1565,50 @@ fn test_withdraw_fee(
     }
 }
 
+#[test]
+fn position_fee_accumulators_baselines() {
+    let SwapTestContext {
+        mut sandbox,
+        token_ids: (token_0, token_1),
+        position_id,
+        ..
+    } = SwapTestContext::new();
+
+    let (init_before, unwithdrawn_before) = sandbox
+        .call(|dex| dex.get_position_fee_accumulators(position_id))
+        .unwrap();
+    assert_eq!(init_before, unwithdrawn_before);
+
+    sandbox
+        .call_mut(|dex| {
+            dex.swap_exact_in(
+                &[token_0.clone(), token_1.clone()],
+                new_amount(100_000),
+                new_amount(0),
+            )
+        })
+        .unwrap();
+    sandbox
+        .call_mut(|dex| dex.withdraw_fee(position_id))
+        .unwrap();
+
+    let (init_after, unwithdrawn_after) = sandbox
+        .call(|dex| dex.get_position_fee_accumulators(position_id))
+        .unwrap();
+    assert_eq!(init_after, init_before);
+    assert_ne!(unwithdrawn_after, unwithdrawn_before);
+    assert!(unwithdrawn_after.0 >= unwithdrawn_before.0);
+    assert!(unwithdrawn_after.1 >= unwithdrawn_before.1);
+
+    assert_matches!(
+        sandbox.call(|dex| dex.get_position_fee_accumulators(position_id + 1)),
+        Err(Error {
+            kind: ErrorKind::PositionDoesNotExist,
+            ..
+        })
+    );
+}
+
 #[test]
 fn test_liqudity_fee_level_distribution() {
     let open_position = |sandbox: &mut Sandbox,
@@ -1542,3 +1785,4388 @@ fn test_log_positions_ticks() {
         Ok(last_logged_tick) if last_logged_tick == 99
     );
 }
+
+#[test]
+fn swap_rejected_when_spot_price_deviates_from_reference() {
+    let SwapTestContext {
+        mut sandbox,
+        owner,
+        token_ids: (token_0, token_1),
+        ..
+    } = SwapTestContext::new();
+    let tokens = (token_0.clone(), token_1.clone());
+
+    sandbox
+        .call_mut(|dex| dex.set_reference_price_setter(Some(owner.clone())))
+        .unwrap();
+    sandbox
+        .call_mut(|dex| dex.set_max_oracle_deviation(Some(100)))
+        .unwrap();
+
+    let spot_price = |sandbox: &Sandbox| {
+        let pool_info = sandbox
+            .call(|dex| dex.get_pool_info(tokens.clone()))
+            .unwrap()
+            .unwrap();
+        pool_info.spot_sqrtprices[0] * pool_info.spot_sqrtprices[0]
+    };
+    // Swapping in first token raises reported spot price, and vice versa
+    let swap_in = |sandbox: &mut Sandbox, token_in: &TokenId, amount: u128| {
+        let token_out = if token_in == &token_0 {
+            &token_1
+        } else {
+            &token_0
+        };
+        sandbox.call_mut(|dex| {
+            dex.swap_exact_in(
+                &[token_in.clone(), token_out.clone()],
+                new_amount(amount),
+                new_amount(0),
+            )
+        })
+    };
+    let swap = |sandbox: &mut Sandbox| swap_in(sandbox, &token_0, 100);
+
+    // Within the band
+    let price = spot_price(&sandbox) * Float::from(1.005);
+    sandbox
+        .call_mut(|dex| dex.set_reference_price(tokens.clone(), Some(price)))
+        .unwrap();
+    assert_matches!(swap(&mut sandbox), Ok(_));
+
+    // Swap which starts within the band, but ends out of it
+    let price = spot_price(&sandbox);
+    sandbox
+        .call_mut(|dex| dex.set_reference_price(tokens.clone(), Some(price)))
+        .unwrap();
+    assert_matches!(
+        swap_in(&mut sandbox, &token_0, 100_000),
+        Err(Error {
+            kind: ErrorKind::OracleDeviationExceeded,
+            ..
+        })
+    );
+
+    // Out of the band
+    let price = spot_price(&sandbox) / Float::from(1.05);
+    sandbox
+        .call_mut(|dex| dex.set_reference_price(tokens.clone(), Some(price)))
+        .unwrap();
+    assert_matches!(
+        swap(&mut sandbox),
+        Err(Error {
+            kind: ErrorKind::OracleDeviationExceeded,
+            ..
+        })
+    );
+    // Swaps moving price toward reference are still allowed
+    let spot_price_before = spot_price(&sandbox);
+    assert_matches!(swap_in(&mut sandbox, &token_1, 1_000), Ok(_));
+    assert!(spot_price(&sandbox) < spot_price_before);
+
+    // Updated reference re-enables swaps
+    let price = spot_price(&sandbox);
+    sandbox
+        .call_mut(|dex| dex.set_reference_price(tokens.clone(), Some(price)))
+        .unwrap();
+    assert_matches!(swap(&mut sandbox), Ok(_));
+
+    // Reference price is reported in requested tokens order
+    let reverse_price = sandbox
+        .call(|dex| dex.get_reference_price((token_1.clone(), token_0.clone())))
+        .unwrap()
+        .unwrap();
+    assert_eq_rel_tol!(reverse_price, price.recip(), 10);
+
+    // Removed reference disables the check
+    sandbox
+        .call_mut(|dex| dex.set_reference_price(tokens.clone(), Some(price / Float::from(2.0))))
+        .unwrap();
+    assert_matches!(
+        swap(&mut sandbox),
+        Err(Error {
+            kind: ErrorKind::OracleDeviationExceeded,
+            ..
+        })
+    );
+    sandbox
+        .call_mut(|dex| dex.set_reference_price(tokens.clone(), None))
+        .unwrap();
+    assert_matches!(swap(&mut sandbox), Ok(_));
+}
+
+#[test]
+fn reference_price_permissions() {
+    let SwapTestContext {
+        mut sandbox,
+        owner,
+        token_ids: (token_0, token_1),
+        ..
+    } = SwapTestContext::new();
+    let tokens = (token_0.clone(), token_1.clone());
+
+    // No setter configured
+    assert_matches!(
+        sandbox.call_mut(|dex| dex.set_reference_price(tokens.clone(), Some(Float::one()))),
+        Err(Error {
+            kind: ErrorKind::PermissionDenied,
+            ..
+        })
+    );
+
+    sandbox
+        .call_mut(|dex| dex.set_reference_price_setter(Some(owner.clone())))
+        .unwrap();
+
+    assert_matches!(
+        sandbox.call_mut(|dex| dex.set_reference_price(tokens.clone(), Some(Float::zero()))),
+        Err(Error {
+            kind: ErrorKind::InvalidParams,
+            ..
+        })
+    );
+
+    let stranger = new_account_id();
+    sandbox.set_initiator_caller_ids(stranger);
+    assert_matches!(
+        sandbox.call_mut(|dex| dex.set_reference_price(tokens.clone(), Some(Float::one()))),
+        Err(Error {
+            kind: ErrorKind::PermissionDenied,
+            ..
+        })
+    );
+    assert_matches!(
+        sandbox.call_mut(|dex| dex.set_max_oracle_deviation(Some(100))),
+        Err(Error {
+            kind: ErrorKind::PermissionDenied,
+            ..
+        })
+    );
+    assert_matches!(
+        sandbox.call(|dex| dex.get_reference_price(tokens.clone())),
+        Ok(None)
+    );
+}
+
+#[test]
+fn swap_not_checked_without_oracle_config() {
+    let SwapTestContext {
+        mut sandbox,
+        owner,
+        token_ids: (token_0, token_1),
+        ..
+    } = SwapTestContext::new();
+    let tokens = (token_0.clone(), token_1.clone());
+
+    // Reference price far from spot, but max deviation not set
+    sandbox
+        .call_mut(|dex| dex.set_reference_price_setter(Some(owner.clone())))
+        .unwrap();
+    sandbox
+        .call_mut(|dex| dex.set_reference_price(tokens, Some(Float::from(1000.0))))
+        .unwrap();
+
+    assert_matches!(
+        sandbox.call_mut(|dex| dex.swap_exact_in(
+            &[token_0.clone(), token_1.clone()],
+            new_amount(100),
+            new_amount(0)
+        )),
+        Ok(_)
+    );
+}
+
+#[test]
+fn verified_tokens_paged() {
+    let acc = new_account_id();
+    let mut sandbox = Sandbox::new_default(acc);
+
+    let tokens = (0..12).map(|_| new_token_id()).collect_vec();
+    sandbox
+        .call_mut(|dex| dex.add_verified_tokens(tokens.clone()))
+        .unwrap();
+
+    let all_tokens = sandbox.call(|dex| dex.get_verified_tokens());
+    assert_eq!(all_tokens.len(), 12);
+    assert_eq!(sandbox.call(|dex| dex.get_verified_tokens_count()), 12);
+
+    // Pages concatenate into full list, with partial last page
+    let pages = [0, 5, 10]
+        .into_iter()
+        .map(|offset| sandbox.call(|dex| dex.get_verified_tokens_paged(offset, 5)))
+        .collect_vec();
+    assert_eq!(pages.iter().map(Vec::len).collect_vec(), vec![5, 5, 2]);
+    assert_eq!(pages.concat(), all_tokens);
+
+    // Order is stable between calls
+    assert_eq!(
+        sandbox.call(|dex| dex.get_verified_tokens_paged(3, 4)),
+        all_tokens[3..7]
+    );
+    // Limit larger than list, offset past the end, zero limit
+    assert_eq!(
+        sandbox.call(|dex| dex.get_verified_tokens_paged(0, 100)),
+        all_tokens
+    );
+    assert!(sandbox
+        .call(|dex| dex.get_verified_tokens_paged(12, 5))
+        .is_empty());
+    assert!(sandbox
+        .call(|dex| dex.get_verified_tokens_paged(0, 0))
+        .is_empty());
+
+    sandbox
+        .call_mut(|dex| dex.remove_verified_tokens(tokens[..2].to_vec()))
+        .unwrap();
+    assert_eq!(sandbox.call(|dex| dex.get_verified_tokens_count()), 10);
+}
+
+#[test]
+fn token_verified_checks() {
+    let acc = new_account_id();
+    let mut sandbox = Sandbox::new_default(acc);
+    let token_0 = new_token_id();
+    let token_1 = new_token_id();
+
+    assert!(!sandbox.call(|dex| dex.is_token_verified(&token_0)));
+    sandbox
+        .call_mut(|dex| dex.add_verified_tokens([token_0.clone()]))
+        .unwrap();
+    assert!(sandbox.call(|dex| dex.is_token_verified(&token_0)));
+    assert!(!sandbox.call(|dex| dex.is_token_verified(&token_1)));
+
+    assert_eq!(
+        sandbox.call(|dex| dex.are_tokens_verified(&[
+            token_1.clone(),
+            token_0.clone(),
+            token_1.clone()
+        ])),
+        vec![false, true, false]
+    );
+    assert!(sandbox.call(|dex| dex.are_tokens_verified(&[])).is_empty());
+
+    sandbox
+        .call_mut(|dex| dex.remove_verified_tokens([token_0.clone()]))
+        .unwrap();
+    assert_eq!(
+        sandbox.call(|dex| dex.are_tokens_verified(&[token_0, token_1])),
+        vec![false, false]
+    );
+}
+
+#[test]
+fn spot_price_scaled_by_token_decimals() {
+    let SwapTestContext {
+        mut sandbox,
+        token_ids: (token_0, token_1),
+        ..
+    } = SwapTestContext::new();
+
+    let raw_price = |sandbox: &Sandbox, tokens: (&TokenId, &TokenId)| {
+        let pool_info = sandbox
+            .call(|dex| dex.get_pool_info((tokens.0.clone(), tokens.1.clone())))
+            .unwrap()
+            .unwrap();
+        pool_info.spot_sqrtprices[1].powi(2)
+    };
+    let scaled_price = |sandbox: &Sandbox, tokens: (&TokenId, &TokenId)| {
+        sandbox
+            .call(|dex| dex.get_spot_price_scaled(tokens.0, tokens.1, 1))
+            .unwrap()
+    };
+    let raw_forward = raw_price(&sandbox, (&token_0, &token_1));
+    let raw_backward = raw_price(&sandbox, (&token_1, &token_0));
+
+    // No decimals registered - raw price
+    assert_eq_rel_tol!(
+        scaled_price(&sandbox, (&token_0, &token_1)),
+        raw_forward,
+        10
+    );
+
+    // Only one token registered - still raw price
+    sandbox
+        .call_mut(|dex| dex.set_token_decimals(token_0.clone(), Some(6)))
+        .unwrap();
+    assert_eq_rel_tol!(
+        scaled_price(&sandbox, (&token_0, &token_1)),
+        raw_forward,
+        10
+    );
+
+    sandbox
+        .call_mut(|dex| dex.set_token_decimals(token_1.clone(), Some(18)))
+        .unwrap();
+    assert_eq!(
+        sandbox.call(|dex| dex.get_token_decimals(&token_1)),
+        Some(18)
+    );
+    assert_eq_rel_tol!(
+        scaled_price(&sandbox, (&token_0, &token_1)),
+        raw_forward * Float::from(1e12),
+        10
+    );
+    assert_eq_rel_tol!(
+        scaled_price(&sandbox, (&token_1, &token_0)),
+        raw_backward * Float::from(1e-12),
+        10
+    );
+
+    // Removing decimals falls back to raw price
+    sandbox
+        .call_mut(|dex| dex.set_token_decimals(token_1.clone(), None))
+        .unwrap();
+    assert_eq_rel_tol!(
+        scaled_price(&sandbox, (&token_1, &token_0)),
+        raw_backward,
+        10
+    );
+
+    assert_matches!(
+        sandbox.call(|dex| dex.get_spot_price_scaled(&token_0, &token_1, NUM_FEE_LEVELS)),
+        Err(Error {
+            kind: ErrorKind::IllegalFeeLevel,
+            ..
+        })
+    );
+
+    sandbox.set_initiator_caller_ids(new_account_id());
+    assert_matches!(
+        sandbox.call_mut(|dex| dex.set_token_decimals(token_0.clone(), Some(8))),
+        Err(Error {
+            kind: ErrorKind::PermissionDenied,
+            ..
+        })
+    );
+}
+
+#[test]
+fn register_accounts_batch() {
+    let owner = new_account_id();
+    let mut sandbox = Sandbox::new_default(owner.clone());
+
+    let existing = new_account_id();
+    sandbox.set_initiator_caller_ids(existing.clone());
+    sandbox.call_mut(|dex| dex.register_account()).unwrap();
+
+    // Only privileged accounts may register on behalf of others
+    let fresh = [new_account_id(), new_account_id()];
+    assert_matches!(
+        sandbox.call_mut(|dex| dex.register_accounts(&fresh)),
+        Err(Error {
+            kind: ErrorKind::PermissionDenied,
+            ..
+        })
+    );
+
+    let relayer = new_account_id();
+    sandbox.set_initiator_caller_ids(owner);
+    sandbox
+        .call_mut(|dex| dex.add_guard_accounts([relayer.clone()]))
+        .unwrap();
+    sandbox.set_initiator_caller_ids(relayer);
+
+    let batch = [fresh[0].clone(), existing.clone(), fresh[1].clone()];
+    assert_eq!(
+        sandbox
+            .call_mut(|dex| dex.register_accounts(&batch))
+            .unwrap(),
+        vec![true, false, true]
+    );
+    for account_id in &batch {
+        assert!(sandbox.call(|dex| dex.contract().as_ref().accounts.contains_key(account_id)));
+    }
+    // Repeated registration reports all accounts as existing
+    assert_eq!(
+        sandbox
+            .call_mut(|dex| dex.register_accounts(&batch))
+            .unwrap(),
+        vec![false, false, false]
+    );
+}
+
+#[test]
+fn swap_cooldown_per_account() {
+    let SwapTestContext {
+        mut sandbox,
+        owner,
+        token_ids: (token_0, token_1),
+        ..
+    } = SwapTestContext::new();
+
+    let other = new_account_id();
+    sandbox.set_initiator_caller_ids(other.clone());
+    sandbox.call_mut(|dex| dex.register_account()).unwrap();
+    sandbox
+        .call_mut(|dex| dex.register_tokens(&other, [&token_0, &token_1]))
+        .unwrap();
+    sandbox
+        .call_mut(|dex| dex.deposit(&other, &token_0, new_amount(1000)))
+        .unwrap();
+
+    sandbox.set_initiator_caller_ids(owner.clone());
+    sandbox
+        .call_mut(|dex| dex.set_swap_cooldown(Some(60)))
+        .unwrap();
+
+    let swap = |sandbox: &mut Sandbox, account: &AccountId, timestamp: u64| {
+        sandbox.set_initiator_caller_ids(account.clone());
+        sandbox.set_block_timestamp(timestamp);
+        sandbox.call_mut(|dex| {
+            dex.swap_exact_in(
+                &[token_0.clone(), token_1.clone()],
+                new_amount(100),
+                new_amount(0),
+            )
+        })
+    };
+
+    assert_matches!(swap(&mut sandbox, &owner, 1_000), Ok(_));
+    // Second swap within cooldown
+    assert_matches!(
+        swap(&mut sandbox, &owner, 1_030),
+        Err(Error {
+            kind: ErrorKind::SwapCooldown,
+            ..
+        })
+    );
+    // Other account isn't affected by owner's swaps
+    assert_matches!(swap(&mut sandbox, &other, 1_030), Ok(_));
+    // Rejected swap doesn't move the timestamp, so owner may swap again after cooldown
+    assert_matches!(swap(&mut sandbox, &owner, 1_060), Ok(_));
+    assert_matches!(swap(&mut sandbox, &owner, 1_200), Ok(_));
+
+    // Disabled cooldown allows swaps in the same block
+    sandbox.call_mut(|dex| dex.set_swap_cooldown(None)).unwrap();
+    assert_matches!(swap(&mut sandbox, &owner, 1_200), Ok(_));
+}
+
+#[test]
+fn active_fee_levels() {
+    let acc = new_account_id();
+    let mut sandbox = Sandbox::new_default(acc.clone());
+    let token_0 = new_token_id();
+    let token_1 = new_token_id();
+    let tokens = (token_0.clone(), token_1.clone());
+
+    // Nonexistent pool
+    assert_eq!(
+        sandbox
+            .call(|dex| dex.get_active_fee_levels(tokens.clone()))
+            .unwrap(),
+        Vec::<u8>::new()
+    );
+
+    sandbox.call_mut(|dex| dex.register_account()).unwrap();
+    sandbox
+        .call_mut(|dex| dex.register_tokens(&acc, [&token_0, &token_1]))
+        .unwrap();
+    sandbox
+        .call_mut(|dex| dex.deposit(&acc, &token_0, new_amount(2_000_000)))
+        .unwrap();
+    sandbox
+        .call_mut(|dex| dex.deposit(&acc, &token_1, new_amount(2_000_000)))
+        .unwrap();
+
+    let fee_rates = sandbox.call(|dex| dex.fee_rates_ticks());
+    for level in [3, 0] {
+        sandbox
+            .call_mut(|dex| {
+                dex.open_position_full(
+                    &token_0,
+                    &token_1,
+                    fee_rates[level],
+                    new_amount(1_000_000),
+                    new_amount(1_000_000),
+                )
+            })
+            .unwrap();
+    }
+
+    assert_eq!(
+        sandbox
+            .call(|dex| dex.get_active_fee_levels(tokens.clone()))
+            .unwrap(),
+        vec![0, 3]
+    );
+    // Order of tokens doesn't matter
+    assert_eq!(
+        sandbox
+            .call(|dex| dex.get_active_fee_levels((token_1.clone(), token_0.clone())))
+            .unwrap(),
+        vec![0, 3]
+    );
+}
+
+#[test]
+fn open_position_for_requires_operator() {
+    let owner = new_account_id();
+    let mut sandbox = Sandbox::new_default(owner.clone());
+    let token_0 = new_token_id();
+    let token_1 = new_token_id();
+
+    let user = new_account_id();
+    sandbox.set_initiator_caller_ids(user.clone());
+    sandbox.call_mut(|dex| dex.register_account()).unwrap();
+    sandbox
+        .call_mut(|dex| dex.register_tokens(&user, [&token_0, &token_1]))
+        .unwrap();
+    sandbox
+        .call_mut(|dex| dex.deposit(&user, &token_0, new_amount(2_000_000)))
+        .unwrap();
+    sandbox
+        .call_mut(|dex| dex.deposit(&user, &token_1, new_amount(2_000_000)))
+        .unwrap();
+
+    let operator = new_account_id();
+    sandbox.set_initiator_caller_ids(operator.clone());
+    sandbox.call_mut(|dex| dex.register_account()).unwrap();
+
+    let position = PositionInit::new_full_range(
+        new_amount(0),
+        new_amount(1_000_000),
+        new_amount(0),
+        new_amount(1_000_000),
+    );
+    let open_position_for = |sandbox: &mut Sandbox| {
+        sandbox
+            .call_mut(|dex| dex.open_position_for(&user, &token_0, &token_1, 1, position.clone()))
+    };
+
+    // Neither arbitrary account nor owner may spend user's deposits
+    for caller in [operator.clone(), owner.clone()] {
+        sandbox.set_initiator_caller_ids(caller);
+        assert_matches!(
+            open_position_for(&mut sandbox),
+            Err(Error {
+                kind: ErrorKind::PermissionDenied,
+                ..
+            })
+        );
+    }
+
+    sandbox
+        .call_mut(|dex| dex.add_operator_accounts([operator.clone()]))
+        .unwrap();
+    assert!(sandbox.call(|dex| dex.is_operator(&operator)));
+
+    sandbox.set_initiator_caller_ids(operator.clone());
+    let (position_id, amount_0, amount_1, _) = open_position_for(&mut sandbox).unwrap();
+
+    // Tokens are taken from user's deposits
+    assert_eq!(
+        sandbox
+            .call(|dex| dex.get_deposit(&user, &token_0))
+            .unwrap(),
+        new_amount(2_000_000) - amount_0
+    );
+    assert_eq!(
+        sandbox
+            .call(|dex| dex.get_deposit(&user, &token_1))
+            .unwrap(),
+        new_amount(2_000_000) - amount_1
+    );
+    // Position belongs to user, not to operator
+    assert_matches!(
+        sandbox.call_mut(|dex| dex.close_position(position_id)),
+        Err(Error {
+            kind: ErrorKind::NotYourPosition,
+            ..
+        })
+    );
+    sandbox.set_initiator_caller_ids(user.clone());
+    sandbox
+        .call_mut(|dex| dex.close_position(position_id))
+        .unwrap();
+
+    // Removed operator loses its permission
+    sandbox.set_initiator_caller_ids(owner);
+    sandbox
+        .call_mut(|dex| dex.remove_operator_accounts([operator.clone()]))
+        .unwrap();
+    sandbox.set_initiator_caller_ids(operator);
+    assert_matches!(
+        open_position_for(&mut sandbox),
+        Err(Error {
+            kind: ErrorKind::PermissionDenied,
+            ..
+        })
+    );
+}
+
+#[test]
+fn position_il_matches_analytic() {
+    let acc = new_account_id();
+    let mut sandbox = Sandbox::new_default(acc.clone());
+    let token_0 = new_token_id();
+    let token_1 = new_token_id();
+    let amount = 1_000_000_000_000_000_000u128;
+
+    sandbox.call_mut(|dex| dex.register_account()).unwrap();
+    sandbox
+        .call_mut(|dex| dex.register_tokens(&acc, [&token_0, &token_1]))
+        .unwrap();
+    sandbox
+        .call_mut(|dex| dex.deposit(&acc, &token_0, new_amount(3 * amount)))
+        .unwrap();
+    sandbox
+        .call_mut(|dex| dex.deposit(&acc, &token_1, new_amount(3 * amount)))
+        .unwrap();
+
+    let (position_id, ..) = sandbox
+        .call_mut(|dex| {
+            dex.open_position_full(
+                &token_0,
+                &token_1,
+                1,
+                new_amount(amount),
+                new_amount(amount),
+            )
+        })
+        .unwrap();
+
+    let spot_sqrtprice = |sandbox: &Sandbox| {
+        let pool_info = sandbox
+            .call(|dex| dex.get_pool_info((token_0.clone(), token_1.clone())))
+            .unwrap()
+            .unwrap();
+        f64::from(pool_info.spot_sqrtprices[0])
+    };
+
+    // No price move - no loss
+    let il = f64::from(
+        sandbox
+            .call(|dex| dex.get_position_il(position_id))
+            .unwrap(),
+    );
+    assert!(il.abs() < 1e-9, "{il}");
+
+    let init_sqrtprice = spot_sqrtprice(&sandbox);
+    sandbox
+        .call_mut(|dex| {
+            dex.swap_exact_in(
+                &[token_0.clone(), token_1.clone()],
+                new_amount(amount),
+                new_amount(0),
+            )
+        })
+        .unwrap();
+    let sqrtprice_ratio = spot_sqrtprice(&sandbox) / init_sqrtprice;
+
+    // Full range position behaves like constant product: IL = 2 * sqrt(r) / (1 + r) - 1
+    let price_ratio = sqrtprice_ratio * sqrtprice_ratio;
+    let expected_il = 2.0 * sqrtprice_ratio / (1.0 + price_ratio) - 1.0;
+    let il = f64::from(
+        sandbox
+            .call(|dex| dex.get_position_il(position_id))
+            .unwrap(),
+    );
+
+    assert!(il < 0.0);
+    assert!((il - expected_il).abs() < 1e-3, "{il} != {expected_il}");
+}
+
+#[test]
+fn fee_level_out_of_range() {
+    let SwapTestContext {
+        mut sandbox,
+        token_ids: (token_0, token_1),
+        ..
+    } = SwapTestContext::new();
+    let tokens = (token_0.clone(), token_1.clone());
+
+    assert_matches!(
+        sandbox.call(|dex| dex.fee_rate_ticks(NUM_FEE_LEVELS)),
+        Err(Error {
+            kind: ErrorKind::IllegalFeeLevel,
+            ..
+        })
+    );
+    assert_matches!(
+        sandbox.call(|dex| dex.get_pool_ticks(tokens.clone(), NUM_FEE_LEVELS)),
+        Err(Error {
+            kind: ErrorKind::IllegalFeeLevel,
+            ..
+        })
+    );
+    assert_matches!(
+        sandbox.call(|dex| dex.get_spot_price_scaled(&token_0, &token_1, NUM_FEE_LEVELS)),
+        Err(Error {
+            kind: ErrorKind::IllegalFeeLevel,
+            ..
+        })
+    );
+    assert_matches!(
+        sandbox.call_mut(|dex| dex.log_ticks_liquidity_change(
+            tokens.clone(),
+            NUM_FEE_LEVELS,
+            Tick::MIN.index(),
+            1
+        )),
+        Err(Error {
+            kind: ErrorKind::IllegalFeeLevel,
+            ..
+        })
+    );
+
+    // Valid levels are still served
+    let last_level = NUM_FEE_LEVELS - 1;
+    assert_matches!(sandbox.call(|dex| dex.fee_rate_ticks(last_level)), Ok(128));
+    assert_matches!(
+        sandbox.call(|dex| dex.get_pool_ticks(tokens.clone(), last_level)),
+        Ok(Some(_))
+    );
+}
+
+#[test]
+fn account_position_count_tracks_positions() {
+    let acc = new_account_id();
+    let mut sandbox = Sandbox::new_default(acc.clone());
+    let token_0 = new_token_id();
+    let token_1 = new_token_id();
+
+    // Unregistered account has no positions
+    let stranger = new_account_id();
+    assert_eq!(
+        sandbox
+            .call(|dex| dex.account_position_count(&stranger))
+            .unwrap(),
+        0
+    );
+    assert!(!sandbox
+        .call(|dex| dex.account_has_positions(&stranger))
+        .unwrap());
+
+    sandbox.call_mut(|dex| dex.register_account()).unwrap();
+    sandbox
+        .call_mut(|dex| dex.register_tokens(&acc, [&token_0, &token_1]))
+        .unwrap();
+    sandbox
+        .call_mut(|dex| dex.deposit(&acc, &token_0, new_amount(10_000_000)))
+        .unwrap();
+    sandbox
+        .call_mut(|dex| dex.deposit(&acc, &token_1, new_amount(10_000_000)))
+        .unwrap();
+    assert!(!sandbox.call(|dex| dex.account_has_positions(&acc)).unwrap());
+
+    let mut position_ids = Vec::new();
+    for count in 1..=3 {
+        let (position_id, _, _, _) = sandbox
+            .call_mut(|dex| {
+                dex.open_position(
+                    &token_0,
+                    &token_1,
+                    1,
+                    PositionInit::new_full_range(
+                        new_amount(0),
+                        new_amount(1_000_000),
+                        new_amount(0),
+                        new_amount(1_000_000),
+                    ),
+                )
+            })
+            .unwrap();
+        position_ids.push(position_id);
+        assert_eq!(
+            sandbox
+                .call(|dex| dex.account_position_count(&acc))
+                .unwrap(),
+            count
+        );
+        assert!(sandbox.call(|dex| dex.account_has_positions(&acc)).unwrap());
+    }
+
+    for (closed, position_id) in position_ids.into_iter().enumerate() {
+        sandbox
+            .call_mut(|dex| dex.close_position(position_id))
+            .unwrap();
+        assert_eq!(
+            sandbox
+                .call(|dex| dex.account_position_count(&acc))
+                .unwrap(),
+            2 - closed
+        );
+    }
+    assert!(!sandbox.call(|dex| dex.account_has_positions(&acc)).unwrap());
+}
+
+#[test]
+fn identical_tokens_rejected() {
+    let acc = new_account_id();
+    let mut sandbox = Sandbox::new_default(acc.clone());
+    let token = new_token_id();
+
+    sandbox.call_mut(|dex| dex.register_account()).unwrap();
+    sandbox
+        .call_mut(|dex| dex.register_tokens(&acc, [&token]))
+        .unwrap();
+    sandbox
+        .call_mut(|dex| dex.deposit(&acc, &token, new_amount(2_000_000)))
+        .unwrap();
+
+    assert_matches!(
+        sandbox.call_mut(|dex| dex.swap_exact_in(
+            &[token.clone(), token.clone()],
+            new_amount(1_000),
+            new_amount(0)
+        )),
+        Err(Error {
+            kind: ErrorKind::IdenticalTokens,
+            ..
+        })
+    );
+    assert_matches!(
+        sandbox.call_mut(|dex| dex.open_position(
+            &token,
+            &token,
+            1,
+            PositionInit::new_full_range(
+                new_amount(0),
+                new_amount(1_000_000),
+                new_amount(0),
+                new_amount(1_000_000),
+            )
+        )),
+        Err(Error {
+            kind: ErrorKind::IdenticalTokens,
+            ..
+        })
+    );
+    assert_matches!(
+        sandbox.call(|dex| dex.get_pool_info((token.clone(), token.clone()))),
+        Err(Error {
+            kind: ErrorKind::IdenticalTokens,
+            ..
+        })
+    );
+}
+
+#[test]
+fn sweep_untracked_leaves_accounted_funds() {
+    let owner = new_account_id();
+    let mut sandbox = Sandbox::new_default(owner.clone());
+    let token_0 = new_token_id();
+    let token_1 = new_token_id();
+
+    sandbox.call_mut(|dex| dex.register_account()).unwrap();
+
+    let user = new_account_id();
+    sandbox.set_initiator_caller_ids(user.clone());
+    sandbox.call_mut(|dex| dex.register_account()).unwrap();
+    sandbox
+        .call_mut(|dex| dex.register_tokens(&user, [&token_0, &token_1]))
+        .unwrap();
+    sandbox
+        .call_mut(|dex| dex.deposit(&user, &token_0, new_amount(2_000_000)))
+        .unwrap();
+    sandbox
+        .call_mut(|dex| dex.deposit(&user, &token_1, new_amount(2_000_000)))
+        .unwrap();
+    sandbox
+        .call_mut(|dex| {
+            dex.open_position(
+                &token_0,
+                &token_1,
+                1,
+                PositionInit::new_full_range(
+                    new_amount(0),
+                    new_amount(1_000_000),
+                    new_amount(0),
+                    new_amount(1_000_000),
+                ),
+            )
+        })
+        .unwrap();
+
+    // Tokens moved into pool are still accounted
+    assert_eq!(
+        sandbox
+            .call(|dex| dex.get_accounted_balance(&token_0))
+            .unwrap(),
+        new_amount(2_000_000)
+    );
+    // Tokens transferred directly to contract
+    sandbox.set_contract_balance(token_0.clone(), new_amount(2_000_777));
+
+    assert_matches!(
+        sandbox.call_mut(|dex| dex.sweep_untracked(&token_0, ())),
+        Err(Error {
+            kind: ErrorKind::PermissionDenied,
+            ..
+        })
+    );
+
+    sandbox.set_initiator_caller_ids(owner.clone());
+    let user_deposit = sandbox
+        .call(|dex| dex.get_deposit(&user, &token_0))
+        .unwrap();
+    let (swept, _) = sandbox
+        .call_mut(|dex| dex.sweep_untracked(&token_0, ()))
+        .unwrap();
+    assert_eq!(swept, new_amount(777));
+    assert_eq!(
+        sandbox.latest_logs(),
+        &[Event::SweepUntracked {
+            recipient: owner.clone(),
+            token: token_0.clone(),
+            amount: new_amount(777),
+        }]
+    );
+    assert_eq!(
+        sandbox
+            .call(|dex| dex.get_deposit(&user, &token_0))
+            .unwrap(),
+        user_deposit
+    );
+
+    // Nothing to sweep when contract holds exactly accounted amount, or even less
+    for balance in [2_000_000, 1_000_000] {
+        sandbox.set_contract_balance(token_0.clone(), new_amount(balance));
+        let (swept, result) = sandbox
+            .call_mut(|dex| dex.sweep_untracked(&token_0, ()))
+            .unwrap();
+        assert_eq!(swept, new_amount(0));
+        assert!(result.is_none());
+    }
+}
+
+#[test]
+fn account_v0_is_upgraded_on_update() {
+    let SwapTestContext {
+        mut sandbox,
+        owner,
+        token_ids: (token_0, _),
+        ..
+    } = SwapTestContext::new();
+
+    // Emulate account stored before `AccountV1` was introduced
+    sandbox
+        .call_mut(|dex| {
+            let StateMembersMut {
+                contract,
+                item_factory,
+                ..
+            } = dex.members_mut();
+            let mut token_balances = item_factory.new_account_token_balances_map();
+            token_balances.insert(token_0.clone(), new_amount(1_000));
+            let account = Account::V0(AccountV0 {
+                token_balances: token_balances.into(),
+                positions: item_factory.new_account_positions_set(),
+                withdraw_tracker: item_factory.new_account_withdraw_tracker(),
+                extra: Default::default(),
+            });
+            contract.latest().accounts.insert(owner.clone(), account);
+            Ok(())
+        })
+        .unwrap();
+
+    assert_eq!(
+        sandbox
+            .call(|dex| dex.get_deposit(&owner, &token_0))
+            .unwrap(),
+        new_amount(1_000)
+    );
+
+    sandbox
+        .call_mut(|dex| dex.set_auto_unregister(true))
+        .unwrap();
+
+    sandbox.call(|dex| {
+        dex.contract()
+            .as_ref()
+            .accounts
+            .inspect(&owner, |account| {
+                assert_matches!(account, Account::V1(_));
+                let account = account.as_ref();
+                assert!(account.auto_unregister_empty);
+                assert_eq!(
+                    account.token_balances.inspect(&token_0, |balance| *balance),
+                    Some(new_amount(1_000))
+                );
+            })
+            .unwrap();
+    });
+}
+
+#[test]
+fn accounted_balance_is_running_total() {
+    let SwapTestContext {
+        mut sandbox,
+        owner,
+        token_ids: (token_0, token_1),
+        ..
+    } = SwapTestContext::new();
+
+    let accounted = |sandbox: &Sandbox| {
+        sandbox
+            .call(|dex| dex.get_accounted_balance(&token_0))
+            .unwrap()
+    };
+    let initial = accounted(&sandbox);
+
+    sandbox
+        .call_mut(|dex| dex.deposit(&owner, &token_0, new_amount(5_000)))
+        .unwrap();
+    assert_eq!(accounted(&sandbox), initial + new_amount(5_000));
+
+    // Moving tokens into pool doesn't change accounted total
+    sandbox
+        .call_mut(|dex| {
+            dex.open_position(
+                &token_0,
+                &token_1,
+                1,
+                PositionInit::new_full_range(
+                    new_amount(0),
+                    new_amount(1_000),
+                    new_amount(0),
+                    new_amount(1_000),
+                ),
+            )
+        })
+        .unwrap();
+    assert_eq!(accounted(&sandbox), initial + new_amount(5_000));
+
+    sandbox
+        .call_mut(|dex| dex.withdraw(&owner, &token_0, new_amount(2_000), false, ()))
+        .unwrap();
+    let tracked = accounted(&sandbox);
+    assert_eq!(tracked, initial + new_amount(3_000));
+
+    // Emulate contract upgraded from version without running totals
+    sandbox
+        .call_mut(|dex| {
+            dex.contract_mut().latest().accounted_balances = None;
+            Ok(())
+        })
+        .unwrap();
+    assert_matches!(
+        sandbox.call(|dex| dex.get_accounted_balance(&token_0)),
+        Err(Error {
+            kind: ErrorKind::AccountedBalancesNotTracked,
+            ..
+        })
+    );
+
+    let (accounts, pools) = sandbox.call(|dex| {
+        let contract = dex.contract().as_ref();
+        let accounts = contract
+            .accounts
+            .iter()
+            .map(|(account_id, _)| account_id.clone())
+            .collect_vec();
+        let pools = contract
+            .pools
+            .iter()
+            .map(|(pool_id, _)| {
+                let (token_a, token_b) = pool_id.as_refs();
+                (token_a.clone(), token_b.clone())
+            })
+            .collect_vec();
+        (accounts, pools)
+    });
+
+    sandbox.set_initiator_caller_ids(new_account_id());
+    assert_matches!(
+        sandbox.call_mut(|dex| dex.init_accounted_balances([], [])),
+        Err(Error {
+            kind: ErrorKind::PermissionDenied,
+            ..
+        })
+    );
+    sandbox.set_initiator_caller_ids(owner.clone());
+    assert_matches!(
+        sandbox.call_mut(|dex| dex.init_accounted_balances([], [])),
+        Err(Error {
+            kind: ErrorKind::PayableAPINotSuspended,
+            ..
+        })
+    );
+
+    sandbox.call_mut(|dex| dex.suspend_payable_api()).unwrap();
+    // Not all pools are counted yet
+    assert!(!sandbox
+        .call_mut(|dex| dex.init_accounted_balances(accounts.clone(), []))
+        .unwrap());
+    assert_matches!(
+        sandbox.call(|dex| dex.get_accounted_balance(&token_0)),
+        Err(Error {
+            kind: ErrorKind::AccountedBalancesNotTracked,
+            ..
+        })
+    );
+    // Already counted accounts are skipped
+    assert!(sandbox
+        .call_mut(|dex| dex.init_accounted_balances(accounts.clone(), pools.clone()))
+        .unwrap());
+    assert_eq!(accounted(&sandbox), tracked);
+}
+
+#[test]
+fn sending_more_than_accounted_fails() {
+    let SwapTestContext {
+        mut sandbox,
+        owner,
+        token_ids: (token_0, _),
+        ..
+    } = SwapTestContext::new();
+
+    sandbox
+        .call_mut(|dex| dex.deposit(&owner, &token_0, new_amount(5_000)))
+        .unwrap();
+    // Emulate accounting anomaly
+    sandbox
+        .call_mut(|dex| {
+            let accounted_balances = dex
+                .contract_mut()
+                .latest()
+                .accounted_balances
+                .as_mut()
+                .unwrap();
+            accounted_balances.insert(token_0.clone(), new_amount(1_000));
+            Ok(())
+        })
+        .unwrap();
+
+    assert_matches!(
+        sandbox.call_mut(|dex| dex.withdraw(&owner, &token_0, new_amount(2_000), false, ())),
+        Err(Error {
+            kind: ErrorKind::AccountedBalanceUnderflow,
+            ..
+        })
+    );
+}
+
+#[test]
+fn pool_info_reports_last_trade() {
+    let mut ctx = SwapTestContext::new();
+    let (token_0, token_1) = ctx.token_ids.clone();
+    let pool_info = |ctx: &SwapTestContext, tokens: (TokenId, TokenId)| {
+        ctx.sandbox
+            .call(|dex| dex.get_pool_info(tokens))
+            .unwrap()
+            .unwrap()
+    };
+
+    // Never traded pool reports current spot price
+    let info = pool_info(&ctx, (token_0.clone(), token_1.clone()));
+    assert_eq!(info.last_trade_ts, 0);
+    assert_eq!(info.last_trade_sqrtprice, info.spot_sqrtprices[0]);
+
+    ctx.sandbox.set_block_timestamp(1_000);
+    ctx.sandbox
+        .call_mut(|dex| {
+            dex.swap_exact_in(
+                &[token_0.clone(), token_1.clone()],
+                new_amount(10_000),
+                new_amount(1),
+            )
+        })
+        .unwrap();
+    let info_after_swap = pool_info(&ctx, (token_0.clone(), token_1.clone()));
+    assert_eq!(info_after_swap.last_trade_ts, 1_000);
+    assert_ne!(
+        info_after_swap.last_trade_sqrtprice,
+        info.last_trade_sqrtprice
+    );
+    assert_eq!(
+        info_after_swap.last_trade_sqrtprice,
+        info_after_swap.spot_sqrtprices[0]
+    );
+
+    // Last trade values don't change without trades
+    ctx.sandbox.set_block_timestamp(2_000);
+    let info = pool_info(&ctx, (token_0.clone(), token_1.clone()));
+    assert_eq!(info.last_trade_ts, 1_000);
+    assert_eq!(
+        info.last_trade_sqrtprice,
+        info_after_swap.last_trade_sqrtprice
+    );
+
+    // Price is reported from the side of requested tokens order
+    let info_reversed = pool_info(&ctx, (token_1, token_0));
+    assert_eq!(info_reversed.last_trade_ts, 1_000);
+    assert_eq_rel_tol!(
+        f64::from(info_reversed.last_trade_sqrtprice),
+        f64::from(info_after_swap.last_trade_sqrtprice.recip()),
+        10
+    );
+}
+
+#[test]
+fn swap_traced_lists_pools_in_order() {
+    let acc = new_account_id();
+    let mut sandbox = Sandbox::new_default(acc.clone());
+    let tokens = (0..3).map(|_| new_token_id()).collect_vec();
+
+    sandbox.call_mut(|dex| dex.register_account()).unwrap();
+    sandbox
+        .call_mut(|dex| dex.register_tokens(&acc, &tokens))
+        .unwrap();
+    for token in &tokens {
+        sandbox
+            .call_mut(|dex| dex.deposit(&acc, token, new_amount(10_000_000)))
+            .unwrap();
+    }
+    for (token_a, token_b) in tokens.iter().tuple_windows() {
+        sandbox
+            .call_mut(|dex| {
+                dex.open_position(
+                    token_a,
+                    token_b,
+                    1,
+                    PositionInit::new_full_range(
+                        new_amount(0),
+                        new_amount(1_000_000),
+                        new_amount(0),
+                        new_amount(1_000_000),
+                    ),
+                )
+            })
+            .unwrap();
+    }
+    let expected_step = |token_in: &TokenId, token_out: &TokenId| {
+        let (pool_id, swapped) =
+            PoolId::try_from_pair((token_in.clone(), token_out.clone())).unwrap();
+        (pool_id, if swapped { Side::Right } else { Side::Left })
+    };
+
+    let (_, _, trace) = sandbox
+        .call_mut(|dex| dex.swap_exact_in_traced(&tokens, new_amount(1_000), new_amount(1)))
+        .unwrap();
+    assert_eq!(
+        trace,
+        vec![
+            expected_step(&tokens[0], &tokens[1]),
+            expected_step(&tokens[1], &tokens[2]),
+        ]
+    );
+
+    let path = [tokens[2].clone(), tokens[1].clone()];
+    let (_, _, trace) = sandbox
+        .call_mut(|dex| dex.swap_exact_out_traced(&path, new_amount(100), new_amount(1_000)))
+        .unwrap();
+    assert_eq!(trace, vec![expected_step(&tokens[2], &tokens[1])]);
+
+    // Failed swap produces no trace
+    assert_matches!(
+        sandbox.call_mut(|dex| dex.swap_exact_in_traced(
+            &tokens[..1],
+            new_amount(1_000),
+            new_amount(1)
+        )),
+        Err(Error {
+            kind: ErrorKind::AtLeastOneSwap,
+            ..
+        })
+    );
+}
+
+#[test]
+fn multiple_path_swap_sum_overflow() {
+    let half = Amount::MAX / Amount::from(2u64);
+
+    // Outputs summing up to exactly `Amount::MAX` are fine
+    assert_matches!(
+        checked_sum([half, half, Amount::from(1u64)]),
+        Ok(sum) if sum == Amount::MAX
+    );
+    // Going past it produces clean error instead of panic
+    assert_matches!(
+        checked_sum([half, half, Amount::from(2u64)]),
+        Err(ErrorKind::AmountOverflow)
+    );
+    assert_matches!(
+        checked_sum([Amount::MAX, Amount::from(1u64)]),
+        Err(ErrorKind::AmountOverflow)
+    );
+    assert_matches!(checked_sum([]), Ok(sum) if sum.is_zero());
+}
+
+#[test]
+fn verified_token_grace_period() {
+    let SwapTestContext {
+        mut sandbox,
+        owner,
+        token_ids: (token_0, token_1),
+        ..
+    } = SwapTestContext::new();
+
+    let swap = |sandbox: &mut Sandbox, timestamp: u64| {
+        sandbox.set_block_timestamp(timestamp);
+        sandbox.call_mut(|dex| {
+            dex.swap_exact_in(
+                &[token_0.clone(), token_1.clone()],
+                new_amount(100),
+                new_amount(0),
+            )
+        })
+    };
+
+    sandbox
+        .call_mut(|dex| dex.set_verified_token_grace(60))
+        .unwrap();
+    sandbox.set_block_timestamp(1_000);
+    sandbox
+        .call_mut(|dex| dex.add_verified_tokens([token_0.clone()]))
+        .unwrap();
+
+    assert_matches!(
+        swap(&mut sandbox, 1_030),
+        Err(Error {
+            kind: ErrorKind::TokenInGracePeriod,
+            ..
+        })
+    );
+    // Deposits aren't gated
+    sandbox
+        .call_mut(|dex| dex.deposit(&owner, &token_0, new_amount(1000)))
+        .unwrap();
+    assert_matches!(
+        swap(&mut sandbox, 1_059),
+        Err(Error {
+            kind: ErrorKind::TokenInGracePeriod,
+            ..
+        })
+    );
+    assert_matches!(swap(&mut sandbox, 1_060), Ok(_));
+
+    // Zero grace disables the check
+    sandbox
+        .call_mut(|dex| dex.set_verified_token_grace(0))
+        .unwrap();
+    sandbox
+        .call_mut(|dex| dex.add_verified_tokens([token_1.clone()]))
+        .unwrap();
+    assert_matches!(swap(&mut sandbox, 1_060), Ok(_));
+}
+
+#[test]
+fn solvency_report_surplus_is_untracked_funds() {
+    let SwapTestContext {
+        mut sandbox,
+        owner,
+        token_ids: (token_0, token_1),
+        ..
+    } = SwapTestContext::new();
+
+    sandbox
+        .call_mut(|dex| dex.deposit(&owner, &token_0, new_amount(5_000)))
+        .unwrap();
+    sandbox
+        .call_mut(|dex| {
+            dex.open_position(
+                &token_0,
+                &token_1,
+                1,
+                PositionInit::new_full_range(
+                    new_amount(0),
+                    new_amount(1_000),
+                    new_amount(0),
+                    new_amount(1_000),
+                ),
+            )
+        })
+        .unwrap();
+
+    let accounted = sandbox
+        .call(|dex| dex.get_accounted_balance(&token_0))
+        .unwrap();
+    // Contract holds exactly what it accounts
+    sandbox.set_contract_balance(token_0.clone(), accounted);
+    assert_matches!(
+        sandbox.call_mut(|dex| dex.solvency_report(&token_0)),
+        Ok((report_accounted, actual)) if report_accounted == accounted && actual == accounted
+    );
+
+    // Tokens transferred directly to contract show up as surplus only
+    sandbox.set_contract_balance(token_0.clone(), accounted + new_amount(321));
+    let (accounted, actual) = sandbox
+        .call_mut(|dex| dex.solvency_report(&token_0))
+        .unwrap();
+    assert!(actual >= accounted);
+    assert_eq!(actual - accounted, new_amount(321));
+}
+
+#[test]
+fn swap_max_underpay_setting() {
+    let SwapTestContext {
+        mut sandbox,
+        token_ids: (token_0, token_1),
+        ..
+    } = SwapTestContext::new();
+
+    // Default preserves the built-in tolerance
+    assert_eq!(
+        sandbox.call(|dex| dex.swap_max_underpay()),
+        SWAP_MAX_UNDERPAY
+    );
+
+    for max_underpay in [-SWAP_MAX_UNDERPAY, SWAP_MAX_UNDERPAY_LIMIT * Float::from(2)] {
+        assert_matches!(
+            sandbox.call_mut(|dex| dex.set_swap_max_underpay(Some(max_underpay))),
+            Err(Error {
+                kind: ErrorKind::InvalidParams,
+                ..
+            })
+        );
+    }
+
+    sandbox
+        .call_mut(|dex| dex.set_swap_max_underpay(Some(SWAP_MAX_UNDERPAY_LIMIT)))
+        .unwrap();
+    assert_eq!(
+        sandbox.call(|dex| dex.swap_max_underpay()),
+        SWAP_MAX_UNDERPAY_LIMIT
+    );
+    assert_matches!(
+        sandbox.call_mut(|dex| {
+            dex.swap_exact_in(
+                &[token_0.clone(), token_1.clone()],
+                new_amount(100),
+                new_amount(0),
+            )
+        }),
+        Ok(_)
+    );
+
+    sandbox
+        .call_mut(|dex| dex.set_swap_max_underpay(None))
+        .unwrap();
+    assert_eq!(
+        sandbox.call(|dex| dex.swap_max_underpay()),
+        SWAP_MAX_UNDERPAY
+    );
+}
+
+#[test]
+fn top_pools_sorted_by_liquidity() {
+    let acc = new_account_id();
+    let mut sandbox = Sandbox::new_default(acc.clone());
+    let tokens = (0..4).map(|_| new_token_id()).collect_vec();
+
+    sandbox.call_mut(|dex| dex.register_account()).unwrap();
+    sandbox
+        .call_mut(|dex| dex.register_tokens(&acc, &tokens))
+        .unwrap();
+    for token in &tokens {
+        sandbox
+            .call_mut(|dex| dex.deposit(&acc, token, new_amount(100_000_000)))
+            .unwrap();
+    }
+    // Pools in the order of increasing liquidity
+    let pairs = [(0, 1), (1, 2), (2, 3)];
+    for (i, &(a, b)) in pairs.iter().enumerate() {
+        let amount = new_amount(1_000_000 * (i as u128 + 1));
+        sandbox
+            .call_mut(|dex| {
+                dex.open_position(
+                    &tokens[a],
+                    &tokens[b],
+                    1,
+                    PositionInit::new_full_range(new_amount(0), amount, new_amount(0), amount),
+                )
+            })
+            .unwrap();
+    }
+    let pool_id = |(a, b): (usize, usize)| {
+        PoolId::try_from_pair((tokens[a].clone(), tokens[b].clone()))
+            .unwrap()
+            .0
+    };
+
+    let top = sandbox
+        .call(|dex| dex.get_top_pools_by_liquidity(2))
+        .unwrap();
+    assert_eq!(
+        top.iter().map(|(pool_id, _)| pool_id.clone()).collect_vec(),
+        vec![pool_id(pairs[2]), pool_id(pairs[1])]
+    );
+    assert!(top[0].1 > top[1].1);
+
+    let all = sandbox
+        .call(|dex| dex.get_top_pools_by_liquidity(10))
+        .unwrap();
+    assert_eq!(all.len(), pairs.len());
+    assert_eq!(all[2].0, pool_id(pairs[0]));
+}
+
+#[test]
+fn swap_exact_in_wasm_amounts() {
+    use crate::chain::wasm::WasmAmount;
+
+    let SwapTestContext {
+        mut sandbox,
+        token_ids: (token_0, token_1),
+        ..
+    } = SwapTestContext::new();
+
+    let expected = sandbox.call_mut(|dex| {
+        dex.swap_exact_in(
+            &[token_0.clone(), token_1.clone()],
+            new_amount(100),
+            new_amount(0),
+        )
+    });
+    let (expected_in, expected_out) = expected.unwrap();
+
+    let SwapTestContext {
+        mut sandbox,
+        token_ids: (token_0, token_1),
+        ..
+    } = SwapTestContext::new();
+
+    assert_eq!(
+        sandbox
+            .call_mut(|dex| dex.swap_exact_in_wasm(
+                &[token_0.clone(), token_1.clone()],
+                &WasmAmount::from(100u64),
+                &WasmAmount::zero(),
+            ))
+            .unwrap(),
+        (expected_in, expected_out)
+    );
+
+    // One more than `Amount::MAX`
+    let overflowing = WasmAmount::from(Amount::MAX) + WasmAmount::from(1u64);
+    assert_matches!(
+        sandbox.call_mut(|dex| dex.swap_exact_in_wasm(
+            &[token_0.clone(), token_1.clone()],
+            &overflowing,
+            &WasmAmount::zero(),
+        )),
+        Err(Error {
+            kind: ErrorKind::ConvOverflow,
+            ..
+        })
+    );
+    assert_matches!(
+        sandbox.call_mut(|dex| dex.swap_exact_in_wasm(
+            &[token_0, token_1],
+            &WasmAmount::from(100u64),
+            &overflowing,
+        )),
+        Err(Error {
+            kind: ErrorKind::ConvOverflow,
+            ..
+        })
+    );
+}
+
+#[rstest]
+fn ticks_for_price_band_brackets_spot(#[values(1, 100, 500, 5000)] band_bp: BasisPoints) {
+    let SwapTestContext {
+        sandbox,
+        token_ids: (token_0, token_1),
+        ..
+    } = SwapTestContext::new();
+
+    let spot_price = sandbox.call(|dex| {
+        let sqrtprice = dex
+            .get_pool_info((token_0.clone(), token_1.clone()))
+            .unwrap()
+            .unwrap()
+            .spot_sqrtprices[0];
+        f64::from(sqrtprice * sqrtprice)
+    });
+    let (tick_low, tick_high) = sandbox
+        .call(|dex| dex.ticks_for_price_band((token_0.clone(), token_1.clone()), 0, band_bp))
+        .unwrap();
+
+    let tick_price = |tick: i32| f64::from(Tick::new(tick).unwrap().spot_sqrtprice()).powi(2);
+    let band = f64::from(band_bp) / 10_000.0;
+    // Snapping to the nearest tick changes price by at most half a tick
+    let tolerance = 0.5e-4;
+    assert!(tick_price(tick_low) < spot_price && spot_price < tick_price(tick_high));
+    assert!((tick_price(tick_low) / (spot_price * (1.0 - band)) - 1.0).abs() <= tolerance);
+    assert!((tick_price(tick_high) / (spot_price * (1.0 + band)) - 1.0).abs() <= tolerance);
+
+    // Ticks are oriented according to tokens order
+    assert_eq!(
+        sandbox
+            .call(|dex| dex.ticks_for_price_band((token_1.clone(), token_0.clone()), 0, band_bp))
+            .unwrap(),
+        (-tick_high, -tick_low)
+    );
+}
+
+#[test]
+fn ticks_for_price_band_failures() {
+    let SwapTestContext {
+        sandbox,
+        token_ids: (token_0, token_1),
+        ..
+    } = SwapTestContext::new();
+
+    assert_matches!(
+        sandbox.call(|dex| dex.ticks_for_price_band((token_0.clone(), new_token_id()), 0, 100)),
+        Err(Error {
+            kind: ErrorKind::PoolNotRegistered,
+            ..
+        })
+    );
+    for band_bp in [0, 10_000] {
+        assert_matches!(
+            sandbox.call(|dex| dex.ticks_for_price_band(
+                (token_0.clone(), token_1.clone()),
+                0,
+                band_bp
+            )),
+            Err(Error {
+                kind: ErrorKind::InvalidParams,
+                ..
+            })
+        );
+    }
+}
+
+#[test]
+fn overlapping_positions_detected() {
+    let SwapTestContext {
+        mut sandbox,
+        owner,
+        token_ids: (token_0, token_1),
+        ..
+    } = SwapTestContext::new_all_1g();
+
+    let fee_rate = 4;
+    let mut open_position = |tick_low: i32, tick_high: i32| {
+        sandbox
+            .call_mut(|dex| {
+                dex.open_position(
+                    &token_0,
+                    &token_1,
+                    fee_rate,
+                    PositionInit {
+                        amount_ranges: (
+                            Range {
+                                min: new_amount(0).into(),
+                                max: new_amount(1_000_000).into(),
+                            },
+                            Range {
+                                min: new_amount(0).into(),
+                                max: new_amount(1_000_000).into(),
+                            },
+                        ),
+                        ticks_range: (Some(tick_low), Some(tick_high)),
+                    },
+                )
+            })
+            .unwrap()
+            .0
+    };
+
+    let pos_a = open_position(-1000, 1000);
+    let pos_b = open_position(500, 2000);
+    // Touches `pos_b` only at the boundary
+    let _pos_c = open_position(2000, 3000);
+
+    let overlapping = sandbox
+        .call(|dex| {
+            dex.get_overlapping_positions(&owner, (token_0.clone(), token_1.clone()), fee_rate)
+        })
+        .unwrap();
+    assert_eq!(overlapping, vec![(pos_a, pos_b)]);
+
+    // Full-range position opened by context is on another fee level
+    let overlapping = sandbox
+        .call(|dex| dex.get_overlapping_positions(&owner, (token_1.clone(), token_0.clone()), 1))
+        .unwrap();
+    assert!(overlapping.is_empty());
+}
+
+#[test]
+fn out_of_range_positions_filtered() {
+    let SwapTestContext {
+        mut sandbox,
+        owner,
+        token_ids: (token_0, token_1),
+        ..
+    } = SwapTestContext::new_all_1g();
+
+    let mut open_position = |tick_low: i32, tick_high: i32| {
+        sandbox
+            .call_mut(|dex| {
+                dex.open_position(
+                    &token_0,
+                    &token_1,
+                    4,
+                    PositionInit {
+                        amount_ranges: (
+                            Range {
+                                min: new_amount(0).into(),
+                                max: new_amount(1_000_000).into(),
+                            },
+                            Range {
+                                min: new_amount(0).into(),
+                                max: new_amount(1_000_000).into(),
+                            },
+                        ),
+                        ticks_range: (Some(tick_low), Some(tick_high)),
+                    },
+                )
+            })
+            .unwrap()
+            .0
+    };
+
+    // Spot price is 1, i.e. at tick 0
+    let _in_range = open_position(-1000, 1000);
+    let above = open_position(500, 2000);
+    let below = open_position(-3000, -2000);
+
+    let get_out_of_range = |sandbox: &Sandbox, offset, limit| {
+        sandbox
+            .call(|dex| dex.get_out_of_range_positions(&owner, offset, limit))
+            .unwrap()
+    };
+
+    // Full-range position opened by context is in range as well
+    assert_eq!(get_out_of_range(&sandbox, 0, 10), vec![above, below]);
+    assert!(get_out_of_range(&sandbox, 0, 2).is_empty());
+    assert_eq!(get_out_of_range(&sandbox, 3, 10), vec![below]);
+    assert!(get_out_of_range(&sandbox, 4, 10).is_empty());
+    assert!(sandbox
+        .call(|dex| dex.get_out_of_range_positions(&new_account_id(), 0, 10))
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn withdraw_protocol_fees_from_several_pools() {
+    let acc = new_account_id();
+    let mut sandbox = Sandbox::new_default(acc.clone());
+    let tokens = (0..5).map(|_| new_token_id()).collect_vec();
+
+    sandbox.call_mut(|dex| dex.register_account()).unwrap();
+    sandbox
+        .call_mut(|dex| dex.register_tokens(&acc, &tokens))
+        .unwrap();
+    for token in &tokens {
+        sandbox
+            .call_mut(|dex| dex.deposit(&acc, token, new_amount(100_000_000)))
+            .unwrap();
+    }
+    let pairs = [(0, 1), (1, 2), (2, 3), (3, 4)];
+    for &(a, b) in &pairs {
+        let amount = new_amount(10_000_000);
+        sandbox
+            .call_mut(|dex| {
+                dex.open_position(
+                    &tokens[a],
+                    &tokens[b],
+                    1,
+                    PositionInit::new_full_range(new_amount(0), amount, new_amount(0), amount),
+                )
+            })
+            .unwrap();
+    }
+    // Accrue fees in all pools except the last one
+    for &(a, b) in &pairs[..3] {
+        sandbox
+            .call_mut(|dex| {
+                dex.swap_exact_in(
+                    &[tokens[a].clone(), tokens[b].clone()],
+                    new_amount(1_000_000),
+                    new_amount(0),
+                )
+            })
+            .unwrap();
+    }
+
+    let balances = |sandbox: &Sandbox| {
+        tokens
+            .iter()
+            .map(|token| sandbox.call(|dex| dex.get_deposit(&acc, token)).unwrap())
+            .collect_vec()
+    };
+    let balances_before = balances(&sandbox);
+
+    let pools = pairs
+        .iter()
+        .map(|&(a, b)| (tokens[a].clone(), tokens[b].clone()))
+        .collect_vec();
+    let withdrawn = sandbox
+        .call_mut(|dex| dex.withdraw_protocol_fees(&pools))
+        .unwrap();
+
+    assert_eq!(
+        withdrawn
+            .iter()
+            .map(|(pool, _, _)| pool.clone())
+            .collect_vec(),
+        pools[..3].to_vec()
+    );
+    let mut expected = balances_before;
+    for (pool, amount_a, amount_b) in &withdrawn {
+        assert!(!amount_a.is_zero());
+        let index = |token: &TokenId| tokens.iter().position(|t| t == token).unwrap();
+        expected[index(&pool.0)] += *amount_a;
+        expected[index(&pool.1)] += *amount_b;
+    }
+    assert_eq!(balances(&sandbox), expected);
+
+    // Nothing left to withdraw
+    assert_eq!(
+        sandbox
+            .call_mut(|dex| dex.withdraw_protocol_fees(&pools))
+            .unwrap(),
+        vec![]
+    );
+}
+
+#[test]
+fn withdraw_protocol_fees_skips_bad_pools() {
+    let mut ctx = SwapTestContext::new_all_1g();
+    let (token_0, token_1) = ctx.token_ids.clone();
+    ctx.sandbox
+        .call_mut(|dex| {
+            dex.swap_exact_in(
+                &[token_0.clone(), token_1.clone()],
+                new_amount(1_000_000),
+                new_amount(0),
+            )
+        })
+        .unwrap();
+
+    let pools = [
+        // Identical tokens
+        (token_0.clone(), token_0.clone()),
+        // No such pool
+        (token_0.clone(), new_token_id()),
+        (token_0.clone(), token_1.clone()),
+    ];
+    let withdrawn = ctx
+        .sandbox
+        .call_mut(|dex| dex.withdraw_protocol_fees(&pools))
+        .unwrap();
+    assert_eq!(withdrawn.len(), 1);
+    assert_eq!(withdrawn[0].0, pools[2]);
+
+    // Batch doesn't bypass timelock
+    ctx.sandbox
+        .call_mut(|dex| dex.set_protocol_fee_withdrawal_delay(3600))
+        .unwrap();
+    assert_matches!(
+        ctx.sandbox
+            .call_mut(|dex| dex.withdraw_protocol_fees(&pools)),
+        Err(Error {
+            kind: ErrorKind::WithdrawalTimelockActive,
+            ..
+        })
+    );
+}
+
+#[test]
+fn eff_sqrtprices_in_canonical_order() {
+    use crate::dex::pool::one_over_one_minus_fee_rate;
+
+    let SwapTestContext {
+        mut sandbox,
+        token_ids: (token_0, token_1),
+        ..
+    } = SwapTestContext::new();
+
+    sandbox
+        .call_mut(|dex| {
+            dex.swap_exact_in(
+                &[token_0.clone(), token_1.clone()],
+                new_amount(10_000),
+                new_amount(0),
+            )
+        })
+        .unwrap();
+
+    let eff_sqrtprices = sandbox
+        .call(|dex| dex.get_eff_sqrtprices((token_0.clone(), token_1.clone())))
+        .unwrap();
+    for (level, (left, right)) in eff_sqrtprices.into_iter().enumerate() {
+        assert_eq_rel_tol!(left * right, one_over_one_minus_fee_rate(level as u8), 30);
+    }
+
+    assert_eq!(
+        sandbox
+            .call(|dex| dex.get_eff_sqrtprices((token_1.clone(), token_0.clone())))
+            .unwrap(),
+        eff_sqrtprices
+    );
+}
+
+#[test]
+fn max_tokens_per_account_enforced() {
+    let acc = new_account_id();
+    let mut sandbox = Sandbox::new_default(acc.clone());
+    let tokens = (0..4).map(|_| new_token_id()).collect_vec();
+
+    sandbox.call_mut(|dex| dex.register_account()).unwrap();
+    sandbox
+        .call_mut(|dex| dex.set_max_tokens_per_account(Some(3)))
+        .unwrap();
+
+    // Up to the cap, duplicates and already registered tokens don't count twice
+    sandbox
+        .call_mut(|dex| dex.register_tokens(&acc, [&tokens[0], &tokens[1], &tokens[0]]))
+        .unwrap();
+    sandbox
+        .call_mut(|dex| dex.register_tokens(&acc, [&tokens[1], &tokens[2]]))
+        .unwrap();
+    // Beyond the cap
+    assert_matches!(
+        sandbox.call_mut(|dex| dex.register_tokens(&acc, [&tokens[3]])),
+        Err(Error {
+            kind: ErrorKind::TooManyTokens,
+            ..
+        })
+    );
+    // Unregistering frees a slot
+    sandbox
+        .call_mut(|dex| dex.unregister_tokens(&acc, [&tokens[0]]))
+        .unwrap();
+    sandbox
+        .call_mut(|dex| dex.register_tokens(&acc, [&tokens[3]]))
+        .unwrap();
+    // Removing the limit allows registering again
+    sandbox
+        .call_mut(|dex| dex.set_max_tokens_per_account(None))
+        .unwrap();
+    sandbox
+        .call_mut(|dex| dex.register_tokens(&acc, [&tokens[0]]))
+        .unwrap();
+}
+
+#[test]
+fn swap_to_price_limit_stops_at_price() {
+    let SwapTestContext {
+        mut sandbox,
+        token_ids: (token_0, token_1),
+        ..
+    } = SwapTestContext::new();
+    let (_, swapped) = PoolId::try_from_pair((token_0.clone(), token_1.clone())).unwrap();
+    // Effective sqrtprice on the side `token_0` enters the pool, at fee level 0
+    let in_side_eff_sqrtprice = |sandbox: &Sandbox| {
+        let (left, right) = sandbox
+            .call(|dex| dex.get_eff_sqrtprices((token_0.clone(), token_1.clone())))
+            .unwrap()[0];
+        if swapped {
+            right
+        } else {
+            left
+        }
+    };
+
+    // Current marginal rate, in `token_1` per `token_0`, and limit 1% worse than it
+    let rate = in_side_eff_sqrtprice(&sandbox).powi(2).recip();
+    let price_limit = rate * Float::from(0.99);
+
+    let max_amount_in = new_amount(500_000);
+    let (amount_in, amount_out) = sandbox
+        .call_mut(|dex| dex.swap_to_price_limit(&token_0, &token_1, max_amount_in, price_limit))
+        .unwrap();
+
+    // Swap was stopped by the limit, not by amount
+    assert!(amount_in > new_amount(0));
+    assert!(amount_in < max_amount_in);
+    // No part of the swap was executed below the limit
+    assert!(Float::from(amount_out) / Float::from(amount_in) >= price_limit);
+    // Pool price has reached the limit
+    assert_eq_rel_tol!(
+        in_side_eff_sqrtprice(&sandbox).powi(2).recip(),
+        price_limit,
+        30
+    );
+
+    // Price is already at the limit, nothing else is traded
+    assert_eq!(
+        sandbox
+            .call_mut(|dex| dex.swap_to_price_limit(&token_0, &token_1, max_amount_in, price_limit))
+            .unwrap(),
+        (new_amount(0), new_amount(0))
+    );
+}
+
+#[rstest]
+fn swap_to_price_wrong_side_limit(#[values(false, true)] checked: bool) {
+    let SwapTestContext {
+        mut sandbox,
+        token_ids: (token_0, token_1),
+        ..
+    } = SwapTestContext::new();
+    let (_, swapped) = PoolId::try_from_pair((token_0.clone(), token_1.clone())).unwrap();
+    let (left, right) = sandbox
+        .call(|dex| dex.get_eff_sqrtprices((token_0.clone(), token_1.clone())))
+        .unwrap()[0];
+    let in_side_eff_sqrtprice = if swapped { right } else { left };
+
+    // Effective price only grows during swap, so a limit below current one can't be reached
+    let effective_price_limit = in_side_eff_sqrtprice.powi(2) * Float::from(0.99);
+    let tokens = [token_0, token_1];
+    let result = sandbox.call_mut(|dex| {
+        if checked {
+            dex.swap_to_price_checked(&tokens, new_amount(1_000), effective_price_limit)
+        } else {
+            dex.swap_to_price(&tokens, new_amount(1_000), effective_price_limit)
+        }
+    });
+
+    if checked {
+        assert_matches!(
+            result,
+            Err(Error {
+                kind: ErrorKind::PriceLimitNotCrossable,
+                ..
+            })
+        );
+    } else {
+        assert_eq!(result.unwrap(), (new_amount(0), new_amount(0)));
+    }
+}
+
+#[rstest]
+fn swap_to_price_limit_rejects_non_positive_price(#[values(0.0, -1.0)] price_limit: f64) {
+    let SwapTestContext {
+        mut sandbox,
+        token_ids: (token_0, token_1),
+        ..
+    } = SwapTestContext::new();
+
+    assert_matches!(
+        sandbox.call_mut(|dex| dex.swap_to_price_limit(
+            &token_0,
+            &token_1,
+            new_amount(1_000),
+            Float::from(price_limit)
+        )),
+        Err(Error {
+            kind: ErrorKind::InvalidParams,
+            ..
+        })
+    );
+}
+
+#[test]
+fn effective_protocol_fee_includes_surcharge() {
+    let SwapTestContext {
+        mut sandbox,
+        token_ids: (token_0, token_1),
+        ..
+    } = SwapTestContext::new();
+    let get_fee = |sandbox: &Sandbox| {
+        sandbox.call(|dex| dex.get_effective_protocol_fee((token_1.clone(), token_0.clone())))
+    };
+
+    sandbox
+        .call_mut(|dex| dex.set_protocol_fee_fraction(1000))
+        .unwrap();
+    assert_eq!(get_fee(&sandbox).unwrap(), 1000);
+
+    sandbox.call_mut(|dex| dex.set_swap_surcharge(500)).unwrap();
+    assert_eq!(get_fee(&sandbox).unwrap(), 1500);
+
+    sandbox
+        .call_mut(|dex| dex.set_swap_surcharge(MAX_PROTOCOL_FEE_FRACTION))
+        .unwrap();
+    assert_eq!(get_fee(&sandbox).unwrap(), MAX_PROTOCOL_FEE_FRACTION);
+
+    assert_matches!(
+        sandbox.call(|dex| dex.get_effective_protocol_fee((token_0.clone(), new_token_id()))),
+        Err(Error {
+            kind: ErrorKind::PoolNotRegistered,
+            ..
+        })
+    );
+}
+
+#[test]
+fn close_position_and_withdraw_sends_both_tokens() {
+    let SwapTestContext {
+        mut sandbox,
+        owner,
+        token_ids: (token_0, token_1),
+        position_id,
+    } = SwapTestContext::new_all_1g();
+
+    sandbox
+        .call_mut(|dex| {
+            dex.swap_exact_in(
+                &[token_0.clone(), token_1.clone()],
+                new_amount(1_000_000),
+                new_amount(0),
+            )
+        })
+        .unwrap();
+
+    let balances_before = [&token_0, &token_1].map(|token| {
+        (
+            token.clone(),
+            sandbox.call(|dex| dex.get_deposit(&owner, token)).unwrap(),
+        )
+    });
+
+    let sends = sandbox
+        .call_mut(|dex| dex.close_position_and_withdraw(position_id, false, ()))
+        .unwrap();
+
+    assert_eq!(sends.len(), 2);
+    for (token, amount, ()) in &sends {
+        let (_, balance_before) = balances_before
+            .iter()
+            .find(|(balance_token, _)| balance_token == token)
+            .unwrap();
+        // Position payout was added to the balance, and everything was withdrawn
+        assert!(amount > balance_before);
+        assert_eq!(
+            sandbox.call(|dex| dex.get_deposit(&owner, token)).unwrap(),
+            new_amount(0)
+        );
+        assert!(sandbox.latest_logs().iter().any(|event| *event
+            == Event::Withdraw {
+                user: owner.clone(),
+                token: token.clone(),
+                amount: *amount,
+                balance: new_amount(0),
+            }));
+    }
+    assert_eq!(
+        sends
+            .iter()
+            .map(|(token, _, _)| token.clone())
+            .sorted()
+            .collect_vec(),
+        [token_0, token_1].into_iter().sorted().collect_vec()
+    );
+
+    assert_matches!(
+        sandbox.call_mut(|dex| dex.close_position_and_withdraw(position_id, false, ())),
+        Err(Error {
+            kind: ErrorKind::PositionDoesNotExist,
+            ..
+        })
+    );
+}
+
+#[test]
+fn position_breakeven_price_moves_away_as_fees_accrue() {
+    let SwapTestContext {
+        mut sandbox,
+        token_ids: (token_0, token_1),
+        position_id,
+        ..
+    } = SwapTestContext::new_all_1g();
+    let swap = |sandbox: &mut Sandbox, tokens: [&TokenId; 2], amount: Amount| {
+        sandbox
+            .call_mut(|dex| dex.swap_exact_in(&tokens.map(Clone::clone), amount, new_amount(0)))
+            .unwrap()
+    };
+    let breakeven_price = |sandbox: &Sandbox| {
+        sandbox
+            .call(|dex| dex.get_position_breakeven_price(position_id))
+            .unwrap()
+    };
+
+    // Price hasn't moved yet
+    assert_eq!(breakeven_price(&sandbox), None);
+
+    let init_sqrtprice = sandbox
+        .call(|dex| dex.get_position_info(position_id))
+        .unwrap()
+        .init_sqrtprice;
+    let init_price = f64::from(init_sqrtprice * init_sqrtprice).recip();
+
+    swap(&mut sandbox, [&token_0, &token_1], new_amount(10_000_000));
+    let first = f64::from(breakeven_price(&sandbox).unwrap());
+
+    // Trade back and forth, so price ends up where it was, while position earns fees
+    for _ in 0..10 {
+        let (_, amount_out) = swap(&mut sandbox, [&token_1, &token_0], new_amount(10_000_000));
+        swap(&mut sandbox, [&token_0, &token_1], amount_out);
+    }
+    let second = f64::from(breakeven_price(&sandbox).unwrap());
+
+    // Break-even is on the same side of initial price, but further away from it
+    assert_eq!(first > init_price, second > init_price);
+    assert!((second / init_price).ln().abs() > (first / init_price).ln().abs());
+}
+
+#[test]
+fn event_verbosity_filters_events() {
+    let SwapTestContext {
+        mut sandbox,
+        token_ids: (token_0, token_1),
+        ..
+    } = SwapTestContext::new();
+    let swap_and_check_events = |sandbox: &mut Sandbox, event_verbosity: u8| {
+        sandbox
+            .call_mut(|dex| dex.set_event_verbosity(event_verbosity))
+            .unwrap();
+        sandbox
+            .call_mut(|dex| {
+                dex.swap_exact_in(
+                    &[token_0.clone(), token_1.clone()],
+                    new_amount(100),
+                    new_amount(0),
+                )
+            })
+            .unwrap();
+        let logs = sandbox.latest_logs();
+        (
+            logs.iter().any(|e| matches!(e, Event::Swap { .. })),
+            logs.iter()
+                .any(|e| matches!(e, Event::UpdatePoolState { .. })),
+        )
+    };
+
+    // Default is all events enabled
+    assert_eq!(
+        sandbox.call(|dex| dex.contract().as_ref().event_verbosity),
+        EVENTS_ALL
+    );
+    assert_eq!(
+        swap_and_check_events(&mut sandbox, EVENTS_ALL),
+        (true, true)
+    );
+    assert_eq!(
+        swap_and_check_events(&mut sandbox, EVENTS_SWAP),
+        (true, false)
+    );
+    assert_eq!(
+        swap_and_check_events(&mut sandbox, EVENTS_POOL_STATE),
+        (false, true)
+    );
+    assert_eq!(swap_and_check_events(&mut sandbox, 0), (false, false));
+}
+
+#[test]
+#[cfg(feature = "smart-routing")]
+fn token_connections_paged() {
+    let mut ctx = SwapTestContext::new();
+    let (token_a, token_b) = ctx.token_ids.clone();
+    let token_c = new_token_id();
+    let token_d = new_token_id();
+    let token_e = new_token_id();
+    ctx.open_position_1g((&token_a, &token_c));
+    ctx.open_position_1g((&token_d, &token_e));
+
+    let connections = ctx
+        .sandbox
+        .call(|dex| dex.get_token_connections(&token_a, 0, 10))
+        .unwrap();
+    assert_eq!(
+        connections.iter().cloned().sorted().collect_vec(),
+        [token_b, token_c].into_iter().sorted().collect_vec()
+    );
+    assert!(!connections.contains(&token_d));
+
+    // Paging splits the same sequence
+    let first = ctx
+        .sandbox
+        .call(|dex| dex.get_token_connections(&token_a, 0, 1))
+        .unwrap();
+    let second = ctx
+        .sandbox
+        .call(|dex| dex.get_token_connections(&token_a, 1, 1))
+        .unwrap();
+    assert_eq!([first, second].concat(), connections);
+
+    assert!(ctx
+        .sandbox
+        .call(|dex| dex.get_token_connections(&new_token_id(), 0, 10))
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn would_create_pool_before_and_after_position() {
+    let mut ctx = SwapTestContext::new();
+    let token_a = ctx.token_ids.0.clone();
+    let token_c = new_token_id();
+
+    assert!(ctx
+        .sandbox
+        .call(|dex| dex.would_create_pool((token_a.clone(), token_c.clone()))));
+
+    ctx.open_position_1g((&token_a, &token_c));
+
+    assert!(!ctx
+        .sandbox
+        .call(|dex| dex.would_create_pool((token_c.clone(), token_a.clone()))));
+    assert!(!ctx
+        .sandbox
+        .call(|dex| dex.would_create_pool((token_a.clone(), token_a.clone()))));
+}
+
+#[rstest]
+fn auto_unregister_on_withdraw(#[values(true, false)] enabled: bool) {
+    let acc = new_account_id();
+    let token_id = new_token_id();
+    let mut sandbox = Sandbox::new_default(acc.clone());
+
+    sandbox.call_mut(|dex| dex.register_account()).unwrap();
+    sandbox
+        .call_mut(|dex| dex.register_tokens(&acc, [&token_id]))
+        .unwrap();
+    sandbox
+        .call_mut(|dex| dex.deposit(&acc, &token_id, new_amount(2_000)))
+        .unwrap();
+    sandbox
+        .call_mut(|dex| dex.set_auto_unregister(enabled))
+        .unwrap();
+
+    // Partial withdrawal never unregisters
+    sandbox
+        .call_mut(|dex| dex.withdraw(&acc, &token_id, new_amount(500), false, ()))
+        .unwrap();
+    assert_eq!(
+        sandbox
+            .call(|dex| dex.get_deposit(&acc, &token_id))
+            .unwrap(),
+        new_amount(1_500)
+    );
+
+    sandbox
+        .call_mut(|dex| dex.withdraw(&acc, &token_id, new_amount(1_500), false, ()))
+        .unwrap();
+    if enabled {
+        assert_matches!(
+            sandbox.call(|dex| dex.get_deposit(&acc, &token_id)),
+            Err(Error {
+                kind: ErrorKind::TokenNotRegistered,
+                ..
+            })
+        );
+    } else {
+        assert_eq!(
+            sandbox
+                .call(|dex| dex.get_deposit(&acc, &token_id))
+                .unwrap(),
+            new_amount(0)
+        );
+    }
+}
+
+#[rstest]
+fn auto_unregister_on_swap(#[values(true, false)] enabled: bool) {
+    let SwapTestContext {
+        mut sandbox,
+        owner,
+        token_ids: (token_0, token_1),
+        ..
+    } = SwapTestContext::new();
+
+    sandbox
+        .call_mut(|dex| dex.set_auto_unregister(enabled))
+        .unwrap();
+    let balance = sandbox
+        .call(|dex| dex.get_deposit(&owner, &token_0))
+        .unwrap();
+    sandbox
+        .call_mut(|dex| {
+            dex.swap_exact_in(&[token_0.clone(), token_1.clone()], balance, new_amount(0))
+        })
+        .unwrap();
+
+    assert_eq!(
+        sandbox
+            .call(|dex| dex.get_deposit(&owner, &token_0))
+            .is_ok(),
+        !enabled
+    );
+    // Output token is kept regardless
+    assert!(sandbox
+        .call(|dex| dex.get_deposit(&owner, &token_1))
+        .is_ok());
+}
+
+#[test]
+fn paused_pool_blocks_swaps_but_not_closes() {
+    let mut ctx = SwapTestContext::new();
+    let (token_0, token_1) = ctx.token_ids.clone();
+    let token_2 = new_token_id();
+    ctx.open_position_1g((&token_0, &token_2));
+    let SwapTestContext {
+        mut sandbox,
+        position_id,
+        ..
+    } = ctx;
+    let paused = (token_0.clone(), token_1.clone());
+    let other = (token_0.clone(), token_2.clone());
+
+    sandbox
+        .call_mut(|dex| dex.pause_pool(paused.clone()))
+        .unwrap();
+    assert_matches!(sandbox.latest_logs(), [Event::PausePool { .. }]);
+    assert!(sandbox
+        .call(|dex| dex.is_pool_paused(paused.clone()))
+        .unwrap());
+    assert!(!sandbox
+        .call(|dex| dex.is_pool_paused(other.clone()))
+        .unwrap());
+
+    assert_matches!(
+        sandbox.call_mut(|dex| dex.swap_exact_in(
+            &[token_1.clone(), token_0.clone()],
+            new_amount(100),
+            new_amount(0)
+        )),
+        Err(Error {
+            kind: ErrorKind::PoolPaused,
+            ..
+        })
+    );
+    // Other pools remain tradable
+    sandbox
+        .call_mut(|dex| {
+            dex.swap_exact_in(
+                &[token_0.clone(), token_2.clone()],
+                new_amount(100),
+                new_amount(0),
+            )
+        })
+        .unwrap();
+
+    let stranger = new_account_id();
+    let (_, owner) = sandbox.set_initiator_caller_ids(stranger);
+    assert_matches!(
+        sandbox.call_mut(|dex| dex.unpause_pool(paused.clone())),
+        Err(Error {
+            kind: ErrorKind::PermissionDenied,
+            ..
+        })
+    );
+    sandbox.set_initiator_caller_ids(owner);
+
+    // LPs can still leave paused pool
+    sandbox
+        .call_mut(|dex| dex.close_position(position_id))
+        .unwrap();
+
+    sandbox
+        .call_mut(|dex| dex.unpause_pool(paused.clone()))
+        .unwrap();
+    assert_matches!(sandbox.latest_logs(), [Event::UnpausePool { .. }]);
+    assert!(!sandbox
+        .call(|dex| dex.is_pool_paused(paused.clone()))
+        .unwrap());
+}
+
+#[test]
+fn pool_and_position_counts() {
+    let mut ctx = SwapTestContext::new();
+    let (token_0, token_1) = ctx.token_ids.clone();
+    let counts = |ctx: &SwapTestContext| {
+        ctx.sandbox
+            .call(|dex| (dex.get_pool_count(), dex.get_total_position_count()))
+    };
+    assert_eq!(counts(&ctx), (1, 1));
+
+    let token_2 = new_token_id();
+    let position_a = ctx.open_position_1g((&token_0, &token_2));
+    assert_eq!(counts(&ctx), (2, 2));
+
+    // Position in existing pool doesn't create new one
+    let position_b = ctx.open_position_1g((&token_1, &token_0));
+    assert_eq!(counts(&ctx), (2, 3));
+
+    ctx.sandbox
+        .call_mut(|dex| dex.close_position(position_a))
+        .unwrap();
+    assert_eq!(counts(&ctx), (2, 2));
+
+    ctx.sandbox
+        .call_mut(|dex| dex.close_position(position_b))
+        .unwrap();
+    let position_id = ctx.position_id;
+    ctx.sandbox
+        .call_mut(|dex| dex.close_position(position_id))
+        .unwrap();
+    // Pools are kept after all their positions are closed
+    assert_eq!(counts(&ctx), (2, 0));
+}
+
+#[test]
+#[cfg(feature = "smart-routing")]
+fn swap_auto_route_finds_multi_hop_path() {
+    let mut ctx = SwapTestContext::new_all_1g();
+    let (token_a, token_b) = ctx.token_ids.clone();
+    let token_c = new_token_id();
+    let token_d = new_token_id();
+    let token_e = new_token_id();
+    ctx.open_position_1g((&token_b, &token_c));
+    ctx.open_position_1g((&token_c, &token_d));
+    let amount_in = new_amount(1_000);
+
+    // Route longer than allowed isn't considered
+    assert_matches!(
+        ctx.sandbox.call_mut(|dex| dex.swap_auto_route(
+            &token_a,
+            &token_d,
+            amount_in,
+            new_amount(1),
+            2
+        )),
+        Err(Error {
+            kind: ErrorKind::NoRouteFound,
+            ..
+        })
+    );
+    // Token without pools can't be reached
+    assert_matches!(
+        ctx.sandbox.call_mut(|dex| dex.swap_auto_route(
+            &token_a,
+            &token_e,
+            amount_in,
+            new_amount(1),
+            MAX_ROUTE_HOPS
+        )),
+        Err(Error {
+            kind: ErrorKind::NoRouteFound,
+            ..
+        })
+    );
+
+    let (path, swapped_in, amount_out) = ctx
+        .sandbox
+        .call_mut(|dex| {
+            dex.swap_auto_route(&token_a, &token_d, amount_in, new_amount(1), MAX_ROUTE_HOPS)
+        })
+        .unwrap();
+    assert_eq!(path, [token_a, token_b, token_c, token_d.clone()]);
+    assert_eq!(swapped_in, amount_in);
+    assert!(amount_out > new_amount(0));
+    let owner = ctx.owner.clone();
+    assert_eq!(
+        ctx.sandbox
+            .call(|dex| dex.get_deposit(&owner, &token_d))
+            .unwrap(),
+        amount_out
+    );
+}
+
+#[test]
+#[cfg(feature = "smart-routing")]
+fn find_best_route_reports_quote_error() {
+    let mut ctx = SwapTestContext::new();
+    let (token_0, token_1) = ctx.token_ids.clone();
+    let position_id = ctx.position_id;
+    ctx.sandbox
+        .call_mut(|dex| dex.close_position(position_id))
+        .unwrap();
+
+    // Path exists, but can't be quoted
+    assert_matches!(
+        ctx.sandbox
+            .call(|dex| dex.find_best_route(&token_0, &token_1, new_amount(1_000), 1)),
+        Err(Error {
+            kind: ErrorKind::PoolHasNoLiquidity,
+            ..
+        })
+    );
+}
+
+#[rstest]
+fn open_position_fee_is_collected_apart_from_protocol_fee(
+    #[values(0, 1_000)] fee: u128,
+    #[values(false, true)] fee_in_pool_token: bool,
+) {
+    let mut ctx = SwapTestContext::new_all_1g();
+    let (token_0, token_1) = ctx.token_ids.clone();
+    let owner = ctx.owner.clone();
+    let fee = new_amount(fee);
+    let fee_token = if fee_in_pool_token {
+        token_1.clone()
+    } else {
+        let fee_token = new_token_id();
+        ctx.sandbox
+            .call_mut(|dex| dex.register_tokens(&owner, [&fee_token]))
+            .unwrap();
+        ctx.sandbox
+            .call_mut(|dex| dex.deposit(&owner, &fee_token, new_amount(1_000_000)))
+            .unwrap();
+        fee_token
+    };
+
+    ctx.sandbox
+        .call_mut(|dex| dex.set_open_position_fee(fee_token.clone(), fee))
+        .unwrap();
+    assert_eq!(
+        ctx.sandbox.call(|dex| dex.get_open_position_fee()),
+        (!fee.is_zero()).then(|| (fee_token.clone(), fee))
+    );
+
+    let get_deposits = |ctx: &SwapTestContext| {
+        ctx.sandbox.call(|dex| {
+            (
+                dex.get_deposit(&owner, &token_0).unwrap(),
+                dex.get_deposit(&owner, &token_1).unwrap(),
+                dex.get_deposit(&owner, &fee_token).unwrap(),
+            )
+        })
+    };
+    let deposits_before = get_deposits(&ctx);
+    let (position_id, amount_0, amount_1, _) = ctx
+        .sandbox
+        .call_mut(|dex| {
+            dex.open_position_full(
+                &token_0,
+                &token_1,
+                1,
+                new_amount(1_000_000),
+                new_amount(1_000_000),
+            )
+        })
+        .unwrap();
+    let deposits_after = get_deposits(&ctx);
+
+    if fee_in_pool_token {
+        assert_eq!(deposits_after.1, deposits_before.1 - amount_1 - fee);
+    } else {
+        assert_eq!(deposits_after.1, deposits_before.1 - amount_1);
+        assert_eq!(deposits_after.2, deposits_before.2 - fee);
+    }
+    assert_eq!(deposits_after.0, deposits_before.0 - amount_0);
+    if fee.is_zero() {
+        assert!(!ctx
+            .sandbox
+            .latest_logs()
+            .iter()
+            .any(|event| matches!(event, Event::OpenPositionFee { .. })));
+    } else {
+        assert_any_matches!(
+            ctx.sandbox.latest_logs(),
+            Event::OpenPositionFee {
+                user,
+                position_id: id,
+                token,
+                amount,
+            } if user == &owner && id == &position_id && token == &fee_token && amount == &fee
+        );
+    }
+
+    // Fee isn't mixed into protocol fee
+    assert_eq!(
+        ctx.sandbox
+            .call_mut(|dex| dex.withdraw_protocol_fee((token_0.clone(), token_1.clone())))
+            .unwrap(),
+        (new_amount(0), new_amount(0))
+    );
+
+    let fee_deposit_before = ctx
+        .sandbox
+        .call(|dex| dex.get_deposit(&owner, &fee_token))
+        .unwrap();
+    assert_eq!(
+        ctx.sandbox
+            .call_mut(|dex| dex.withdraw_open_position_fees(&fee_token))
+            .unwrap(),
+        fee
+    );
+    assert_eq!(
+        ctx.sandbox
+            .call(|dex| dex.get_deposit(&owner, &fee_token))
+            .unwrap(),
+        fee_deposit_before + fee
+    );
+    // Nothing left to withdraw
+    assert_eq!(
+        ctx.sandbox
+            .call_mut(|dex| dex.withdraw_open_position_fees(&fee_token))
+            .unwrap(),
+        new_amount(0)
+    );
+}
+
+#[test]
+fn open_position_fee_not_covered_by_deposit() {
+    let mut ctx = SwapTestContext::new();
+    let (token_0, token_1) = ctx.token_ids.clone();
+    let owner = ctx.owner.clone();
+    let fee_token_deposit = ctx
+        .sandbox
+        .call(|dex| dex.get_deposit(&owner, &token_0))
+        .unwrap();
+
+    ctx.sandbox
+        .call_mut(|dex| dex.set_open_position_fee(token_0.clone(), fee_token_deposit))
+        .unwrap();
+    assert_matches!(
+        ctx.sandbox.call_mut(|dex| dex.open_position_full(
+            &token_0,
+            &token_1,
+            1,
+            new_amount(1_000),
+            new_amount(1_000)
+        )),
+        Err(Error {
+            kind: ErrorKind::NotEnoughTokens,
+            ..
+        })
+    );
+
+    // Fee token must be deposited even if it's not one of pool tokens
+    ctx.sandbox
+        .call_mut(|dex| dex.set_open_position_fee(new_token_id(), new_amount(1)))
+        .unwrap();
+    assert!(ctx
+        .sandbox
+        .call_mut(|dex| dex.open_position_full(
+            &token_0,
+            &token_1,
+            1,
+            new_amount(1_000),
+            new_amount(1_000)
+        ))
+        .is_err());
+}
+
+#[test]
+fn pool_fee_growth_matches_position_reward() {
+    let mut ctx = SwapTestContext::new_all_1g();
+    let position_id = ctx.position_id;
+    // Use pool order of tokens, same as in position info
+    let PositionInfo {
+        tokens_ids: (token_0, token_1),
+        fee_level,
+        net_liquidity,
+        ..
+    } = ctx
+        .sandbox
+        .call(|dex| dex.get_position_info(position_id))
+        .unwrap();
+    let get_fee_growth = |ctx: &SwapTestContext| {
+        ctx.sandbox
+            .call(|dex| dex.get_pool_fee_growth((token_0.clone(), token_1.clone()), fee_level))
+            .unwrap()
+    };
+
+    let initial_growth = get_fee_growth(&ctx);
+    let mut prev_growth = initial_growth;
+    for swap_tokens in [
+        [token_0.clone(), token_1.clone()],
+        [token_1.clone(), token_0.clone()],
+        [token_0.clone(), token_1.clone()],
+    ] {
+        ctx.sandbox
+            .call_mut(|dex| dex.swap_exact_in(&swap_tokens, new_amount(1_000_000), new_amount(1)))
+            .unwrap();
+        let growth = get_fee_growth(&ctx);
+        // Fee is charged in input token
+        if swap_tokens[0] == token_0 {
+            assert!(growth.0 > prev_growth.0);
+            assert!(growth.1 >= prev_growth.1);
+        } else {
+            assert!(growth.0 >= prev_growth.0);
+            assert!(growth.1 > prev_growth.1);
+        }
+        prev_growth = growth;
+    }
+
+    // Position spans whole price range, so it earns global fee growth
+    let fee_liquidity = Float::from(fee_liquidity_from_net_liquidity(
+        Liquidity::try_from(net_liquidity).unwrap(),
+        fee_level,
+    ));
+    let reward = ctx
+        .sandbox
+        .call(|dex| dex.get_position_info(position_id))
+        .unwrap()
+        .reward_since_creation;
+    assert_eq_rel_tol!(
+        Float::from(reward.0),
+        fee_liquidity * Float::from((prev_growth.0 - initial_growth.0).value),
+        20
+    );
+    assert_eq_rel_tol!(
+        Float::from(reward.1),
+        fee_liquidity * Float::from((prev_growth.1 - initial_growth.1).value),
+        20
+    );
+}
+
+#[test]
+fn open_position_min_liquidity_reverts_after_price_move() {
+    let mut ctx = SwapTestContext::new_all_1g();
+    let (token_0, token_1) = ctx.token_ids.clone();
+    let owner = ctx.owner.clone();
+    let position = PositionInit::new_full_range(
+        new_amount(1),
+        new_amount(1_000_000),
+        new_amount(1),
+        new_amount(1_000_000),
+    );
+
+    let (_, _, _, expected_liquidity) = ctx
+        .sandbox
+        .call_mut(|dex| {
+            dex.open_position_with_min_liquidity(
+                &token_0,
+                &token_1,
+                1,
+                position.clone(),
+                Liquidity::zero(),
+            )
+        })
+        .unwrap();
+
+    // Price moves between estimation and execution
+    ctx.sandbox
+        .call_mut(|dex| {
+            dex.swap_exact_in(
+                &[token_0.clone(), token_1.clone()],
+                new_amount(100_000_000),
+                new_amount(1),
+            )
+        })
+        .unwrap();
+
+    let get_state = |ctx: &SwapTestContext| {
+        ctx.sandbox.call(|dex| {
+            (
+                dex.get_deposit(&owner, &token_0).unwrap(),
+                dex.get_deposit(&owner, &token_1).unwrap(),
+                dex.get_total_position_count(),
+            )
+        })
+    };
+    let state_before = get_state(&ctx);
+    assert_matches!(
+        ctx.sandbox
+            .call_mut(|dex| dex.open_position_with_min_liquidity(
+                &token_0,
+                &token_1,
+                1,
+                position.clone(),
+                expected_liquidity
+            )),
+        Err(Error {
+            kind: ErrorKind::Slippage,
+            ..
+        })
+    );
+    assert_eq!(get_state(&ctx), state_before);
+
+    // Realized liquidity is accepted when within bound
+    let (_, _, _, liquidity) = ctx
+        .sandbox
+        .call_mut(|dex| {
+            dex.open_position_with_min_liquidity(
+                &token_0,
+                &token_1,
+                1,
+                position.clone(),
+                Liquidity::zero(),
+            )
+        })
+        .unwrap();
+    assert!(liquidity < expected_liquidity);
+}
+
+#[test]
+fn export_import_account_round_trip() {
+    let mut ctx = SwapTestContext::new();
+    let (token_0, token_1) = ctx.token_ids.clone();
+    let account = ctx.owner.clone();
+    ctx.open_position_1g((&token_0, &token_1));
+    let exported = ctx
+        .sandbox
+        .call(|dex| dex.export_account(&account))
+        .unwrap();
+
+    let deposits = [&token_0, &token_1].map(|token| {
+        ctx.sandbox
+            .call(|dex| dex.get_deposit(&account, token))
+            .unwrap()
+    });
+
+    let new_owner = new_account_id();
+    let mut sandbox = Sandbox::new_default(new_owner.clone());
+    // Import is allowed only during migration
+    assert_matches!(
+        sandbox.call_mut(|dex| dex.import_account(&account, &exported)),
+        Err(Error {
+            kind: ErrorKind::PayableAPINotSuspended,
+            ..
+        })
+    );
+    sandbox.call_mut(|dex| dex.suspend_payable_api()).unwrap();
+    // Deposits must be transferred to contract first
+    assert_matches!(
+        sandbox.call_mut(|dex| dex.import_account(&account, &exported)),
+        Err(Error {
+            kind: ErrorKind::ImportedDepositNotFunded,
+            ..
+        })
+    );
+    for (token, deposit) in [&token_0, &token_1].into_iter().zip(deposits) {
+        sandbox.set_contract_balance(token.clone(), deposit);
+    }
+    sandbox
+        .call_mut(|dex| dex.import_account(&account, &exported))
+        .unwrap();
+
+    assert_eq!(
+        sandbox.call(|dex| dex.export_account(&account)).unwrap(),
+        exported
+    );
+    for (token, deposit) in [&token_0, &token_1].into_iter().zip(deposits) {
+        assert_eq!(
+            sandbox
+                .call(|dex| dex.get_deposit(&account, token))
+                .unwrap(),
+            deposit
+        );
+        assert_eq!(
+            sandbox
+                .call(|dex| dex.get_accounted_balance(token))
+                .unwrap(),
+            deposit
+        );
+    }
+
+    for (token, deposit) in [&token_0, &token_1].into_iter().zip(deposits) {
+        sandbox.set_contract_balance(token.clone(), deposit + deposit);
+    }
+    // Existing account isn't overwritten
+    assert_matches!(
+        sandbox.call_mut(|dex| dex.import_account(&account, &exported)),
+        Err(Error {
+            kind: ErrorKind::AccountAlreadyRegistered,
+            ..
+        })
+    );
+    // Already imported positions can't be attached to another account
+    assert_matches!(
+        sandbox.call_mut(|dex| dex.import_account(&new_account_id(), &exported)),
+        Err(Error {
+            kind: ErrorKind::PositionAlreadyExists,
+            ..
+        })
+    );
+    // Unknown export version is rejected
+    let mut unsupported = exported.clone();
+    unsupported[1] ^= 0xff;
+    assert_matches!(
+        sandbox.call_mut(|dex| dex.import_account(&new_account_id(), &unsupported)),
+        Err(Error {
+            kind: ErrorKind::InvalidAccountExport,
+            ..
+        })
+    );
+    // Only owner can import
+    sandbox.set_initiator_caller_ids(account.clone());
+    assert_matches!(
+        sandbox.call_mut(|dex| dex.import_account(&new_account_id(), &exported)),
+        Err(Error {
+            kind: ErrorKind::PermissionDenied,
+            ..
+        })
+    );
+}
+
+#[test]
+fn gc_dust_position_by_third_party() {
+    let mut ctx = SwapTestContext::new_all_1g();
+    let owner = ctx.owner.clone();
+    let (pool_id, _) = PoolId::try_from_pair(ctx.token_ids.clone()).unwrap();
+    let (token_0, token_1) = pool_id.as_refs();
+    let open = |ctx: &mut SwapTestContext, amount: u128| {
+        let (position_id, _, _, liquidity) = ctx
+            .sandbox
+            .call_mut(|dex| {
+                dex.open_position_full(token_0, token_1, 1, new_amount(amount), new_amount(amount))
+            })
+            .unwrap();
+        (position_id, liquidity)
+    };
+    let (dust_position, _) = open(&mut ctx, 1_000);
+    let (large_position, threshold) = open(&mut ctx, 1_000_000);
+    let gc_reward = new_amount(5);
+    ctx.sandbox
+        .call_mut(|dex| dex.set_dust_gc_config(threshold, gc_reward))
+        .unwrap();
+    ctx.sandbox
+        .call_mut(|dex| dex.fund_gc_rewards(token_0, new_amount(7)))
+        .unwrap();
+    // Accumulate protocol fee in left token, which must not be used to pay reward
+    ctx.sandbox
+        .call_mut(|dex| {
+            dex.swap_exact_in(
+                &[token_0.clone(), token_1.clone()],
+                new_amount(10_000_000),
+                new_amount(1),
+            )
+        })
+        .unwrap();
+    let get_protocol_fee = |ctx: &SwapTestContext| {
+        ctx.sandbox
+            .call(|dex| dex.get_protocol_fee((token_0.clone(), token_1.clone())))
+            .unwrap()
+            .0
+    };
+    let protocol_fee_before = get_protocol_fee(&ctx);
+    assert!(!protocol_fee_before.0.is_zero());
+
+    let collector = new_account_id();
+    ctx.sandbox.set_initiator_caller_ids(collector.clone());
+    assert_matches!(
+        ctx.sandbox
+            .call_mut(|dex| dex.gc_dust_position(dust_position)),
+        Err(Error {
+            kind: ErrorKind::AccountNotRegistered,
+            ..
+        })
+    );
+    ctx.sandbox
+        .call_mut(|dex| {
+            dex.register_account()?;
+            dex.register_tokens(&collector, [token_0])
+        })
+        .unwrap();
+    assert_matches!(
+        ctx.sandbox
+            .call_mut(|dex| dex.gc_dust_position(large_position)),
+        Err(Error {
+            kind: ErrorKind::PositionNotDust,
+            ..
+        })
+    );
+
+    let get_owner_deposits = |ctx: &SwapTestContext| {
+        ctx.sandbox.call(|dex| {
+            (
+                dex.get_deposit(&owner, token_0).unwrap(),
+                dex.get_deposit(&owner, token_1).unwrap(),
+            )
+        })
+    };
+    let deposits_before = get_owner_deposits(&ctx);
+    let returned = ctx
+        .sandbox
+        .call_mut(|dex| dex.gc_dust_position(dust_position))
+        .unwrap();
+    assert_eq!(
+        get_owner_deposits(&ctx),
+        (
+            deposits_before.0 + returned.0,
+            deposits_before.1 + returned.1
+        )
+    );
+    assert!(!returned.0.is_zero() || !returned.1.is_zero());
+    assert_eq!(
+        ctx.sandbox
+            .call(|dex| dex.get_deposit(&collector, token_0))
+            .unwrap(),
+        gc_reward
+    );
+    assert_matches!(
+        ctx.sandbox.call(|dex| dex.get_position_info(dust_position)),
+        Err(_)
+    );
+    // Reward didn't come out of protocol fee
+    assert!(get_protocol_fee(&ctx).0 >= protocol_fee_before.0);
+
+    // Rest of funds is returned to owner
+    ctx.sandbox.set_initiator_caller_ids(owner.clone());
+    assert_eq!(
+        ctx.sandbox
+            .call_mut(|dex| dex.withdraw_gc_reward_funds(token_0))
+            .unwrap(),
+        new_amount(2)
+    );
+}
+
+#[test]
+fn gc_dust_position_requires_indexed_owner() {
+    let SwapTestContext {
+        mut sandbox,
+        owner,
+        position_id,
+        ..
+    } = SwapTestContext::new();
+    sandbox
+        .call_mut(|dex| dex.set_dust_gc_config(Liquidity::from(u128::MAX), Amount::zero()))
+        .unwrap();
+    // Emulate position opened before owners were indexed
+    sandbox
+        .call_mut(|dex| {
+            dex.contract_mut().latest().position_owners = None;
+            Ok(())
+        })
+        .unwrap();
+    assert_matches!(
+        sandbox.call_mut(|dex| dex.gc_dust_position(position_id)),
+        Err(Error {
+            kind: ErrorKind::PositionOwnerNotIndexed,
+            ..
+        })
+    );
+
+    sandbox.set_initiator_caller_ids(new_account_id());
+    assert_matches!(
+        sandbox.call_mut(|dex| dex.index_position_owners([owner.clone()])),
+        Err(Error {
+            kind: ErrorKind::PermissionDenied,
+            ..
+        })
+    );
+    sandbox.set_initiator_caller_ids(owner.clone());
+    sandbox
+        .call_mut(|dex| dex.index_position_owners([owner.clone()]))
+        .unwrap();
+    assert_matches!(
+        sandbox.call_mut(|dex| dex.gc_dust_position(position_id)),
+        Ok(_)
+    );
+    // Closed position is dropped from index
+    sandbox.call(|dex| {
+        assert!(dex
+            .contract()
+            .as_ref()
+            .position_owners
+            .unwrap()
+            .inspect(&position_id, |_| ())
+            .is_none());
+    });
+}
+
+#[test]
+fn positions_ranked_by_fees() {
+    let mut ctx = SwapTestContext::new_all_1g();
+    let (token_0, token_1) = ctx.token_ids.clone();
+    let owner = ctx.owner.clone();
+    let large_position = ctx.position_id;
+    let mut open = |amount: u128| {
+        ctx.sandbox
+            .call_mut(|dex| {
+                dex.open_position_full(
+                    &token_0,
+                    &token_1,
+                    1,
+                    new_amount(amount),
+                    new_amount(amount),
+                )
+            })
+            .unwrap()
+            .0
+    };
+    let small_position = open(1_000_000);
+    let medium_position = open(10_000_000);
+    // Position in other pool is ignored
+    let token_2 = new_token_id();
+    ctx.open_position_1g((&token_0, &token_2));
+
+    for swap_tokens in [
+        [token_0.clone(), token_1.clone()],
+        [token_1.clone(), token_0.clone()],
+    ] {
+        ctx.sandbox
+            .call_mut(|dex| dex.swap_exact_in(&swap_tokens, new_amount(100_000_000), new_amount(1)))
+            .unwrap();
+    }
+
+    let ranked = ctx
+        .sandbox
+        .call(|dex| dex.get_positions_by_fees(&owner, (token_0.clone(), token_1.clone()), 10))
+        .unwrap();
+    assert_eq!(
+        ranked.iter().map(|(id, _, _)| *id).collect_vec(),
+        [large_position, medium_position, small_position]
+    );
+    for ((_, fee_0, fee_1), (_, next_fee_0, next_fee_1)) in ranked.iter().tuple_windows() {
+        assert!(fee_0 >= next_fee_0 && fee_1 >= next_fee_1);
+    }
+    // Fees are reported in order of requested tokens
+    let reversed = ctx
+        .sandbox
+        .call(|dex| dex.get_positions_by_fees(&owner, (token_1.clone(), token_0.clone()), 2))
+        .unwrap();
+    assert_eq!(
+        reversed,
+        ranked
+            .iter()
+            .take(2)
+            .map(|(id, fee_0, fee_1)| (*id, *fee_1, *fee_0))
+            .collect_vec()
+    );
+}
+
+#[test]
+fn spot_prices_batch() {
+    let mut ctx = SwapTestContext::new();
+    let (token_0, token_1) = ctx.token_ids.clone();
+    let token_2 = new_token_id();
+    ctx.open_position_1g((&token_0, &token_2));
+    let missing = new_token_id();
+
+    let pairs = [
+        (token_0.clone(), token_1.clone()),
+        (token_1.clone(), token_0.clone()),
+        (token_0.clone(), missing.clone()),
+        (token_2.clone(), token_0.clone()),
+        (token_0.clone(), token_0.clone()),
+    ];
+    let prices = ctx
+        .sandbox
+        .call(|dex| dex.get_spot_prices(&pairs, 1))
+        .unwrap();
+    assert_eq!(prices.len(), pairs.len());
+
+    for (pair, price) in pairs.iter().zip(&prices) {
+        let expected = ctx
+            .sandbox
+            .call(|dex| dex.get_spot_price_scaled(&pair.0, &pair.1, 1))
+            .ok();
+        assert_eq!(*price, expected);
+    }
+    assert_matches!(prices[..], [Some(_), Some(_), None, Some(_), None]);
+    assert_eq_rel_tol!(prices[0].unwrap(), prices[1].unwrap().recip(), 10);
+
+    assert_matches!(
+        ctx.sandbox
+            .call(|dex| dex.get_spot_prices(&pairs, NUM_FEE_LEVELS)),
+        Err(Error {
+            kind: ErrorKind::IllegalFeeLevel,
+            ..
+        })
+    );
+}
+
+#[test]
+fn position_value_in_reference_token() {
+    let SwapTestContext {
+        sandbox,
+        token_ids: (token_0, token_1),
+        position_id,
+        ..
+    } = SwapTestContext::new();
+
+    let fee_level = sandbox
+        .call(|dex| dex.get_position_info(position_id))
+        .unwrap()
+        .fee_level;
+    let value_0 = sandbox
+        .call(|dex| dex.get_position_value_in(position_id, &token_0))
+        .unwrap();
+    let value_1 = sandbox
+        .call(|dex| dex.get_position_value_in(position_id, &token_1))
+        .unwrap();
+    // Price of token_1 expressed in token_0
+    let price = sandbox
+        .call(|dex| dex.get_spot_price_scaled(&token_0, &token_1, fee_level))
+        .unwrap();
+
+    assert!(value_0 > Amount::zero() && value_1 > Amount::zero());
+    assert_eq_rel_tol!(Float::from(value_0), Float::from(value_1) * price, 35);
+
+    assert_matches!(
+        sandbox.call(|dex| dex.get_position_value_in(position_id, &new_token_id())),
+        Err(Error {
+            kind: ErrorKind::InvalidParams,
+            ..
+        })
+    );
+}
+
+#[test]
+fn force_remove_stuck_position() {
+    let SwapTestContext {
+        mut sandbox,
+        owner,
+        token_ids,
+        position_id,
+    } = SwapTestContext::new();
+    let (pool_id, _) = PoolId::try_from_pair(token_ids).unwrap();
+
+    // Consistent position must be closed normally
+    assert_matches!(
+        sandbox.call_mut(|dex| dex.force_remove_position(position_id)),
+        Err(Error {
+            kind: ErrorKind::PositionNotStuck,
+            ..
+        })
+    );
+
+    // Break state: position still refers to pool which no longer exists
+    sandbox
+        .call_mut(|dex| {
+            dex.contract_mut().latest().pools.remove(&pool_id);
+            Ok(())
+        })
+        .unwrap();
+    assert_matches!(
+        sandbox.call_mut(|dex| dex.close_position(position_id)),
+        Err(Error {
+            kind: ErrorKind::InternalLogicError,
+            ..
+        })
+    );
+
+    sandbox.set_initiator_caller_ids(new_account_id());
+    assert_matches!(
+        sandbox.call_mut(|dex| dex.force_remove_position(position_id)),
+        Err(Error {
+            kind: ErrorKind::PermissionDenied,
+            ..
+        })
+    );
+
+    sandbox.set_initiator_caller_ids(owner.clone());
+    sandbox
+        .call_mut(|dex| dex.force_remove_position(position_id))
+        .unwrap();
+    assert_any_matches!(
+        sandbox.latest_logs(),
+        Event::ForceRemovePosition {
+            position_id: id,
+            pool,
+            owner: Some(position_owner),
+            account,
+        } if
+            *id == position_id
+            && pool.0 == pool_id.0 && pool.1 == pool_id.1
+            && position_owner == &owner
+            && account == &owner
+    );
+
+    sandbox.call(|dex| {
+        let contract = dex.contract().as_ref();
+        assert!(contract
+            .position_to_pool_id
+            .inspect(&position_id, |_| ())
+            .is_none());
+        assert!(contract
+            .position_owners
+            .unwrap()
+            .inspect(&position_id, |_| ())
+            .is_none());
+        assert!(!contract
+            .accounts
+            .inspect(&owner, |account| {
+                let account = account.as_ref();
+                account.positions.contains_item(&position_id)
+            })
+            .unwrap());
+    });
+
+    assert_matches!(
+        sandbox.call_mut(|dex| dex.force_remove_position(position_id)),
+        Err(Error {
+            kind: ErrorKind::PositionDoesNotExist,
+            ..
+        })
+    );
+}
+
+#[test]
+fn open_position_by_price_range() {
+    let SwapTestContext {
+        mut sandbox,
+        token_ids: (token_0, token_1),
+        ..
+    } = SwapTestContext::new_all_1g();
+    let (price_low, price_high) = (Float::from(1u64) / Float::from(2u64), Float::from(2u64));
+
+    let (position_id, ..) = sandbox
+        .call_mut(|dex| {
+            dex.open_position_by_price(
+                &token_0,
+                &token_1,
+                1,
+                price_low,
+                price_high,
+                new_amount(1_000_000),
+                new_amount(1_000_000),
+            )
+        })
+        .unwrap();
+
+    let info = sandbox
+        .call(|dex| dex.get_position_info(position_id))
+        .unwrap();
+    // Ticks in requested order of tokens
+    let (tick_low, tick_high) = if info.tokens_ids.0 == token_0 {
+        info.range_ticks
+    } else {
+        (info.range_ticks.1.opposite(), info.range_ticks.0.opposite())
+    };
+    let spot_price = |index: i32| {
+        let sqrtprice = Tick::new(index).unwrap().spot_sqrtprice();
+        sqrtprice * sqrtprice
+    };
+    for (tick, price) in [(tick_low, price_low), (tick_high, price_high)] {
+        assert!(spot_price(tick.index() - 1) <= price && price <= spot_price(tick.index() + 1));
+    }
+
+    for (low, high) in [
+        (price_high, price_low),
+        (price_low, price_low),
+        (Float::zero(), price_high),
+    ] {
+        assert_matches!(
+            sandbox.call_mut(|dex| dex.open_position_by_price(
+                &token_0,
+                &token_1,
+                1,
+                low,
+                high,
+                new_amount(1_000_000),
+                new_amount(1_000_000),
+            )),
+            Err(Error {
+                kind: ErrorKind::InvalidParams,
+                ..
+            })
+        );
+    }
+}
+
+#[test]
+fn open_position_by_price_scaled_by_token_decimals() {
+    let SwapTestContext {
+        mut sandbox,
+        token_ids: (token_0, token_1),
+        ..
+    } = SwapTestContext::new_all_1g();
+    sandbox
+        .call_mut(|dex| dex.set_token_decimals(token_0.clone(), Some(8)))
+        .unwrap();
+    sandbox
+        .call_mut(|dex| dex.set_token_decimals(token_1.clone(), Some(6)))
+        .unwrap();
+    // Whole token prices; raw prices are 100 times higher, i.e. 1/2 and 2
+    let (price_low, price_high) = (
+        Float::from(1u64) / Float::from(200u64),
+        Float::from(1u64) / Float::from(50u64),
+    );
+    let raw_scale = Float::from(100u64);
+
+    let (position_id, ..) = sandbox
+        .call_mut(|dex| {
+            dex.open_position_by_price(
+                &token_0,
+                &token_1,
+                1,
+                price_low,
+                price_high,
+                new_amount(1_000_000),
+                new_amount(1_000_000),
+            )
+        })
+        .unwrap();
+
+    let info = sandbox
+        .call(|dex| dex.get_position_info(position_id))
+        .unwrap();
+    // Ticks in requested order of tokens
+    let (tick_low, tick_high) = if info.tokens_ids.0 == token_0 {
+        info.range_ticks
+    } else {
+        (info.range_ticks.1.opposite(), info.range_ticks.0.opposite())
+    };
+    let spot_price = |index: i32| {
+        let sqrtprice = Tick::new(index).unwrap().spot_sqrtprice();
+        sqrtprice * sqrtprice
+    };
+    for (tick, price) in [(tick_low, price_low), (tick_high, price_high)] {
+        let raw_price = price * raw_scale;
+        assert!(
+            spot_price(tick.index() - 1) <= raw_price && raw_price <= spot_price(tick.index() + 1)
+        );
+    }
+}
+
+#[test]
+fn all_ticks_liquidity_paged_sweep() {
+    let SwapTestContext {
+        mut sandbox,
+        token_ids: (token_0, token_1),
+        position_id,
+        ..
+    } = SwapTestContext::new_all_1g();
+
+    let mut position_ids = Vec::new();
+    for (low, high) in [(2u64, 2u64), (4, 4), (2, 4)] {
+        let (id, ..) = sandbox
+            .call_mut(|dex| {
+                dex.open_position_by_price(
+                    &token_0,
+                    &token_1,
+                    1,
+                    Float::one() / Float::from(low),
+                    Float::from(high),
+                    new_amount(1_000_000),
+                    new_amount(1_000_000),
+                )
+            })
+            .unwrap();
+        position_ids.push(id);
+    }
+
+    let infos = position_ids
+        .iter()
+        .map(|id| sandbox.call(|dex| dex.get_position_info(*id)).unwrap())
+        .collect_vec();
+    let fee_level = sandbox
+        .call(|dex| dex.get_position_info(position_id))
+        .unwrap()
+        .fee_level;
+    let ticks_count = sandbox
+        .call(|dex| dex.get_pool_ticks((token_0.clone(), token_1.clone()), fee_level))
+        .unwrap()
+        .unwrap();
+    // Bounds of positions opened by price, in requested order of tokens
+    let bounds = infos
+        .iter()
+        .flat_map(|info| {
+            let (low, high) = info.range_ticks;
+            if info.tokens_ids.0 == token_0 {
+                [low.index(), high.index()]
+            } else {
+                [-high.index(), -low.index()]
+            }
+        })
+        .sorted()
+        .dedup()
+        .collect_vec();
+
+    for tokens in [
+        (token_0.clone(), token_1.clone()),
+        (token_1.clone(), token_0.clone()),
+    ] {
+        let mut swept = Vec::new();
+        loop {
+            let page = sandbox
+                .call(|dex| dex.get_all_ticks_liquidity(tokens.clone(), fee_level, swept.len(), 2))
+                .unwrap();
+            assert!(page.len() <= 2);
+            if page.is_empty() {
+                break;
+            }
+            swept.extend(page);
+        }
+
+        let ticks = swept.iter().map(|(tick, _)| *tick).collect_vec();
+        assert_eq!(ticks.len(), ticks_count);
+        assert!(ticks.windows(2).all(|w| w[0] < w[1]));
+        for tick in &bounds {
+            let tick = if tokens.0 == token_0 { *tick } else { -tick };
+            assert!(ticks.contains(&tick));
+        }
+        // Every position adds liquidity at its low tick and removes it at the high one
+        assert!(swept[0].1 > Float::zero());
+        assert!(swept[swept.len() - 1].1 < Float::zero());
+    }
+
+    assert_matches!(
+        sandbox.call(|dex| dex.get_all_ticks_liquidity(
+            (token_0.clone(), new_token_id()),
+            fee_level,
+            0,
+            10
+        )),
+        Err(Error {
+            kind: ErrorKind::PoolNotRegistered,
+            ..
+        })
+    );
+}
+
+#[test]
+fn withdraw_v2_modes() {
+    let acc = new_account_id();
+    let token_id = new_token_id();
+
+    let mut sandbox = Sandbox::new_default(acc.clone());
+    sandbox.call_mut(|dex| dex.register_account()).unwrap();
+    sandbox
+        .call_mut(|dex| dex.register_tokens(&acc, [&token_id]))
+        .unwrap();
+    sandbox
+        .call_mut(|dex| dex.deposit(&acc, &token_id, new_amount(5_000)))
+        .unwrap();
+
+    let balance = |sandbox: &Sandbox| {
+        sandbox
+            .call(|dex| dex.get_deposit(&acc, &token_id))
+            .unwrap()
+    };
+
+    // Neither zero exact amount nor `None` drain balance
+    for mode in [
+        WithdrawMode::Exact(new_amount(0).into()),
+        WithdrawMode::None,
+    ] {
+        assert_matches!(
+            sandbox.call_mut(|dex| dex.withdraw_v2(&acc, &token_id, mode, false, ())),
+            Ok(None)
+        );
+        assert_eq!(balance(&sandbox), new_amount(5_000));
+        assert!(!sandbox
+            .latest_logs()
+            .iter()
+            .any(|event| matches!(event, Event::Withdraw { .. })));
+    }
+
+    assert_matches!(
+        sandbox.call_mut(|dex| dex.withdraw_v2(
+            &acc,
+            &token_id,
+            WithdrawMode::Exact(new_amount(2_000).into()),
+            false,
+            ()
+        )),
+        Ok(Some(()))
+    );
+    assert_eq!(balance(&sandbox), new_amount(3_000));
+
+    assert_matches!(
+        sandbox.call_mut(|dex| dex.withdraw_v2(
+            &acc,
+            &token_id,
+            WithdrawMode::Exact(new_amount(3_001).into()),
+            false,
+            ()
+        )),
+        Err(Error {
+            kind: ErrorKind::NotEnoughTokens,
+            ..
+        })
+    );
+
+    assert_matches!(
+        sandbox.call_mut(|dex| dex.withdraw_v2(&acc, &token_id, WithdrawMode::All, false, ())),
+        Ok(Some(()))
+    );
+    assert_eq!(balance(&sandbox), new_amount(0));
+}
+
+#[test]
+fn admin_config_snapshot() {
+    let owner = new_account_id();
+    let mut sandbox = Sandbox::new_default(owner.clone());
+    let guard = new_account_id();
+
+    let config = sandbox.call(|dex| dex.get_admin_config());
+    assert_eq!(config.owner, owner);
+    assert!(config.guards.is_empty());
+    assert_eq!(config.guard_count, 0);
+    assert!(!config.suspended);
+    assert_eq!(config.protocol_fee_fraction, 1300);
+
+    sandbox
+        .call_mut(|dex| dex.add_guard_accounts([guard.clone()]))
+        .unwrap();
+    sandbox.call_mut(|dex| dex.suspend_payable_api()).unwrap();
+
+    let config = sandbox.call(|dex| dex.get_admin_config());
+    assert_eq!(config.owner, owner);
+    assert_eq!(config.guards, vec![guard]);
+    assert_eq!(config.guard_count, 1);
+    assert!(config.suspended);
+}
+
+#[test]
+fn deposit_overflow_rejected() {
+    let owner = new_account_id();
+    let mut sandbox = Sandbox::new_default(owner.clone());
+    let token = new_token_id();
+
+    sandbox.call_mut(|dex| dex.register_account()).unwrap();
+    sandbox
+        .call_mut(|dex| dex.register_tokens(&owner, [&token]))
+        .unwrap();
+
+    let near_max = Amount::MAX - Amount::from(1u64);
+    sandbox
+        .call_mut(|dex| dex.deposit(&owner, &token, near_max))
+        .unwrap();
+
+    assert_matches!(
+        sandbox.call_mut(|dex| dex.deposit(&owner, &token, Amount::from(2u64))),
+        Err(Error {
+            kind: ErrorKind::DepositWouldOverflow,
+            ..
+        })
+    );
+    assert_eq!(
+        sandbox.call(|dex| dex.get_deposit(&owner, &token)).unwrap(),
+        near_max
+    );
+
+    // Filling balance up to exactly `Amount::MAX` is fine
+    assert_eq!(
+        sandbox
+            .call_mut(|dex| dex.deposit(&owner, &token, Amount::from(1u64)))
+            .unwrap(),
+        Amount::MAX
+    );
+}
+
+#[test]
+fn account_pools_distinct() {
+    let mut ctx = SwapTestContext::new();
+    let (token_0, token_1) = ctx.token_ids.clone();
+    let token_2 = new_token_id();
+
+    // Second position in the same pool, and one in another pool
+    ctx.open_position_1g((&token_1, &token_0));
+    ctx.open_position_1g((&token_1, &token_2));
+
+    let pair = |tokens: (&TokenId, &TokenId)| {
+        let (pool_id, _) = PoolId::try_from_pair((tokens.0.clone(), tokens.1.clone())).unwrap();
+        (pool_id.0.clone(), pool_id.1.clone())
+    };
+    let mut pools = ctx
+        .sandbox
+        .call(|dex| dex.get_account_pools(&ctx.owner))
+        .unwrap();
+    pools.sort();
+    let mut expected = vec![pair((&token_0, &token_1)), pair((&token_1, &token_2))];
+    expected.sort();
+    assert_eq!(pools, expected);
+
+    // Account without positions has no pools
+    let other = new_account_id();
+    ctx.sandbox.set_initiator_caller_ids(other.clone());
+    ctx.sandbox.call_mut(|dex| dex.register_account()).unwrap();
+    assert!(ctx
+        .sandbox
+        .call(|dex| dex.get_account_pools(&other))
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn pool_allowed_swap_side() {
+    let mut ctx = SwapTestContext::new_all_1g();
+    let (token_0, token_1) = ctx.token_ids.clone();
+    let swap = |ctx: &mut SwapTestContext, tokens: [TokenId; 2]| {
+        ctx.sandbox
+            .call_mut(|dex| dex.swap_exact_in(&tokens, new_amount(1000), new_amount(1)))
+    };
+
+    // Buy-only: first token may be swapped into the pool, but not the other way round
+    ctx.sandbox
+        .call_mut(|dex| {
+            dex.set_pool_allowed_swap_side((token_0.clone(), token_1.clone()), Some(Side::Left))
+        })
+        .unwrap();
+    ctx.sandbox.call(|dex| {
+        assert_matches!(
+            dex.get_pool_allowed_swap_side((token_0.clone(), token_1.clone())),
+            Ok(Some(Side::Left))
+        );
+        assert_matches!(
+            dex.get_pool_allowed_swap_side((token_1.clone(), token_0.clone())),
+            Ok(Some(Side::Right))
+        );
+    });
+
+    swap(&mut ctx, [token_0.clone(), token_1.clone()]).unwrap();
+    assert_matches!(
+        swap(&mut ctx, [token_1.clone(), token_0.clone()]),
+        Err(Error {
+            kind: ErrorKind::SwapDirectionNotAllowed,
+            ..
+        })
+    );
+
+    ctx.sandbox
+        .call_mut(|dex| dex.set_pool_allowed_swap_side((token_0.clone(), token_1.clone()), None))
+        .unwrap();
+    swap(&mut ctx, [token_0.clone(), token_1.clone()]).unwrap();
+    swap(&mut ctx, [token_1, token_0]).unwrap();
+}
+
+#[test]
+fn spot_sqrtprice_fixed_point() {
+    let SwapTestContext {
+        sandbox,
+        token_ids: (token_0, token_1),
+        ..
+    } = SwapTestContext::new();
+
+    for (token_in, token_out) in [(&token_0, &token_1), (&token_1, &token_0)] {
+        let sqrtprice = sandbox
+            .call(|dex| dex.get_spot_sqrtprice_fp(token_in, token_out, 1))
+            .unwrap();
+        let price = sandbox
+            .call(|dex| dex.get_spot_prices(&[(token_in.clone(), token_out.clone())], 1))
+            .unwrap()[0]
+            .unwrap();
+        let sqrtprice = Float::from(sqrtprice);
+        assert_eq_rel_tol!(sqrtprice * sqrtprice, price, 10);
+    }
+
+    assert_matches!(
+        sandbox.call(|dex| dex.get_spot_sqrtprice_fp(&token_0, &new_token_id(), 1)),
+        Err(Error {
+            kind: ErrorKind::PoolNotRegistered,
+            ..
+        })
+    );
+}
+
+#[test]
+fn protocol_fee_withdrawal_timelock() {
+    const DELAY: u64 = 3600;
+
+    let mut ctx = SwapTestContext::new_all_1g();
+    let tokens = ctx.token_ids.clone();
+    let acc = ctx.owner.clone();
+    ctx.sandbox
+        .call_mut(|dex| {
+            dex.swap_exact_in(
+                &[tokens.0.clone(), tokens.1.clone()],
+                new_amount(1_000_000),
+                new_amount(0),
+            )
+        })
+        .unwrap();
+    ctx.sandbox
+        .call_mut(|dex| dex.set_protocol_fee_withdrawal_delay(DELAY))
+        .unwrap();
+
+    // Immediate withdrawal bypassing timelock is forbidden
+    assert_matches!(
+        ctx.sandbox
+            .call_mut(|dex| dex.withdraw_protocol_fee(tokens.clone())),
+        Err(Error {
+            kind: ErrorKind::WithdrawalTimelockActive,
+            ..
+        })
+    );
+    assert_matches!(
+        ctx.sandbox
+            .call_mut(|dex| dex.execute_protocol_fee_withdrawal(tokens.clone())),
+        Err(Error {
+            kind: ErrorKind::NoPendingWithdrawal,
+            ..
+        })
+    );
+
+    let accounted_before = ctx
+        .sandbox
+        .call(|dex| dex.get_accounted_balance(&tokens.0))
+        .unwrap();
+    let now = ctx.sandbox.block_timestamp();
+    let (amounts, release_ts) = ctx
+        .sandbox
+        .call_mut(|dex| dex.request_protocol_fee_withdrawal(tokens.clone()))
+        .unwrap();
+    assert!(!amounts.0.is_zero());
+    assert_eq!(release_ts, now + DELAY);
+    assert_eq!(
+        ctx.sandbox
+            .call(
+                |dex| dex.get_pending_protocol_fee_withdrawal((tokens.1.clone(), tokens.0.clone()))
+            )
+            .unwrap(),
+        Some(((amounts.1, amounts.0), release_ts))
+    );
+    // Pending fee is still accounted by contract
+    assert_eq!(
+        ctx.sandbox
+            .call(|dex| dex.get_accounted_balance(&tokens.0))
+            .unwrap(),
+        accounted_before
+    );
+
+    let deposits = |ctx: &SwapTestContext| {
+        (
+            ctx.sandbox
+                .call(|dex| dex.get_deposit(&acc, &tokens.0))
+                .unwrap(),
+            ctx.sandbox
+                .call(|dex| dex.get_deposit(&acc, &tokens.1))
+                .unwrap(),
+        )
+    };
+    let deposits_before = deposits(&ctx);
+
+    ctx.sandbox.set_block_timestamp(release_ts - 1);
+    assert_matches!(
+        ctx.sandbox
+            .call_mut(|dex| dex.execute_protocol_fee_withdrawal(tokens.clone())),
+        Err(Error {
+            kind: ErrorKind::WithdrawalTimelockActive,
+            ..
+        })
+    );
+    assert_eq!(deposits(&ctx), deposits_before);
+
+    ctx.sandbox.set_block_timestamp(release_ts);
+    assert_eq!(
+        ctx.sandbox
+            .call_mut(|dex| dex.execute_protocol_fee_withdrawal(tokens.clone()))
+            .unwrap(),
+        amounts
+    );
+    assert_eq!(
+        deposits(&ctx),
+        (deposits_before.0 + amounts.0, deposits_before.1 + amounts.1)
+    );
+    assert_eq!(
+        ctx.sandbox
+            .call(|dex| dex.get_pending_protocol_fee_withdrawal(tokens.clone()))
+            .unwrap(),
+        None
+    );
+}
+
+#[test]
+fn withdraw_all_fees_skips_empty_positions() {
+    let mut ctx = SwapTestContext::new_all_1g();
+    let (token_0, token_1) = ctx.token_ids.clone();
+    let token_2 = new_token_id();
+    let position_0 = ctx.position_id;
+    let position_1 = ctx.open_position_1g((&token_0, &token_2));
+    // Pool of this position is never traded
+    ctx.open_position_1g((&token_1, &token_2));
+
+    for token_out in [&token_1, &token_2] {
+        ctx.sandbox
+            .call_mut(|dex| {
+                dex.swap_exact_in(
+                    &[token_0.clone(), token_out.clone()],
+                    new_amount(1_000_000),
+                    new_amount(0),
+                )
+            })
+            .unwrap();
+    }
+    let accrued = |ctx: &SwapTestContext, position_id| {
+        ctx.sandbox
+            .call(|dex| dex.get_position_info(position_id))
+            .unwrap()
+            .reward_since_last_withdraw
+    };
+    let fees_0 = accrued(&ctx, position_0);
+    let fees_1 = accrued(&ctx, position_1);
+
+    // Continue after the first position
+    assert_eq!(
+        ctx.sandbox
+            .call_mut(|dex| dex.withdraw_all_fees(Some(position_0)))
+            .unwrap(),
+        (vec![(position_1, fees_1.0, fees_1.1)], None)
+    );
+    assert_eq!(
+        ctx.sandbox
+            .call_mut(|dex| dex.withdraw_all_fees(None))
+            .unwrap(),
+        (vec![(position_0, fees_0.0, fees_0.1)], None)
+    );
+    assert_eq!(accrued(&ctx, position_0), (Amount::zero(), Amount::zero()));
+    assert_eq!(accrued(&ctx, position_1), (Amount::zero(), Amount::zero()));
+
+    // Nothing left to harvest
+    assert_eq!(
+        ctx.sandbox
+            .call_mut(|dex| dex.withdraw_all_fees(None))
+            .unwrap(),
+        (vec![], None)
+    );
+}
+
+#[test]
+fn position_rounding_residual_goes_to_protocol_fee() {
+    let mut ctx = SwapTestContext::new();
+    let tokens = ctx.token_ids.clone();
+    for _ in 0..3 {
+        ctx.open_position_1g((&tokens.0, &tokens.1));
+    }
+
+    // Pool was never traded, so protocol fee consists of rounding residual only
+    let (accrued, rounding) = ctx
+        .sandbox
+        .call(|dex| dex.get_protocol_fee(tokens.clone()))
+        .unwrap();
+    assert!(rounding.0 > Float::zero() || rounding.1 > Float::zero());
+    for (accrued, rounding) in [(accrued.0, rounding.0), (accrued.1, rounding.1)] {
+        let accrued = Float::from(accrued);
+        assert!(accrued <= rounding && rounding < accrued + Float::one());
+    }
+
+    let (reversed_accrued, reversed_rounding) = ctx
+        .sandbox
+        .call(|dex| dex.get_protocol_fee((tokens.1.clone(), tokens.0.clone())))
+        .unwrap();
+    assert_eq!(reversed_accrued, (accrued.1, accrued.0));
+    assert_eq!(reversed_rounding, (rounding.1, rounding.0));
+
+    assert_eq!(
+        ctx.sandbox
+            .call_mut(|dex| dex.withdraw_protocol_fee(tokens.clone()))
+            .unwrap(),
+        accrued
+    );
+}
+
+#[test]
+fn pool_observations_twap() {
+    let mut ctx = SwapTestContext::new_all_1g();
+    let (token_0, token_1) = ctx.token_ids.clone();
+    let tokens = (token_0.clone(), token_1.clone());
+    let swap_at = |ctx: &mut SwapTestContext, timestamp: u64, tokens: [TokenId; 2]| {
+        ctx.sandbox.set_block_timestamp(timestamp);
+        ctx.sandbox
+            .call_mut(|dex| dex.swap_exact_in(&tokens, new_amount(10_000_000), new_amount(1)))
+            .unwrap();
+        ctx.sandbox
+            .call(|dex| dex.get_spot_prices(&[(tokens[0].clone(), tokens[1].clone())], 0))
+            .unwrap()[0]
+            .unwrap()
+    };
+    let twap = |ctx: &SwapTestContext, window_secs: u64| {
+        ctx.sandbox
+            .call(|dex| dex.observe_twap(&token_0, &token_1, window_secs))
+    };
+
+    ctx.sandbox
+        .call_mut(|dex| dex.set_pool_observation_cardinality(tokens.clone(), 3))
+        .unwrap();
+    assert_matches!(
+        ctx.sandbox
+            .call_mut(|dex| dex.set_pool_observation_cardinality(tokens.clone(), 0)),
+        Err(Error {
+            kind: ErrorKind::InvalidParams,
+            ..
+        })
+    );
+
+    let price_1 = swap_at(&mut ctx, 1_000, [token_0.clone(), token_1.clone()]);
+    let price_2 = swap_at(&mut ctx, 1_100, [token_0.clone(), token_1.clone()]);
+    let price_3 = swap_at(&mut ctx, 1_300, [token_1.clone(), token_0.clone()]).recip();
+    ctx.sandbox.set_block_timestamp(1_400);
+
+    // Observations are at 1000, 1100 and 1300; price is constant between them
+    assert_eq_rel_tol!(twap(&ctx, 100).unwrap(), price_3, 41);
+    assert_eq_rel_tol!(
+        twap(&ctx, 300).unwrap().powi(3),
+        price_2.powi(2) * price_3,
+        43
+    );
+    assert_eq_rel_tol!(
+        twap(&ctx, 400).unwrap().powi(4),
+        price_1 * price_2.powi(2) * price_3,
+        43
+    );
+    assert_eq!(
+        ctx.sandbox
+            .call(|dex| dex.observe_twap(&token_1, &token_0, 300))
+            .unwrap(),
+        twap(&ctx, 300).unwrap().recip()
+    );
+    assert_matches!(
+        twap(&ctx, 401),
+        Err(Error {
+            kind: ErrorKind::ObservationTooOld,
+            ..
+        })
+    );
+    assert_matches!(
+        twap(&ctx, 0),
+        Err(Error {
+            kind: ErrorKind::InvalidParams,
+            ..
+        })
+    );
+
+    // Reducing cardinality drops the oldest observation
+    ctx.sandbox
+        .call_mut(|dex| dex.set_pool_observation_cardinality(tokens.clone(), 2))
+        .unwrap();
+    assert_matches!(
+        ctx.sandbox
+            .call(|dex| dex.get_pool_observation_cardinality(tokens.clone())),
+        Ok(2)
+    );
+    assert!(twap(&ctx, 300).is_ok());
+    assert_matches!(
+        twap(&ctx, 301),
+        Err(Error {
+            kind: ErrorKind::ObservationTooOld,
+            ..
+        })
+    );
+
+    // New observation overwrites the oldest one
+    swap_at(&mut ctx, 1_500, [token_0.clone(), token_1.clone()]);
+    assert!(twap(&ctx, 200).is_ok());
+    assert_matches!(
+        twap(&ctx, 201),
+        Err(Error {
+            kind: ErrorKind::ObservationTooOld,
+            ..
+        })
+    );
+}
+
+#[test]
+fn canonicalize_pair_matches_pool_id() {
+    let SwapTestContext {
+        sandbox,
+        token_ids: (token_0, token_1),
+        ..
+    } = SwapTestContext::new();
+
+    for (token_a, token_b) in [(&token_0, &token_1), (&token_1, &token_0)] {
+        let (pool_id, swapped) = PoolId::try_from_pair((token_a.clone(), token_b.clone())).unwrap();
+        let (canonical, canonical_swapped) = sandbox
+            .call(|dex| dex.canonicalize_pair(token_a, token_b))
+            .unwrap();
+        assert_eq!(canonical_swapped, swapped);
+        assert_eq!(canonical, <(TokenId, TokenId)>::from(pool_id));
+        assert_eq!(
+            canonical,
+            if swapped {
+                (token_b.clone(), token_a.clone())
+            } else {
+                (token_a.clone(), token_b.clone())
+            }
+        );
+    }
+
+    assert_matches!(
+        sandbox.call(|dex| dex.canonicalize_pair(&token_0, &token_0)),
+        Err(Error {
+            kind: ErrorKind::IdenticalTokens,
+            ..
+        })
+    );
+}
+
+#[test]
+fn multi_hop_swap_reports_hop_rounded_to_zero() {
+    let mut ctx = SwapTestContext::new_all_1g();
+    let (token_a, token_b) = ctx.token_ids.clone();
+    let token_c = new_token_id();
+    let token_d = new_token_id();
+    // Token `c` is worth a million of token `b`
+    ctx.open_position(
+        (&token_b, &token_c),
+        (new_amount(1_000_000_000), new_amount(1_000)),
+    );
+    ctx.open_position_1g((&token_c, &token_d));
+    let path = [token_a.clone(), token_b, token_c, token_d];
+
+    assert_matches!(
+        ctx.sandbox
+            .call_mut(|dex| dex.swap_exact_in(&path, new_amount(1), new_amount(0))),
+        Err(Error {
+            kind: ErrorKind::IntermediateSwapRoundedToZero(0),
+            ..
+        })
+    );
+    assert_matches!(
+        ctx.sandbox
+            .call_mut(|dex| dex.swap_exact_in(&path, new_amount(100), new_amount(0))),
+        Err(Error {
+            kind: ErrorKind::IntermediateSwapRoundedToZero(1),
+            ..
+        })
+    );
+    // Last hop rounding to zero is reported as usual
+    assert_matches!(
+        ctx.sandbox
+            .call_mut(|dex| dex.swap_exact_in(&path[..3], new_amount(100), new_amount(0))),
+        Err(Error {
+            kind: ErrorKind::SwapAmountTooSmall,
+            ..
+        })
+    );
+    ctx.sandbox
+        .call_mut(|dex| dex.swap_exact_in(&path, new_amount(10_000_000), new_amount(1)))
+        .unwrap();
+}
+
+#[test]
+fn fee_schedule_is_consistent() {
+    let SwapTestContext { mut sandbox, .. } = SwapTestContext::new();
+    sandbox
+        .call_mut(|dex| dex.set_protocol_fee_fraction(1_300))
+        .unwrap();
+    let schedule = sandbox.call(|dex| dex.get_fee_schedule());
+
+    assert_eq!(
+        schedule.fee_rate_ticks,
+        sandbox.call(|dex| dex.fee_rates_ticks())
+    );
+    assert_eq!(schedule.basis_point_divisor, BASIS_POINT_DIVISOR);
+    assert_eq!(schedule.protocol_fee_fraction, 1_300);
+    for (ticks, fraction) in schedule
+        .fee_rate_ticks
+        .into_iter()
+        .zip(schedule.fee_rate_fractions)
+    {
+        // Fee rate is `1 - 1.0001^(-ticks)`, which is a bit less than `ticks` basis points
+        let one_over_one_minus_fee_rate = Tick::new(2 * i32::from(ticks)).unwrap().spot_sqrtprice();
+        assert_eq_rel_tol!(
+            fraction,
+            Float::one() - one_over_one_minus_fee_rate.recip(),
+            10
+        );
+        let bp_fraction = Float::from(ticks) / Float::from(BASIS_POINT_DIVISOR);
+        assert!(fraction < bp_fraction);
+        assert!(fraction > bp_fraction * Float::from(99u64) / Float::from(100u64));
+    }
+}
+
+#[test]
+fn open_position_single_sided_deposits_one_token() {
+    let mut ctx = SwapTestContext::new_all_1g();
+    let (token_0, token_1) = ctx.token_ids.clone();
+    let owner = ctx.owner.clone();
+    let position = PositionInit::new_full_range(
+        new_amount(1),
+        new_amount(1_000_000),
+        new_amount(1),
+        new_amount(1_000_000),
+    );
+    let get_deposits = |ctx: &SwapTestContext| {
+        ctx.sandbox.call(|dex| {
+            (
+                dex.get_deposit(&owner, &token_0).unwrap(),
+                dex.get_deposit(&owner, &token_1).unwrap(),
+            )
+        })
+    };
+
+    // Only first token is deducted
+    let before = get_deposits(&ctx);
+    let (_, amount_0, amount_1, _) = ctx
+        .sandbox
+        .call_mut(|dex| {
+            dex.open_position_single_sided(
+                &token_0,
+                &token_1,
+                1,
+                position.clone(),
+                Some(Side::Left),
+            )
+        })
+        .unwrap();
+    assert!(amount_0 > new_amount(0));
+    assert_eq!(amount_1, new_amount(0));
+    assert_eq!(get_deposits(&ctx), (before.0 - amount_0, before.1));
+
+    // Same with tokens in reverse order: only `token_0` is deducted, being the second one
+    let before = get_deposits(&ctx);
+    let (_, amount_1, amount_0, _) = ctx
+        .sandbox
+        .call_mut(|dex| {
+            dex.open_position_single_sided(
+                &token_1,
+                &token_0,
+                1,
+                position.clone(),
+                Some(Side::Right),
+            )
+        })
+        .unwrap();
+    assert_eq!(amount_1, new_amount(0));
+    assert!(amount_0 > new_amount(0));
+    assert_eq!(get_deposits(&ctx), (before.0 - amount_0, before.1));
+
+    // Range entirely above spot price can hold only the second token
+    let above_spot = PositionInit {
+        ticks_range: (Some(100), Some(200)),
+        ..position.clone()
+    };
+    assert_matches!(
+        ctx.sandbox.call_mut(|dex| dex.open_position_single_sided(
+            &token_0,
+            &token_1,
+            1,
+            above_spot.clone(),
+            Some(Side::Left)
+        )),
+        Err(Error {
+            kind: ErrorKind::SingleSidedRangeConflict,
+            ..
+        })
+    );
+    let (_, amount_0, amount_1, _) = ctx
+        .sandbox
+        .call_mut(|dex| {
+            dex.open_position_single_sided(&token_0, &token_1, 1, above_spot, Some(Side::Right))
+        })
+        .unwrap();
+    assert_eq!(amount_0, new_amount(0));
+    assert!(amount_1 > new_amount(0));
+
+    // Without forcing, both tokens are deposited
+    let (_, amount_0, amount_1, _) = ctx
+        .sandbox
+        .call_mut(|dex| dex.open_position_single_sided(&token_0, &token_1, 1, position, None))
+        .unwrap();
+    assert!(amount_0 > new_amount(0) && amount_1 > new_amount(0));
+}
+
+#[test]
+fn is_suspended_follows_payable_api_state() {
+    let SwapTestContext { mut sandbox, .. } = SwapTestContext::new();
+    assert!(!sandbox.call(|dex| dex.is_suspended()));
+
+    sandbox.call_mut(|dex| dex.suspend_payable_api()).unwrap();
+    assert!(sandbox.call(|dex| dex.is_suspended()));
+
+    sandbox.call_mut(|dex| dex.resume_payable_api()).unwrap();
+    assert!(!sandbox.call(|dex| dex.is_suspended()));
+}
+
+#[test]
+fn swap_exact_in_with_slippage_uses_quote() {
+    let mut ctx = SwapTestContext::new_all_1g();
+    let (token_a, token_b) = ctx.token_ids.clone();
+    let token_c = new_token_id();
+    ctx.open_position_1g((&token_b, &token_c));
+    let path = [token_a, token_b, token_c];
+    let amount_in = new_amount(1_000_000);
+    let max_slippage_bp = 50;
+
+    // Manual quote, hop by hop
+    let quote = ctx.sandbox.call(|dex| {
+        path.iter()
+            .tuple_windows()
+            .fold(amount_in, |amount, (token_in, token_out)| {
+                dex.estimate_swap_exact(
+                    true,
+                    token_in.clone(),
+                    token_out.clone(),
+                    amount,
+                    SlippageTolerance::default(),
+                )
+                .unwrap()
+                .result
+            })
+    });
+    let expected_min_amount_out = Amount::try_from(
+        Float::from(quote)
+            * (Float::one() - Float::from(max_slippage_bp) / Float::from(BASIS_POINT_DIVISOR)),
+    )
+    .unwrap();
+    let min_amount_out = ctx
+        .sandbox
+        .call(|dex| dex.quote_min_amount_out(&path, amount_in, max_slippage_bp))
+        .unwrap();
+    assert_eq!(min_amount_out, expected_min_amount_out);
+    assert!(min_amount_out < quote);
+
+    assert_matches!(
+        ctx.sandbox.call_mut(|dex| dex.swap_exact_in_with_slippage(
+            &path,
+            amount_in,
+            BASIS_POINT_DIVISOR + 1
+        )),
+        Err(Error {
+            kind: ErrorKind::IllegalSlippageTolerance,
+            ..
+        })
+    );
+    let (swapped_in, amount_out) = ctx
+        .sandbox
+        .call_mut(|dex| dex.swap_exact_in_with_slippage(&path, amount_in, max_slippage_bp))
+        .unwrap();
+    assert_eq!(swapped_in, amount_in);
+    assert_eq!(amount_out, quote);
+}
@@ -8,7 +8,7 @@ use crate::dex::DepositPayment;
 use super::dex;
 use assert_matches::assert_matches;
 use dex::test_utils::{new_account_id, new_amount, BalanceTracker, Change, SwapTestContext};
-use dex::{Action, Error, ErrorKind};
+use dex::{Action, Error, ErrorKind, PositionInit, Range};
 
 #[test]
 fn success_just_deposit() {
@@ -30,9 +30,10 @@ fn success_just_deposit() {
                 amount,
             }],
             &mut |_, _, _| Ok(()),
-            vec![Action::Deposit]
+            vec![Action::Deposit],
+            None
         )),
-        Ok(v) if v.is_empty()
+        Ok((v, _)) if v.is_empty()
     );
 
     bal_track.assert_changes(&sandbox, [Change::FromLogs, Change::NoChange]);
@@ -58,9 +59,10 @@ fn fail_mutable_api_stopped() {
                 amount
             }],
             &mut |_, _, _| Ok(()),
-            vec![Action::Deposit]
+            vec![Action::Deposit],
+            None
         )),
-        Ok(o) if o.is_empty()
+        Ok((o, _)) if o.is_empty()
     );
     bal_track.assert_changes(&sandbox, [Change::FromLogs, Change::NoChange]);
     assert_matches!(sandbox.call_mut(|dex| dex.suspend_payable_api()), Ok(_));
@@ -73,7 +75,8 @@ fn fail_mutable_api_stopped() {
                 amount
             }],
             &mut |_, _, _| Ok(()),
-            vec![Action::Deposit]
+            vec![Action::Deposit],
+            None
         )),
         Err(Error {
             kind: ErrorKind::PayableAPISuspended,
@@ -91,9 +94,10 @@ fn fail_mutable_api_stopped() {
                 amount
             }],
             &mut |_, _, _| Ok(()),
-            vec![Action::Deposit]
+            vec![Action::Deposit],
+            None
         )),
-        Ok(o) if o.is_empty()
+        Ok((o, _)) if o.is_empty()
     );
     bal_track.assert_changes(&sandbox, [Change::FromLogs, Change::NoChange]);
 }
@@ -117,7 +121,8 @@ fn fail_caller_not_initiator() {
                 amount: new_amount(1_000),
             }],
             &mut |_, _, _| Ok(()),
-            vec![Action::Deposit]
+            vec![Action::Deposit],
+            None
         )),
         Err(Error {
             kind: ErrorKind::DepositSenderMustBeSigner,
@@ -136,7 +141,8 @@ fn fail_caller_not_initiator() {
                 amount: new_amount(1_000),
             }],
             &mut |_, _, _| Ok(()),
-            vec![Action::Deposit]
+            vec![Action::Deposit],
+            None
         )),
         Err(Error {
             kind: ErrorKind::DepositSenderMustBeSigner,
@@ -144,3 +150,102 @@ fn fail_caller_not_initiator() {
         })
     );
 }
+
+#[test]
+fn fail_batch_too_large() {
+    let SwapTestContext {
+        mut sandbox,
+        token_ids,
+        owner,
+        ..
+    } = SwapTestContext::new();
+
+    sandbox
+        .call_mut(|dex| dex.set_max_actions_per_batch(1))
+        .unwrap();
+    let bal_track = BalanceTracker::new_with_caller(&sandbox, [&token_ids.0, &token_ids.1]);
+
+    assert_matches!(
+        sandbox.call_mut(|dex| dex.deposit_execute_actions(
+            &owner,
+            &[DepositPayment {
+                token_id: token_ids.0.clone(),
+                amount: new_amount(1_000),
+            }],
+            &mut |_, _, _| Ok(()),
+            vec![Action::Deposit, Action::Deposit],
+            None
+        )),
+        Err(Error {
+            kind: ErrorKind::BatchTooLarge,
+            ..
+        })
+    );
+
+    bal_track.assert_changes(&sandbox, [Change::NoChange, Change::NoChange]);
+}
+
+#[test]
+fn success_deposit_split_reported() {
+    let SwapTestContext {
+        mut sandbox,
+        token_ids,
+        owner,
+        ..
+    } = SwapTestContext::new_all_1g();
+
+    let deposited = new_amount(1_000);
+    let balance_before = sandbox
+        .call(|dex| dex.get_deposit(&owner, &token_ids.0))
+        .unwrap();
+
+    let (withdrawals, split) = sandbox
+        .call_mut(|dex| {
+            dex.deposit_execute_actions(
+                &owner,
+                &[DepositPayment {
+                    token_id: token_ids.0.clone(),
+                    amount: deposited,
+                }],
+                &mut |_, _, _| Ok(()),
+                vec![
+                    Action::Deposit,
+                    Action::OpenPosition {
+                        tokens: token_ids.clone(),
+                        fee_rate: 1,
+                        position: PositionInit {
+                            amount_ranges: (
+                                Range {
+                                    min: new_amount(1).into(),
+                                    max: new_amount(600).into(),
+                                },
+                                Range {
+                                    min: new_amount(1).into(),
+                                    max: new_amount(600).into(),
+                                },
+                            ),
+                            ticks_range: (None, None),
+                        },
+                    },
+                ],
+                None,
+            )
+        })
+        .unwrap();
+    assert!(withdrawals.is_empty());
+
+    let balance_after = sandbox
+        .call(|dex| dex.get_deposit(&owner, &token_ids.0))
+        .unwrap();
+
+    assert_matches!(
+        &split[..],
+        [(token_id, d, consumed, remaining)]
+            if token_id == &token_ids.0
+                && *d == deposited
+                && *consumed > new_amount(0)
+                && *consumed <= new_amount(600)
+                && *remaining == balance_after
+                && balance_before + deposited == balance_after + *consumed
+    );
+}
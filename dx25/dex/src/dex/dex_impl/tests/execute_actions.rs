@@ -4,9 +4,13 @@
 //! * Check token amounts
 
 use super::dex;
+use crate::Float;
 use assert_matches::assert_matches;
-use dex::test_utils::{new_amount, new_token_id, BalanceTracker, Change, SwapTestContext};
-use dex::{Action, Error, ErrorKind, SwapAction};
+use dex::test_utils::{new_amount, new_token_id, BalanceTracker, Change, Sandbox, SwapTestContext};
+use dex::{
+    Action, Error, ErrorKind, Map as _, State as _, SwapAction, SwapToPriceAction,
+    DEFAULT_MAX_ACTIONS_PER_BATCH, MAX_IDEMPOTENCY_KEYS,
+};
 use rstest::rstest;
 
 #[allow(clippy::unnecessary_wraps)] // Expected - func is a stub for register account constructor
@@ -22,7 +26,7 @@ fn its_ok<T: dex::Types>(
 fn empty() {
     let SwapTestContext { mut sandbox, .. } = SwapTestContext::new();
 
-    assert_matches!(sandbox.call_mut(|dex| dex.execute_actions(&mut its_ok, vec![])), Ok((v, None)) if v.is_empty());
+    assert_matches!(sandbox.call_mut(|dex| dex.execute_actions(&mut its_ok, vec![], None)), Ok((v, None)) if v.is_empty());
     assert_eq!(sandbox.latest_logs().len(), 0);
 }
 
@@ -30,11 +34,11 @@ fn empty() {
 fn fail_mutable_api_stopped() {
     let SwapTestContext { mut sandbox, .. } = SwapTestContext::new();
 
-    assert_matches!(sandbox.call_mut(|dex| dex.execute_actions(&mut its_ok, vec![])), Ok((v, None)) if v.is_empty());
+    assert_matches!(sandbox.call_mut(|dex| dex.execute_actions(&mut its_ok, vec![], None)), Ok((v, None)) if v.is_empty());
     assert_matches!(sandbox.call_mut(|dex| dex.suspend_payable_api()), Ok(_));
 
     assert_matches!(
-        sandbox.call_mut(|dex| dex.execute_actions(&mut its_ok, vec![])),
+        sandbox.call_mut(|dex| dex.execute_actions(&mut its_ok, vec![], None)),
         Err(Error {
             kind: ErrorKind::PayableAPISuspended,
             ..
@@ -42,7 +46,7 @@ fn fail_mutable_api_stopped() {
     );
 
     assert_matches!(sandbox.call_mut(|dex| dex.resume_payable_api()), Ok(_));
-    assert_matches!(sandbox.call_mut(|dex| dex.execute_actions(&mut its_ok, vec![])), Ok((v, None)) if v.is_empty());
+    assert_matches!(sandbox.call_mut(|dex| dex.execute_actions(&mut its_ok, vec![], None)), Ok((v, None)) if v.is_empty());
 }
 
 #[rstest]
@@ -75,7 +79,7 @@ fn success_two_swap_ins_chain(#[values(200, 1_000, 5_000)] amount: u128) {
                 amount: None,
                 amount_limit: amount_limit.into(),
             }),
-        ])),
+        ], None)),
         Ok((outs, Some(a))) if outs.is_empty() => a
     );
 
@@ -117,7 +121,7 @@ fn success_two_swap_outs_chain(#[values(200, 1_000, 5_000)] amount: u128) {
                 amount: None,
                 amount_limit: amount_limit.into(),
             }),
-        ])),
+        ], None)),
         Ok((outs, Some(a))) if outs.is_empty() => a
     );
 
@@ -128,3 +132,243 @@ fn success_two_swap_outs_chain(#[values(200, 1_000, 5_000)] amount: u128) {
 
     assert!((amount..=amount_limit).contains(&amount_out));
 }
+
+#[test]
+fn idempotency_key_prevents_replay() {
+    let SwapTestContext {
+        mut sandbox,
+        owner,
+        token_ids: (token_0, token_1),
+        ..
+    } = SwapTestContext::new_all_1g();
+
+    let swap = || {
+        vec![Action::SwapExactIn(SwapAction {
+            token_in: token_0.clone(),
+            token_out: token_1.clone(),
+            amount: Some(new_amount(1_000).into()),
+            amount_limit: new_amount(1).into(),
+        })]
+    };
+    let balance = |sandbox: &Sandbox| {
+        sandbox
+            .call(|dex| dex.get_deposit(&owner, &token_0))
+            .unwrap()
+    };
+
+    assert_matches!(
+        sandbox.call_mut(|dex| dex.execute_actions(&mut its_ok, swap(), Some([1; 32]))),
+        Ok(_)
+    );
+    let balance_after_first = balance(&sandbox);
+
+    // Replay is rejected and changes nothing
+    assert_matches!(
+        sandbox.call_mut(|dex| dex.execute_actions(&mut its_ok, swap(), Some([1; 32]))),
+        Err(Error {
+            kind: ErrorKind::DuplicateRequest,
+            ..
+        })
+    );
+    assert_eq!(balance(&sandbox), balance_after_first);
+
+    // New key executes normally
+    assert_matches!(
+        sandbox.call_mut(|dex| dex.execute_actions(&mut its_ok, swap(), Some([2; 32]))),
+        Ok(_)
+    );
+    assert_eq!(balance(&sandbox), balance_after_first - new_amount(1_000));
+
+    // Batches without key are never deduplicated
+    for _ in 0..2 {
+        assert_matches!(
+            sandbox.call_mut(|dex| dex.execute_actions(&mut its_ok, swap(), None)),
+            Ok(_)
+        );
+    }
+}
+
+#[test]
+fn idempotency_keys_bounded() {
+    let SwapTestContext {
+        mut sandbox, owner, ..
+    } = SwapTestContext::new();
+
+    for i in 0..=MAX_IDEMPOTENCY_KEYS {
+        let key = [u8::try_from(i).unwrap(); 32];
+        assert_matches!(
+            sandbox.call_mut(|dex| dex.execute_actions(&mut its_ok, vec![], Some(key))),
+            Ok(_)
+        );
+    }
+
+    let keys_len = sandbox.call(|dex| {
+        dex.contract()
+            .as_ref()
+            .accounts
+            .inspect(&owner, |account| {
+                let account = account.as_ref();
+                account.idempotency_keys.len()
+            })
+            .unwrap()
+    });
+    assert_eq!(keys_len, MAX_IDEMPOTENCY_KEYS);
+
+    // Oldest key was evicted, most recent one is still remembered
+    assert_matches!(
+        sandbox.call_mut(|dex| dex.execute_actions(&mut its_ok, vec![], Some([0; 32]))),
+        Ok(_)
+    );
+    let last_key = [u8::try_from(MAX_IDEMPOTENCY_KEYS).unwrap(); 32];
+    assert_matches!(
+        sandbox.call_mut(|dex| dex.execute_actions(&mut its_ok, vec![], Some(last_key))),
+        Err(Error {
+            kind: ErrorKind::DuplicateRequest,
+            ..
+        })
+    );
+}
+
+#[test]
+fn batch_size_limited() {
+    let SwapTestContext {
+        mut sandbox,
+        owner,
+        token_ids: (token_0, token_1),
+        ..
+    } = SwapTestContext::new_all_1g();
+
+    let swaps = |n: u16| {
+        (0..n)
+            .map(|_| {
+                Action::SwapExactIn(SwapAction {
+                    token_in: token_0.clone(),
+                    token_out: token_1.clone(),
+                    amount: Some(new_amount(1_000).into()),
+                    amount_limit: new_amount(1).into(),
+                })
+            })
+            .collect::<Vec<_>>()
+    };
+    let balance = |sandbox: &Sandbox| {
+        sandbox
+            .call(|dex| dex.get_deposit(&owner, &token_0))
+            .unwrap()
+    };
+
+    assert_eq!(
+        sandbox.call(|dex| dex.get_max_actions_per_batch()),
+        DEFAULT_MAX_ACTIONS_PER_BATCH
+    );
+
+    let balance_before = balance(&sandbox);
+    assert_matches!(
+        sandbox.call_mut(|dex| dex.execute_actions(
+            &mut its_ok,
+            swaps(DEFAULT_MAX_ACTIONS_PER_BATCH),
+            None
+        )),
+        Ok(_)
+    );
+    let balance_after = balance(&sandbox);
+    assert_eq!(
+        balance_after,
+        balance_before - new_amount(1_000 * u128::from(DEFAULT_MAX_ACTIONS_PER_BATCH))
+    );
+
+    // Oversized batch is rejected before any of its actions is executed
+    assert_matches!(
+        sandbox.call_mut(|dex| dex.execute_actions(
+            &mut its_ok,
+            swaps(DEFAULT_MAX_ACTIONS_PER_BATCH + 1),
+            None
+        )),
+        Err(Error {
+            kind: ErrorKind::BatchTooLarge,
+            ..
+        })
+    );
+    assert_eq!(balance(&sandbox), balance_after);
+
+    // Limit is configurable by owner, but can't be zero
+    assert_matches!(
+        sandbox.call_mut(|dex| dex.set_max_actions_per_batch(0)),
+        Err(Error {
+            kind: ErrorKind::InvalidParams,
+            ..
+        })
+    );
+    sandbox
+        .call_mut(|dex| dex.set_max_actions_per_batch(2))
+        .unwrap();
+    assert_matches!(
+        sandbox.call_mut(|dex| dex.execute_actions(&mut its_ok, swaps(3), None)),
+        Err(Error {
+            kind: ErrorKind::BatchTooLarge,
+            ..
+        })
+    );
+    assert_matches!(
+        sandbox.call_mut(|dex| dex.execute_actions(&mut its_ok, swaps(2), None)),
+        Ok(_)
+    );
+}
+
+#[test]
+fn swaps_update_last_trade() {
+    let SwapTestContext {
+        mut sandbox,
+        token_ids: (token_0, token_1),
+        ..
+    } = SwapTestContext::new_all_1g();
+    let last_trade = |sandbox: &Sandbox| {
+        let info = sandbox
+            .call(|dex| dex.get_pool_info((token_0.clone(), token_1.clone())))
+            .unwrap()
+            .unwrap();
+        (
+            info.last_trade_ts,
+            info.last_trade_sqrtprice,
+            info.spot_sqrtprices[0],
+        )
+    };
+
+    sandbox.set_block_timestamp(1_000);
+    sandbox
+        .call_mut(|dex| {
+            dex.execute_actions(
+                &mut its_ok,
+                vec![Action::SwapExactIn(SwapAction {
+                    token_in: token_0.clone(),
+                    token_out: token_1.clone(),
+                    amount: Some(new_amount(1_000).into()),
+                    amount_limit: new_amount(0).into(),
+                })],
+                None,
+            )
+        })
+        .unwrap();
+    let (ts, sqrtprice, spot_sqrtprice) = last_trade(&sandbox);
+    assert_eq!(ts, 1_000);
+    assert_eq!(sqrtprice, spot_sqrtprice);
+
+    sandbox.set_block_timestamp(2_000);
+    sandbox
+        .call_mut(|dex| {
+            dex.execute_actions(
+                &mut its_ok,
+                vec![Action::SwapToPrice(SwapToPriceAction {
+                    token_in: token_1.clone(),
+                    token_out: token_0.clone(),
+                    amount: Some(new_amount(1_000).into()),
+                    effective_price_limit: Float::from(1_000_000.0),
+                })],
+                None,
+            )
+        })
+        .unwrap();
+    let (ts, new_sqrtprice, spot_sqrtprice) = last_trade(&sandbox);
+    assert_eq!(ts, 2_000);
+    assert_ne!(new_sqrtprice, sqrtprice);
+    assert_eq!(new_sqrtprice, spot_sqrtprice);
+}
@@ -507,7 +507,7 @@ fn fail_open_position() {
             }]
         )),
         Err(Error {
-            kind: ErrorKind::TokenDuplicates,
+            kind: ErrorKind::IdenticalTokens,
             ..
         })
     );
@@ -646,7 +646,8 @@ fn fail_no_start_amount() {
                     amount: None,
                     amount_limit: new_amount(5_000).into(),
                 })
-            ]
+            ],
+            None
         )),
         Err(Error {
             kind: ErrorKind::WrongActionResult,
@@ -2,6 +2,7 @@
 // which is not the same for other DEX's
 #![allow(clippy::useless_conversion)]
 
+use super::super::{GraceGuard, OracleGuard, PauseGuard};
 use super::dex;
 use crate::chain::{AccountId, Amount, TokenId};
 use crate::dex::SwapToPriceAction;
@@ -12,7 +13,7 @@ use dex::test_utils::{
 };
 use dex::utils::swap_if;
 use dex::{
-    Account, Dex, Error, ErrorKind, Map as _, Result, StateMembersMut, StateMut, SwapAction,
+    Dex, Error, ErrorKind, Map as _, Result, State as _, StateMembersMut, StateMut, SwapAction,
     SwapKind, Types,
 };
 use std::borrow::BorrowMut;
@@ -30,18 +31,37 @@ fn dex_execute_swap_action<T: Types, S: StateMut<T>, SS: BorrowMut<S>>(
     amount: Option<Amount>,
     amount_limit: Amount,
 ) -> Result<(TokenId, SwapKind, Amount)> {
+    let now = dex.get_block_timestamp();
+    let max_underpay = dex.swap_max_underpay();
     let StateMembersMut {
         contract, logger, ..
     } = dex.members_mut();
     let contract = contract.latest();
+    let oracle_guard = OracleGuard::<T> {
+        reference_prices: contract.reference_prices.as_ref(),
+        max_deviation_bp: contract.max_oracle_deviation_bp,
+    };
+    let grace_guard = GraceGuard::<T> {
+        token_verified_at: contract.token_verified_at.as_ref(),
+        grace_secs: contract.verified_token_grace_secs,
+        now,
+    };
+    let pause_guard = PauseGuard::<T> {
+        paused_pools: contract.paused_pools.as_ref(),
+    };
     contract
         .accounts
-        .update(account_id, |Account::V0(ref mut account)| {
+        .update(account_id, |account| {
+            let account = account.latest();
             Dex::<T, S, SS>::execute_swap_action(
                 account_id,
                 account,
                 &mut contract.pools,
+                &oracle_guard,
+                &grace_guard,
+                &pause_guard,
                 logger,
+                contract.event_verbosity,
                 prev_swap_result,
                 exact,
                 SwapAction {
@@ -51,6 +71,7 @@ fn dex_execute_swap_action<T: Types, S: StateMut<T>, SS: BorrowMut<S>>(
                     amount_limit: amount_limit.into(),
                 },
                 contract.protocol_fee_fraction,
+                max_underpay,
             )
         })
         .unwrap() // Not intended for checking here
@@ -66,18 +87,37 @@ fn dex_execute_swap_to_price_action<T: Types, S: StateMut<T>, SS: BorrowMut<S>>(
     amount: Option<Amount>,
     effective_price_limit: Float,
 ) -> Result<(TokenId, SwapKind, Amount)> {
+    let now = dex.get_block_timestamp();
+    let max_underpay = dex.swap_max_underpay();
     let StateMembersMut {
         contract, logger, ..
     } = dex.members_mut();
     let contract = contract.latest();
+    let oracle_guard = OracleGuard::<T> {
+        reference_prices: contract.reference_prices.as_ref(),
+        max_deviation_bp: contract.max_oracle_deviation_bp,
+    };
+    let grace_guard = GraceGuard::<T> {
+        token_verified_at: contract.token_verified_at.as_ref(),
+        grace_secs: contract.verified_token_grace_secs,
+        now,
+    };
+    let pause_guard = PauseGuard::<T> {
+        paused_pools: contract.paused_pools.as_ref(),
+    };
     contract
         .accounts
-        .update(account_id, |Account::V0(ref mut account)| {
+        .update(account_id, |account| {
+            let account = account.latest();
             Dex::<T, S, SS>::execute_swap_to_price_action(
                 account_id,
                 account,
                 &mut contract.pools,
+                &oracle_guard,
+                &grace_guard,
+                &pause_guard,
                 logger,
+                contract.event_verbosity,
                 prev_swap_result,
                 SwapToPriceAction {
                     token_in: token_in.clone(),
@@ -86,6 +126,7 @@ fn dex_execute_swap_to_price_action<T: Types, S: StateMut<T>, SS: BorrowMut<S>>(
                     effective_price_limit,
                 },
                 contract.protocol_fee_fraction,
+                max_underpay,
             )
         })
         .unwrap() // Not intended for checking here
@@ -336,7 +377,7 @@ fn fail_duplicate_tokens(#[values(SwapKind::ExactIn, SwapKind::ExactOut)] exact:
                 new_amount(5_000),
             )),
             Err(Error {
-                kind: ErrorKind::TokenDuplicates,
+                kind: ErrorKind::IdenticalTokens,
                 ..
             })
         );
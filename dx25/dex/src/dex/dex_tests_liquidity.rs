@@ -8,13 +8,17 @@
     clippy::too_many_arguments
 )]
 
-use super::test_utils::{new_account_id, new_amount, new_token_id};
+use super::test_utils::{new_account_id, new_amount, new_token_id, SwapTestContext};
 use super::{BasisPoints, EstimateAddLiquidityResult, Estimations};
-use crate::chain::TokenId;
+use crate::chain::{TokenId, MAX_TICK, MIN_TICK};
+use crate::dex::pool::fee_rates_ticks;
 use crate::dex::test_utils::Sandbox;
 use crate::dex::tick::Tick;
-use crate::dex::{PoolInfo, PositionId, PositionInit, Range, Result};
-use crate::{Amount, Float, Liquidity};
+use crate::dex::{
+    Action, Error, ErrorKind, PoolInfo, PositionId, PositionInit, Range, Result, Side,
+    SlippageTolerance, SwapAction,
+};
+use crate::{assert_eq_rel_tol, Amount, Float, Liquidity};
 use assert_matches::assert_matches;
 use rstest::rstest;
 
@@ -150,7 +154,7 @@ impl TestContext {
                 amount_a,
                 amount_b,
                 user_price,
-                slippage_tolerance_bp,
+                SlippageTolerance::from_bp(slippage_tolerance_bp).unwrap(),
             )
         })
     }
@@ -486,3 +490,301 @@ fn from_two_amounts_success_existing(
     );
     assert_matches!(result, Ok(_));
 }
+
+#[rstest]
+fn liquidity_for_target_price_sets_spot_price(
+    #[values(false, true)] swap_token_ids: bool,
+    #[values(0.25, 1.0, 3.5)] target_price: f64,
+    #[values(Side::Left, Side::Right)] side: Side,
+) {
+    let fee_rate = 1;
+    let ticks_range = (Some(-30_000), Some(30_000));
+    let amount = new_amount(1_000_000_000_000);
+
+    let mut context = TestContext::new();
+    context.tokens = swap_tokens(swap_token_ids, &context.tokens);
+
+    let (amount_a, amount_b, net_liquidity) = context
+        .state
+        .call(|dex| {
+            dex.liquidity_for_target_price(
+                context.tokens.clone(),
+                fee_rate,
+                ticks_range,
+                target_price.into(),
+                side,
+                amount,
+            )
+        })
+        .unwrap();
+    assert!((amount_a, amount_b)[side] <= amount);
+    assert_eq_rel_tol!((amount_a, amount_b)[side], amount, 40);
+    //
+    // Deposit evaluated amounts to a fresh pool
+    //
+    let acc = new_account_id();
+    context.state.set_initiator_caller_ids(acc.clone());
+    context
+        .state
+        .call_mut(|dex| dex.register_account())
+        .unwrap();
+    context
+        .state
+        .call_mut(|dex| dex.register_tokens(&acc, [&context.tokens.0, &context.tokens.1]))
+        .unwrap();
+    context
+        .state
+        .call_mut(|dex| dex.deposit(&acc, &context.tokens.0, amount_a))
+        .unwrap();
+    context
+        .state
+        .call_mut(|dex| dex.deposit(&acc, &context.tokens.1, amount_b))
+        .unwrap();
+
+    let (_, _, _, opened_liquidity) = context
+        .open_position(
+            fee_rate,
+            amount_a,
+            amount_b,
+            Tick::new(ticks_range.0.unwrap()).unwrap(),
+            Tick::new(ticks_range.1.unwrap()).unwrap(),
+        )
+        .unwrap();
+    assert_eq_rel_tol!(opened_liquidity, net_liquidity, 40);
+
+    let fee_level = fee_rates_ticks()
+        .iter()
+        .position(|&r| r == fee_rate)
+        .unwrap();
+    let spot_price = context.get_pool_info().unwrap().spot_sqrtprices[fee_level].powi(2);
+    assert_eq_rel_tol!(spot_price, Float::from(target_price), 40);
+}
+
+#[rstest]
+fn liquidity_for_target_price_outside_range_fails(#[values(0.25, 4.0)] target_price: f64) {
+    let context = TestContext::new();
+    // Ticks range corresponds to prices about 0.5..2.0
+    let ticks_range = (Some(-6_932), Some(6_932));
+
+    assert_matches!(
+        context.state.call(|dex| dex.liquidity_for_target_price(
+            context.tokens.clone(),
+            1,
+            ticks_range,
+            target_price.into(),
+            Side::Left,
+            new_amount(1_000_000),
+        )),
+        Err(Error {
+            kind: ErrorKind::InvalidParams,
+            ..
+        })
+    );
+}
+
+#[test]
+fn estimate_actions_gas_sums_op_estimates_and_rejects_uncalibrated() {
+    let SwapTestContext {
+        sandbox, token_ids, ..
+    } = SwapTestContext::new();
+    let open_position = |fee_rate: BasisPoints| Action::<()>::OpenPosition {
+        tokens: token_ids.clone(),
+        fee_rate,
+        position: PositionInit {
+            amount_ranges: (
+                Range {
+                    min: new_amount(0).into(),
+                    max: new_amount(1000).into(),
+                },
+                Range {
+                    min: new_amount(0).into(),
+                    max: new_amount(1000).into(),
+                },
+            ),
+            ticks_range: (None, None),
+        },
+    };
+    let estimate_open_position = |fee_rate: BasisPoints| {
+        sandbox
+            .call(|dex| {
+                dex.estimate_liq_add(
+                    token_ids.clone(),
+                    fee_rate,
+                    (None, None),
+                    Some(new_amount(1000)),
+                    Some(new_amount(1000)),
+                    None,
+                    SlippageTolerance::from_bp(0).unwrap(),
+                )
+            })
+            .unwrap()
+            .tx_cost
+            .gas_cost_max
+    };
+
+    let actions = vec![
+        // Fee level with ticks initialized by context's position
+        open_position(1),
+        // Fee level without ticks
+        open_position(4),
+    ];
+
+    let estimate = sandbox
+        .call(|dex| dex.estimate_actions_gas(&actions))
+        .unwrap();
+    assert_eq!(
+        estimate.gas_cost_max,
+        estimate_open_position(1) + estimate_open_position(4)
+    );
+    assert!(estimate_open_position(1) > estimate_open_position(4));
+    assert_eq!(estimate.storage_fee_max, Amount::from(0u16));
+
+    let uncalibrated: [Action<_>; 2] = [
+        Action::SwapExactIn(SwapAction {
+            token_in: token_ids.0.clone(),
+            token_out: token_ids.1.clone(),
+            amount: Some(new_amount(100).into()),
+            amount_limit: new_amount(0).into(),
+        }),
+        Action::Withdraw(token_ids.1.clone(), new_amount(100).into(), ()),
+    ];
+    for action in uncalibrated {
+        assert_matches!(
+            sandbox.call(|dex| dex.estimate_actions_gas(&[open_position(1), action])),
+            Err(Error {
+                kind: ErrorKind::GasCostUnknown,
+                ..
+            })
+        );
+    }
+}
+
+#[test]
+fn preview_open_position_matches_deductions() {
+    let SwapTestContext {
+        mut sandbox,
+        owner,
+        token_ids: (token_0, token_1),
+        ..
+    } = SwapTestContext::new();
+    // Tokens in reverse order, to check handling of pool side
+    let (token_a, token_b) = (token_1, token_0);
+
+    let position = |max_amount: u128| PositionInit {
+        amount_ranges: (
+            Range {
+                min: new_amount(0).into(),
+                max: new_amount(max_amount).into(),
+            },
+            Range {
+                min: new_amount(0).into(),
+                max: new_amount(max_amount).into(),
+            },
+        ),
+        ticks_range: (Some(-2_000), Some(3_000)),
+    };
+    let balances = |sandbox: &Sandbox| {
+        sandbox.call(|dex| {
+            (
+                dex.get_deposit(&owner, &token_a).unwrap(),
+                dex.get_deposit(&owner, &token_b).unwrap(),
+            )
+        })
+    };
+
+    let preview = sandbox
+        .call(|dex| dex.preview_open_position(&owner, &token_a, &token_b, 1, position(10_000)))
+        .unwrap();
+
+    let before = balances(&sandbox);
+    let (_, _, _, net_liquidity) = sandbox
+        .call_mut(|dex| dex.open_position(&token_a, &token_b, 1, position(10_000)))
+        .unwrap();
+    let after = balances(&sandbox);
+
+    assert_eq!(
+        preview,
+        (before.0 - after.0, before.1 - after.1, net_liquidity)
+    );
+
+    assert_matches!(
+        sandbox.call(|dex| dex.preview_open_position(
+            &owner,
+            &token_a,
+            &token_b,
+            1,
+            position(1_000_000_000)
+        )),
+        Err(Error {
+            kind: ErrorKind::NotEnoughTokens,
+            ..
+        })
+    );
+}
+
+#[rstest]
+fn preview_full_range_position_matches_estimate(
+    #[values(false, true)] swap_token_ids: bool,
+    #[values(1, 16)] fee_rate: BasisPoints,
+) {
+    let context = TestContext::new_with_price(2.0, fee_rate, swap_token_ids);
+    let amount = new_amount(1_000_000_000);
+
+    for (amount_a, amount_b) in [(Some(amount), None), (None, Some(amount))] {
+        let preview = context
+            .state
+            .call(|dex| {
+                dex.preview_full_range_position(
+                    context.tokens.clone(),
+                    fee_rate,
+                    amount_a,
+                    amount_b,
+                )
+            })
+            .unwrap();
+        let estimate = context
+            .estimate_liq_add(
+                context.tokens.clone(),
+                fee_rate,
+                (Some(MIN_TICK), Some(MAX_TICK)),
+                amount_a,
+                amount_b,
+                None,
+                0,
+            )
+            .unwrap();
+
+        assert_eq_rel_tol!(preview.0, estimate.max_a, 40);
+        assert_eq_rel_tol!(preview.1, estimate.max_b, 40);
+        assert_eq_rel_tol!(preview.2, estimate.position_net_liquidity, 40);
+
+        // Both amounts specified explicitly give the same position
+        let explicit = context
+            .state
+            .call(|dex| {
+                dex.preview_full_range_position(
+                    context.tokens.clone(),
+                    fee_rate,
+                    Some(preview.0),
+                    Some(preview.1),
+                )
+            })
+            .unwrap();
+        assert_eq_rel_tol!(explicit.0, preview.0, 40);
+        assert_eq_rel_tol!(explicit.1, preview.1, 40);
+        assert_eq_rel_tol!(explicit.2, preview.2, 40);
+    }
+
+    assert_matches!(
+        context.state.call(|dex| dex.preview_full_range_position(
+            context.tokens.clone(),
+            fee_rate,
+            None,
+            None
+        )),
+        Err(Error {
+            kind: ErrorKind::SwapAmountTooSmall,
+            ..
+        })
+    );
+}
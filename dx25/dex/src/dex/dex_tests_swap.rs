@@ -6,15 +6,17 @@ use super::test_utils::{new_account_id, new_amount, new_token_id};
 use super::Estimations;
 use crate::chain::TokenId;
 use crate::dex::pool::{one_over_one_minus_fee_rate, one_over_sqrt_one_minus_fee_rate};
-use crate::dex::test_utils::Sandbox;
+use crate::dex::test_utils::{Event, Sandbox};
 use crate::dex::tick::Tick;
 use crate::dex::utils::swap_if;
 use crate::dex::{
-    Error, ErrorKind, FeeLevel, PoolInfo, PositionId, PositionInfo, PositionInit, Range, Result,
-    Side, SwapKind,
+    BasisPoints, Error, ErrorKind, FeeLevel, PoolId, PoolInfo, PositionId, PositionInfo,
+    PositionInit, Range, Result, Side, SimOp, SimOpResult, SlippageTolerance, State as _, SwapKind,
+    MAX_PROTOCOL_FEE_FRACTION, MAX_QUOTE_LADDER_LEN,
 };
 use crate::{assert_eq_rel_tol, Amount, Float, Liquidity};
 use assert_matches::assert_matches;
+use itertools::Itertools;
 use num_traits::Zero;
 use rstest::rstest;
 use rug::ops::Pow;
@@ -86,10 +88,22 @@ impl SwapContext {
 
         self.state.call(|dex| match swap_type {
             SwapKind::ExactIn => dex
-                .estimate_swap_exact(true, token_in, token_out, amount, 10)
+                .estimate_swap_exact(
+                    true,
+                    token_in,
+                    token_out,
+                    amount,
+                    SlippageTolerance::from_bp(10).unwrap(),
+                )
                 .map(|r| r.result),
             SwapKind::ExactOut => dex
-                .estimate_swap_exact(false, token_in, token_out, amount, 10)
+                .estimate_swap_exact(
+                    false,
+                    token_in,
+                    token_out,
+                    amount,
+                    SlippageTolerance::from_bp(10).unwrap(),
+                )
                 .map(|r| r.result),
             SwapKind::ToPrice => unreachable!("Use swap_to_price"),
         })
@@ -407,6 +421,105 @@ fn test_estimate_swap_with_tick_crossing() -> Result<()> {
     Ok(())
 }
 
+/// Estimated protocol fee must match protocol fee actually collected by the equivalent swap
+#[test]
+fn test_estimate_swap_fee_split_matches_protocol_fee() -> Result<()> {
+    let mut ctx = new_swap_context();
+    let fee_level: FeeLevel = 3;
+    let position_amounts = 1_000_000_000_000_000_u128;
+    let tick_halfrange: i32 = 10000;
+
+    ctx.state
+        .call_mut(|dex| dex.set_protocol_fee_fraction(2000))?;
+    ctx.open_position(
+        fee_level,
+        position_amounts.into(),
+        position_amounts.into(),
+        Tick::new(-tick_halfrange).unwrap(),
+        Tick::new(tick_halfrange).unwrap(),
+    )?;
+
+    let amount_in: Amount = 1_000_000_000_000_u128.into();
+    let (token_in, token_out) = ctx.tokens.clone();
+    let estimate = ctx.state.call(|dex| {
+        dex.estimate_swap_exact(
+            true,
+            token_in,
+            token_out,
+            amount_in,
+            SlippageTolerance::from_bp(10).unwrap(),
+        )
+    })?;
+
+    assert!(!estimate.protocol_fee_in_spent_tok.is_zero());
+    assert_eq!(
+        estimate.lp_fee_in_spent_tok + estimate.protocol_fee_in_spent_tok,
+        estimate.fee_in_spent_tok
+    );
+
+    ctx.swap(Side::Left, SwapKind::ExactIn, amount_in)?;
+    let (protocol_fee, _) = ctx.withdraw_protocol_fee()?;
+
+    assert_eq_rel_tol!(protocol_fee, estimate.protocol_fee_in_spent_tok, 30);
+
+    Ok(())
+}
+
+/// Protocol fee collected from a fixed swap, for given protocol fee fraction and swap surcharge
+fn protocol_fee_with_surcharge(
+    protocol_fee_fraction: BasisPoints,
+    surcharge_bp: BasisPoints,
+) -> Result<Amount> {
+    let mut ctx = new_swap_context();
+    ctx.state
+        .call_mut(|dex| dex.set_protocol_fee_fraction(protocol_fee_fraction))?;
+    ctx.state
+        .call_mut(|dex| dex.set_swap_surcharge(surcharge_bp))?;
+    assert_matches!(
+        ctx.state.latest_logs(),
+        [Event::SetSwapSurcharge { old: 0, new, .. }] if *new == surcharge_bp
+    );
+    ctx.open_position(
+        3,
+        1_000_000_000_000_000_u128.into(),
+        1_000_000_000_000_000_u128.into(),
+        Tick::new(-10_000).unwrap(),
+        Tick::new(10_000).unwrap(),
+    )?;
+
+    ctx.swap(Side::Left, SwapKind::ExactIn, 1_000_000_000_000_u128.into())?;
+    Ok(ctx.withdraw_protocol_fee()?.0)
+}
+
+#[test]
+fn test_swap_surcharge_increases_protocol_fee() -> Result<()> {
+    let base = protocol_fee_with_surcharge(1000, 0)?;
+    let surcharged = protocol_fee_with_surcharge(1000, 500)?;
+
+    assert!(surcharged > base);
+    assert_eq!(surcharged, protocol_fee_with_surcharge(1500, 0)?);
+    Ok(())
+}
+
+#[test]
+fn test_swap_surcharge_is_capped() -> Result<()> {
+    assert_eq!(
+        protocol_fee_with_surcharge(4000, 3000)?,
+        protocol_fee_with_surcharge(MAX_PROTOCOL_FEE_FRACTION, 0)?
+    );
+
+    let mut ctx = new_swap_context();
+    assert_matches!(
+        ctx.state
+            .call_mut(|dex| dex.set_swap_surcharge(MAX_PROTOCOL_FEE_FRACTION + 1)),
+        Err(Error {
+            kind: ErrorKind::InvalidParams,
+            ..
+        })
+    );
+    Ok(())
+}
+
 fn new_swap_context_in_inactive_region() -> SwapContext {
     let mut ctx = new_swap_context();
     let (pos0_id, _, _, _) = ctx
@@ -493,6 +606,268 @@ fn test_swap_beyond_position_end_fails() {
     );
 }
 
+#[test]
+fn test_swap_to_price_extreme_reports_tick_out_of_bounds() {
+    let mut ctx = new_swap_context();
+    // Full-range positions on different levels end at different effective ticks,
+    // so once level 0 position is passed, level 0 price leaves the price scale
+    ctx.open_position(
+        0,
+        1_000_u128.into(),
+        1_000_u128.into(),
+        Tick::MIN,
+        Tick::MAX,
+    )
+    .unwrap();
+    ctx.open_position(
+        3,
+        1_000_u128.into(),
+        1_000_u128.into(),
+        Tick::MIN,
+        Tick::MAX,
+    )
+    .unwrap();
+
+    let res = ctx.swap(
+        Side::Left,
+        SwapKind::ExactIn,
+        100_000_000_000_000_000_000_000_u128.into(),
+    );
+    assert_matches!(
+        res,
+        Err(Error {
+            kind: ErrorKind::PriceTickOutOfBoundsAt(tick_index),
+            ..
+        }) if tick_index > Tick::MAX.index()
+    );
+}
+
+#[test]
+fn test_swap_logs_crossed_ticks() {
+    let mut ctx = new_swap_context();
+    ctx.open_position(
+        0,
+        1_000_000_000_000_000_000_000_u128.into(),
+        1_000_000_000_000_000_000_000_u128.into(),
+        Tick::MIN,
+        Tick::MAX,
+    )
+    .unwrap();
+    ctx.open_position(
+        0,
+        1_000_000_000_000_000_000_u128.into(),
+        1_000_000_000_000_000_000_u128.into(),
+        Tick::new(-100).unwrap(),
+        Tick::new(100).unwrap(),
+    )
+    .unwrap();
+
+    // Small swap stays within the narrow position's range
+    ctx.swap(Side::Left, SwapKind::ExactIn, 1_000_000_000_000_u128.into())
+        .unwrap();
+    assert!(!ctx
+        .state
+        .latest_logs()
+        .iter()
+        .any(|event| matches!(event, Event::TickCrossed { .. })));
+
+    // Large swap moves price by much more than 100 ticks
+    ctx.swap(
+        Side::Left,
+        SwapKind::ExactIn,
+        100_000_000_000_000_000_000_u128.into(),
+    )
+    .unwrap();
+
+    let crossed: Vec<_> = ctx
+        .state
+        .latest_logs()
+        .iter()
+        .filter_map(|event| match event {
+            Event::TickCrossed {
+                pool,
+                fee_level,
+                tick,
+                side,
+            } => Some((pool.clone(), *fee_level, *tick, *side)),
+            _ => None,
+        })
+        .collect();
+
+    let (pool_id, swapped) = PoolId::try_from_pair(ctx.tokens.clone()).unwrap();
+    let (expected_tick, expected_side) = if swapped {
+        (-100, Side::Right)
+    } else {
+        (100, Side::Left)
+    };
+    assert_eq!(
+        crossed,
+        vec![(
+            (pool_id.0.clone(), pool_id.1.clone()),
+            0,
+            expected_tick,
+            expected_side
+        )]
+    );
+}
+
+#[test]
+fn test_last_swap_tick_crossings_stored() {
+    let mut ctx = new_swap_context();
+    ctx.open_position(
+        0,
+        1_000_000_000_000_000_000_000_u128.into(),
+        1_000_000_000_000_000_000_000_u128.into(),
+        Tick::MIN,
+        Tick::MAX,
+    )
+    .unwrap();
+    for ticks in [100, 200] {
+        ctx.open_position(
+            0,
+            1_000_000_000_000_000_000_u128.into(),
+            1_000_000_000_000_000_000_u128.into(),
+            Tick::new(-ticks).unwrap(),
+            Tick::new(ticks).unwrap(),
+        )
+        .unwrap();
+    }
+    let last_swap_crossings = |ctx: &SwapContext| {
+        ctx.state
+            .call(|dex| dex.get_last_swap_crossings(ctx.tokens.clone()))
+            .unwrap()
+    };
+    assert_eq!(last_swap_crossings(&ctx), 0);
+
+    // Large swap moves price beyond both narrow positions
+    ctx.swap(
+        Side::Left,
+        SwapKind::ExactIn,
+        100_000_000_000_000_000_000_u128.into(),
+    )
+    .unwrap();
+    let logged_crossings = ctx
+        .state
+        .latest_logs()
+        .iter()
+        .filter(|event| matches!(event, Event::TickCrossed { .. }))
+        .count();
+    assert_eq!(logged_crossings, 2);
+    assert_eq!(last_swap_crossings(&ctx), 2);
+
+    // Small swap back doesn't reach narrow positions again
+    ctx.swap(Side::Right, SwapKind::ExactIn, 1_000_000_u128.into())
+        .unwrap();
+    assert_eq!(last_swap_crossings(&ctx), 0);
+}
+
+#[test]
+fn test_swap_tick_crossings_ceiling() {
+    let mut ctx = new_swap_context();
+    ctx.open_position(
+        0,
+        1_000_000_000_000_000_000_000_u128.into(),
+        1_000_000_000_000_000_000_000_u128.into(),
+        Tick::MIN,
+        Tick::MAX,
+    )
+    .unwrap();
+    for ticks in [100, 200, 300, 400] {
+        ctx.open_position(
+            0,
+            1_000_000_000_000_000_000_u128.into(),
+            1_000_000_000_000_000_000_u128.into(),
+            Tick::new(-ticks).unwrap(),
+            Tick::new(ticks).unwrap(),
+        )
+        .unwrap();
+    }
+    ctx.state
+        .call_mut(|dex| dex.set_max_swap_tick_crossings(Some(2)))
+        .unwrap();
+
+    // Large swap moves price beyond all narrow positions
+    assert_matches!(
+        ctx.swap(
+            Side::Left,
+            SwapKind::ExactIn,
+            100_000_000_000_000_000_000_u128.into(),
+        ),
+        Err(Error {
+            kind: ErrorKind::TooManyTickCrossings,
+            ..
+        })
+    );
+
+    // Small swap stays within all ranges
+    ctx.swap(Side::Left, SwapKind::ExactIn, 1_000_000_000_000_u128.into())
+        .unwrap();
+
+    // Removing the ceiling allows large swap
+    ctx.state
+        .call_mut(|dex| dex.set_max_swap_tick_crossings(None))
+        .unwrap();
+    ctx.swap(
+        Side::Left,
+        SwapKind::ExactIn,
+        100_000_000_000_000_000_000_u128.into(),
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_swap_logs_position_range_boundary_crossings() {
+    let mut ctx = new_swap_context();
+    ctx.open_position(
+        0,
+        1_000_000_000_000_000_000_000_u128.into(),
+        1_000_000_000_000_000_000_000_u128.into(),
+        Tick::MIN,
+        Tick::MAX,
+    )
+    .unwrap();
+    let (position_id, ..) = ctx
+        .open_position(
+            0,
+            1_000_000_000_000_000_000_u128.into(),
+            1_000_000_000_000_000_000_u128.into(),
+            Tick::new(-100).unwrap(),
+            Tick::new(100).unwrap(),
+        )
+        .unwrap();
+    let range_crossings = |ctx: &SwapContext| -> Vec<(PositionId, bool)> {
+        ctx.state
+            .latest_logs()
+            .iter()
+            .filter_map(|event| match event {
+                Event::PositionRangeBoundaryCrossed {
+                    position_id,
+                    entered,
+                } => Some((*position_id, *entered)),
+                _ => None,
+            })
+            .collect()
+    };
+
+    // Price leaves the narrow position's range
+    ctx.swap(
+        Side::Left,
+        SwapKind::ExactIn,
+        100_000_000_000_000_000_000_u128.into(),
+    )
+    .unwrap();
+    assert_eq!(range_crossings(&ctx), vec![(position_id, false)]);
+
+    // Price returns, first entering the range again
+    ctx.swap(
+        Side::Right,
+        SwapKind::ExactIn,
+        100_000_000_000_000_000_000_u128.into(),
+    )
+    .unwrap();
+    assert_eq!(range_crossings(&ctx).first(), Some(&(position_id, true)));
+}
+
 #[test]
 fn test_swap_two_overlapping_positions() {
     let mut ctx = new_swap_context();
@@ -674,3 +1049,515 @@ fn test_swap_two_cl_positions(
         pool_info.total_reserves.0.is_zero() && pool_info.total_reserves.1.is_zero();
     assert!(is_total_reserves_zero);
 }
+
+#[test]
+fn test_min_effective_swap_in() -> Result<()> {
+    let mut ctx = new_swap_context();
+    // Position is unbalanced, so that several units of left token are worth one unit of right token
+    ctx.open_position(
+        0,
+        new_amount(1_000_000),
+        new_amount(100_000),
+        Tick::MIN,
+        Tick::MAX,
+    )?;
+
+    let (token_0, token_1) = ctx.tokens.clone();
+    let threshold = ctx
+        .state
+        .call(|dex| dex.min_effective_swap_in(token_0.clone(), token_1.clone()))?;
+    assert!(threshold > new_amount(1));
+
+    assert_matches!(
+        ctx.swap(Side::Left, SwapKind::ExactIn, threshold - new_amount(1)),
+        Err(Error {
+            kind: ErrorKind::SwapAmountTooSmall,
+            ..
+        })
+    );
+    let amount_out = ctx.swap(Side::Left, SwapKind::ExactIn, threshold)?;
+    assert!(amount_out >= new_amount(1));
+
+    Ok(())
+}
+
+#[test]
+fn test_quote_ladder() -> Result<()> {
+    let mut ctx = new_swap_context();
+    ctx.open_position(
+        0,
+        new_amount(1_000_000),
+        new_amount(1_000_000),
+        Tick::MIN,
+        Tick::MAX,
+    )?;
+
+    let (token_0, token_1) = ctx.tokens.clone();
+    let amounts: Vec<_> = (1..=10).map(|i| new_amount(i * 50_000)).collect();
+    let amounts_out = ctx
+        .state
+        .call(|dex| dex.quote_ladder(token_0.clone(), token_1.clone(), &amounts))?;
+    assert_eq!(amounts_out.len(), amounts.len());
+
+    // Quotes are independent, so each one matches a swap on the unchanged pool
+    let single = ctx
+        .state
+        .call(|dex| dex.quote_ladder(token_0.clone(), token_1.clone(), &amounts[4..5]))?;
+    assert_eq!(single[0], amounts_out[4]);
+
+    // Outputs grow with input
+    for (prev, next) in amounts_out.iter().tuple_windows() {
+        assert!(prev < next);
+    }
+    // ...but slower and slower because of price impact; allow 1 unit for rounding
+    for (a, b, c) in amounts_out.iter().tuple_windows() {
+        assert!(*c - *b <= *b - *a + new_amount(1));
+    }
+    assert!(amounts_out[9] - amounts_out[8] < amounts_out[0]);
+
+    Ok(())
+}
+
+#[test]
+fn test_quote_ladder_failures() {
+    let ctx = new_swap_context();
+    let (token_0, token_1) = ctx.tokens.clone();
+
+    assert_matches!(
+        ctx.state.call(|dex| dex.quote_ladder(
+            token_0.clone(),
+            token_1.clone(),
+            &[new_amount(1000)]
+        )),
+        Err(Error {
+            kind: ErrorKind::PoolNotRegistered,
+            ..
+        })
+    );
+    let amounts = vec![new_amount(1000); MAX_QUOTE_LADDER_LEN + 1];
+    assert_matches!(
+        ctx.state
+            .call(|dex| dex.quote_ladder(token_0.clone(), token_1.clone(), &amounts)),
+        Err(Error {
+            kind: ErrorKind::InvalidParams,
+            ..
+        })
+    );
+}
+
+#[rstest]
+fn test_quote_exact_out_matches_swap(#[values(Side::Left, Side::Right)] side: Side) -> Result<()> {
+    let mut ctx = new_swap_context();
+    ctx.open_position(
+        0,
+        new_amount(1_000_000),
+        new_amount(1_000_000),
+        Tick::MIN,
+        Tick::MAX,
+    )?;
+    ctx.open_position(
+        3,
+        new_amount(500_000),
+        new_amount(500_000),
+        Tick::new(-1000).unwrap(),
+        Tick::new(1000).unwrap(),
+    )?;
+
+    let (token_in, token_out) = swap_if(side == Side::Right, ctx.tokens.clone());
+    let amount_out = new_amount(300_000);
+    let quote = ctx
+        .state
+        .call(|dex| dex.quote_exact_out(token_in.clone(), token_out.clone(), amount_out))?;
+
+    let amount_in = ctx.swap(side, SwapKind::ExactOut, amount_out)?;
+    assert_eq!(quote, amount_in);
+
+    Ok(())
+}
+
+#[test]
+fn test_quote_exact_out_insufficient_liquidity() -> Result<()> {
+    let mut ctx = new_swap_context();
+    let (token_0, token_1) = ctx.tokens.clone();
+    assert_matches!(
+        ctx.state.call(|dex| dex.quote_exact_out(
+            token_0.clone(),
+            token_1.clone(),
+            new_amount(1000)
+        )),
+        Err(Error {
+            kind: ErrorKind::PoolNotRegistered,
+            ..
+        })
+    );
+
+    let (_, _, amount_1, _) = ctx.open_position(
+        0,
+        new_amount(1_000_000),
+        new_amount(1_000_000),
+        Tick::MIN,
+        Tick::MAX,
+    )?;
+    assert_matches!(
+        ctx.state
+            .call(|dex| dex.quote_exact_out(token_0.clone(), token_1.clone(), amount_1)),
+        Err(Error {
+            kind: ErrorKind::InsufficientLiquidity,
+            ..
+        })
+    );
+
+    Ok(())
+}
+
+#[rstest]
+fn test_average_execution_price_matches_estimate(
+    #[values(Side::Left, Side::Right)] side: Side,
+) -> Result<()> {
+    let mut ctx = new_swap_context();
+    ctx.open_position(
+        3,
+        new_amount(100_000),
+        new_amount(200_000),
+        Tick::new(20_000).unwrap(),
+        Tick::new(30_000).unwrap(),
+    )?;
+
+    let (token_in, token_out) = swap_if(side == Side::Right, ctx.tokens.clone());
+    let amount_in = new_amount(10_000);
+    let price = ctx.state.call(|dex| {
+        dex.get_average_execution_price(token_in.clone(), token_out.clone(), amount_in)
+    })?;
+    let estimate = ctx.state.call(|dex| {
+        dex.estimate_swap_exact(
+            true,
+            token_in.clone(),
+            token_out.clone(),
+            amount_in,
+            SlippageTolerance::from_bp(10).unwrap(),
+        )
+    })?;
+    assert_eq!(Some(price), estimate.swap_price);
+
+    // Swap runs beyond the only position's range
+    assert_matches!(
+        ctx.state.call(|dex| dex.get_average_execution_price(
+            token_in.clone(),
+            token_out.clone(),
+            new_amount(1_000_000_000_000_000)
+        )),
+        Err(Error {
+            kind: ErrorKind::InsufficientLiquidity,
+            ..
+        })
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_close_position_impact_keeps_price() -> Result<()> {
+    let mut ctx = new_swap_context();
+    ctx.open_position(
+        1,
+        new_amount(1_000_000),
+        new_amount(1_000_000),
+        Tick::MIN,
+        Tick::MAX,
+    )?;
+    let (position_id, ..) = ctx.open_position(
+        1,
+        new_amount(50_000_000),
+        new_amount(50_000_000),
+        Tick::new(-1000).unwrap(),
+        Tick::new(1000).unwrap(),
+    )?;
+
+    let impact = ctx
+        .state
+        .call(|dex| dex.estimate_close_position_impact(position_id))?;
+    assert_eq!(impact.spot_price_before, impact.spot_price_after);
+    assert!(impact.liquidity_after < impact.liquidity_before);
+
+    // Estimation doesn't modify the pool
+    ctx.state
+        .call(|dex| dex.get_position_info(position_id))
+        .unwrap();
+
+    assert_matches!(
+        ctx.state
+            .call(|dex| dex.estimate_close_position_impact(position_id + 1)),
+        Err(Error {
+            kind: ErrorKind::PositionDoesNotExist,
+            ..
+        })
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_simulate_matches_real_execution() -> Result<()> {
+    let mut ctx = new_swap_context();
+    ctx.open_position(
+        0,
+        new_amount(1_000_000_000),
+        new_amount(1_000_000_000),
+        Tick::MIN,
+        Tick::MAX,
+    )?;
+    let tokens = ctx.tokens.clone();
+
+    let (tick_low, tick_high) = (Tick::new(-1000).unwrap(), Tick::new(1000).unwrap());
+    let position = PositionInit {
+        amount_ranges: (
+            Range {
+                min: new_amount(0).into(),
+                max: new_amount(1_000_000).into(),
+            },
+            Range {
+                min: new_amount(0).into(),
+                max: new_amount(1_000_000).into(),
+            },
+        ),
+        ticks_range: (tick_low.to_opt_index(), tick_high.to_opt_index()),
+    };
+    let next_position_id = ctx
+        .state
+        .call(|dex| dex.contract().as_ref().next_free_position_id);
+    let ops = [
+        SimOp::Open {
+            fee_rate: 1,
+            position,
+        },
+        SimOp::Swap {
+            side: Side::Left,
+            kind: SwapKind::ExactIn,
+            amount: new_amount(10_000_000),
+        },
+        SimOp::Close {
+            position_id: next_position_id,
+        },
+    ];
+
+    let pool_before = ctx.get_pool_info().unwrap();
+    let sim = ctx.state.call(|dex| dex.simulate(tokens.clone(), &ops))?;
+    // Simulation doesn't touch the actual state
+    assert_eq!(
+        ctx.get_pool_info().unwrap().total_reserves,
+        pool_before.total_reserves
+    );
+    assert_eq!(
+        ctx.get_pool_info().unwrap().spot_sqrtprices,
+        pool_before.spot_sqrtprices
+    );
+    //
+    // Execute the same sequence for real
+    //
+    let (position_id, deposited_0, deposited_1, _) = ctx.open_position(
+        0,
+        new_amount(1_000_000),
+        new_amount(1_000_000),
+        tick_low,
+        tick_high,
+    )?;
+    let amount_out = ctx.swap(Side::Left, SwapKind::ExactIn, new_amount(10_000_000))?;
+    let position_info = ctx.get_position_info(position_id)?;
+    ctx.close_position(position_id)?;
+
+    assert_eq!(position_id, next_position_id);
+    assert_matches!(
+        &sim.outputs[..],
+        [
+            SimOpResult::Open {
+                position_id: sim_position_id,
+                deposited_amounts,
+                ..
+            },
+            SimOpResult::Swap {
+                amount_out: sim_amount_out,
+                ..
+            },
+            SimOpResult::Close { balance, fees },
+        ] if *sim_position_id == position_id
+            && *deposited_amounts == (deposited_0, deposited_1)
+            && *sim_amount_out == amount_out
+            && *balance == position_info.balance
+            && *fees == position_info.reward_since_last_withdraw
+    );
+
+    let pool_after = ctx.get_pool_info().unwrap();
+    assert_eq!(sim.pool_info.total_reserves, pool_after.total_reserves);
+    assert_eq!(sim.pool_info.spot_sqrtprices, pool_after.spot_sqrtprices);
+
+    Ok(())
+}
+
+#[rstest]
+fn test_estimate_zap_in_matches_real_execution(#[values(Side::Left, Side::Right)] side: Side) {
+    let mut ctx = new_swap_context();
+    ctx.open_position(
+        0,
+        new_amount(1_000_000_000),
+        new_amount(1_000_000_000),
+        Tick::MIN,
+        Tick::MAX,
+    )
+    .unwrap();
+    let (token_have, token_other) = swap_if(side == Side::Right, ctx.tokens.clone());
+    let (tick_low, tick_high) = (Tick::new(-1000).unwrap(), Tick::new(1000).unwrap());
+    let amount = new_amount(1_000_000);
+
+    let pool_before = ctx.get_pool_info().unwrap();
+    let estimate = ctx
+        .state
+        .call(|dex| {
+            dex.estimate_zap_in(
+                token_have.clone(),
+                token_other.clone(),
+                1,
+                (tick_low.to_opt_index(), tick_high.to_opt_index()),
+                amount,
+            )
+        })
+        .unwrap();
+    assert!(estimate.swap_amount > new_amount(0) && estimate.swap_amount < amount);
+    // Estimation doesn't touch the actual state
+    assert_eq!(
+        ctx.get_pool_info().unwrap().spot_sqrtprices,
+        pool_before.spot_sqrtprices
+    );
+
+    // Execute the same zap for real
+    let amount_out = ctx
+        .swap(side, SwapKind::ExactIn, estimate.swap_amount)
+        .unwrap();
+    let (max_left, max_right) = swap_if(
+        side == Side::Right,
+        (amount - estimate.swap_amount, amount_out),
+    );
+    let (_, deposited_left, deposited_right, _) = ctx
+        .open_position(0, max_left, max_right, tick_low, tick_high)
+        .unwrap();
+    let deposited = swap_if(side == Side::Right, (deposited_left, deposited_right));
+
+    assert_eq!(estimate.deposited, deposited);
+    assert_eq!(
+        estimate.leftover,
+        amount - estimate.swap_amount - deposited.0
+    );
+    assert!(amount_out - deposited.1 <= new_amount(1));
+}
+
+#[test]
+fn test_estimate_zap_in_single_sided_range() {
+    let mut ctx = new_swap_context();
+    ctx.open_position(
+        0,
+        new_amount(1_000_000_000),
+        new_amount(1_000_000_000),
+        Tick::MIN,
+        Tick::MAX,
+    )
+    .unwrap();
+    let amount = new_amount(1_000_000);
+    let ticks_range = (Some(1000), None);
+
+    let [estimate_left, estimate_right] = [Side::Left, Side::Right].map(|side| {
+        let (token_have, token_other) = swap_if(side == Side::Right, ctx.tokens.clone());
+        ctx.state
+            .call(|dex| dex.estimate_zap_in(token_have, token_other, 1, ticks_range, amount))
+            .unwrap()
+    });
+
+    // Range above the current price is funded by one token only,
+    // so one direction needs no swap, while the other swaps everything
+    let (no_swap, full_swap) = if estimate_left.swap_amount.is_zero() {
+        (estimate_left, estimate_right)
+    } else {
+        (estimate_right, estimate_left)
+    };
+    assert_eq!(no_swap.swap_amount, new_amount(0));
+    assert_eq!(no_swap.deposited.1, new_amount(0));
+    assert_eq!(full_swap.swap_amount, amount);
+    assert_eq!(full_swap.deposited.0, new_amount(0));
+    assert_eq!(full_swap.leftover, new_amount(0));
+}
+
+#[test]
+fn test_min_effective_swap_in_no_pool() {
+    let ctx = new_swap_context();
+    let (token_0, token_1) = ctx.tokens.clone();
+
+    assert_matches!(
+        ctx.state
+            .call(|dex| dex.min_effective_swap_in(token_0.clone(), token_1.clone())),
+        Err(Error {
+            kind: ErrorKind::PoolNotRegistered,
+            ..
+        })
+    );
+}
+
+#[rstest]
+fn test_missing_and_empty_pool_errors(
+    #[values(SwapKind::ExactIn, SwapKind::ExactOut)] swap_type: SwapKind,
+) -> Result<()> {
+    let mut ctx = new_swap_context();
+    let (token_0, token_1) = ctx.tokens.clone();
+
+    // Pair was never created
+    assert_matches!(
+        ctx.swap(Side::Left, swap_type, new_amount(1000)),
+        Err(Error {
+            kind: ErrorKind::PoolNotRegistered,
+            ..
+        })
+    );
+    assert_matches!(
+        ctx.estimate_swap(Side::Left, swap_type, new_amount(1000)),
+        Err(Error {
+            kind: ErrorKind::PoolNotRegistered,
+            ..
+        })
+    );
+
+    let (position_id, _, _, _) = ctx.open_position(
+        0,
+        new_amount(1_000_000),
+        new_amount(1_000_000),
+        Tick::MIN,
+        Tick::MAX,
+    )?;
+    ctx.close_position(position_id)?;
+    assert!(ctx.get_pool_info().is_some());
+
+    // Pool exists, but has no liquidity left
+    assert_matches!(
+        ctx.swap(Side::Left, swap_type, new_amount(1000)),
+        Err(Error {
+            kind: ErrorKind::PoolHasNoLiquidity,
+            ..
+        })
+    );
+    assert_matches!(
+        ctx.estimate_swap(Side::Left, swap_type, new_amount(1000)),
+        Err(Error {
+            kind: ErrorKind::PoolHasNoLiquidity,
+            ..
+        })
+    );
+    assert_matches!(
+        ctx.state.call(|dex| dex.quote_exact_out(
+            token_0.clone(),
+            token_1.clone(),
+            new_amount(1000)
+        )),
+        Err(Error {
+            kind: ErrorKind::PoolHasNoLiquidity,
+            ..
+        })
+    );
+
+    Ok(())
+}
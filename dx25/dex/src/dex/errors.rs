@@ -313,6 +313,8 @@ pub enum ErrorKind {
     AtLeastOneSwap,
     #[error("Only one swap allowed")]
     ExactOneSwap,
+    /// Pool has liquidity, but not enough to complete the swap.
+    /// See `PoolHasNoLiquidity` for pools without any liquidity
     #[error("Insufficient liquidity in the pool to perform the swap")]
     InsufficientLiquidity,
     #[error("Swap amount too small")]
@@ -322,6 +324,8 @@ pub enum ErrorKind {
     #[error("Invalid params")]
     InvalidParams,
     // pool manage
+    /// Pool over specified pair was never created.
+    /// See `PoolHasNoLiquidity` for pools which exist but are empty
     #[error("Liquidity pool not registered")]
     PoolNotRegistered,
     #[error("Token duplicated")]
@@ -373,6 +377,78 @@ pub enum ErrorKind {
     InternalLogicError,
     #[error("Tick value is either too large or too small")]
     PriceTickOutOfBounds,
+    #[error("Slippage tolerance must be within 0..=100%")]
+    IllegalSlippageTolerance,
+    #[error("Pool spot price deviates from reference price more than allowed")]
+    OracleDeviationExceeded,
+    #[error("Swap is not allowed yet, previous swap of this account was too recent")]
+    SwapCooldown,
+    #[error("Actions batch with the same idempotency key was already executed")]
+    DuplicateRequest,
+    #[error("Position was opened before its initial amounts were recorded")]
+    PositionInitAmountsUnknown,
+    #[error("Fee level is out of range")]
+    IllegalFeeLevel,
+    #[error("Pair consists of identical tokens")]
+    IdenticalTokens,
+    #[error("Accounted balances aren't tracked yet")]
+    AccountedBalancesNotTracked,
+    #[error("Amount overflow")]
+    AmountOverflow,
+    #[error("Token was verified too recently to be swapped")]
+    TokenInGracePeriod,
+    /// Same as `PriceTickOutOfBounds`, but carries offending tick index
+    #[error("Tick {0} is either too large or too small")]
+    PriceTickOutOfBoundsAt(i32),
+    #[error("Account has too many registered tokens")]
+    TooManyTokens,
+    #[error("Price limit is already reached, swap can't move the price towards it")]
+    PriceLimitNotCrossable,
+    #[error("Swaps in pool are paused")]
+    PoolPaused,
+    #[error("Liquidity pool exists but has no liquidity")]
+    PoolHasNoLiquidity,
+    #[error("No route found between tokens")]
+    NoRouteFound,
+    #[error("Account is already registered")]
+    AccountAlreadyRegistered,
+    #[error("Malformed account export or unsupported export version")]
+    InvalidAccountExport,
+    #[error("Position liquidity is not below dust threshold")]
+    PositionNotDust,
+    #[error("Position owner isn't indexed yet")]
+    PositionOwnerNotIndexed,
+    /// Positions reserves and LP fees exceed pool's total reserves; indicates accounting anomaly
+    #[error("Protocol fee is negative")]
+    NegativeProtocolFee,
+    #[error("Too many actions in batch")]
+    BatchTooLarge,
+    #[error("Position can be closed normally and must not be force-removed")]
+    PositionNotStuck,
+    #[error("Swap crosses too many ticks")]
+    TooManyTickCrossings,
+    #[error("Swaps in this direction are not allowed in the pool")]
+    SwapDirectionNotAllowed,
+    #[error("Protocol fee withdrawal is timelocked")]
+    WithdrawalTimelockActive,
+    #[error("No protocol fee withdrawal was requested")]
+    NoPendingWithdrawal,
+    #[error("Requested window starts before the oldest price observation")]
+    ObservationTooOld,
+    /// Carries index of the hop in swap path, counting from zero
+    #[error("Swap at hop {0} of the path results in zero amount")]
+    IntermediateSwapRoundedToZero(usize),
+    #[error("Position range lies entirely on the other side of spot price")]
+    SingleSidedRangeConflict,
+    #[error("No gas cost was calibrated for the action")]
+    GasCostUnknown,
+    #[error("Payable API must be suspended")]
+    PayableAPINotSuspended,
+    /// Contract sends out more tokens than it has accounted for; indicates accounting anomaly
+    #[error("Accounted balance of token is exceeded")]
+    AccountedBalanceUnderflow,
+    #[error("Contract doesn't hold tokens of imported deposit")]
+    ImportedDepositNotFunded,
 }
 
 // Custom debug implementation to not use `derive`, because it blows up binary size
@@ -48,9 +48,63 @@ pub type BasisPoints = u16;
 pub type PositionId = u64;
 pub type FeeLevel = u8;
 pub type PoolsNumber = usize;
+/// Client-chosen key which identifies actions batch, to prevent its double execution
+pub type IdempotencyKey = [u8; 32];
 
 pub const BASIS_POINT_DIVISOR: BasisPoints = 10_000;
 
+/// Maximal number of input amounts accepted by a single `quote_ladder` call
+pub const MAX_QUOTE_LADDER_LEN: usize = 64;
+
+/// Maximal number of pools inspected by a single `get_top_pools_by_liquidity` call
+pub const MAX_TOP_POOLS_SCAN: usize = 512;
+
+/// Maximal number of pools inspected by a single `get_token_connections` call
+pub const MAX_TOKEN_CONNECTIONS_SCAN: usize = 512;
+
+/// Maximal number of pools inspected by a single `find_best_route` call
+pub const MAX_ROUTE_POOLS_SCAN: usize = 512;
+
+/// Maximal number of account's positions inspected by a single `get_positions_by_fees` call
+pub const MAX_FEE_RANKING_SCAN: usize = 128;
+
+/// Maximal number of account's positions processed by a single `withdraw_all_fees` call
+pub const MAX_FEE_HARVEST_BATCH: usize = 64;
+
+/// Maximal number of guard accounts listed by `get_admin_config`
+pub const MAX_ADMIN_CONFIG_GUARDS: usize = 64;
+
+/// Maximal number of swaps in a route discovered by `swap_auto_route`
+pub const MAX_ROUTE_HOPS: usize = 3;
+
+/// Version of account format produced by `export_account`; stored as first field of export
+pub const ACCOUNT_EXPORT_VERSION: u16 = 0;
+
+/// Default maximal number of actions in a single batch, see `Dex::set_max_actions_per_batch`
+pub const DEFAULT_MAX_ACTIONS_PER_BATCH: u16 = 32;
+
+/// Maximal number of ticks returned by single `get_all_ticks_liquidity` call
+pub const MAX_TICKS_LIQUIDITY_PAGE: usize = 256;
+
+/// Maximal number of positions registered per tick for range crossing notifications.
+/// Bounds both registry entry size and number of events emitted per crossed tick
+pub const MAX_NOTIFIED_POSITIONS_PER_TICK: usize = 16;
+
+/// Default number of price observations kept per pool, enough for TWAP since the latest swap
+pub const DEFAULT_OBSERVATION_CARDINALITY: u16 = 2;
+
+/// Maximal number of price observations kept per pool, see `Dex::set_pool_observation_cardinality`
+pub const MAX_OBSERVATION_CARDINALITY: u16 = 64;
+
+/// Number of most recent idempotency keys remembered per account
+pub const MAX_IDEMPOTENCY_KEYS: usize = 16;
+
+/// Event categories, used as bits of `event_verbosity` contract setting.
+/// Only events of enabled categories are emitted; other events are always emitted
+pub const EVENTS_SWAP: u8 = 1 << 0;
+pub const EVENTS_POOL_STATE: u8 = 1 << 1;
+pub const EVENTS_ALL: u8 = u8::MAX;
+
 pub const MIN_PROTOCOL_FEE_FRACTION: BasisPoints = 1;
 pub const MAX_PROTOCOL_FEE_FRACTION: BasisPoints = BASIS_POINT_DIVISOR / 2;
 
@@ -97,3 +151,11 @@ pub fn validate_protocol_fee_fraction(
         Err(ErrorKind::IllegalFee)
     }
 }
+
+pub fn validate_fee_level(fee_level: FeeLevel) -> Result<FeeLevel, ErrorKind> {
+    if fee_level < latest::NUM_FEE_LEVELS {
+        Ok(fee_level)
+    } else {
+        Err(ErrorKind::IllegalFeeLevel)
+    }
+}
@@ -2,7 +2,8 @@
 use crate::{dex, AmountUFP, FeeLiquidityUFP, GrossLiquidityUFP, Liquidity, NetLiquidityUFP};
 use dex::v0::RawFeeLevelsArray;
 use dex::{
-    Amount, Error, FeeLevel, Float, PoolId, PoolInfo, PositionId, PositionInfo, Result, Side, Types,
+    Amount, Error, FeeLevel, Float, PoolId, PoolInfo, PositionId, PositionInfo, Result, Side, Tick,
+    Types,
 };
 
 pub mod pool_impl;
@@ -19,6 +20,15 @@ use super::{BasisPoints, PositionClosedInfo, PositionInit, PositionOpenedInfo, S
 /// ```
 pub const SWAP_MAX_UNDERPAY: Float = Float::from_bits(0x3c_e0_00_00_00_00_00_00_u64);
 
+/// Largest value to which swap underpay tolerance may be relaxed by owner.
+/// ```
+/// assert_eq!(((1u64<<30) as f64).recip().to_bits(), 0x3e_10_00_00_00_00_00_00_u64);
+/// ```
+pub const SWAP_MAX_UNDERPAY_LIMIT: Float = Float::from_bits(0x3e_10_00_00_00_00_00_00_u64);
+
+/// Ticks crossed during a swap, in order of crossing, along with their fee levels
+pub type CrossedTicks = Vec<(FeeLevel, Tick)>;
+
 pub trait Pool<T: Types> {
     fn spot_sqrtprice(&self, side: Side, level: FeeLevel) -> Float;
 
@@ -44,12 +54,19 @@ pub trait Pool<T: Types> {
 
     fn withdraw_fee(&mut self, position_id: u64) -> Result<(Amount, Amount)>;
 
+    /// Protocol fee accrued in the pool, which can be withdrawn with `withdraw_protocol_fee`
+    fn protocol_fee(&self) -> Result<(Amount, Amount)>;
+
     fn withdraw_protocol_fee(&mut self) -> Result<(Amount, Amount)>;
 
     fn withdraw_fee_and_close_position(&mut self, position_id: u64) -> Result<PositionClosedInfo>;
 
     fn get_position_info(&self, pool_id: &PoolId, position_id: PositionId) -> Result<PositionInfo>;
 
+    fn get_position_il(&self, position_id: PositionId) -> Result<Float>;
+
+    fn get_position_breakeven_price(&self, position_id: PositionId) -> Result<Option<Float>>;
+
     fn open_position(
         &mut self,
         position: PositionInit,
@@ -64,42 +81,54 @@ pub trait Pool<T: Types> {
         swap_type: SwapKind,
         amount: Amount,
         protocol_fee_fraction: BasisPoints,
+        max_underpay: Float,
         price_limit: Option<Float>,
-    ) -> Result<(Amount, Amount, u32)>;
+    ) -> Result<(Amount, Amount, CrossedTicks)>;
 
+    /// Swap until effective sqrtprice on `side` reaches `max_eff_sqrtprice`
+    ///
+    /// If it is already at or beyond `max_eff_sqrtprice`, nothing is swapped
+    /// and zero amounts are returned.
+    ///
     /// Returns:
     ///  - `amount_in`
     ///  - `amount_out`
-    ///  - number of tick crossings
+    ///  - crossed ticks
     fn swap_to_price(
         &mut self,
         side: Side,
         max_amount_in: Amount,
         max_eff_sqrtprice: Float,
         protocol_fee_fraction: BasisPoints,
-    ) -> Result<(Amount, Amount, u32)>;
+        max_underpay: Float,
+    ) -> Result<(Amount, Amount, CrossedTicks)>;
 
     /// Returns:
     ///  - actually spent `amount_in` (may differ from `amount_in` argument)
     ///  - `amount_out`
-    ///  - number of tick crossings
+    ///  - crossed ticks
+    ///
+    /// `max_underpay` is the fraction of amount-in which may be underpaid by trader
+    /// due to numeric errors, see `SWAP_MAX_UNDERPAY`
     fn swap_exact_in(
         &mut self,
         side: Side,
         amount_in: Amount,
         protocol_fee_fraction: BasisPoints,
-    ) -> Result<(Amount, Amount, u32)>;
+        max_underpay: Float,
+    ) -> Result<(Amount, Amount, CrossedTicks)>;
 
     /// Returns:
     ///  - `amount_in`
     ///  - `amount_out`
-    ///  - number of tick crossings
+    ///  - crossed ticks
     fn swap_exact_out(
         &mut self,
         side: Side,
         amount_out: Amount,
         protocol_fee_fraction: BasisPoints,
-    ) -> Result<(Amount, Amount, u32)>;
+        max_underpay: Float,
+    ) -> Result<(Amount, Amount, CrossedTicks)>;
 
     #[cfg(feature = "smart-routing")]
     fn reserves_ratio(&self) -> Liquidity;
@@ -10,15 +10,15 @@ use dex::latest::{
 use dex::traits::{Map as _, OrderedMap as _};
 use dex::utils::{next_down, next_up, swap_if, MinSome as _, PairExt as _};
 use dex::{
-    traits, Amount, BasisPoints, EffTick, Error, ErrorKind, FeeLevel, PoolId, PoolInfo, PoolV0,
-    Position, PositionClosedInfo, PositionId, PositionInfo, PositionInit, PositionOpenedInfo,
-    PositionV0, Range, Result, Side, SwapKind, Tick, TickState, BASIS_POINT_DIVISOR,
-    MAX_NET_LIQUIDITY, MIN_NET_LIQUIDITY, PRECALCULATED_TICKS,
+    traits, Amount, BasisPoints, EffTick, Error, ErrorKind, FeeLevel, PoolId, PoolInfo, Position,
+    PositionClosedInfo, PositionId, PositionInfo, PositionInit, PositionLatest, PositionOpenedInfo,
+    Range, Result, Side, SwapKind, Tick, TickState, BASIS_POINT_DIVISOR, MAX_NET_LIQUIDITY,
+    MIN_NET_LIQUIDITY, PRECALCULATED_TICKS,
 };
 use num_traits::{CheckedAdd, CheckedMul, CheckedSub, Zero};
 #[cfg(feature = "smartlib")]
 use pool::{inc_ticks_counter, reset_ticks_counter};
-use pool::{Pool, PoolState, SWAP_MAX_UNDERPAY};
+use pool::{CrossedTicks, Pool, PoolState};
 use std::cmp::Ordering;
 use std::ops::Neg;
 
@@ -30,13 +30,13 @@ pub enum StepLimit {
     TickCrossing,
 }
 
-impl<T: traits::Types> PoolV0<T> {
+impl<T: traits::Types> dex::Pool<T> {
     pub fn get_all_ticks_liquidity_change(
         &self,
         fee_level: FeeLevel,
         side: Side,
     ) -> Vec<(Tick, Float)> {
-        let mut ticks = self.tick_states[fee_level]
+        let mut ticks = self.tick_states()[fee_level]
             .iter()
             .map(|(tick, tick_state)| {
                 let TickState::V0(ref tick_state) = *tick_state;
@@ -69,7 +69,7 @@ impl<T: traits::Types> PoolV0<T> {
 
         for _ in 0..number {
             let result =
-                self.tick_states[fee_level].inspect_above(&target_tick, |tick, tick_state| {
+                self.tick_states()[fee_level].inspect_above(&target_tick, |tick, tick_state| {
                     let TickState::V0(ref tick_state) = *tick_state;
                     (*tick, Float::from(tick_state.net_liquidity_change))
                 });
@@ -95,6 +95,91 @@ impl<T: traits::Types> PoolV0<T> {
     }
 }
 
+impl<T: traits::Types> dex::PoolLatest<T> {
+    /// Price observations in chronological order, see `PoolV1::observations`
+    fn chronological_observations(&self) -> impl Iterator<Item = (u64, i64)> + '_ {
+        let len = self.observations.len();
+        let latest = usize::from(self.observation_index);
+        (1..=len).map(move |i| self.observations[(latest + i) % len])
+    }
+
+    /// Index of tick nearest to current spot price, as seen from left side
+    fn spot_tick_index(&self) -> i32 {
+        Tick::nearest_to_spot_sqrtprice(self.spot_sqrtprice(Side::Left, 0)).index()
+    }
+
+    /// Record price observation at `now`, accumulating current spot tick since the latest one.
+    /// Must be called before any price-moving operation. At most one observation per timestamp is kept.
+    pub fn write_observation(&mut self, now: u64) -> Result<()> {
+        let observation = match self.observations.get(usize::from(self.observation_index)) {
+            None => (now, 0),
+            Some(&(latest_ts, _)) if latest_ts >= now => return Ok(()),
+            Some(_) => (now, self.tick_cumulative_at(now)?),
+        };
+
+        if self.observations.len() < usize::from(self.observation_cardinality) {
+            self.observations.push(observation);
+            self.observation_index = u16::try_from(self.observations.len() - 1)
+                .map_err(|_| error_here!(ErrorKind::InternalLogicError))?;
+        } else {
+            let index = (usize::from(self.observation_index) + 1) % self.observations.len();
+            self.observations[index] = observation;
+            // Fits, as `index` is less than `observations.len()`
+            #[allow(clippy::cast_possible_truncation)]
+            {
+                self.observation_index = index as u16;
+            }
+        }
+        Ok(())
+    }
+
+    /// Change maximal number of kept price observations, dropping the oldest ones if necessary
+    pub fn set_observation_cardinality(&mut self, cardinality: u16) {
+        let mut observations = self.chronological_observations().collect::<Vec<_>>();
+        let excess = observations.len().saturating_sub(usize::from(cardinality));
+        observations.drain(..excess);
+
+        // Fits, as length doesn't exceed `cardinality`
+        #[allow(clippy::cast_possible_truncation)]
+        {
+            self.observation_index = observations.len().saturating_sub(1) as u16;
+        }
+        self.observations = observations;
+        self.observation_cardinality = cardinality;
+    }
+
+    /// Time integral of spot tick at `at`, interpolated between bracketing observations,
+    /// or extrapolated with current spot tick after the latest one
+    pub fn tick_cumulative_at(&self, at: u64) -> Result<i64> {
+        let &(latest_ts, latest_cum) = self
+            .observations
+            .get(usize::from(self.observation_index))
+            .ok_or_else(|| error_here!(ErrorKind::ObservationTooOld))?;
+
+        let cumulative = if at >= latest_ts {
+            i128::from(latest_cum) + i128::from(self.spot_tick_index()) * i128::from(at - latest_ts)
+        } else {
+            let mut prev = None;
+            let (prev_ts, prev_cum, next_ts, next_cum) = self
+                .chronological_observations()
+                .find_map(|(ts, cum)| {
+                    if ts > at {
+                        Some(prev.map(|(prev_ts, prev_cum)| (prev_ts, prev_cum, ts, cum)))
+                    } else {
+                        prev = Some((ts, cum));
+                        None
+                    }
+                })
+                .flatten()
+                .ok_or_else(|| error_here!(ErrorKind::ObservationTooOld))?;
+            i128::from(prev_cum)
+                + (i128::from(next_cum) - i128::from(prev_cum)) * i128::from(at - prev_ts)
+                    / i128::from(next_ts - prev_ts)
+        };
+        i64::try_from(cumulative).map_err(|_| error_here!(ErrorKind::ConvOverflow))
+    }
+}
+
 impl<T: traits::Types, PS: PoolState<T>> Pool<T> for PS {
     fn spot_sqrtprice(&self, side: Side, level: FeeLevel) -> Float {
         self.eff_sqrtprice(level, side) / one_over_sqrt_one_minus_fee_rate(level)
@@ -119,6 +204,15 @@ impl<T: traits::Types, PS: PoolState<T>> Pool<T> for PS {
         let position_reserves = position_reserves_ufp
             .try_map_into::<Amount, _>()
             .map_err(|e| error_here!(e))?;
+        let (last_trade_ts, last_trade_sqrtprice) = self.last_trade();
+        let last_trade_sqrtprice = if last_trade_sqrtprice.is_zero() {
+            // Never traded, report current spot price instead
+            self.spot_sqrtprice(side, 0)
+        } else if side == Side::Right {
+            last_trade_sqrtprice.recip()
+        } else {
+            last_trade_sqrtprice
+        };
         Ok(PoolInfo {
             total_reserves,
             position_reserves,
@@ -129,6 +223,8 @@ impl<T: traits::Types, PS: PoolState<T>> Pool<T> for PS {
             liquidities: self.liquidities(),
             fee_rates: fee_rates_ticks(),
             fee_divisor: BASIS_POINT_DIVISOR,
+            last_trade_ts,
+            last_trade_sqrtprice,
         })
     }
 
@@ -161,24 +257,28 @@ impl<T: traits::Types, PS: PoolState<T>> Pool<T> for PS {
     }
 
     fn withdraw_fee(&mut self, position_id: u64) -> Result<(Amount, Amount)> {
-        let Position::V0(pos) = self
+        let pos = self
             .get_position(position_id)
-            .ok_or(error_here!(ErrorKind::PositionDoesNotExist))?;
+            .ok_or(error_here!(ErrorKind::PositionDoesNotExist))?
+            .into_latest();
 
         let acc_lp_fees_per_fee_liquidity =
             self.acc_range_lp_fees_per_fee_liquidity(pos.fee_level, pos.tick_bounds)?;
         let reward_ufp = self.position_reward_ufp(&pos, false)?;
 
+        // Reward is rounded down; the residual remains in the pool and is collected
+        // as a part of protocol fee.
         let reward = reward_ufp
-            .try_map_into::<Amount, _>()
+            .try_map(|reward_ufp| to_amount_floor(reward_ufp).map(|(reward, _residual)| reward))
             .map_err(|e| error_here!(e))?;
 
-        let Position::V0(mut pos) = self
+        let mut pos = self
             .get_position(position_id)
-            .ok_or(error_here!(ErrorKind::PositionDoesNotExist))?;
+            .ok_or(error_here!(ErrorKind::PositionDoesNotExist))?
+            .into_latest();
 
         pos.unwithdrawn_acc_lp_fees_per_fee_liquidity = acc_lp_fees_per_fee_liquidity;
-        self.insert_position(position_id, Position::V0(pos));
+        self.insert_position(position_id, Position::V1(pos));
 
         self.dec_total_reserves(reward)
             .map_err(|()| error_here!(ErrorKind::InternalLogicError))?;
@@ -189,23 +289,41 @@ impl<T: traits::Types, PS: PoolState<T>> Pool<T> for PS {
         Ok(reward)
     }
 
-    fn withdraw_protocol_fee(&mut self) -> Result<(Amount, Amount)> {
+    fn protocol_fee(&self) -> Result<(Amount, Amount)> {
         let total_reserves = self.total_reserves().map_into::<AmountUFP>();
         let sum_position_reserves = self.sum_position_reserves();
 
-        let payout_x = Amount::try_from(
-            (total_reserves.0 - sum_position_reserves.0 - self.acc_lp_fee(Side::Left)).floor(),
-        )
+        // Computed in signed domain, so accounting anomaly results in error instead of underflow
+        let protocol_fee = |total_reserve: AmountUFP, position_reserve: AmountUFP, side: Side| {
+            (AmountSFP::from(total_reserve)
+                - AmountSFP::from(position_reserve)
+                - AmountSFP::from(self.acc_lp_fee(side)))
+            .try_into_unsigned()
+            .map_err(|_| error_here!(ErrorKind::NegativeProtocolFee))
+        };
+        let (payout_x, _residual_x) = to_amount_floor(protocol_fee(
+            total_reserves.0,
+            sum_position_reserves.0,
+            Side::Left,
+        )?)
         .map_err(|e| error_here!(e))?;
-        let payout_y = Amount::try_from(
-            (total_reserves.1 - sum_position_reserves.1 - self.acc_lp_fee(Side::Right)).floor(),
-        )
+        let (payout_y, _residual_y) = to_amount_floor(protocol_fee(
+            total_reserves.1,
+            sum_position_reserves.1,
+            Side::Right,
+        )?)
         .map_err(|e| error_here!(e))?;
 
-        self.dec_total_reserves((payout_x, payout_y))
+        Ok((payout_x, payout_y))
+    }
+
+    fn withdraw_protocol_fee(&mut self) -> Result<(Amount, Amount)> {
+        let payout = self.protocol_fee()?;
+
+        self.dec_total_reserves(payout)
             .map_err(|()| error_here!(ErrorKind::InternalLogicError))?;
 
-        Ok((payout_x, payout_y))
+        Ok(payout)
     }
 
     /// Withdraw LP reward fees and close position.
@@ -213,9 +331,10 @@ impl<T: traits::Types, PS: PoolState<T>> Pool<T> for PS {
     fn withdraw_fee_and_close_position(&mut self, position_id: u64) -> Result<PositionClosedInfo> {
         let fees = self.withdraw_fee(position_id)?;
 
-        let Position::V0(pos) = self
+        let pos = self
             .get_position(position_id)
-            .ok_or(error_here!(ErrorKind::PositionDoesNotExist))?;
+            .ok_or(error_here!(ErrorKind::PositionDoesNotExist))?
+            .into_latest();
 
         let balance_ufp = pos.eval_position_balance_ufp(self.eff_sqrtprices_at(pos.fee_level))?;
 
@@ -281,9 +400,10 @@ impl<T: traits::Types, PS: PoolState<T>> Pool<T> for PS {
     }
 
     fn get_position_info(&self, pool_id: &PoolId, position_id: PositionId) -> Result<PositionInfo> {
-        let Position::V0(pos) = self
+        let pos = self
             .get_position(position_id)
-            .ok_or(error_here!(ErrorKind::PositionDoesNotExist))?;
+            .ok_or(error_here!(ErrorKind::PositionDoesNotExist))?
+            .into_latest();
         Ok(PositionInfo {
             tokens_ids: pool_id.as_refs().map(Clone::clone),
             fee_level: pos.fee_level,
@@ -296,6 +416,95 @@ impl<T: traits::Types, PS: PoolState<T>> Pool<T> for PS {
         })
     }
 
+    /// Impermanent loss of position, as a fraction of value of initially deposited tokens
+    /// if they were just held. Both values are evaluated at current spot price, fees are not included.
+    fn get_position_il(&self, position_id: PositionId) -> Result<Float> {
+        let pos = self
+            .get_position(position_id)
+            .ok_or(error_here!(ErrorKind::PositionDoesNotExist))?
+            .into_latest();
+        ensure_here!(
+            pos.init_amounts != (Amount::zero(), Amount::zero()),
+            ErrorKind::PositionInitAmountsUnknown
+        );
+        let balance = self.eval_position_balance(&pos)?;
+        // Price of right token expressed in left token
+        let price = self.spot_price(Side::Left, pos.fee_level);
+
+        let position_value = Float::from(balance.0) + Float::from(balance.1) * price;
+        let hold_value = Float::from(pos.init_amounts.0) + Float::from(pos.init_amounts.1) * price;
+        ensure_here!(hold_value.is_normal(), ErrorKind::InternalLogicError);
+
+        Ok(position_value / hold_value - Float::one())
+    }
+
+    /// Spot price, of right token expressed in left token, at which value of position
+    /// with fees accrued since creation equals value of initially deposited tokens if they were just held.
+    ///
+    /// Searched between price at position creation and position range bound towards which
+    /// price has moved since. Returns `None` if price hasn't moved, or if accrued fees
+    /// outweigh impermanent loss all the way up to the bound.
+    fn get_position_breakeven_price(&self, position_id: PositionId) -> Result<Option<Float>> {
+        /// Number of bisection steps, each halves search interval in logarithmic scale
+        const SEARCH_STEPS: usize = 64;
+
+        let pos = self
+            .get_position(position_id)
+            .ok_or(error_here!(ErrorKind::PositionDoesNotExist))?
+            .into_latest();
+        ensure_here!(
+            pos.init_amounts != (Amount::zero(), Amount::zero()),
+            ErrorKind::PositionInitAmountsUnknown
+        );
+        let fees = self.position_reward(&pos, true)?;
+        let one_over_sqrt_one_minus_fee_rate = one_over_sqrt_one_minus_fee_rate(pos.fee_level);
+
+        // Difference between position value, fees included, and hold value, at given left side effective sqrtprice.
+        // It's concave in price and non-negative at creation price, so has at most one root on each side of it
+        let value_gain = |eff_sqrtprice: Float| -> Result<Float> {
+            let eff_sqrtprices =
+                EffSqrtprices::from_value(eff_sqrtprice, Side::Left, pos.fee_level, None)
+                    .map_err(|e| error_here!(e))?;
+            let balance = pos.eval_position_balance_ufp(eff_sqrtprices)?;
+            let spot_sqrtprice = eff_sqrtprice / one_over_sqrt_one_minus_fee_rate;
+            let price = spot_sqrtprice * spot_sqrtprice;
+
+            let position_value = Float::from(balance.0)
+                + Float::from(fees.0)
+                + (Float::from(balance.1) + Float::from(fees.1)) * price;
+            let hold_value =
+                Float::from(pos.init_amounts.0) + Float::from(pos.init_amounts.1) * price;
+            Ok(position_value - hold_value)
+        };
+
+        // `init_sqrtprice` is right side spot sqrtprice
+        let init_eff_sqrtprice = pos.init_sqrtprice.recip() * one_over_sqrt_one_minus_fee_rate;
+        let eff_sqrtprice = self.eff_sqrtprice(pos.fee_level, Side::Left);
+        let bound_tick = match eff_sqrtprice.partial_cmp(&init_eff_sqrtprice) {
+            Some(Ordering::Greater) => pos.tick_bounds.1,
+            Some(Ordering::Less) => pos.tick_bounds.0,
+            _ => return Ok(None),
+        };
+        let bound_eff_sqrtprice = bound_tick.eff_sqrtprice(pos.fee_level, Side::Left);
+        if value_gain(bound_eff_sqrtprice)? > Float::zero() {
+            return Ok(None);
+        }
+
+        let (mut gain_eff_sqrtprice, mut loss_eff_sqrtprice) =
+            (init_eff_sqrtprice, bound_eff_sqrtprice);
+        for _ in 0..SEARCH_STEPS {
+            let mid_eff_sqrtprice = (gain_eff_sqrtprice * loss_eff_sqrtprice).sqrt();
+            if value_gain(mid_eff_sqrtprice)? >= Float::zero() {
+                gain_eff_sqrtprice = mid_eff_sqrtprice;
+            } else {
+                loss_eff_sqrtprice = mid_eff_sqrtprice;
+            }
+        }
+
+        let spot_sqrtprice = gain_eff_sqrtprice / one_over_sqrt_one_minus_fee_rate;
+        Ok(Some(spot_sqrtprice * spot_sqrtprice))
+    }
+
     /// Evaluate amounts of tokens to be deposited in the pool,
     /// and actually accunted net liquidity of the position.
     #[allow(clippy::too_many_lines)] // Refactor?
@@ -365,6 +574,21 @@ impl<T: traits::Types, PS: PoolState<T>> Pool<T> for PS {
             ErrorKind::PositionAlreadyExists
         );
 
+        let accounted_deposit_ufp = eval_position_balance_ufp(
+            accounted_net_liquidity,
+            tick_low,
+            tick_high,
+            self.eff_sqrtprices_at(fee_level),
+            fee_level,
+        )?;
+
+        // We can't charge LP with a non-integer amount of tokens, so we round the amounts up.
+        // The difference goes into the protocol fee, and is reported as `rounding_residual`.
+        let actual_deposit = (
+            Amount::try_from(accounted_deposit_ufp.0.ceil()).map_err(|e| error_here!(e))?,
+            Amount::try_from(accounted_deposit_ufp.1.ceil()).map_err(|e| error_here!(e))?,
+        );
+
         self.insert_position(
             position_id,
             factory.new_position(
@@ -373,6 +597,7 @@ impl<T: traits::Types, PS: PoolState<T>> Pool<T> for PS {
                 init_acc_lp_fees_per_fee_liquidity,
                 (tick_low, tick_high),
                 init_sqrtprice,
+                actual_deposit,
             )?,
         );
 
@@ -390,14 +615,6 @@ impl<T: traits::Types, PS: PoolState<T>> Pool<T> for PS {
             LiquiditySFP::from(accounted_net_liquidity).neg(),
         )?;
 
-        let accounted_deposit_ufp = eval_position_balance_ufp(
-            accounted_net_liquidity,
-            tick_low,
-            tick_high,
-            self.eff_sqrtprices_at(fee_level),
-            fee_level,
-        )?;
-
         self.inc_position_reserve_at(fee_level, Side::Left, accounted_deposit_ufp.0)
             .map_err(|()| error_here!(ErrorKind::DepositWouldOverflow))?;
         self.inc_position_reserve_at(fee_level, Side::Right, accounted_deposit_ufp.1)
@@ -412,13 +629,6 @@ impl<T: traits::Types, PS: PoolState<T>> Pool<T> for PS {
             self.inc_net_liquidity_at(fee_level, accounted_net_liquidity);
         }
 
-        // We can't charge LP with a non-integer amount of tokens, so we round the amounts up.
-        // The difference will effectively go into the protocol fee.
-        let actual_deposit = (
-            Amount::try_from(accounted_deposit_ufp.0.ceil()).map_err(|e| error_here!(e))?,
-            Amount::try_from(accounted_deposit_ufp.1.ceil()).map_err(|e| error_here!(e))?,
-        );
-
         // Accounted deposit must never exceed the actual one:
         ensure_here!(actual_deposit.0 <= left_max, ErrorKind::InternalLogicError);
         ensure_here!(actual_deposit.1 <= right_max, ErrorKind::InternalLogicError);
@@ -447,6 +657,10 @@ impl<T: traits::Types, PS: PoolState<T>> Pool<T> for PS {
 
         Ok(PositionOpenedInfo {
             deposited_amounts: actual_deposit,
+            rounding_residual: (
+                AmountUFP::from(actual_deposit.0) - accounted_deposit_ufp.0,
+                AmountUFP::from(actual_deposit.1) - accounted_deposit_ufp.1,
+            ),
             net_liquidity: accounted_net_liquidity,
             low_tick_liquidity_change: (tick_low, Float::from(low_tick_liquidity_change)),
             high_tick_liquidity_change: (tick_high, Float::from(high_tick_liquidity_change)),
@@ -458,8 +672,15 @@ impl<T: traits::Types, PS: PoolState<T>> Pool<T> for PS {
         side: Side,
         amount_in: Amount,
         protocol_fee_fraction: BasisPoints,
-    ) -> Result<(Amount, Amount, u32)> {
-        self.swap_exact_in_or_to_price_impl((side, amount_in, protocol_fee_fraction, None))
+        max_underpay: Float,
+    ) -> Result<(Amount, Amount, CrossedTicks)> {
+        self.swap_exact_in_or_to_price_impl((
+            side,
+            amount_in,
+            protocol_fee_fraction,
+            max_underpay,
+            None,
+        ))
     }
 
     fn swap_exact_out(
@@ -467,9 +688,10 @@ impl<T: traits::Types, PS: PoolState<T>> Pool<T> for PS {
         side: Side,
         amount_out: Amount,
         protocol_fee_fraction: BasisPoints,
-    ) -> Result<(Amount, Amount, u32)> {
+        max_underpay: Float,
+    ) -> Result<(Amount, Amount, CrossedTicks)> {
         ensure_here!(!amount_out.is_zero(), ErrorKind::InvalidParams);
-        ensure_here!(self.is_spot_price_set(), ErrorKind::InsufficientLiquidity);
+        ensure_here!(self.is_spot_price_set(), ErrorKind::PoolHasNoLiquidity);
 
         #[cfg(feature = "smartlib")]
         reset_ticks_counter();
@@ -479,7 +701,7 @@ impl<T: traits::Types, PS: PoolState<T>> Pool<T> for PS {
 
         let mut amount_in_float = Float::zero();
         let mut amount_out_sfp = AmountSFP::from(amount_out);
-        let mut num_tick_crossings = 0_u32;
+        let mut crossed_ticks = CrossedTicks::new();
 
         while amount_out_sfp > AmountSFP::zero() {
             let sum_gross_liquidities = Float::from(self.active_gross_liquidity());
@@ -489,13 +711,13 @@ impl<T: traits::Types, PS: PoolState<T>> Pool<T> for PS {
                 Float::from(amount_out_sfp),
                 sum_gross_liquidities,
             )?;
-            let (in_amount_change, out_amount_change, _limit_kind, num_tick_crossings_this_step) =
-                self.try_step_to_price(
+            let (in_amount_change, out_amount_change, _limit_kind, crossed_ticks_this_step) = self
+                .try_step_to_price(
                     new_eff_sqrtprice,
                     sum_gross_liquidities,
                     protocol_fee_fraction,
                 )?;
-            num_tick_crossings += num_tick_crossings_this_step;
+            crossed_ticks.extend(crossed_ticks_this_step);
 
             amount_in_float += in_amount_change;
             amount_out_sfp -= AmountSFP::from(out_amount_change);
@@ -514,7 +736,7 @@ impl<T: traits::Types, PS: PoolState<T>> Pool<T> for PS {
         ensure_here!(amount_in > Amount::zero(), ErrorKind::SwapAmountTooSmall);
         ensure_here!(
             amount_in_float / Float::from(amount_out)
-                >= (Float::one() - SWAP_MAX_UNDERPAY) * init_eff_sqrtprice * init_eff_sqrtprice,
+                >= (Float::one() - max_underpay) * init_eff_sqrtprice * init_eff_sqrtprice,
             ErrorKind::InternalLogicError
         );
 
@@ -522,7 +744,7 @@ impl<T: traits::Types, PS: PoolState<T>> Pool<T> for PS {
             .map_err(|()| error_here!(ErrorKind::DepositWouldOverflow))?;
         self.dec_total_reserve(side.opposite(), amount_out)
             .map_err(|()| error_here!(ErrorKind::InternalLogicError))?;
-        Ok((amount_in, amount_out, num_tick_crossings))
+        Ok((amount_in, amount_out, crossed_ticks))
     }
 
     fn swap(
@@ -531,15 +753,26 @@ impl<T: traits::Types, PS: PoolState<T>> Pool<T> for PS {
         swap_type: SwapKind,
         amount: Amount,
         protocol_fee_fraction: BasisPoints,
+        max_underpay: Float,
         price_limit: Option<Float>,
-    ) -> Result<(Amount, Amount, u32)> {
+    ) -> Result<(Amount, Amount, CrossedTicks)> {
         match swap_type {
-            SwapKind::ExactIn => self.swap_exact_in(side, amount, protocol_fee_fraction),
-            SwapKind::ExactOut => self.swap_exact_out(side, amount, protocol_fee_fraction),
+            SwapKind::ExactIn => {
+                self.swap_exact_in(side, amount, protocol_fee_fraction, max_underpay)
+            }
+            SwapKind::ExactOut => {
+                self.swap_exact_out(side, amount, protocol_fee_fraction, max_underpay)
+            }
             SwapKind::ToPrice => {
                 ensure_here!(price_limit.is_some(), ErrorKind::InvalidParams);
 
-                self.swap_to_price(side, amount, price_limit.unwrap(), protocol_fee_fraction)
+                self.swap_to_price(
+                    side,
+                    amount,
+                    price_limit.unwrap(),
+                    protocol_fee_fraction,
+                    max_underpay,
+                )
             }
         }
     }
@@ -550,14 +783,16 @@ impl<T: traits::Types, PS: PoolState<T>> Pool<T> for PS {
         max_amount_in: Amount,
         max_eff_sqrtprice: Float,
         protocol_fee_fraction: BasisPoints,
-    ) -> Result<(Amount, Amount, u32)> {
+        max_underpay: Float,
+    ) -> Result<(Amount, Amount, CrossedTicks)> {
         if max_eff_sqrtprice <= self.eff_sqrtprice(0, side) {
-            return Ok((Amount::zero(), Amount::zero(), 0));
+            return Ok((Amount::zero(), Amount::zero(), CrossedTicks::new()));
         }
         self.swap_exact_in_or_to_price_impl((
             side,
             max_amount_in,
             protocol_fee_fraction,
+            max_underpay,
             Some(max_eff_sqrtprice),
         ))
     }
@@ -621,7 +856,7 @@ pub(crate) trait PoolImpl<T: traits::Types>: PoolState<T> {
     }
 
     /// Amount of tokens locked in position
-    fn eval_position_balance(&self, pos: &PositionV0<T>) -> Result<(Amount, Amount), Error> {
+    fn eval_position_balance(&self, pos: &PositionLatest<T>) -> Result<(Amount, Amount), Error> {
         let balances_ufp = pos.eval_position_balance_ufp(self.eff_sqrtprices_at(pos.fee_level))?;
 
         let balance = balances_ufp
@@ -745,7 +980,9 @@ pub(crate) trait PoolImpl<T: traits::Types>: PoolState<T> {
         side: Side,
         fee_level: FeeLevel,
     ) -> Result<()> {
-        self.set_pivot(find_pivot(EffTick::default(), eff_sqrtprice).map_err(|e| error_here!(e))?);
+        // Pool may be re-initialized after being emptied, in which case stored pivot
+        // is likely still close to the new price, and is a better seed than default one
+        self.set_pivot(find_pivot(self.pivot(), eff_sqrtprice).map_err(|e| error_here!(e))?);
         for i_fee_level in 0..NUM_FEE_LEVELS {
             let pivot_opposite_this_level = EffTick::new(
                 self.pivot().index() - i32::from(fee_rate_ticks(fee_level))
@@ -1008,7 +1245,7 @@ pub(crate) trait PoolImpl<T: traits::Types>: PoolState<T> {
 
     fn position_reward_ufp(
         &self,
-        pos: &PositionV0<T>,
+        pos: &PositionLatest<T>,
         since_creation: bool,
     ) -> Result<(AmountUFP, AmountUFP)> {
         let pos_acc_lp_fees_per_fee_liquidity =
@@ -1050,7 +1287,7 @@ pub(crate) trait PoolImpl<T: traits::Types>: PoolState<T> {
 
     fn position_reward(
         &self,
-        pos: &PositionV0<T>,
+        pos: &PositionLatest<T>,
         since_creation: bool,
     ) -> Result<(Amount, Amount)> {
         self.position_reward_ufp(pos, since_creation)?
@@ -1059,14 +1296,14 @@ pub(crate) trait PoolImpl<T: traits::Types>: PoolState<T> {
     }
 
     /// Returns:
-    ///  - number of crossed ticks
+    ///  - crossed ticks, along with their fee levels
     fn cross_ticks(
         &mut self,
         crossed_eff_tick: EffTick,
         top_active_level: FeeLevel,
         side: Side,
-    ) -> Result<u32> {
-        let mut num_tick_crossings = 0_u32;
+    ) -> Result<CrossedTicks> {
+        let mut crossed_ticks = CrossedTicks::new();
         for level in 0..=top_active_level {
             if let Some(next_active_tick) = self.next_active_tick(level, side) {
                 if EffTick::from_tick(next_active_tick, level, side) == crossed_eff_tick {
@@ -1097,15 +1334,15 @@ pub(crate) trait PoolImpl<T: traits::Types>: PoolState<T> {
                             self.set_next_active_tick(level, Side::Right, new_next_active_tick);
                         }
                     };
-                    num_tick_crossings += 1;
+                    crossed_ticks.push((level, next_active_tick));
                 }
             }
         }
 
         #[cfg(feature = "smartlib")]
-        inc_ticks_counter(num_tick_crossings as usize);
+        inc_ticks_counter(crossed_ticks.len());
 
-        Ok(num_tick_crossings)
+        Ok(crossed_ticks)
     }
 
     fn update_prices_and_position_reserves(
@@ -1271,14 +1508,14 @@ pub(crate) trait PoolImpl<T: traits::Types>: PoolState<T> {
     ///  - `amount_in`,
     ///  - `amount_out`,
     ///  - `step_limit`,
-    ///  - number of tick crossings
+    ///  - crossed ticks
     #[allow(clippy::too_many_lines)]
     fn try_step_to_price(
         &mut self,
         mut new_eff_sqrtprice: Float,
         sum_gross_liquidities: Float,
         protocol_fee_fraction: BasisPoints,
-    ) -> Result<(Float, AmountUFP, StepLimit, u32)> {
+    ) -> Result<(Float, AmountUFP, StepLimit, CrossedTicks)> {
         ensure_here!(
             new_eff_sqrtprice >= self.active_eff_sqrtprice(),
             ErrorKind::InternalLogicError
@@ -1325,16 +1562,16 @@ pub(crate) trait PoolImpl<T: traits::Types>: PoolState<T> {
             let mut new_eff_sqrtprices = if limit_kind == StepLimit::TickCrossing {
                 let next_active_eff_tick =
                     nearest_active_eff_tick.ok_or(error_here!(ErrorKind::InternalLogicError))?;
-                EffSqrtprices::from_tick(
-                    &next_active_eff_tick
-                        .to_tick(level, self.active_side())
-                        .map_err(|e| match e {
-                            ErrorKind::PriceTickOutOfBounds => ErrorKind::InsufficientLiquidity,
-                            other => other,
-                        })
-                        .map_err(|e| error_here!(e))?,
-                    level,
-                )
+                // Crossing tick on one level may correspond to a tick beyond price scale
+                // on another one, which means the swap reaches the price extreme
+                let next_active_tick = next_active_eff_tick
+                    .to_tick(level, self.active_side())
+                    .map_err(|_| {
+                        error_here!(ErrorKind::PriceTickOutOfBoundsAt(
+                            next_active_eff_tick.tick_index(level, self.active_side())
+                        ))
+                    })?;
+                EffSqrtprices::from_tick(&next_active_tick, level)
             } else {
                 EffSqrtprices::from_value(
                     new_eff_sqrtprice,
@@ -1383,7 +1620,7 @@ pub(crate) trait PoolImpl<T: traits::Types>: PoolState<T> {
             self.inc_top_active_level();
         }
 
-        let num_tick_crossings = if limit_kind == StepLimit::TickCrossing {
+        let crossed_ticks = if limit_kind == StepLimit::TickCrossing {
             let next_active_eff_tick =
                 nearest_active_eff_tick.ok_or(error_here!(ErrorKind::InternalLogicError))?;
             self.cross_ticks(
@@ -1392,14 +1629,14 @@ pub(crate) trait PoolImpl<T: traits::Types>: PoolState<T> {
                 self.active_side(),
             )?
         } else {
-            0
+            CrossedTicks::new()
         };
 
         Ok((
             in_amount_change,
             out_amount_change,
             limit_kind,
-            num_tick_crossings,
+            crossed_ticks,
         ))
     }
 
@@ -1409,13 +1646,14 @@ pub(crate) trait PoolImpl<T: traits::Types>: PoolState<T> {
         // side: Side,
         // max_amount_in: Amount,
         // protocol_fee_fraction: BasisPoints,
+        // max_underpay: Float,
         // max_eff_sqrtprice: Option<Float>,
-        args: (Side, Amount, BasisPoints, Option<Float>),
-    ) -> Result<(Amount, Amount, u32)> {
-        let (side, max_amount_in, protocol_fee_fraction, max_eff_sqrtprice) = args;
+        args: (Side, Amount, BasisPoints, Float, Option<Float>),
+    ) -> Result<(Amount, Amount, CrossedTicks)> {
+        let (side, max_amount_in, protocol_fee_fraction, max_underpay, max_eff_sqrtprice) = args;
 
         ensure_here!(!max_amount_in.is_zero(), ErrorKind::InvalidParams);
-        ensure_here!(self.is_spot_price_set(), ErrorKind::InsufficientLiquidity);
+        ensure_here!(self.is_spot_price_set(), ErrorKind::PoolHasNoLiquidity);
 
         #[cfg(feature = "smartlib")]
         reset_ticks_counter();
@@ -1427,7 +1665,7 @@ pub(crate) trait PoolImpl<T: traits::Types>: PoolState<T> {
         let mut amount_in_float = Float::zero();
         let mut remaining_amount_in_float = max_amount_in_float;
         let mut amount_out_ufp = AmountUFP::zero();
-        let mut num_tick_crossings = 0_u32;
+        let mut crossed_ticks = CrossedTicks::new();
 
         loop {
             let sum_gross_liquidities = Float::from(self.active_gross_liquidity());
@@ -1442,17 +1680,17 @@ pub(crate) trait PoolImpl<T: traits::Types>: PoolState<T> {
                 new_eff_sqrtprice = new_eff_sqrtprice.min(eff_sqrtprice_limit);
             }
 
-            let (in_amount_change, out_amount_change, limit_kind, num_tick_crossings_this_step) =
-                self.try_step_to_price(
-                    new_eff_sqrtprice,
-                    sum_gross_liquidities,
-                    protocol_fee_fraction,
-                )?;
+            let (in_amount_change, out_amount_change, limit_kind, crossed_ticks_this_step) = self
+                .try_step_to_price(
+                new_eff_sqrtprice,
+                sum_gross_liquidities,
+                protocol_fee_fraction,
+            )?;
 
             remaining_amount_in_float -= in_amount_change;
             amount_in_float += in_amount_change;
             amount_out_ufp += out_amount_change;
-            num_tick_crossings += num_tick_crossings_this_step;
+            crossed_ticks.extend(crossed_ticks_this_step);
 
             if limit_kind == StepLimit::StepComplete {
                 break;
@@ -1462,7 +1700,7 @@ pub(crate) trait PoolImpl<T: traits::Types>: PoolState<T> {
         // Amount-in corresponding to the actual price shift may slightly exceed specified amount_in
         // due to numberic errors. The difference will be covered from the protocol fee.
         ensure_here!(
-            remaining_amount_in_float >= -max_amount_in_float * SWAP_MAX_UNDERPAY,
+            remaining_amount_in_float >= -max_amount_in_float * max_underpay,
             ErrorKind::InternalLogicError
         );
         // In exact-in swap we charge all provided amount_in
@@ -1496,7 +1734,7 @@ pub(crate) trait PoolImpl<T: traits::Types>: PoolState<T> {
         ensure_here!(
             amount_out.is_zero()
                 || amount_in_float / Float::from(amount_out)
-                    >= (Float::one() - SWAP_MAX_UNDERPAY) * init_eff_sqrtprice * init_eff_sqrtprice,
+                    >= (Float::one() - max_underpay) * init_eff_sqrtprice * init_eff_sqrtprice,
             ErrorKind::InternalLogicError
         );
 
@@ -1505,7 +1743,7 @@ pub(crate) trait PoolImpl<T: traits::Types>: PoolState<T> {
         self.dec_total_reserve(side.opposite(), amount_out)
             .map_err(|()| error_here!(ErrorKind::InternalLogicError))?;
 
-        Ok((amount_in, amount_out, num_tick_crossings))
+        Ok((amount_in, amount_out, crossed_ticks))
     }
 }
 
@@ -1546,6 +1784,26 @@ pub fn fee_rates_ticks() -> RawFeeLevelsArray<BasisPoints> {
     array_init(|level| fee_rate_ticks(as_fee_level(level)))
 }
 
+/// Convert `value` to `Amount`, rounding down
+///
+/// Returns the converted amount and the discarded fractional residual,
+/// i.e. `value == amount + residual`
+pub fn to_amount_floor(value: AmountUFP) -> Result<(Amount, AmountUFP), ErrorKind> {
+    let floor = value.floor();
+    let amount = Amount::try_from(floor)?;
+    Ok((amount, value - floor))
+}
+
+/// Convert `value` to `Amount`, rounding up
+///
+/// Returns the converted amount and the added fractional residual,
+/// i.e. `value == amount - residual`
+pub fn to_amount_ceil(value: AmountUFP) -> Result<(Amount, AmountUFP), ErrorKind> {
+    let ceil = value.ceil();
+    let amount = Amount::try_from(ceil)?;
+    Ok((amount, ceil - value))
+}
+
 /// Effective sqrtprice in the opposite swap direction
 ///
 /// Since the ticks are not precisely equidistant, we use pivot tick for the inversion.
@@ -1569,7 +1827,18 @@ pub fn eff_sqrtprice_opposite_side(
     Ok((pivot.eff_sqrtprice() / eff_sqrtprice) * pivot.opposite(fee_level).eff_sqrtprice())
 }
 
+/// Find tick which spot sqrtprice is close enough to `eff_sqrtprice` to be used as pivot,
+/// starting search from `init_pivot`. The closer the initial guess, the fewer steps are needed,
+/// so during swaps pivot stored in pool state should be used as a seed.
 pub fn find_pivot(init_pivot: EffTick, eff_sqrtprice: Float) -> Result<EffTick, ErrorKind> {
+    find_pivot_counting_steps(init_pivot, eff_sqrtprice).map(|(pivot, _)| pivot)
+}
+
+/// Same as `find_pivot`, but also returns number of steps taken to adjust pivot
+pub(crate) fn find_pivot_counting_steps(
+    init_pivot: EffTick,
+    eff_sqrtprice: Float,
+) -> Result<(EffTick, u32), ErrorKind> {
     /// Min and max "distance" between `pivot.spot_sqrtprice`() and `eff_sqrtprice`, expressed as factor.
     /// This "distance" must not exceed 1 tick in order to achive sufficiently accurate price inversion.
     /// Currently chosen values are +/- 0.625 ticks.
@@ -1605,6 +1874,7 @@ pub fn find_pivot(init_pivot: EffTick, eff_sqrtprice: Float) -> Result<EffTick,
     const MIN_APPROXIMATE_LOG: Float = Float::from_bits(0x3FEA_12FE_77BF_A405);
 
     let mut pivot = init_pivot;
+    let mut steps = 0;
     loop {
         // "distance" between eff_sqrtprice and pivot spot sqrtprice, expressed as factor.
         // `log(distance_factor)` is the actual distance between eff_sqrtprice
@@ -1660,9 +1930,10 @@ pub fn find_pivot(init_pivot: EffTick, eff_sqrtprice: Float) -> Result<EffTick,
         }
 
         pivot = pivot.shifted(step_ticks)?;
+        steps += 1;
     }
 
-    Ok(pivot)
+    Ok((pivot, steps))
 }
 
 /// Evaluate initial effective sqrtprice
@@ -1980,3 +2251,66 @@ fn conversion_one_minus_one_over_one_minus_fee_rate_to_fee_liquidity_ufp_never_f
         );
     }
 }
+
+#[cfg(test)]
+#[test]
+fn to_amount_floor_and_ceil_reconstruct_value() {
+    use rand::Rng;
+
+    let mut rng = rand::thread_rng();
+    for _ in 0..1000 {
+        let value = AmountUFP::from(u128::from(rng.gen::<u64>()))
+            / AmountUFP::from(rng.gen_range(1..1000_u128));
+
+        let (floor, floor_residual) = to_amount_floor(value).unwrap();
+        assert_eq!(AmountUFP::from(floor) + floor_residual, value);
+        assert!(floor_residual < AmountUFP::one());
+
+        let (ceil, ceil_residual) = to_amount_ceil(value).unwrap();
+        assert_eq!(AmountUFP::from(ceil) - ceil_residual, value);
+        assert!(ceil_residual < AmountUFP::one());
+
+        // Rounding up never under-collects, rounding down never over-pays
+        assert!(AmountUFP::from(ceil) >= value);
+        assert!(AmountUFP::from(floor) <= value);
+        assert!(ceil - floor <= Amount::from(1u128));
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn to_amount_floor_and_ceil_overflow() {
+    let value = AmountUFP::from(Amount::MAX) + AmountUFP::one() / AmountUFP::from(2_u128);
+
+    assert_eq!(to_amount_floor(value).unwrap().0, Amount::MAX);
+    assert!(matches!(
+        to_amount_ceil(value),
+        Err(ErrorKind::ConvOverflow)
+    ));
+}
+
+#[cfg(test)]
+#[test]
+fn find_pivot_seeded_from_previous_pivot_takes_fewer_steps() {
+    // Sequence of small swaps moving price within a narrow band far from default pivot
+    let eff_sqrtprices = (0..100)
+        .map(|i| Float::from(3.0) * Float::from(1.0 + 1e-5 * f64::from(i % 10)))
+        .collect::<Vec<_>>();
+
+    let mut steps_from_default = 0;
+    for &eff_sqrtprice in &eff_sqrtprices {
+        let (_, steps) = find_pivot_counting_steps(EffTick::default(), eff_sqrtprice).unwrap();
+        steps_from_default += steps;
+    }
+
+    let mut steps_from_cached = 0;
+    let mut pivot = EffTick::default();
+    for &eff_sqrtprice in &eff_sqrtprices {
+        let (new_pivot, steps) = find_pivot_counting_steps(pivot, eff_sqrtprice).unwrap();
+        pivot = new_pivot;
+        steps_from_cached += steps;
+    }
+
+    // Only the first search starts from scratch, subsequent ones are within a tick or two
+    assert!(steps_from_cached < steps_from_default / 2);
+}
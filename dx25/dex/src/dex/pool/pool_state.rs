@@ -7,7 +7,7 @@ use crate::dex::Position;
 use crate::dex::PositionId;
 use crate::dex::TickState;
 use crate::dex::TickStateV0;
-use crate::dex::{ErrorKind, PoolV0, Tick};
+use crate::dex::{ErrorKind, PoolV0, PoolV1, Tick};
 use crate::ensure_here;
 use crate::NetLiquiditySFP;
 use crate::{
@@ -46,6 +46,10 @@ pub(crate) trait PoolState<T: traits::Types> {
     /// Set pivot tick.
     fn set_pivot(&mut self, pivot: EffTick);
 
+    /// Block timestamp of the last swap, and square root of spot price, as seen from left side,
+    /// right after it. Price is zero if pool has never been traded.
+    fn last_trade(&self) -> (u64, Float);
+
     /// Sqrt of effective price at the given fee level, in the given swap direction.
     fn eff_sqrtprice(&self, level: FeeLevel, side: Side) -> Float;
 
@@ -216,90 +220,454 @@ pub(crate) trait PoolState<T: traits::Types> {
     fn remove_position(&mut self, position_id: PositionId);
 }
 
-impl<T: Types> PoolState<T> for crate::dex::PoolV0<T> {
+/// Evaluates `$body` with `$p` bound to reference to pool struct of any version
+macro_rules! with_pool {
+    ($pool:expr, |$p:ident| $body:expr) => {
+        match $pool {
+            crate::dex::Pool::V0($p) => $body,
+            crate::dex::Pool::V1($p) => $body,
+        }
+    };
+}
+
+/// Implements `PoolState` for a version of pool struct. All versions share fields used by it,
+/// except ones of the last trade, which are evaluated by `$last_trade` expression
+macro_rules! impl_pool_state {
+    ($pool:ident, |$last_trade_pool:ident| $last_trade:expr) => {
+        impl<T: Types> PoolState<T> for crate::dex::$pool<T> {
+            fn active_side(&self) -> Side {
+                self.active_side
+            }
+
+            fn set_active_side(&mut self, side: Side) {
+                self.active_side = side;
+            }
+
+            fn reset_top_active_level(&mut self) {
+                self.top_active_level = 0;
+            }
+
+            fn inc_top_active_level(&mut self) {
+                self.top_active_level += 1;
+            }
+
+            fn top_active_level(&self) -> FeeLevel {
+                if self.top_active_level >= NUM_FEE_LEVELS {
+                    unsafe { std::hint::unreachable_unchecked() }
+                }
+                self.top_active_level
+            }
+
+            fn eff_sqrtprice(&self, level: FeeLevel, side: Side) -> Float {
+                self.eff_sqrtprices[level].value(side)
+            }
+
+            fn eff_sqrtprices_at(&self, level: FeeLevel) -> EffSqrtprices {
+                self.eff_sqrtprices[level]
+            }
+
+            fn eff_sqrtprices(&self) -> RawFeeLevelsArray<EffSqrtprices> {
+                self.eff_sqrtprices.into()
+            }
+
+            fn set_eff_sqrtprices_at(&mut self, level: FeeLevel, eff_sqrtprices: EffSqrtprices) {
+                self.eff_sqrtprices[level] = eff_sqrtprices;
+            }
+
+            fn reset_eff_sqrtprices(&mut self) {
+                self.eff_sqrtprices = FeeLevelsArray::default();
+            }
+
+            fn pivot(&self) -> EffTick {
+                self.pivot
+            }
+
+            fn set_pivot(&mut self, pivot: EffTick) {
+                self.pivot = pivot;
+            }
+
+            fn last_trade(&self) -> (u64, Float) {
+                let $last_trade_pool = self;
+                $last_trade
+            }
+
+            fn total_reserves(&self) -> (Amount, Amount) {
+                self.total_reserves
+            }
+
+            fn inc_total_reserve(&mut self, side: Side, increment: Amount) -> Result<(), ()> {
+                let total_reserve = &mut self.total_reserves[side];
+                match total_reserve.checked_add(increment) {
+                    Some(new_total_reserve) => {
+                        *total_reserve = new_total_reserve;
+                        Ok(())
+                    }
+                    None => Err(()),
+                }
+            }
+
+            fn dec_total_reserve(&mut self, side: Side, decrement: Amount) -> Result<(), ()> {
+                let total_reserve = &mut self.total_reserves[side];
+                match total_reserve.checked_sub(decrement) {
+                    Some(new_total_reserve) => {
+                        *total_reserve = new_total_reserve;
+                        Ok(())
+                    }
+                    None => Err(()),
+                }
+            }
+
+            fn position_reserves(&self) -> RawFeeLevelsArray<(AmountUFP, AmountUFP)> {
+                self.position_reserves.into()
+            }
+
+            fn position_reserves_at(&self, level: FeeLevel) -> (AmountUFP, AmountUFP) {
+                self.position_reserves[level]
+            }
+
+            fn inc_position_reserve_at(
+                &mut self,
+                level: FeeLevel,
+                side: Side,
+                increment: AmountUFP,
+            ) -> Result<(), ()> {
+                let position_reserve = &mut self.position_reserves[level][side];
+                match position_reserve.checked_add(&increment) {
+                    Some(new_position_reserve) => {
+                        *position_reserve = new_position_reserve;
+                        Ok(())
+                    }
+                    None => Err(()),
+                }
+            }
+
+            fn dec_position_reserve_at(
+                &mut self,
+                level: FeeLevel,
+                side: Side,
+                decrement: AmountUFP,
+            ) -> Result<(), ()> {
+                let position_reserve = &mut self.position_reserves[level][side];
+                match position_reserve.checked_sub(&decrement) {
+                    Some(new_position_reserve) => {
+                        *position_reserve = new_position_reserve;
+                        Ok(())
+                    }
+                    None => Err(()),
+                }
+            }
+
+            fn net_liquidity_at(&self, level: FeeLevel) -> NetLiquidityUFP {
+                self.net_liquidities[level]
+            }
+
+            fn inc_net_liquidity_at(
+                &mut self,
+                level: FeeLevel,
+                net_liquidity_increment: NetLiquidityUFP,
+            ) {
+                self.net_liquidities[level] += net_liquidity_increment;
+            }
+
+            fn dec_net_liquidity_at(
+                &mut self,
+                level: FeeLevel,
+                net_liquidity_decrement: NetLiquidityUFP,
+            ) {
+                self.net_liquidities[level] -= net_liquidity_decrement;
+            }
+
+            fn next_active_tick(&self, level: FeeLevel, side: Side) -> Option<Tick> {
+                match side {
+                    Side::Left => self.next_active_ticks_left[level],
+                    Side::Right => self.next_active_ticks_right[level],
+                }
+            }
+
+            fn set_next_active_tick(&mut self, level: FeeLevel, side: Side, tick: Option<Tick>) {
+                match side {
+                    Side::Left => self.next_active_ticks_left[level] = tick,
+                    Side::Right => self.next_active_ticks_right[level] = tick,
+                }
+            }
+
+            fn acc_lp_fees(&self) -> (AmountUFP, AmountUFP) {
+                self.acc_lp_fee
+            }
+            fn acc_lp_fee(&self, side: Side) -> AmountUFP {
+                self.acc_lp_fee[side]
+            }
+
+            fn inc_acc_lp_fee(&mut self, side: Side, amount: AmountUFP) {
+                self.acc_lp_fee[side] += amount;
+            }
+
+            fn dec_acc_lp_fee(&mut self, side: Side, amount: AmountUFP) {
+                self.acc_lp_fee[side] -= amount;
+            }
+
+            fn acc_lp_fee_per_fee_liquidity_at(
+                &self,
+                level: FeeLevel,
+                side: Side,
+            ) -> LPFeePerFeeLiquidity {
+                self.acc_lp_fees_per_fee_liquidity[level][side]
+            }
+
+            fn acc_lp_fees_per_fee_liquidity_at(
+                &self,
+                level: FeeLevel,
+            ) -> (LPFeePerFeeLiquidity, LPFeePerFeeLiquidity) {
+                self.acc_lp_fees_per_fee_liquidity[level]
+            }
+
+            fn inc_acc_lp_fee_per_fee_liquidity(
+                &mut self,
+                side: Side,
+                top_active_level: FeeLevel,
+                lp_fee_per_fee_liquidity: LPFeePerFeeLiquidity,
+            ) {
+                self.acc_lp_fees_per_fee_liquidity[top_active_level][side] +=
+                    lp_fee_per_fee_liquidity;
+            }
+
+            fn contains_any_positions(&self) -> bool {
+                self.tick_states
+                    .iter()
+                    .any(|tick_states| tick_states.inspect_min(|_, _| ()).is_some())
+            }
+
+            fn find_next_active_tick_on_level(
+                &self,
+                begin_excluding: Tick,
+                fee_level: FeeLevel,
+                side: Side,
+            ) -> Option<Tick> {
+                match side {
+                    Side::Left => self.tick_states[fee_level]
+                        .inspect_above(&begin_excluding, |tick: &Tick, _| *tick),
+                    Side::Right => self.tick_states[fee_level]
+                        .inspect_below(&begin_excluding, |tick: &Tick, _| *tick),
+                }
+            }
+
+            /// `level` must be valid
+            /// Returns 0 if tick is not active
+            fn get_tick_acc_lp_fees_per_fee_liquidity(
+                &self,
+                level: FeeLevel,
+                tick: Tick,
+            ) -> (LPFeePerFeeLiquidity, LPFeePerFeeLiquidity) {
+                self.tick_states[level]
+                    .inspect(&tick, |TickState::V0(tick_state)| {
+                        tick_state.acc_lp_fees_per_fee_liquidity_outside
+                    })
+                    .unwrap_or_default()
+            }
+
+            fn flip_tick_acc_lp_fees_per_fee_liquidity_and_update_net_liquidity(
+                &mut self,
+                level: FeeLevel,
+                tick: Tick,
+                side: Side,
+            ) -> Result<()> {
+                let global_acc_lp_fees_per_fee_liquidity =
+                    self.acc_lp_fees_per_fee_liquidity(level);
+                let net_liquidity_change = self.tick_states[level]
+                    .update(&tick, |TickState::V0(tick_state)| {
+                        Self::flip_tick_acc_lp_fees_per_fee_liquidity(
+                            tick_state,
+                            global_acc_lp_fees_per_fee_liquidity,
+                        )
+                    })
+                    .ok_or(error_here!(ErrorKind::InternalTickNotFound))??;
+
+                let net_liquidity_change = match side {
+                    Side::Left => net_liquidity_change,
+                    Side::Right => net_liquidity_change.neg(),
+                };
+
+                if net_liquidity_change.non_negative {
+                    self.net_liquidities[level] += net_liquidity_change.value;
+                } else {
+                    self.net_liquidities[level] -= net_liquidity_change.value;
+                };
+                Ok(())
+            }
+
+            fn tick_add_liquidity(
+                &mut self,
+                factory: &mut dyn dex::ItemFactory<T>,
+                level: FeeLevel,
+                tick: Tick,
+                net_liquidity_change_increment: NetLiquiditySFP,
+            ) -> Result<NetLiquiditySFP> {
+                let mut existing_tick_state =
+                    self.tick_states[level].inspect(&tick, |tick_state| tick_state.clone());
+                let mut tick_state = if let Some(tick_state) = existing_tick_state {
+                    tick_state
+                } else {
+                    factory.new_default_tick()?
+                };
+                let new_net_liquidity_change = match tick_state {
+                    TickState::V0(ref mut tick_state) => {
+                        tick_state.net_liquidity_change += net_liquidity_change_increment;
+                        tick_state.reference_counter += 1;
+                        tick_state.net_liquidity_change
+                    }
+                };
+                self.tick_states[level].insert(tick, tick_state);
+                Ok(new_net_liquidity_change)
+            }
+
+            fn tick_remove_liquidity(
+                &mut self,
+                level: FeeLevel,
+                tick: Tick,
+                net_liquidity_change_decrement: NetLiquiditySFP,
+            ) -> Result<NetLiquiditySFP> {
+                let mut tick_state = self.tick_states[level]
+                    .inspect(&tick, |tick_state| tick_state.clone())
+                    .ok_or(error_here!(ErrorKind::InternalTickNotFound))?;
+                let (new_net_liquidity_change, tick_remains_active) = match tick_state {
+                    TickState::V0(ref mut tick_state) => {
+                        tick_state.net_liquidity_change -= net_liquidity_change_decrement;
+                        ensure_here!(
+                            tick_state.reference_counter > 0,
+                            ErrorKind::InternalLogicError
+                        );
+                        tick_state.reference_counter -= 1;
+                        (
+                            tick_state.net_liquidity_change,
+                            tick_state.reference_counter > 0,
+                        )
+                    }
+                };
+
+                if tick_remains_active {
+                    self.tick_states[level].insert(tick, tick_state);
+                } else {
+                    ensure_here!(
+                        new_net_liquidity_change.is_zero(),
+                        ErrorKind::InternalLogicError
+                    );
+
+                    self.tick_states[level].remove(&tick);
+
+                    if self.next_active_tick(level, Side::Left) == Some(tick) {
+                        self.set_next_active_tick(
+                            level,
+                            Side::Left,
+                            self.find_next_active_tick_on_level(tick, level, Side::Left),
+                        );
+                    }
+                    if self.next_active_tick(level, Side::Right) == Some(tick) {
+                        self.set_next_active_tick(
+                            level,
+                            Side::Right,
+                            self.find_next_active_tick_on_level(tick, level, Side::Right),
+                        );
+                    }
+                }
+
+                Ok(new_net_liquidity_change)
+            }
+
+            fn get_position(&self, position_id: PositionId) -> Option<Position<T>> {
+                self.positions
+                    .inspect(&position_id, |position| position.clone())
+            }
+
+            fn insert_position(&mut self, position_id: PositionId, position: Position<T>) {
+                self.positions.insert(position_id, position);
+            }
+
+            fn remove_position(&mut self, position_id: PositionId) {
+                self.positions.remove(&position_id);
+            }
+        }
+    };
+}
+
+impl_pool_state!(PoolV0, |_pool| (0, Float::zero()));
+impl_pool_state!(PoolV1, |pool| (
+    pool.last_trade_ts,
+    pool.last_trade_sqrtprice
+));
+
+/// Pool of any version. Reads are served by stored version,
+/// while any modification upgrades pool to the latest version first
+impl<T: Types> PoolState<T> for crate::dex::Pool<T> {
     fn active_side(&self) -> Side {
-        self.active_side
+        with_pool!(self, |pool| pool.active_side())
     }
 
     fn set_active_side(&mut self, side: Side) {
-        self.active_side = side;
+        self.latest().set_active_side(side);
     }
 
     fn reset_top_active_level(&mut self) {
-        self.top_active_level = 0;
+        self.latest().reset_top_active_level();
     }
 
     fn inc_top_active_level(&mut self) {
-        self.top_active_level += 1;
+        self.latest().inc_top_active_level();
     }
 
     fn top_active_level(&self) -> FeeLevel {
-        if self.top_active_level >= NUM_FEE_LEVELS {
-            unsafe { std::hint::unreachable_unchecked() }
-        }
-        self.top_active_level
+        with_pool!(self, |pool| pool.top_active_level())
     }
 
     fn eff_sqrtprice(&self, level: FeeLevel, side: Side) -> Float {
-        self.eff_sqrtprices[level].value(side)
+        with_pool!(self, |pool| pool.eff_sqrtprice(level, side))
     }
 
     fn eff_sqrtprices_at(&self, level: FeeLevel) -> EffSqrtprices {
-        self.eff_sqrtprices[level]
+        with_pool!(self, |pool| pool.eff_sqrtprices_at(level))
     }
 
     fn eff_sqrtprices(&self) -> RawFeeLevelsArray<EffSqrtprices> {
-        self.eff_sqrtprices.into()
+        with_pool!(self, |pool| pool.eff_sqrtprices())
     }
 
     fn set_eff_sqrtprices_at(&mut self, level: FeeLevel, eff_sqrtprices: EffSqrtprices) {
-        self.eff_sqrtprices[level] = eff_sqrtprices;
+        self.latest().set_eff_sqrtprices_at(level, eff_sqrtprices);
     }
 
     fn reset_eff_sqrtprices(&mut self) {
-        self.eff_sqrtprices = FeeLevelsArray::default();
+        self.latest().reset_eff_sqrtprices();
     }
 
     fn pivot(&self) -> EffTick {
-        self.pivot
+        with_pool!(self, |pool| pool.pivot())
     }
 
     fn set_pivot(&mut self, pivot: EffTick) {
-        self.pivot = pivot;
+        self.latest().set_pivot(pivot);
+    }
+
+    fn last_trade(&self) -> (u64, Float) {
+        with_pool!(self, |pool| pool.last_trade())
     }
 
     fn total_reserves(&self) -> (Amount, Amount) {
-        self.total_reserves
+        with_pool!(self, |pool| pool.total_reserves())
     }
 
     fn inc_total_reserve(&mut self, side: Side, increment: Amount) -> Result<(), ()> {
-        let total_reserve = &mut self.total_reserves[side];
-        match total_reserve.checked_add(increment) {
-            Some(new_total_reserve) => {
-                *total_reserve = new_total_reserve;
-                Ok(())
-            }
-            None => Err(()),
-        }
+        self.latest().inc_total_reserve(side, increment)
     }
 
     fn dec_total_reserve(&mut self, side: Side, decrement: Amount) -> Result<(), ()> {
-        let total_reserve = &mut self.total_reserves[side];
-        match total_reserve.checked_sub(decrement) {
-            Some(new_total_reserve) => {
-                *total_reserve = new_total_reserve;
-                Ok(())
-            }
-            None => Err(()),
-        }
+        self.latest().dec_total_reserve(side, decrement)
     }
 
     fn position_reserves(&self) -> RawFeeLevelsArray<(AmountUFP, AmountUFP)> {
-        self.position_reserves.into()
+        with_pool!(self, |pool| pool.position_reserves())
     }
 
     fn position_reserves_at(&self, level: FeeLevel) -> (AmountUFP, AmountUFP) {
-        self.position_reserves[level]
+        with_pool!(self, |pool| pool.position_reserves_at(level))
     }
 
     fn inc_position_reserve_at(
@@ -308,14 +676,8 @@ impl<T: Types> PoolState<T> for crate::dex::PoolV0<T> {
         side: Side,
         increment: AmountUFP,
     ) -> Result<(), ()> {
-        let position_reserve = &mut self.position_reserves[level][side];
-        match position_reserve.checked_add(&increment) {
-            Some(new_position_reserve) => {
-                *position_reserve = new_position_reserve;
-                Ok(())
-            }
-            None => Err(()),
-        }
+        self.latest()
+            .inc_position_reserve_at(level, side, increment)
     }
 
     fn dec_position_reserve_at(
@@ -324,66 +686,58 @@ impl<T: Types> PoolState<T> for crate::dex::PoolV0<T> {
         side: Side,
         decrement: AmountUFP,
     ) -> Result<(), ()> {
-        let position_reserve = &mut self.position_reserves[level][side];
-        match position_reserve.checked_sub(&decrement) {
-            Some(new_position_reserve) => {
-                *position_reserve = new_position_reserve;
-                Ok(())
-            }
-            None => Err(()),
-        }
+        self.latest()
+            .dec_position_reserve_at(level, side, decrement)
     }
 
     fn net_liquidity_at(&self, level: FeeLevel) -> NetLiquidityUFP {
-        self.net_liquidities[level]
+        with_pool!(self, |pool| pool.net_liquidity_at(level))
     }
 
     fn inc_net_liquidity_at(&mut self, level: FeeLevel, net_liquidity_increment: NetLiquidityUFP) {
-        self.net_liquidities[level] += net_liquidity_increment;
+        self.latest()
+            .inc_net_liquidity_at(level, net_liquidity_increment);
     }
 
     fn dec_net_liquidity_at(&mut self, level: FeeLevel, net_liquidity_decrement: NetLiquidityUFP) {
-        self.net_liquidities[level] -= net_liquidity_decrement;
+        self.latest()
+            .dec_net_liquidity_at(level, net_liquidity_decrement);
     }
 
     fn next_active_tick(&self, level: FeeLevel, side: Side) -> Option<Tick> {
-        match side {
-            Side::Left => self.next_active_ticks_left[level],
-            Side::Right => self.next_active_ticks_right[level],
-        }
+        with_pool!(self, |pool| pool.next_active_tick(level, side))
     }
 
     fn set_next_active_tick(&mut self, level: FeeLevel, side: Side, tick: Option<Tick>) {
-        match side {
-            Side::Left => self.next_active_ticks_left[level] = tick,
-            Side::Right => self.next_active_ticks_right[level] = tick,
-        }
+        self.latest().set_next_active_tick(level, side, tick);
     }
 
     fn acc_lp_fees(&self) -> (AmountUFP, AmountUFP) {
-        self.acc_lp_fee
+        with_pool!(self, |pool| pool.acc_lp_fees())
     }
+
     fn acc_lp_fee(&self, side: Side) -> AmountUFP {
-        self.acc_lp_fee[side]
+        with_pool!(self, |pool| pool.acc_lp_fee(side))
     }
 
     fn inc_acc_lp_fee(&mut self, side: Side, amount: AmountUFP) {
-        self.acc_lp_fee[side] += amount;
+        self.latest().inc_acc_lp_fee(side, amount);
     }
 
     fn dec_acc_lp_fee(&mut self, side: Side, amount: AmountUFP) {
-        self.acc_lp_fee[side] -= amount;
+        self.latest().dec_acc_lp_fee(side, amount);
     }
 
     fn acc_lp_fee_per_fee_liquidity_at(&self, level: FeeLevel, side: Side) -> LPFeePerFeeLiquidity {
-        self.acc_lp_fees_per_fee_liquidity[level][side]
+        with_pool!(self, |pool| pool
+            .acc_lp_fee_per_fee_liquidity_at(level, side))
     }
 
     fn acc_lp_fees_per_fee_liquidity_at(
         &self,
         level: FeeLevel,
     ) -> (LPFeePerFeeLiquidity, LPFeePerFeeLiquidity) {
-        self.acc_lp_fees_per_fee_liquidity[level]
+        with_pool!(self, |pool| pool.acc_lp_fees_per_fee_liquidity_at(level))
     }
 
     fn inc_acc_lp_fee_per_fee_liquidity(
@@ -392,13 +746,15 @@ impl<T: Types> PoolState<T> for crate::dex::PoolV0<T> {
         top_active_level: FeeLevel,
         lp_fee_per_fee_liquidity: LPFeePerFeeLiquidity,
     ) {
-        self.acc_lp_fees_per_fee_liquidity[top_active_level][side] += lp_fee_per_fee_liquidity;
+        self.latest().inc_acc_lp_fee_per_fee_liquidity(
+            side,
+            top_active_level,
+            lp_fee_per_fee_liquidity,
+        );
     }
 
     fn contains_any_positions(&self) -> bool {
-        self.tick_states
-            .iter()
-            .any(|tick_states| tick_states.inspect_min(|_, _| ()).is_some())
+        with_pool!(self, |pool| pool.contains_any_positions())
     }
 
     fn find_next_active_tick_on_level(
@@ -407,28 +763,20 @@ impl<T: Types> PoolState<T> for crate::dex::PoolV0<T> {
         fee_level: FeeLevel,
         side: Side,
     ) -> Option<Tick> {
-        match side {
-            Side::Left => {
-                self.tick_states[fee_level].inspect_above(&begin_excluding, |tick: &Tick, _| *tick)
-            }
-            Side::Right => {
-                self.tick_states[fee_level].inspect_below(&begin_excluding, |tick: &Tick, _| *tick)
-            }
-        }
+        with_pool!(self, |pool| pool.find_next_active_tick_on_level(
+            begin_excluding,
+            fee_level,
+            side
+        ))
     }
 
-    /// `level` must be valid
-    /// Returns 0 if tick is not active
     fn get_tick_acc_lp_fees_per_fee_liquidity(
         &self,
         level: FeeLevel,
         tick: Tick,
     ) -> (LPFeePerFeeLiquidity, LPFeePerFeeLiquidity) {
-        self.tick_states[level]
-            .inspect(&tick, |TickState::V0(tick_state)| {
-                tick_state.acc_lp_fees_per_fee_liquidity_outside
-            })
-            .unwrap_or_default()
+        with_pool!(self, |pool| pool
+            .get_tick_acc_lp_fees_per_fee_liquidity(level, tick))
     }
 
     fn flip_tick_acc_lp_fees_per_fee_liquidity_and_update_net_liquidity(
@@ -437,27 +785,8 @@ impl<T: Types> PoolState<T> for crate::dex::PoolV0<T> {
         tick: Tick,
         side: Side,
     ) -> Result<()> {
-        let global_acc_lp_fees_per_fee_liquidity = self.acc_lp_fees_per_fee_liquidity(level);
-        let net_liquidity_change = self.tick_states[level]
-            .update(&tick, |TickState::V0(tick_state)| {
-                Self::flip_tick_acc_lp_fees_per_fee_liquidity(
-                    tick_state,
-                    global_acc_lp_fees_per_fee_liquidity,
-                )
-            })
-            .ok_or(error_here!(ErrorKind::InternalTickNotFound))??;
-
-        let net_liquidity_change = match side {
-            Side::Left => net_liquidity_change,
-            Side::Right => net_liquidity_change.neg(),
-        };
-
-        if net_liquidity_change.non_negative {
-            self.net_liquidities[level] += net_liquidity_change.value;
-        } else {
-            self.net_liquidities[level] -= net_liquidity_change.value;
-        };
-        Ok(())
+        self.latest()
+            .flip_tick_acc_lp_fees_per_fee_liquidity_and_update_net_liquidity(level, tick, side)
     }
 
     fn tick_add_liquidity(
@@ -465,89 +794,31 @@ impl<T: Types> PoolState<T> for crate::dex::PoolV0<T> {
         factory: &mut dyn dex::ItemFactory<T>,
         level: FeeLevel,
         tick: Tick,
-        net_liquidity_change_increment: NetLiquiditySFP,
+        net_liquidity_change: NetLiquiditySFP,
     ) -> Result<NetLiquiditySFP> {
-        let mut existing_tick_state =
-            self.tick_states[level].inspect(&tick, |tick_state| tick_state.clone());
-        let mut tick_state = if let Some(tick_state) = existing_tick_state {
-            tick_state
-        } else {
-            factory.new_default_tick()?
-        };
-        let new_net_liquidity_change = match tick_state {
-            TickState::V0(ref mut tick_state) => {
-                tick_state.net_liquidity_change += net_liquidity_change_increment;
-                tick_state.reference_counter += 1;
-                tick_state.net_liquidity_change
-            }
-        };
-        self.tick_states[level].insert(tick, tick_state);
-        Ok(new_net_liquidity_change)
+        self.latest()
+            .tick_add_liquidity(factory, level, tick, net_liquidity_change)
     }
 
     fn tick_remove_liquidity(
         &mut self,
         level: FeeLevel,
         tick: Tick,
-        net_liquidity_change_decrement: NetLiquiditySFP,
+        net_liquidity_change: NetLiquiditySFP,
     ) -> Result<NetLiquiditySFP> {
-        let mut tick_state = self.tick_states[level]
-            .inspect(&tick, |tick_state| tick_state.clone())
-            .ok_or(error_here!(ErrorKind::InternalTickNotFound))?;
-        let (new_net_liquidity_change, tick_remains_active) = match tick_state {
-            TickState::V0(ref mut tick_state) => {
-                tick_state.net_liquidity_change -= net_liquidity_change_decrement;
-                ensure_here!(
-                    tick_state.reference_counter > 0,
-                    ErrorKind::InternalLogicError
-                );
-                tick_state.reference_counter -= 1;
-                (
-                    tick_state.net_liquidity_change,
-                    tick_state.reference_counter > 0,
-                )
-            }
-        };
-
-        if tick_remains_active {
-            self.tick_states[level].insert(tick, tick_state);
-        } else {
-            ensure_here!(
-                new_net_liquidity_change.is_zero(),
-                ErrorKind::InternalLogicError
-            );
-
-            self.tick_states[level].remove(&tick);
-
-            if self.next_active_tick(level, Side::Left) == Some(tick) {
-                self.set_next_active_tick(
-                    level,
-                    Side::Left,
-                    self.find_next_active_tick_on_level(tick, level, Side::Left),
-                );
-            }
-            if self.next_active_tick(level, Side::Right) == Some(tick) {
-                self.set_next_active_tick(
-                    level,
-                    Side::Right,
-                    self.find_next_active_tick_on_level(tick, level, Side::Right),
-                );
-            }
-        }
-
-        Ok(new_net_liquidity_change)
+        self.latest()
+            .tick_remove_liquidity(level, tick, net_liquidity_change)
     }
 
     fn get_position(&self, position_id: PositionId) -> Option<Position<T>> {
-        self.positions
-            .inspect(&position_id, |position| position.clone())
+        with_pool!(self, |pool| pool.get_position(position_id))
     }
 
     fn insert_position(&mut self, position_id: PositionId, position: Position<T>) {
-        self.positions.insert(position_id, position);
+        self.latest().insert_position(position_id, position);
     }
 
     fn remove_position(&mut self, position_id: PositionId) {
-        self.positions.remove(&position_id);
+        self.latest().remove_position(position_id);
     }
 }
@@ -1,7 +1,17 @@
 use super::map_with_context::{MapContext, MapWithContext};
-use super::{v0, BasisPoints, ErrorKind, FeeLevel, Float, Side, Types};
-use crate::chain::{AccountId, Amount, AmountUFP, LPFeePerFeeLiquidity, Liquidity, LiquiditySFP};
+use super::pool::SWAP_MAX_UNDERPAY;
+use super::{
+    v0, BasisPoints, ErrorKind, FeeLevel, Float, IdempotencyKey, Side, Types,
+    DEFAULT_MAX_ACTIONS_PER_BATCH, DEFAULT_OBSERVATION_CARDINALITY, EVENTS_ALL,
+    MAX_PROTOCOL_FEE_FRACTION,
+};
+use crate::chain::{
+    AccountId, Amount, AmountUFP, LPFeePerFeeLiquidity, Liquidity, LiquiditySFP, TokenId,
+};
 use crate::dex::tick::{EffTick, Tick};
+use crate::dex::Map as _;
+use crate::error_here;
+use num_traits::Zero;
 use paste::paste;
 use std::marker::PhantomData;
 
@@ -396,6 +406,116 @@ versioned! {
             pub protocol_fee_fraction: BasisPoints,
 
             pub extra: T::ContractExtraV1,
+        },
+        2 => {
+            /// Account of the owner.
+            pub owner_id: AccountId,
+            /// Accounts that are allowed to set permitions for payable methods.
+            pub guards: T::AccountIdSet,
+            /// Payable API state
+            pub suspended: bool,
+            /// Map of all the pools.
+            pub pools: PoolsMap<T>,
+            /// Accounts registered, keeping track all the amounts deposited, storage and more.
+            pub accounts: AccountsMap<T>,
+            /// Set of allowed tokens by "owner".
+            pub verified_tokens: T::VerifiedTokensSet,
+            /// number of pools
+            pub pool_count: u64,
+            /// Counter for position
+            pub next_free_position_id: u64,
+            /// Map of position to token_pair, in pool of which it exists
+            pub position_to_pool_id: PositionToPoolMap<T>,
+            /// Fraction of the total fee, that will go to the DEX.
+            /// The rest of the fee will be distributed among the liquidity providers.
+            /// Specified in units of 1/FEE_DIVISOR. For example, if FEE_DIVISOR
+            /// is 10000, and one wants 13% of the total fee to go to the DEX, one must set
+            /// protocol_fee_fraction = 0.13*10000 = 1300. In such case, if a swap is performed
+            /// on a level with e.g. 0.2% total fee rate, and the total amount paid by the
+            /// trader is e.g. 100000 tokens, then the total charged fee will be 2000 tokens,
+            /// out of which 260 tokens will go to the DEX, and the rest 1740 tokens
+            /// will be distributed among the LPs.
+            pub protocol_fee_fraction: BasisPoints,
+
+            pub extra: T::ContractExtraV1,
+
+            /// Account allowed to push reference prices, see `reference_prices`
+            pub reference_price_setter: Option<AccountId>,
+            /// Maximal allowed deviation of pool spot price from its reference price,
+            /// in basis points. Swaps in pools which deviate more are rejected.
+            /// `None` disables the check.
+            pub max_oracle_deviation_bp: Option<BasisPoints>,
+            /// Reference prices pushed by `reference_price_setter`, per pool.
+            /// Created on first use.
+            pub reference_prices: Option<T::ReferencePricesMap>,
+            /// Number of decimals of tokens, as registered by owner.
+            /// Used only to scale prices in views. Created on first use.
+            pub token_decimals: Option<T::TokenDecimalsMap>,
+            /// Minimal time between swaps of the same account, in seconds.
+            /// `None` disables the check.
+            pub swap_cooldown_secs: Option<u64>,
+            /// Accounts allowed to open positions on behalf of other accounts.
+            /// Created on first use.
+            pub operators: Option<T::AccountIdSet>,
+            /// Running totals of tokens accounted by contract, i.e. held in deposits and pools,
+            /// updated when tokens are deposited or sent out. `None` in contracts upgraded from
+            /// earlier versions until `init_accounted_balances` is called
+            pub accounted_balances: Option<T::AccountedBalancesMap>,
+            /// Time after token verification during which swaps in pairs with it
+            /// are rejected, in seconds. Zero disables the check.
+            pub verified_token_grace_secs: u64,
+            /// Block timestamps at which tokens were added to `verified_tokens`.
+            /// Created on first use.
+            pub token_verified_at: Option<T::TokenVerifiedAtMap>,
+            /// Fraction of amount-in which may be underpaid by trader in a swap due to numeric errors.
+            /// `None` means `SWAP_MAX_UNDERPAY` is used.
+            pub swap_max_underpay: Option<Float>,
+            /// Extra protocol fee fraction applied to swaps, set by guards during incidents.
+            /// Total fraction is capped by `MAX_PROTOCOL_FEE_FRACTION`
+            pub swap_surcharge_bp: BasisPoints,
+            /// Maximal number of distinct tokens single account may have registered.
+            /// `None` means unlimited.
+            pub max_tokens_per_account: Option<u32>,
+            /// Bitmask of event categories which are emitted, see `EVENTS_*` constants
+            pub event_verbosity: u8,
+            /// Pools in which swaps are paused by guards; positions can still be closed.
+            /// Created on first use.
+            pub paused_pools: Option<T::PoolIdSet>,
+            /// Flat fee charged when opening position, as token and amount.
+            /// Accrues to `collected_open_position_fees`.
+            pub open_position_fee: Option<(TokenId, Amount)>,
+            /// Positions with net liquidity below this threshold may be closed by anyone,
+            /// see `gc_dust_position`. Zero disables such closing.
+            pub dust_liquidity_threshold: Liquidity,
+            /// Amount of left pool token paid from `gc_reward_funds` to whoever closes dust position
+            pub gc_reward: Amount,
+            /// Owners of open positions, maintained on position opening and closing.
+            /// Created on first use; positions opened earlier are added by `index_position_owners`
+            pub position_owners: Option<T::PositionOwnersMap>,
+            /// Maximal number of actions in a single `execute_actions` batch
+            pub max_actions_per_batch: u16,
+            /// Registry of positions bounded by each tick, used to notify about
+            /// position range crossings. Created on first use.
+            pub tick_positions: Option<T::TickPositionsMap>,
+            /// Maximal number of ticks single swap may cross, larger swaps are reverted.
+            /// `None` means unlimited.
+            pub max_swap_tick_crossings: Option<u32>,
+            /// Delay between request and payout of protocol fee withdrawal, in seconds.
+            /// Zero means protocol fee is withdrawn immediately.
+            pub protocol_fee_withdrawal_delay_secs: u64,
+            /// Totals collected by paged `init_accounted_balances`,
+            /// moved to `accounted_balances` once all accounts and pools are counted
+            pub accounted_balances_init: Option<T::AccountedBalancesMap>,
+            /// Accounts already counted into `accounted_balances_init`
+            pub accounted_balances_init_accounts: Option<T::AccountIdSet>,
+            /// Pools already counted into `accounted_balances_init`
+            pub accounted_balances_init_pools: Option<T::PoolIdSet>,
+            /// Position opening fees collected and not yet withdrawn by owner,
+            /// indexed by token. Created on first use.
+            pub collected_open_position_fees: Option<T::AccountedBalancesMap>,
+            /// Balances set aside by owner to pay `gc_reward` from, indexed by token.
+            /// Created on first use.
+            pub gc_reward_funds: Option<T::AccountedBalancesMap>,
         }
     }
 }
@@ -411,6 +531,39 @@ pub struct ContractRef<'a, T: Types> {
     pub next_free_position_id: u64,
     pub position_to_pool_id: &'a PositionToPoolMap<T>,
     pub protocol_fee_fraction: BasisPoints,
+    pub reference_price_setter: Option<&'a AccountId>,
+    pub max_oracle_deviation_bp: Option<BasisPoints>,
+    pub reference_prices: Option<&'a T::ReferencePricesMap>,
+    pub token_decimals: Option<&'a T::TokenDecimalsMap>,
+    pub swap_cooldown_secs: Option<u64>,
+    pub operators: Option<&'a T::AccountIdSet>,
+    pub accounted_balances: Option<&'a T::AccountedBalancesMap>,
+    pub verified_token_grace_secs: u64,
+    pub token_verified_at: Option<&'a T::TokenVerifiedAtMap>,
+    /// Effective swap underpay tolerance, with default already applied
+    pub swap_max_underpay: Float,
+    pub swap_surcharge_bp: BasisPoints,
+    pub max_tokens_per_account: Option<u32>,
+    pub event_verbosity: u8,
+    pub paused_pools: Option<&'a T::PoolIdSet>,
+    pub open_position_fee: Option<&'a (TokenId, Amount)>,
+    pub dust_liquidity_threshold: Liquidity,
+    pub gc_reward: Amount,
+    pub position_owners: Option<&'a T::PositionOwnersMap>,
+    pub max_actions_per_batch: u16,
+    pub tick_positions: Option<&'a T::TickPositionsMap>,
+    pub max_swap_tick_crossings: Option<u32>,
+    pub protocol_fee_withdrawal_delay_secs: u64,
+}
+
+impl<'a, T: Types> ContractRef<'a, T> {
+    /// Protocol fee fraction applied to swaps: base fraction with surcharge added,
+    /// capped by `MAX_PROTOCOL_FEE_FRACTION`
+    pub fn swap_protocol_fee_fraction(&self) -> BasisPoints {
+        self.protocol_fee_fraction
+            .saturating_add(self.swap_surcharge_bp)
+            .min(MAX_PROTOCOL_FEE_FRACTION)
+    }
 }
 
 impl<T: Types> Contract<T> {
@@ -439,7 +592,7 @@ impl<T: Types> Contract<T> {
 
                 std::ptr::write(
                     self as *mut _,
-                    Contract::V1(ContractLatest {
+                    Contract::V2(ContractLatest {
                         owner_id,
                         guards,
                         suspended,
@@ -451,12 +604,101 @@ impl<T: Types> Contract<T> {
                         position_to_pool_id,
                         protocol_fee_fraction,
                         extra: T::ContractExtraV1::default(),
+                        reference_price_setter: None,
+                        max_oracle_deviation_bp: None,
+                        reference_prices: None,
+                        token_decimals: None,
+                        swap_cooldown_secs: None,
+                        operators: None,
+                        accounted_balances: None,
+                        verified_token_grace_secs: 0,
+                        token_verified_at: None,
+                        swap_max_underpay: None,
+                        swap_surcharge_bp: 0,
+                        max_tokens_per_account: None,
+                        event_verbosity: EVENTS_ALL,
+                        paused_pools: None,
+                        open_position_fee: None,
+                        dust_liquidity_threshold: Liquidity::zero(),
+                        gc_reward: Amount::zero(),
+                        position_owners: None,
+                        max_actions_per_batch: DEFAULT_MAX_ACTIONS_PER_BATCH,
+                        tick_positions: None,
+                        max_swap_tick_crossings: None,
+                        protocol_fee_withdrawal_delay_secs: 0,
+                        accounted_balances_init: None,
+                        accounted_balances_init_accounts: None,
+                        accounted_balances_init_pools: None,
+                        collected_open_position_fees: None,
+                        gc_reward_funds: None,
+                    }),
+                );
+
+                self.latest()
+            },
+            Contract::V1(ref mut contract) => unsafe {
+                // See above
+                let ContractV1 {
+                    owner_id,
+                    guards,
+                    suspended,
+                    pools,
+                    accounts,
+                    verified_tokens,
+                    pool_count,
+                    next_free_position_id,
+                    position_to_pool_id,
+                    protocol_fee_fraction,
+                    extra,
+                } = std::ptr::read(contract as *const _);
+
+                std::ptr::write(
+                    self as *mut _,
+                    Contract::V2(ContractLatest {
+                        owner_id,
+                        guards,
+                        suspended,
+                        pools,
+                        accounts,
+                        verified_tokens,
+                        pool_count,
+                        next_free_position_id,
+                        position_to_pool_id,
+                        protocol_fee_fraction,
+                        extra,
+                        reference_price_setter: None,
+                        max_oracle_deviation_bp: None,
+                        reference_prices: None,
+                        token_decimals: None,
+                        swap_cooldown_secs: None,
+                        operators: None,
+                        accounted_balances: None,
+                        verified_token_grace_secs: 0,
+                        token_verified_at: None,
+                        swap_max_underpay: None,
+                        swap_surcharge_bp: 0,
+                        max_tokens_per_account: None,
+                        event_verbosity: EVENTS_ALL,
+                        paused_pools: None,
+                        open_position_fee: None,
+                        dust_liquidity_threshold: Liquidity::zero(),
+                        gc_reward: Amount::zero(),
+                        position_owners: None,
+                        max_actions_per_batch: DEFAULT_MAX_ACTIONS_PER_BATCH,
+                        tick_positions: None,
+                        max_swap_tick_crossings: None,
+                        protocol_fee_withdrawal_delay_secs: 0,
+                        accounted_balances_init: None,
+                        accounted_balances_init_accounts: None,
+                        accounted_balances_init_pools: None,
+                        collected_open_position_fees: None,
+                        gc_reward_funds: None,
                     }),
                 );
 
                 self.latest()
             },
-            Contract::V1(ref mut contract) => contract,
+            Contract::V2(ref mut contract) => contract,
         }
     }
     /// Retrieves immutable view of contract root state, regardless of its version
@@ -473,6 +715,28 @@ impl<T: Types> Contract<T> {
                 next_free_position_id: contract.next_free_position_id,
                 position_to_pool_id: &contract.position_to_pool_id,
                 protocol_fee_fraction: contract.protocol_fee_fraction,
+                reference_price_setter: None,
+                max_oracle_deviation_bp: None,
+                reference_prices: None,
+                token_decimals: None,
+                swap_cooldown_secs: None,
+                operators: None,
+                accounted_balances: None,
+                verified_token_grace_secs: 0,
+                token_verified_at: None,
+                swap_max_underpay: SWAP_MAX_UNDERPAY,
+                swap_surcharge_bp: 0,
+                max_tokens_per_account: None,
+                event_verbosity: EVENTS_ALL,
+                paused_pools: None,
+                open_position_fee: None,
+                dust_liquidity_threshold: Liquidity::zero(),
+                gc_reward: Amount::zero(),
+                position_owners: None,
+                max_actions_per_batch: DEFAULT_MAX_ACTIONS_PER_BATCH,
+                tick_positions: None,
+                max_swap_tick_crossings: None,
+                protocol_fee_withdrawal_delay_secs: 0,
             },
             Contract::V1(ref contract) => ContractRef {
                 owner_id: &contract.owner_id,
@@ -485,11 +749,117 @@ impl<T: Types> Contract<T> {
                 next_free_position_id: contract.next_free_position_id,
                 position_to_pool_id: &contract.position_to_pool_id,
                 protocol_fee_fraction: contract.protocol_fee_fraction,
+                reference_price_setter: None,
+                max_oracle_deviation_bp: None,
+                reference_prices: None,
+                token_decimals: None,
+                swap_cooldown_secs: None,
+                operators: None,
+                accounted_balances: None,
+                verified_token_grace_secs: 0,
+                token_verified_at: None,
+                swap_max_underpay: SWAP_MAX_UNDERPAY,
+                swap_surcharge_bp: 0,
+                max_tokens_per_account: None,
+                event_verbosity: EVENTS_ALL,
+                paused_pools: None,
+                open_position_fee: None,
+                dust_liquidity_threshold: Liquidity::zero(),
+                gc_reward: Amount::zero(),
+                position_owners: None,
+                max_actions_per_batch: DEFAULT_MAX_ACTIONS_PER_BATCH,
+                tick_positions: None,
+                max_swap_tick_crossings: None,
+                protocol_fee_withdrawal_delay_secs: 0,
+            },
+            Contract::V2(ref contract) => ContractRef {
+                owner_id: &contract.owner_id,
+                guards: &contract.guards,
+                suspended: contract.suspended,
+                pools: &contract.pools,
+                accounts: &contract.accounts,
+                verified_tokens: &contract.verified_tokens,
+                pool_count: contract.pool_count,
+                next_free_position_id: contract.next_free_position_id,
+                position_to_pool_id: &contract.position_to_pool_id,
+                protocol_fee_fraction: contract.protocol_fee_fraction,
+                reference_price_setter: contract.reference_price_setter.as_ref(),
+                max_oracle_deviation_bp: contract.max_oracle_deviation_bp,
+                reference_prices: contract.reference_prices.as_ref(),
+                token_decimals: contract.token_decimals.as_ref(),
+                swap_cooldown_secs: contract.swap_cooldown_secs,
+                operators: contract.operators.as_ref(),
+                accounted_balances: contract.accounted_balances.as_ref(),
+                verified_token_grace_secs: contract.verified_token_grace_secs,
+                token_verified_at: contract.token_verified_at.as_ref(),
+                swap_max_underpay: contract.swap_max_underpay.unwrap_or(SWAP_MAX_UNDERPAY),
+                swap_surcharge_bp: contract.swap_surcharge_bp,
+                max_tokens_per_account: contract.max_tokens_per_account,
+                event_verbosity: contract.event_verbosity,
+                paused_pools: contract.paused_pools.as_ref(),
+                open_position_fee: contract.open_position_fee.as_ref(),
+                dust_liquidity_threshold: contract.dust_liquidity_threshold,
+                gc_reward: contract.gc_reward,
+                position_owners: contract.position_owners.as_ref(),
+                max_actions_per_batch: contract.max_actions_per_batch,
+                tick_positions: contract.tick_positions.as_ref(),
+                max_swap_tick_crossings: contract.max_swap_tick_crossings,
+                protocol_fee_withdrawal_delay_secs: contract.protocol_fee_withdrawal_delay_secs,
             },
         }
     }
 }
 
+impl<T: Types> ContractLatest<T> {
+    /// Add `amount` of token received by contract to its accounted balance.
+    /// Does nothing if accounted balances aren't tracked
+    pub fn inc_accounted_balance(
+        &mut self,
+        token_id: &TokenId,
+        amount: Amount,
+    ) -> super::Result<()> {
+        let Some(accounted_balances) = self.accounted_balances.as_mut() else {
+            return Ok(());
+        };
+        accounted_balances.update_or_insert(
+            token_id,
+            || Ok(Amount::zero()),
+            |balance, _| {
+                *balance = balance
+                    .checked_add(amount)
+                    .ok_or_else(|| error_here!(ErrorKind::DepositWouldOverflow))?;
+                Ok(())
+            },
+        )
+    }
+
+    /// Subtract `amount` of token sent out by contract from its accounted balance.
+    /// Does nothing if accounted balances aren't tracked
+    ///
+    /// Sent tokens are always taken out of some deposit, so exceeding accounted balance
+    /// indicates accounting anomaly and fails with `AccountedBalanceUnderflow`
+    pub fn dec_accounted_balance(
+        &mut self,
+        token_id: &TokenId,
+        amount: Amount,
+    ) -> super::Result<()> {
+        let Some(accounted_balances) = self.accounted_balances.as_mut() else {
+            return Ok(());
+        };
+        let updated = accounted_balances.update(token_id, |balance| {
+            *balance = balance
+                .checked_sub(amount)
+                .ok_or_else(|| error_here!(ErrorKind::AccountedBalanceUnderflow))?;
+            Ok(())
+        });
+        match updated {
+            Some(result) => result,
+            None if amount.is_zero() => Ok(()),
+            None => Err(error_here!(ErrorKind::AccountedBalanceUnderflow)),
+        }
+    }
+}
+
 map_with_ctxt!(AccountTokenBalancesMap, ErrorKind::TokenNotRegistered);
 
 versioned! {
@@ -503,6 +873,90 @@ versioned! {
             pub withdraw_tracker: T::AccountWithdrawTracker,
             /// Blockchain-specific extra information, may be `()`
             pub extra: T::AccountExtra,
+        },
+        1 => {
+            /// Amounts of various tokens deposited to this account
+            pub token_balances: AccountTokenBalancesMap<T>,
+            /// Positions which belong to current account
+            pub positions: T::AccountPositionsSet,
+            /// Tracks withdrawals which may be multistage or even asynchronous
+            pub withdraw_tracker: T::AccountWithdrawTracker,
+            /// Blockchain-specific extra information, may be `()`
+            pub extra: T::AccountExtra,
+            /// Block timestamp of last swap made by this account, in seconds
+            pub last_swap_ts: u64,
+            /// Idempotency keys of recently executed action batches, oldest first;
+            /// holds at most `MAX_IDEMPOTENCY_KEYS` entries
+            pub idempotency_keys: Vec<IdempotencyKey>,
+            /// If set, tokens which balance becomes zero due to withdrawal or swap
+            /// are unregistered automatically
+            pub auto_unregister_empty: bool,
+        }
+    }
+}
+
+pub struct AccountRef<'a, T: Types> {
+    pub token_balances: &'a AccountTokenBalancesMap<T>,
+    pub positions: &'a T::AccountPositionsSet,
+    pub withdraw_tracker: &'a T::AccountWithdrawTracker,
+    pub extra: &'a T::AccountExtra,
+    pub last_swap_ts: u64,
+    pub idempotency_keys: &'a [IdempotencyKey],
+    pub auto_unregister_empty: bool,
+}
+
+impl<T: Types> Account<T> {
+    /// Automatically upgrade Account to latest version and return reference
+    pub fn latest(&mut self) -> &mut AccountLatest<T> {
+        match self {
+            Account::V0(ref mut account) => unsafe {
+                // See `Contract::latest`
+                let AccountV0 {
+                    token_balances,
+                    positions,
+                    withdraw_tracker,
+                    extra,
+                } = std::ptr::read(account as *const _);
+
+                std::ptr::write(
+                    self as *mut _,
+                    Account::V1(AccountLatest {
+                        token_balances,
+                        positions,
+                        withdraw_tracker,
+                        extra,
+                        last_swap_ts: 0,
+                        idempotency_keys: Vec::new(),
+                        auto_unregister_empty: false,
+                    }),
+                );
+
+                self.latest()
+            },
+            Account::V1(ref mut account) => account,
+        }
+    }
+    /// Retrieves immutable view of account state, regardless of its version
+    pub fn as_ref(&self) -> AccountRef<'_, T> {
+        match self {
+            Account::V0(ref account) => AccountRef {
+                token_balances: &account.token_balances,
+                positions: &account.positions,
+                withdraw_tracker: &account.withdraw_tracker,
+                extra: &account.extra,
+                last_swap_ts: 0,
+                idempotency_keys: &[],
+                auto_unregister_empty: false,
+            },
+            Account::V1(ref account) => AccountRef {
+                token_balances: &account.token_balances,
+                positions: &account.positions,
+                withdraw_tracker: &account.withdraw_tracker,
+                extra: &account.extra,
+                last_swap_ts: account.last_swap_ts,
+                idempotency_keys: &account.idempotency_keys,
+                auto_unregister_empty: account.auto_unregister_empty,
+            },
         }
     }
 }
@@ -544,6 +998,141 @@ versioned! {
             /// effective sqrtprice in the opposite direction.
             /// See `eff_sqrtprice_opposite_side` for details.
             pub pivot: EffTick,
+        },
+        1 => {
+            /// Liquidity positions of this pool
+            pub positions: PoolPositionsMap<T>,
+            /// Tick states per fee level
+            pub tick_states: v0::FeeLevelsArray<TickStatesMap<T>>,
+            /// Total amounts of tokens, including the positions and collected fees (LP and protocol)
+            pub total_reserves: (Amount, Amount),
+            /// Amounts of tokens locked in positions.
+            pub position_reserves: v0::FeeLevelsArray<(AmountUFP, AmountUFP)>,
+            /// Total amount of LP fee reward to be paid out to all LPs (in case all pasitions are closed)
+            pub acc_lp_fee: (AmountUFP, AmountUFP),
+            /// Global sqrtprice shift accumulators per top-active-level and for each swap direction.
+            /// These are sums of price shifts, performed in swaps with top active level equal to
+            /// the index of the array. Hence, to get the total price shift on level `k`
+            /// one has to sum up the values from index k to NUM_FEE_LEVELS.
+            pub acc_lp_fees_per_fee_liquidity: v0::FeeLevelsArray<(LPFeePerFeeLiquidity, LPFeePerFeeLiquidity)>,
+            /// Effective price on each of the levels
+            pub eff_sqrtprices: v0::FeeLevelsArray<v0::EffSqrtprices>,
+            /// next active ticks for swaps in left direction
+            pub next_active_ticks_left: v0::FeeLevelsArray<Option<Tick>>,
+            /// next active ticks for swaps in right direction
+            pub next_active_ticks_right: v0::FeeLevelsArray<Option<Tick>>,
+            /// Current effective net liquidity. Equal to: liquidity * sqrt(1-fee_rate)
+            pub net_liquidities: v0::FeeLevelsArray<Liquidity>,
+            /// Current top active level
+            pub top_active_level: FeeLevel,
+            pub active_side: Side,
+            /// A tick which spot price is sufficiently close (less than 1 tick away) to the
+            /// current effective sqrtprice in the active direction. It is used to evaluate the
+            /// effective sqrtprice in the opposite direction.
+            /// See `eff_sqrtprice_opposite_side` for details.
+            pub pivot: EffTick,
+            /// Block timestamp of the last swap in this pool, in seconds
+            pub last_trade_ts: u64,
+            /// Square root of spot price, as seen from left side, right after the last swap.
+            /// Zero if pool has never been traded
+            pub last_trade_sqrtprice: Float,
+            /// Number of ticks crossed by the last swap in this pool, over all fee levels
+            pub last_swap_tick_crossings: u32,
+            /// If set, only swaps putting tokens into the pool from this side are allowed
+            pub allowed_swap_side: Option<Side>,
+            /// Protocol fee taken out of reserves by owner's withdrawal request, as
+            /// `(amounts, release_ts)`. Paid out to owner once block timestamp reaches `release_ts`
+            pub pending_protocol_fee: Option<((Amount, Amount), u64)>,
+            /// Total rounding residual of position deposits since pool creation, see
+            /// `PositionOpenedInfo::rounding_residual`. Part of protocol fee, tracked for reporting
+            pub acc_position_rounding_residual: (AmountUFP, AmountUFP),
+            /// Ring buffer of price observations `(timestamp, tick_cumulative)`, written before swaps.
+            /// `tick_cumulative` is time integral of spot tick, i.e. of logarithm of spot price
+            /// measured in ticks, as seen from left side
+            pub observations: Vec<(u64, i64)>,
+            /// Index of the latest entry in `observations`
+            pub observation_index: u16,
+            /// Maximal number of entries in `observations`
+            pub observation_cardinality: u16,
+        }
+    }
+}
+
+impl<T: Types> Pool<T> {
+    /// Automatically upgrade Pool to latest version and return reference
+    pub fn latest(&mut self) -> &mut PoolLatest<T> {
+        match self {
+            Pool::V0(ref mut pool) => unsafe {
+                // See `Contract::latest`
+                let PoolV0 {
+                    positions,
+                    tick_states,
+                    total_reserves,
+                    position_reserves,
+                    acc_lp_fee,
+                    acc_lp_fees_per_fee_liquidity,
+                    eff_sqrtprices,
+                    next_active_ticks_left,
+                    next_active_ticks_right,
+                    net_liquidities,
+                    top_active_level,
+                    active_side,
+                    pivot,
+                } = std::ptr::read(pool as *const _);
+
+                std::ptr::write(
+                    self as *mut _,
+                    Pool::V1(PoolLatest {
+                        positions,
+                        tick_states,
+                        total_reserves,
+                        position_reserves,
+                        acc_lp_fee,
+                        acc_lp_fees_per_fee_liquidity,
+                        eff_sqrtprices,
+                        next_active_ticks_left,
+                        next_active_ticks_right,
+                        net_liquidities,
+                        top_active_level,
+                        active_side,
+                        pivot,
+                        last_trade_ts: 0,
+                        last_trade_sqrtprice: Float::zero(),
+                        last_swap_tick_crossings: 0,
+                        allowed_swap_side: None,
+                        pending_protocol_fee: None,
+                        acc_position_rounding_residual: (AmountUFP::zero(), AmountUFP::zero()),
+                        observations: Vec::new(),
+                        observation_index: 0,
+                        observation_cardinality: DEFAULT_OBSERVATION_CARDINALITY,
+                    }),
+                );
+
+                self.latest()
+            },
+            Pool::V1(ref mut pool) => pool,
+        }
+    }
+    /// Retrieves latest version of pool, or `None` if pool wasn't upgraded yet.
+    /// Pool which wasn't upgraded has default values of all fields added after V0
+    pub fn as_latest(&self) -> Option<&PoolLatest<T>> {
+        match self {
+            Pool::V0(_) => None,
+            Pool::V1(ref pool) => Some(pool),
+        }
+    }
+    /// Liquidity positions of the pool, regardless of its version
+    pub fn positions(&self) -> &PoolPositionsMap<T> {
+        match self {
+            Pool::V0(ref pool) => &pool.positions,
+            Pool::V1(ref pool) => &pool.positions,
+        }
+    }
+    /// Tick states per fee level, regardless of pool version
+    pub fn tick_states(&self) -> &v0::FeeLevelsArray<TickStatesMap<T>> {
+        match self {
+            Pool::V0(ref pool) => &pool.tick_states,
+            Pool::V1(ref pool) => &pool.tick_states,
         }
     }
 }
@@ -565,6 +1154,24 @@ versioned! {
             pub tick_bounds: (Tick, Tick),
             /// Phantom data, to bind T and unify all state types declarations
             pub phantom_t: PhantomData<T>,
+        },
+        1 => {
+            /// Fee level index where the position is open
+            pub fee_level: FeeLevel,
+            /// Liquidity of the position
+            pub net_liquidity: Liquidity,
+            /// Accumulated effective sqrt price shifts in the pool by the moment when the position was created.
+            pub init_acc_lp_fees_per_fee_liquidity: (LPFeePerFeeLiquidity, LPFeePerFeeLiquidity),
+            /// Accumulated effective sqrt price shifts in the pool by the last time when the fees were withdrawn.
+            pub unwithdrawn_acc_lp_fees_per_fee_liquidity: (LPFeePerFeeLiquidity, LPFeePerFeeLiquidity),
+            /// Square root of price at the momemnt of position creation
+            pub init_sqrtprice: Float,
+            /// Amounts of tokens deposited at the moment of position creation
+            pub init_amounts: (Amount, Amount),
+            /// Concentrated liquidity bounds
+            pub tick_bounds: (Tick, Tick),
+            /// Phantom data, to bind T and unify all state types declarations
+            pub phantom_t: PhantomData<T>,
         }
     }
 }
@@ -593,6 +1200,45 @@ impl<T: Types> Clone for Position<T> {
                 tick_bounds: position.tick_bounds,
                 phantom_t: PhantomData,
             }),
+            Position::V1(position) => Position::V1(PositionV1 {
+                fee_level: position.fee_level,
+                net_liquidity: position.net_liquidity,
+                init_acc_lp_fees_per_fee_liquidity: position.init_acc_lp_fees_per_fee_liquidity,
+                unwithdrawn_acc_lp_fees_per_fee_liquidity: position
+                    .unwithdrawn_acc_lp_fees_per_fee_liquidity,
+                init_sqrtprice: position.init_sqrtprice,
+                init_amounts: position.init_amounts,
+                tick_bounds: position.tick_bounds,
+                phantom_t: PhantomData,
+            }),
+        }
+    }
+}
+
+impl<T: Types> Position<T> {
+    /// Upgrade Position to latest version. Positions created before `init_amounts`
+    /// were recorded get zero ones
+    pub fn into_latest(self) -> PositionLatest<T> {
+        match self {
+            Position::V0(PositionV0 {
+                fee_level,
+                net_liquidity,
+                init_acc_lp_fees_per_fee_liquidity,
+                unwithdrawn_acc_lp_fees_per_fee_liquidity,
+                init_sqrtprice,
+                tick_bounds,
+                phantom_t,
+            }) => PositionLatest {
+                fee_level,
+                net_liquidity,
+                init_acc_lp_fees_per_fee_liquidity,
+                unwithdrawn_acc_lp_fees_per_fee_liquidity,
+                init_sqrtprice,
+                init_amounts: (Amount::zero(), Amount::zero()),
+                tick_bounds,
+                phantom_t,
+            },
+            Position::V1(position) => position,
         }
     }
 }
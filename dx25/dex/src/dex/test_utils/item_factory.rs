@@ -66,4 +66,54 @@ impl dex::ItemFactory<Types> for ItemFactory {
     fn new_guards(&mut self) -> <Types as dex::Types>::AccountIdSet {
         self.new_map()
     }
+
+    fn new_reference_prices_map(&mut self) -> <Types as dex::Types>::ReferencePricesMap {
+        self.new_map()
+    }
+
+    fn new_token_decimals_map(&mut self) -> <Types as dex::Types>::TokenDecimalsMap {
+        self.new_map()
+    }
+
+    fn new_token_verified_at_map(&mut self) -> <Types as dex::Types>::TokenVerifiedAtMap {
+        self.new_map()
+    }
+
+    fn new_operators(&mut self) -> <Types as dex::Types>::AccountIdSet {
+        self.new_map()
+    }
+
+    fn new_accounted_balances_map(&mut self) -> <Types as dex::Types>::AccountedBalancesMap {
+        self.new_map()
+    }
+
+    fn new_paused_pools(&mut self) -> <Types as dex::Types>::PoolIdSet {
+        self.new_map()
+    }
+
+    fn new_position_owners_map(&mut self) -> <Types as dex::Types>::PositionOwnersMap {
+        self.new_map()
+    }
+
+    fn new_tick_positions_map(&mut self) -> <Types as dex::Types>::TickPositionsMap {
+        self.new_map()
+    }
+
+    fn new_accounted_balances_init_accounts(&mut self) -> <Types as dex::Types>::AccountIdSet {
+        self.new_map()
+    }
+
+    fn new_accounted_balances_init_pools(&mut self) -> <Types as dex::Types>::PoolIdSet {
+        self.new_map()
+    }
+
+    fn new_collected_open_position_fees_map(
+        &mut self,
+    ) -> <Types as dex::Types>::AccountedBalancesMap {
+        self.new_map()
+    }
+
+    fn new_gc_reward_funds_map(&mut self) -> <Types as dex::Types>::AccountedBalancesMap {
+        self.new_map()
+    }
 }
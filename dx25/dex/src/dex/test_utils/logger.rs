@@ -1,6 +1,6 @@
 use super::dex;
 use crate::chain::{AccountId, Amount, TokenId};
-use dex::{latest, BasisPoints, FeeLevel, PositionId, Tick};
+use dex::{latest, BasisPoints, FeeLevel, PositionId, Side, Tick};
 
 /// Enum with all event types written into blockchain.
 /// Intended for matching in tests, so stores all values directly.
@@ -62,18 +62,75 @@ pub enum Event {
     RemoveGuardAccounts {
         accounts: Vec<AccountId>,
     },
+    AddOperatorAccounts {
+        accounts: Vec<AccountId>,
+    },
+    RemoveOperatorAccounts {
+        accounts: Vec<AccountId>,
+    },
     SuspendPayableAPI {
         account: AccountId,
     },
     ResumePayableAPI {
         account: AccountId,
     },
+    SetProtocolFee {
+        old: BasisPoints,
+        new: BasisPoints,
+        account: AccountId,
+    },
+    SetSwapSurcharge {
+        old: BasisPoints,
+        new: BasisPoints,
+        account: AccountId,
+    },
+    PausePool {
+        pool: (TokenId, TokenId),
+        account: AccountId,
+    },
+    UnpausePool {
+        pool: (TokenId, TokenId),
+        account: AccountId,
+    },
+    OpenPositionFee {
+        user: AccountId,
+        position_id: PositionId,
+        token: TokenId,
+        amount: Amount,
+    },
+    ForceRemovePosition {
+        position_id: PositionId,
+        pool: (TokenId, TokenId),
+        owner: Option<AccountId>,
+        account: AccountId,
+    },
+    SweepUntracked {
+        recipient: AccountId,
+        token: TokenId,
+        amount: Amount,
+    },
+    PoolCreated {
+        pool: (TokenId, TokenId),
+        fee_level: u8,
+        initial_sqrtprice: f64,
+        account: AccountId,
+    },
     TickUpdate {
         pool: (TokenId, TokenId),
         fee_level: u8,
         tick: i32,
         liquidity_change: f64,
     },
+    TickCrossed {
+        pool: (TokenId, TokenId),
+        fee_level: u8,
+        tick: i32,
+        side: Side,
+    },
+    PositionRangeBoundaryCrossed {
+        position_id: PositionId,
+        entered: bool,
+    },
 }
 /// Mock event logger, with persistent and mutable parts
 pub struct Logger {
@@ -236,6 +293,18 @@ impl dex::Logger for Logger {
         });
     }
 
+    fn log_add_operator_accounts_event(&mut self, accounts: &[AccountId]) {
+        self.mutable.push(Event::AddOperatorAccounts {
+            accounts: accounts.to_vec(),
+        });
+    }
+
+    fn log_remove_operator_accounts_event(&mut self, accounts: &[AccountId]) {
+        self.mutable.push(Event::RemoveOperatorAccounts {
+            accounts: accounts.to_vec(),
+        });
+    }
+
     fn log_suspend_payable_api_event(&mut self, account: &AccountId) {
         self.mutable.push(Event::SuspendPayableAPI {
             account: account.clone(),
@@ -248,6 +317,94 @@ impl dex::Logger for Logger {
         });
     }
 
+    fn log_set_protocol_fee_event(&mut self, old: BasisPoints, new: BasisPoints, by: &AccountId) {
+        self.mutable.push(Event::SetProtocolFee {
+            old,
+            new,
+            account: by.clone(),
+        });
+    }
+
+    fn log_set_swap_surcharge_event(&mut self, old: BasisPoints, new: BasisPoints, by: &AccountId) {
+        self.mutable.push(Event::SetSwapSurcharge {
+            old,
+            new,
+            account: by.clone(),
+        });
+    }
+
+    fn log_pause_pool_event(&mut self, pool: (&TokenId, &TokenId), by: &AccountId) {
+        self.mutable.push(Event::PausePool {
+            pool: (pool.0.clone(), pool.1.clone()),
+            account: by.clone(),
+        });
+    }
+
+    fn log_unpause_pool_event(&mut self, pool: (&TokenId, &TokenId), by: &AccountId) {
+        self.mutable.push(Event::UnpausePool {
+            pool: (pool.0.clone(), pool.1.clone()),
+            account: by.clone(),
+        });
+    }
+
+    fn log_open_position_fee_event(
+        &mut self,
+        user: &AccountId,
+        position_id: PositionId,
+        token: &TokenId,
+        amount: &Amount,
+    ) {
+        self.mutable.push(Event::OpenPositionFee {
+            user: user.clone(),
+            position_id,
+            token: token.clone(),
+            amount: *amount,
+        });
+    }
+
+    fn log_force_remove_position_event(
+        &mut self,
+        position_id: PositionId,
+        pool: (&TokenId, &TokenId),
+        owner: Option<&AccountId>,
+        by: &AccountId,
+    ) {
+        self.mutable.push(Event::ForceRemovePosition {
+            position_id,
+            pool: (pool.0.clone(), pool.1.clone()),
+            owner: owner.cloned(),
+            account: by.clone(),
+        });
+    }
+
+    fn log_sweep_untracked_event(
+        &mut self,
+        recipient: &AccountId,
+        token: &TokenId,
+        amount: &Amount,
+    ) {
+        self.mutable.push(Event::SweepUntracked {
+            recipient: recipient.clone(),
+            token: token.clone(),
+            amount: *amount,
+        });
+    }
+
+    fn log_pool_created_event(
+        &mut self,
+        pool: (&TokenId, &TokenId),
+        fee_level: FeeLevel,
+        initial_sqrtprice: dex::Float,
+        by: &AccountId,
+    ) {
+        self.mutable.push(Event::PoolCreated {
+            pool: (pool.0.clone(), pool.1.clone()),
+            fee_level,
+            initial_sqrtprice: f64::from(initial_sqrtprice),
+            account: by.clone(),
+        });
+    }
+
     fn log_tick_update_event(
         &mut self,
         pool: (&TokenId, &TokenId),
@@ -262,4 +419,30 @@ impl dex::Logger for Logger {
             liquidity_change: f64::from(liquidity_change),
         });
     }
+
+    fn log_tick_crossed_event(
+        &mut self,
+        pool: (&TokenId, &TokenId),
+        fee_level: FeeLevel,
+        tick: Tick,
+        side: Side,
+    ) {
+        self.mutable.push(Event::TickCrossed {
+            pool: (pool.0.clone(), pool.1.clone()),
+            fee_level,
+            tick: tick.index(),
+            side,
+        });
+    }
+
+    fn log_position_range_boundary_crossed_event(
+        &mut self,
+        position_id: PositionId,
+        entered: bool,
+    ) {
+        self.mutable.push(Event::PositionRangeBoundaryCrossed {
+            position_id,
+            entered,
+        });
+    }
 }
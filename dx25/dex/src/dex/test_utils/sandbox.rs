@@ -41,6 +41,7 @@ use super::logger::{Event, Logger};
 use super::traits::PersistentBound;
 use crate::chain::{AccountId, Amount, TokenId};
 use dex::{latest, Account, BasisPoints, Dex, Pool, PoolId, Position, PositionId, Result};
+use std::collections::BTreeMap;
 
 #[allow(unused)]
 use num_traits::Zero; // Some `Amount`'s have `zero` as inherent method, some as trait impl
@@ -62,6 +63,8 @@ pub struct Sandbox {
     logger: Logger,
     caller_id: AccountId,
     initiator_id: AccountId,
+    block_timestamp: u64,
+    contract_balances: BTreeMap<TokenId, Amount>,
 }
 
 #[allow(unused)]
@@ -89,6 +92,8 @@ impl Sandbox {
             logger: Logger::new(),
             caller_id: owner_id.clone(),
             initiator_id: owner_id,
+            block_timestamp: 0,
+            contract_balances: BTreeMap::new(),
         }
     }
 
@@ -108,6 +113,28 @@ impl Sandbox {
         std::mem::replace(&mut self.initiator_id, initiator_id)
     }
 
+    pub fn block_timestamp(&self) -> u64 {
+        self.block_timestamp
+    }
+
+    pub fn set_block_timestamp(&mut self, block_timestamp: u64) -> u64 {
+        std::mem::replace(&mut self.block_timestamp, block_timestamp)
+    }
+
+    pub fn contract_balance(&self, token_id: &TokenId) -> Amount {
+        self.contract_balances
+            .get(token_id)
+            .copied()
+            .unwrap_or_else(Amount::zero)
+    }
+    /// Set amount of tokens which contract is assumed to actually hold on chain.
+    /// Sandbox doesn't track transfers, so this value changes only via this method
+    pub fn set_contract_balance(&mut self, token_id: TokenId, amount: Amount) -> Amount {
+        self.contract_balances
+            .insert(token_id, amount)
+            .unwrap_or_else(Amount::zero)
+    }
+
     pub fn set_initiator_caller_ids(&mut self, account_id: AccountId) -> (AccountId, AccountId) {
         let old_caller = self.set_caller_id(account_id.clone());
         let old_init = self.set_initiator_id(account_id);
@@ -150,6 +177,8 @@ impl Sandbox {
         let mut inner = StateInnerMut {
             caller_id: &self.caller_id,
             initiator_id: &self.initiator_id,
+            block_timestamp: self.block_timestamp,
+            contract_balances: &self.contract_balances,
             contract: &mut contract,
             item_factory: &mut item_factory,
             logger: &mut self.logger,
@@ -194,6 +223,8 @@ impl dex::State<Types> for StateInner {
 pub struct StateInnerMut<'a> {
     caller_id: &'a AccountId,
     initiator_id: &'a AccountId,
+    block_timestamp: u64,
+    contract_balances: &'a BTreeMap<TokenId, Amount>,
     contract: &'a mut dex::Contract<Types>,
     item_factory: &'a mut ItemFactory,
     logger: &'a mut Logger,
@@ -221,6 +252,7 @@ impl<'a> dex::StateMut<Types> for StateInnerMut<'a> {
     fn send_tokens(
         &mut self,
         account_id: &AccountId,
+        _recipient: &AccountId,
         token_id: &TokenId,
         _amount: Amount,
         unregister: bool,
@@ -229,7 +261,8 @@ impl<'a> dex::StateMut<Types> for StateInnerMut<'a> {
         self.contract
             .latest()
             .accounts
-            .try_update(account_id, |dex::Account::V0(ref mut account)| {
+            .try_update(account_id, |account| {
+                let account = account.latest();
                 // Always succeed
                 // TODO: may need ways to simulate failure
 
@@ -250,6 +283,17 @@ impl<'a> dex::StateMut<Types> for StateInnerMut<'a> {
     fn get_caller_id(&self) -> AccountId {
         self.caller_id.clone()
     }
+
+    fn get_block_timestamp(&self) -> u64 {
+        self.block_timestamp
+    }
+
+    fn get_contract_balance(&self, token_id: &TokenId) -> Amount {
+        self.contract_balances
+            .get(token_id)
+            .copied()
+            .unwrap_or_else(Amount::zero)
+    }
 }
 // Mock for extra account data
 #[derive(Default)]
@@ -290,6 +334,15 @@ impl dex::Types for Types {
 
     type AccountIdSet = Map<AccountId, ()>;
 
+    type PoolIdSet = Map<PoolId, ()>;
+
+    type ReferencePricesMap = Map<PoolId, dex::Float>;
+    type TokenDecimalsMap = Map<TokenId, u8>;
+    type AccountedBalancesMap = Map<TokenId, Amount>;
+    type TokenVerifiedAtMap = Map<TokenId, u64>;
+    type PositionOwnersMap = Map<PositionId, AccountId>;
+    type TickPositionsMap = Map<(PoolId, dex::FeeLevel, dex::Tick), Vec<(PositionId, bool)>>;
+
     #[cfg(feature = "smart-routing")]
     type TokenConnectionsMap = Map<TokenId, Self::TokensSet>;
 
@@ -167,6 +167,32 @@ impl Tick {
         EffTick::from_tick(*self, fee_level, side).eff_sqrtprice()
     }
 
+    /// Tick which spot sqrtprice is nearest to the specified one, in logarithmic scale.
+    /// Values beyond price scale are clamped to `Tick::MIN` or `Tick::MAX`.
+    pub fn nearest_to_spot_sqrtprice(spot_sqrtprice: Float) -> Self {
+        if spot_sqrtprice <= Self::MIN.spot_sqrtprice() {
+            return Self::MIN;
+        }
+        if spot_sqrtprice >= Self::MAX.spot_sqrtprice() {
+            return Self::MAX;
+        }
+        // Invariant: `low.spot_sqrtprice() < spot_sqrtprice < high.spot_sqrtprice()`
+        let (mut low, mut high) = (Self::MIN, Self::MAX);
+        while high.index() - low.index() > 1 {
+            let middle = Self(low.index() + (high.index() - low.index()) / 2);
+            if middle.spot_sqrtprice() <= spot_sqrtprice {
+                low = middle;
+            } else {
+                high = middle;
+            }
+        }
+        if spot_sqrtprice * spot_sqrtprice <= low.spot_sqrtprice() * high.spot_sqrtprice() {
+            low
+        } else {
+            high
+        }
+    }
+
     /// Tick corresponding to the opposite spot sqrtprice
     pub fn opposite(&self) -> Self {
         // unwrap will succeed as long as tick itself is valid and the range of valid ticks is symmetric
@@ -239,11 +265,16 @@ impl EffTick {
     }
 
     pub fn to_tick(&self, fee_level: FeeLevel, side: Side) -> Result<Tick, ErrorKind> {
-        let tick_index = match side {
+        Tick::new(self.tick_index(fee_level, side))
+    }
+
+    /// Index of the tick which corresponds to this effective tick on specified level and side.
+    /// Unlike `to_tick`, doesn't check if index is within bounds
+    pub fn tick_index(&self, fee_level: FeeLevel, side: Side) -> i32 {
+        match side {
             Side::Left => self.index() - i32::from(fee_rate_ticks(fee_level)),
             Side::Right => -self.index() + i32::from(fee_rate_ticks(fee_level)),
-        };
-        Tick::new(tick_index)
+        }
     }
 
     pub fn eff_sqrtprice(&self) -> Float {
@@ -317,6 +348,30 @@ mod tests {
         assert_eq_rel_tol!(actual_sqrtprice, expected_sqrtprice, 3);
     }
 
+    #[rstest]
+    #[case::max_tick(MAX_TICK)]
+    #[case::large_pos(21_114)]
+    #[case::one(1)]
+    #[case::zero(0)]
+    #[case::neg_one(-1)]
+    #[case::large_neg(-41114)]
+    #[case::min_tick(MIN_TICK)]
+    fn test_nearest_to_spot_sqrtprice(#[case] tick_number: i32) {
+        let tick = Tick::new(tick_number).unwrap();
+        let sqrtprice = tick.spot_sqrtprice();
+        let quarter_tick = Tick::BASE.sqrt().sqrt();
+
+        assert_eq!(Tick::nearest_to_spot_sqrtprice(sqrtprice), tick);
+        assert_eq!(
+            Tick::nearest_to_spot_sqrtprice(sqrtprice * quarter_tick),
+            tick
+        );
+        assert_eq!(
+            Tick::nearest_to_spot_sqrtprice(sqrtprice / quarter_tick),
+            tick
+        );
+    }
+
     #[rstest]
     fn test_eff_sqrtprice_for_extreme_ticks_succeed(
         #[values(MIN_TICK, MIN_TICK + 1, MAX_TICK - 1, MAX_TICK)] tick_index: i32,
@@ -8,7 +8,7 @@ use super::errors::Result;
 use super::{
     latest, Account, AccountLatest, BasisPoints, Contract, ContractLatest, FeeLevel, Float, Pool,
     PoolId, PoolLatest, PoolUpdateReason, Position, PositionId, PositionLatest, Side, TickState,
-    TickStateV0,
+    TickStateV0, DEFAULT_MAX_ACTIONS_PER_BATCH, DEFAULT_OBSERVATION_CARDINALITY, EVENTS_ALL,
 };
 use crate::chain::{AccountId, Amount, LPFeePerFeeLiquidity, Liquidity, LiquiditySFP, TokenId};
 use crate::dex::tick::{EffTick, Tick};
@@ -151,6 +151,29 @@ pub trait Types: Sized {
     /// Set of accounts
     type AccountIdSet: PersistentCollection<Self::Bound> + Set<Item = AccountId>;
 
+    /// Set of pool identifiers
+    type PoolIdSet: PersistentCollection<Self::Bound> + Set<Item = PoolId>;
+
+    /// Reference prices indexed by pool identifier
+    type ReferencePricesMap: PersistentCollection<Self::Bound>
+        + MapRemoveKey<Key = PoolId, Value = Float>;
+    /// Token decimals indexed by token identifier
+    type TokenDecimalsMap: PersistentCollection<Self::Bound>
+        + MapRemoveKey<Key = TokenId, Value = u8>;
+    /// Total amounts of tokens accounted by contract indexed by token identifier
+    type AccountedBalancesMap: PersistentCollection<Self::Bound>
+        + MapRemoveKey<Key = TokenId, Value = Amount>;
+    /// Block timestamps of token verification indexed by token identifier
+    type TokenVerifiedAtMap: PersistentCollection<Self::Bound>
+        + MapRemoveKey<Key = TokenId, Value = u64>;
+    /// Owners of positions indexed by position id
+    type PositionOwnersMap: PersistentCollection<Self::Bound>
+        + MapRemoveKey<Key = PositionId, Value = AccountId>;
+    /// Positions bounded by tick, indexed by pool, fee level and tick.
+    /// Flag tells whether tick is lower bound of position, in pool order
+    type TickPositionsMap: PersistentCollection<Self::Bound>
+        + MapRemoveKey<Key = (PoolId, FeeLevel, Tick), Value = Vec<(PositionId, bool)>>;
+
     /// Map of existing connections between tokens
     /// Connection means being in one pool
     #[cfg(feature = "smart-routing")]
@@ -245,7 +268,9 @@ pub trait StateMut<T: Types + ?Sized>: State<T> {
     /// pseudo-sync calls, while others may use async calls with result handling through callback
     ///
     /// # Parameters
-    /// * `account_id` - account to which tokens should be sent
+    /// * `account_id` - account on behalf of which tokens are sent; if send fails,
+    ///     tokens are returned to its deposit
+    /// * `recipient` - account to which tokens should be sent, usually same as `account_id`
     /// * `token_id` - token which should be sent
     /// * `amount` - amount of tokens to send
     /// * `unregister_token` - whether to attempt to unregister token; this parameter
@@ -257,6 +282,7 @@ pub trait StateMut<T: Types + ?Sized>: State<T> {
     fn send_tokens(
         &mut self,
         account_id: &AccountId,
+        recipient: &AccountId,
         token_id: &TokenId,
         amount: Amount,
         unregister_token: bool,
@@ -267,6 +293,11 @@ pub trait StateMut<T: Types + ?Sized>: State<T> {
     fn get_initiator_id(&self) -> AccountId;
     /// Retrieve identifier of entity which called smart contract's method
     fn get_caller_id(&self) -> AccountId;
+    /// Retrieve timestamp of current block, in seconds
+    fn get_block_timestamp(&self) -> u64;
+    /// Retrieve actual amount of specified token held by contract on chain,
+    /// including tokens which aren't accounted by contract state
+    fn get_contract_balance(&self, token_id: &TokenId) -> Amount;
     /// Make temporary mutable `Dex` instance out of `&mut self`
     fn as_dex_mut(&mut self) -> super::Dex<T, Self, &mut Self>
     where
@@ -287,6 +318,18 @@ pub trait ItemFactory<T: Types + ?Sized> {
     fn new_verified_tokens_set(&mut self) -> T::VerifiedTokensSet;
     fn new_position_to_pool_map(&mut self) -> T::PositionToPoolMap;
     fn new_guards(&mut self) -> T::AccountIdSet;
+    fn new_reference_prices_map(&mut self) -> T::ReferencePricesMap;
+    fn new_token_decimals_map(&mut self) -> T::TokenDecimalsMap;
+    fn new_token_verified_at_map(&mut self) -> T::TokenVerifiedAtMap;
+    fn new_operators(&mut self) -> T::AccountIdSet;
+    fn new_accounted_balances_map(&mut self) -> T::AccountedBalancesMap;
+    fn new_paused_pools(&mut self) -> T::PoolIdSet;
+    fn new_position_owners_map(&mut self) -> T::PositionOwnersMap;
+    fn new_tick_positions_map(&mut self) -> T::TickPositionsMap;
+    fn new_accounted_balances_init_accounts(&mut self) -> T::AccountIdSet;
+    fn new_accounted_balances_init_pools(&mut self) -> T::PoolIdSet;
+    fn new_collected_open_position_fees_map(&mut self) -> T::AccountedBalancesMap;
+    fn new_gc_reward_funds_map(&mut self) -> T::AccountedBalancesMap;
 
     fn new_contract(
         &mut self,
@@ -298,7 +341,7 @@ pub trait ItemFactory<T: Types + ?Sized> {
             fee_rates == [1, 2, 4, 8, 16, 32, 64, 128],
             ErrorKind::InvalidParams
         );
-        Ok(Contract::V1(ContractLatest {
+        Ok(Contract::V2(ContractLatest {
             owner_id,
             guards: self.new_guards(),
             suspended: false,
@@ -311,20 +354,50 @@ pub trait ItemFactory<T: Types + ?Sized> {
             protocol_fee_fraction: validate_protocol_fee_fraction(protocol_fee_fraction)
                 .map_err(|e| error_here!(e))?,
             extra: T::ContractExtraV1::default(),
+            reference_price_setter: None,
+            max_oracle_deviation_bp: None,
+            reference_prices: None,
+            token_decimals: None,
+            swap_cooldown_secs: None,
+            operators: None,
+            accounted_balances: Some(self.new_accounted_balances_map()),
+            verified_token_grace_secs: 0,
+            token_verified_at: None,
+            swap_max_underpay: None,
+            swap_surcharge_bp: 0,
+            max_tokens_per_account: None,
+            event_verbosity: EVENTS_ALL,
+            paused_pools: None,
+            open_position_fee: None,
+            dust_liquidity_threshold: Liquidity::zero(),
+            gc_reward: Amount::zero(),
+            position_owners: None,
+            max_actions_per_batch: DEFAULT_MAX_ACTIONS_PER_BATCH,
+            tick_positions: None,
+            max_swap_tick_crossings: None,
+            protocol_fee_withdrawal_delay_secs: 0,
+            accounted_balances_init: None,
+            accounted_balances_init_accounts: None,
+            accounted_balances_init_pools: None,
+            collected_open_position_fees: None,
+            gc_reward_funds: None,
         }))
     }
 
     fn new_account(&mut self) -> Result<Account<T>> {
-        Ok(Account::V0(AccountLatest {
+        Ok(Account::V1(AccountLatest {
             token_balances: self.new_account_token_balances_map().into(),
             positions: self.new_account_positions_set(),
             withdraw_tracker: self.new_account_withdraw_tracker(),
             extra: Default::default(),
+            last_swap_ts: 0,
+            idempotency_keys: Vec::new(),
+            auto_unregister_empty: false,
         }))
     }
 
     fn new_pool(&mut self) -> Result<Pool<T>> {
-        Ok(Pool::V0(PoolLatest {
+        Ok(Pool::V1(PoolLatest {
             total_reserves: (Amount::zero(), Amount::zero()),
             positions: self.new_pool_positions_map().into(),
             eff_sqrtprices: latest::FeeLevelsArray::default(),
@@ -344,6 +417,15 @@ pub trait ItemFactory<T: Types + ?Sized> {
             top_active_level: 0,
             active_side: Side::Left,
             pivot: EffTick::default(),
+            last_trade_ts: 0,
+            last_trade_sqrtprice: Float::zero(),
+            last_swap_tick_crossings: 0,
+            allowed_swap_side: None,
+            pending_protocol_fee: None,
+            acc_position_rounding_residual: (AmountUFP::zero(), AmountUFP::zero()),
+            observations: Vec::new(),
+            observation_index: 0,
+            observation_cardinality: DEFAULT_OBSERVATION_CARDINALITY,
         }))
     }
 
@@ -354,11 +436,13 @@ pub trait ItemFactory<T: Types + ?Sized> {
         init_acc_lp_fees_per_fee_liquidity: (LPFeePerFeeLiquidity, LPFeePerFeeLiquidity),
         ticks_range: (Tick, Tick),
         init_sqrtprice: Float,
+        init_amounts: (Amount, Amount),
     ) -> Result<Position<T>> {
-        Ok(Position::V0(PositionLatest {
+        Ok(Position::V1(PositionLatest {
             fee_level,
             net_liquidity,
             init_sqrtprice,
+            init_amounts,
             init_acc_lp_fees_per_fee_liquidity,
             tick_bounds: ticks_range,
             unwithdrawn_acc_lp_fees_per_fee_liquidity: init_acc_lp_fees_per_fee_liquidity,
@@ -677,6 +761,20 @@ pub trait Logger {
         position_id: PositionId,
         ticks_range: (Tick, Tick),
     );
+    fn log_open_position_fee_event(
+        &mut self,
+        user: &AccountId,
+        position_id: PositionId,
+        token: &TokenId,
+        amount: &Amount,
+    );
+    fn log_force_remove_position_event(
+        &mut self,
+        position_id: PositionId,
+        pool: (&TokenId, &TokenId),
+        owner: Option<&AccountId>,
+        by: &AccountId,
+    );
     fn log_harvest_fee_event(&mut self, position_id: PositionId, fee_amounts: (Amount, Amount));
     fn log_close_position_event(&mut self, position_id: PositionId, amounts: (Amount, Amount));
     fn log_swap_event(
@@ -702,9 +800,33 @@ pub trait Logger {
     fn log_add_guard_accounts_event(&mut self, tokens: &[AccountId]);
     fn log_remove_guard_accounts_event(&mut self, tokens: &[AccountId]);
 
+    fn log_add_operator_accounts_event(&mut self, accounts: &[AccountId]);
+    fn log_remove_operator_accounts_event(&mut self, accounts: &[AccountId]);
+
     fn log_suspend_payable_api_event(&mut self, account: &AccountId);
     fn log_resume_payable_api_event(&mut self, account: &AccountId);
 
+    fn log_set_protocol_fee_event(&mut self, old: BasisPoints, new: BasisPoints, by: &AccountId);
+    fn log_set_swap_surcharge_event(&mut self, old: BasisPoints, new: BasisPoints, by: &AccountId);
+
+    fn log_pause_pool_event(&mut self, pool: (&TokenId, &TokenId), by: &AccountId);
+    fn log_unpause_pool_event(&mut self, pool: (&TokenId, &TokenId), by: &AccountId);
+
+    fn log_sweep_untracked_event(
+        &mut self,
+        recipient: &AccountId,
+        token: &TokenId,
+        amount: &Amount,
+    );
+
+    fn log_pool_created_event(
+        &mut self,
+        pool: (&TokenId, &TokenId),
+        fee_level: FeeLevel,
+        initial_sqrtprice: Float,
+        by: &AccountId,
+    );
+
     fn log_tick_update_event(
         &mut self,
         pool: (&TokenId, &TokenId),
@@ -712,4 +834,16 @@ pub trait Logger {
         tick: Tick,
         liquidity_change: Float,
     );
+
+    /// Tick crossed by a swap, `side` is the side from which tokens were put into the pool
+    fn log_tick_crossed_event(
+        &mut self,
+        pool: (&TokenId, &TokenId),
+        fee_level: FeeLevel,
+        tick: Tick,
+        side: Side,
+    );
+
+    /// Price moved across range boundary of position, either into (`entered`) or out of its range
+    fn log_position_range_boundary_crossed_event(&mut self, position_id: PositionId, entered: bool);
 }
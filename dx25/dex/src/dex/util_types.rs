@@ -1,7 +1,10 @@
 use super::utils::swap_if;
-use super::{latest, BasisPoints, ErrorKind as DexErrorKind, FeeLevel, Float, PositionId, WasmApi};
+use super::{
+    latest, BasisPoints, ErrorKind as DexErrorKind, FeeLevel, Float, PositionId, WasmApi,
+    BASIS_POINT_DIVISOR,
+};
 use crate::chain::wasm::WasmAmount;
-use crate::chain::{Amount, Liquidity, NetLiquidityUFP, TokenId};
+use crate::chain::{AccountId, Amount, AmountUFP, Liquidity, NetLiquidityUFP, TokenId};
 use crate::dex::tick::Tick;
 use crate::ensure;
 use std::ops::{Deref, Index, IndexMut};
@@ -46,10 +49,10 @@ impl PoolId {
     /// Construct pool identifier from pair of token identifiers, if possible
     ///
     /// # Return
-    /// * `Err(ErrorKind::TokenDuplicates)` if tokens in pair are equal
+    /// * `Err(ErrorKind::IdenticalTokens)` if tokens in pair are equal
     /// * `Ok((pool_id, swapped))` on success, where `swapped` is `true` if token identifiers were swapped
     pub fn try_from_pair(pair: (TokenId, TokenId)) -> Result<(PoolId, bool), DexErrorKind> {
-        ensure!(pair.0 != pair.1, DexErrorKind::TokenDuplicates);
+        ensure!(pair.0 != pair.1, DexErrorKind::IdenticalTokens);
         let swapped = pair.0 < pair.1;
         let pair = swap_if(swapped, pair);
         Ok((Self { pair }, swapped))
@@ -150,7 +153,7 @@ pub struct Range<T: std::fmt::Debug + WasmApi> {
 #[cfg_attr(feature = "concordium", derive(Serialize, SchemaType))]
 #[cfg_attr(
     feature = "multiversx",
-    derive(TopDecode, TopEncode, NestedEncode, NestedDecode)
+    derive(TopDecode, TopEncode, NestedEncode, NestedDecode, TypeAbi)
 )]
 #[cfg_attr(feature = "test-utils", derive(serde::Serialize, serde::Deserialize))]
 pub enum Side {
@@ -270,6 +273,25 @@ pub enum Action<E: 'static + Sized + super::WasmApi> {
     WithdrawFee(PositionId),
 }
 
+/// Amount of token to withdraw, see `Dex::withdraw_v2`
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+#[cfg_attr(feature = "near", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "near", serde(crate = "near_sdk::serde"))]
+#[cfg_attr(feature = "concordium", derive(Serialize, SchemaType))]
+#[cfg_attr(
+    feature = "multiversx",
+    derive(TopDecode, TopEncode, NestedEncode, NestedDecode, TypeAbi)
+)]
+#[derive(Clone)]
+pub enum WithdrawMode {
+    /// Withdraw exactly specified amount; zero amount withdraws nothing
+    Exact(WasmAmount),
+    /// Withdraw whole balance
+    All,
+    /// Withdraw nothing
+    None,
+}
+
 #[cfg_attr(feature = "near", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "near", serde(crate = "near_sdk::serde"))]
 #[cfg_attr(feature = "concordium", derive(Serialize, SchemaType))]
@@ -334,6 +356,9 @@ pub struct PositionInfo {
 pub struct PositionOpenedInfo {
     /// Actual deposit
     pub deposited_amounts: (Amount, Amount),
+    /// Difference between actual and accounted deposit, caused by rounding up.
+    /// It isn't accounted to position, and goes into protocol fee
+    pub rounding_residual: (AmountUFP, AmountUFP),
     /// Accounted net liquidity
     pub net_liquidity: NetLiquidityUFP,
     /// Liquidity change of LOW tick from the position range after opening position
@@ -382,6 +407,14 @@ pub struct PoolInfo {
 
     /// Scale factor for the fee levels.
     pub fee_divisor: BasisPoints,
+
+    /// Block timestamp of the last swap in the pool, in seconds.
+    /// Zero if the pool has never been traded.
+    pub last_trade_ts: u64,
+
+    /// Square root of the spot price right after the last swap.
+    /// If the pool has never been traded, current spot price at fee level 0 is reported.
+    pub last_trade_sqrtprice: Float,
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -411,6 +444,32 @@ pub struct VersionInfo {
     pub version: String,
 }
 
+/// Snapshot of contract's administrative configuration, see `Dex::get_admin_config`
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[cfg_attr(feature = "multiversx", derive(TopDecode, TopEncode, TypeAbi))]
+pub struct AdminConfig {
+    pub owner: AccountId,
+    /// At most `MAX_ADMIN_CONFIG_GUARDS` guard accounts
+    pub guards: Vec<AccountId>,
+    /// Total number of guard accounts, may exceed length of `guards`
+    pub guard_count: u64,
+    pub suspended: bool,
+    pub protocol_fee_fraction: BasisPoints,
+}
+
+/// Complete fee configuration of the dex, see `Dex::get_fee_schedule`
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+pub struct FeeSchedule {
+    /// Fee rate of each fee level, in ticks
+    pub fee_rate_ticks: latest::RawFeeLevelsArray<BasisPoints>,
+    /// Fee rate of each fee level, as fraction of swapped amount
+    pub fee_rate_fractions: latest::RawFeeLevelsArray<Float>,
+    /// Scale factor for `protocol_fee_fraction`
+    pub basis_point_divisor: BasisPoints,
+    /// Fraction of fee which goes to the protocol, in basis points
+    pub protocol_fee_fraction: BasisPoints,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd)]
 #[cfg_attr(feature = "near", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "concordium", derive(Serialize, SchemaType))]
@@ -429,6 +488,48 @@ pub struct DepositPayment {
     pub amount: Amount,
 }
 
+/// Slippage tolerance, stored in basis points
+///
+/// Guaranteed to be within `[0, BASIS_POINT_DIVISOR]`, i.e. from 0% to 100%
+#[derive(Copy, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+pub struct SlippageTolerance(BasisPoints);
+
+impl SlippageTolerance {
+    /// Construct slippage tolerance from basis points, e.g. `100` means 1%
+    pub fn from_bp(bp: BasisPoints) -> Result<Self, DexErrorKind> {
+        ensure!(
+            bp <= BASIS_POINT_DIVISOR,
+            DexErrorKind::IllegalSlippageTolerance
+        );
+        Ok(Self(bp))
+    }
+    /// Construct slippage tolerance from percents, e.g. `1.0` means 1%
+    ///
+    /// Value is rounded to the nearest basis point
+    pub fn from_percent(percent: Float) -> Result<Self, DexErrorKind> {
+        let bp = percent * Float::from(BASIS_POINT_DIVISOR / 100);
+        // NaN fails both comparisons
+        ensure!(
+            bp >= Float::zero() && bp <= Float::from(BASIS_POINT_DIVISOR),
+            DexErrorKind::IllegalSlippageTolerance
+        );
+        let bp = i32::try_from(bp.round())
+            .ok()
+            .and_then(|bp| BasisPoints::try_from(bp).ok())
+            .ok_or(DexErrorKind::IllegalSlippageTolerance)?;
+        Self::from_bp(bp)
+    }
+    /// Slippage tolerance in basis points
+    pub fn bp(self) -> BasisPoints {
+        self.0
+    }
+    /// Slippage tolerance as fraction of one
+    pub fn fraction(self) -> Float {
+        Float::from(self.0) / Float::from(BASIS_POINT_DIVISOR)
+    }
+}
+
 #[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
 pub struct EstimateSwapExactResult {
     pub result: Amount,
@@ -437,6 +538,11 @@ pub struct EstimateSwapExactResult {
     pub swap_price: Option<Float>,
     pub swap_price_worst: Option<Float>,
     pub fee_in_spent_tok: Amount,
+    /// Part of `fee_in_spent_tok` going to liquidity providers
+    pub lp_fee_in_spent_tok: Amount,
+    /// Part of `fee_in_spent_tok` collected as protocol fee.
+    /// Together with `lp_fee_in_spent_tok` sums up to `fee_in_spent_tok`, up to rounding
+    pub protocol_fee_in_spent_tok: Amount,
     pub num_tick_crossings: u32,
 }
 
@@ -463,3 +569,135 @@ pub struct EstimateAddLiquidityResult {
 pub struct EstimateRemoveLiquidityResult {
     pub tx_cost: TxCostEstimate,
 }
+
+/// Outcome of zapping a single token into a position, see `Estimations::estimate_zap_in`.
+/// Deposited amounts are in the order `(token_have, token_other)`.
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+pub struct ZapEstimate {
+    /// Amount of `token_have` to be swapped into `token_other`
+    pub swap_amount: Amount,
+    pub position_net_liquidity: Float,
+    pub deposited: (Amount, Amount),
+    /// Amount of `token_have` which is neither swapped nor deposited
+    pub leftover: Amount,
+}
+
+/// Effect of closing a position on its pool, see `Estimations::estimate_close_position_impact`.
+/// Prices are of the second pool token expressed in the first one, at position's fee level.
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+pub struct ClosePositionImpact {
+    pub spot_price_before: Float,
+    pub spot_price_after: Float,
+    /// Active liquidity at position's fee level before closing
+    pub liquidity_before: Liquidity,
+    /// Active liquidity at position's fee level after closing
+    pub liquidity_after: Liquidity,
+}
+
+/// Hypothetical pool operation, see `Estimations::simulate`.
+/// Amounts, sides and tick ranges are in the order of tokens passed to simulation.
+#[derive(Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+pub enum SimOp {
+    Open {
+        fee_rate: BasisPoints,
+        position: PositionInit,
+    },
+    Swap {
+        /// Side of the token being swapped in
+        side: Side,
+        kind: SwapKind,
+        amount: Amount,
+    },
+    Close {
+        position_id: PositionId,
+    },
+}
+
+/// Outcome of a single `SimOp`
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+pub enum SimOpResult {
+    Open {
+        position_id: PositionId,
+        deposited_amounts: (Amount, Amount),
+        net_liquidity: Float,
+    },
+    Swap {
+        amount_in: Amount,
+        amount_out: Amount,
+    },
+    Close {
+        balance: (Amount, Amount),
+        fees: (Amount, Amount),
+    },
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+pub struct SimResult {
+    /// State of the pool after all operations were applied
+    pub pool_info: PoolInfo,
+    /// Outcomes of operations, in the order of application
+    pub outputs: Vec<SimOpResult>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_matches::assert_matches;
+
+    #[test]
+    fn slippage_tolerance_from_bp() {
+        assert_eq!(SlippageTolerance::from_bp(0).unwrap().bp(), 0);
+        assert_eq!(SlippageTolerance::from_bp(100).unwrap().bp(), 100);
+        assert_eq!(
+            SlippageTolerance::from_bp(BASIS_POINT_DIVISOR)
+                .unwrap()
+                .bp(),
+            BASIS_POINT_DIVISOR
+        );
+        assert_matches!(
+            SlippageTolerance::from_bp(BASIS_POINT_DIVISOR + 1),
+            Err(DexErrorKind::IllegalSlippageTolerance)
+        );
+        assert_matches!(
+            SlippageTolerance::from_bp(BasisPoints::MAX),
+            Err(DexErrorKind::IllegalSlippageTolerance)
+        );
+    }
+
+    #[test]
+    fn slippage_tolerance_from_percent() {
+        assert_eq!(
+            SlippageTolerance::from_percent(Float::zero()).unwrap().bp(),
+            0
+        );
+        assert_eq!(
+            SlippageTolerance::from_percent(Float::one()).unwrap().bp(),
+            100
+        );
+        assert_eq!(
+            SlippageTolerance::from_percent(Float::from(0.01))
+                .unwrap()
+                .bp(),
+            1
+        );
+        assert_eq!(
+            SlippageTolerance::from_percent(Float::from(100.0))
+                .unwrap()
+                .bp(),
+            BASIS_POINT_DIVISOR
+        );
+        assert_eq!(
+            SlippageTolerance::from_percent(Float::one())
+                .unwrap()
+                .fraction(),
+            Float::from(0.01)
+        );
+        for percent in [-0.01, -1.0, 100.01, 1e300, f64::NAN, f64::INFINITY] {
+            assert_matches!(
+                SlippageTolerance::from_percent(Float::from(percent)),
+                Err(DexErrorKind::IllegalSlippageTolerance)
+            );
+        }
+    }
+}
@@ -1,5 +1,5 @@
-use crate::dex::{Float, PoolId};
-use crate::TokenId;
+use crate::dex::{ErrorKind, Float, PoolId};
+use crate::{Amount, TokenId};
 use std::borrow::Borrow;
 
 /// Swap values in pair if condition is `true`, return unchanged otherwise
@@ -10,6 +10,12 @@ pub fn swap_if<T>(condition: bool, pair: (T, T)) -> (T, T) {
         pair
     }
 }
+/// Sum amounts, failing with `ErrorKind::AmountOverflow` instead of panicking on overflow
+pub fn checked_sum(amounts: impl IntoIterator<Item = Amount>) -> Result<Amount, ErrorKind> {
+    amounts.into_iter().try_fold(Amount::zero(), |sum, amount| {
+        sum.checked_add(amount).ok_or(ErrorKind::AmountOverflow)
+    })
+}
 /// Similar to `assert!`, but bails out with specified error instead of panicking
 ///
 /// # Parameters
@@ -1,11 +1,14 @@
 use super::super::errors::{ErrorKind, Result};
-use super::super::{AccountV0, AccountWithdrawTracker, Map, MapRemoveKey, Types};
+use super::super::{
+    AccountLatest, AccountWithdrawTracker, IdempotencyKey, Map, MapRemoveKey, Types,
+    MAX_IDEMPOTENCY_KEYS,
+};
 use crate::chain::{Amount, TokenId};
 use crate::{ensure_here, error_here};
 #[allow(unused)] // Some impls use it, some don't
 use num_traits::Zero;
 
-impl<T: Types> AccountV0<T> {
+impl<T: Types> AccountLatest<T> {
     #[track_caller]
     #[allow(unused)] // Need to use it in `Dex`, to properly check if account can be unregistered
     pub(in super::super) fn ensure_no_withdraw_in_progress(&self) -> Result<()> {
@@ -16,6 +19,32 @@ impl<T: Types> AccountV0<T> {
         Ok(())
     }
 
+    /// Record swap made at `now`, failing if previous one was less than `cooldown` seconds ago
+    pub(crate) fn record_swap(&mut self, now: u64, cooldown: Option<u64>) -> Result<()> {
+        if let Some(cooldown) = cooldown {
+            ensure_here!(
+                now.saturating_sub(self.last_swap_ts) >= cooldown,
+                ErrorKind::SwapCooldown
+            );
+        }
+        self.last_swap_ts = now;
+        Ok(())
+    }
+
+    /// Remember idempotency key of executed batch, failing if it was already seen.
+    /// Oldest key is forgotten once there are `MAX_IDEMPOTENCY_KEYS` of them
+    pub(crate) fn record_idempotency_key(&mut self, key: IdempotencyKey) -> Result<()> {
+        ensure_here!(
+            !self.idempotency_keys.contains(&key),
+            ErrorKind::DuplicateRequest
+        );
+        if self.idempotency_keys.len() >= MAX_IDEMPOTENCY_KEYS {
+            self.idempotency_keys.remove(0);
+        }
+        self.idempotency_keys.push(key);
+        Ok(())
+    }
+
     pub(crate) fn register_token(&mut self, token_id: &TokenId) {
         if !self.token_balances.contains_key(token_id) {
             self.token_balances.insert(token_id.clone(), Amount::zero());
@@ -28,6 +57,40 @@ impl<T: Types> AccountV0<T> {
         }
     }
 
+    /// Register tokens, failing with `TooManyTokens` if account would end up
+    /// with more than `max_tokens` registered tokens. Nothing is registered on failure
+    pub(crate) fn register_tokens_capped<'a>(
+        &mut self,
+        tokens: impl IntoIterator<Item = &'a TokenId>,
+        max_tokens: Option<u32>,
+    ) -> Result<()> {
+        let tokens = tokens.into_iter().collect::<Vec<_>>();
+        if let Some(max_tokens) = max_tokens {
+            let mut new_tokens = Vec::<&TokenId>::new();
+            for &token in &tokens {
+                if !self.token_balances.contains_key(token) && !new_tokens.contains(&token) {
+                    new_tokens.push(token);
+                }
+            }
+            ensure_here!(
+                self.token_balances.len() + new_tokens.len() <= max_tokens as usize,
+                ErrorKind::TooManyTokens
+            );
+        }
+        self.register_tokens(tokens);
+        Ok(())
+    }
+
+    /// Unregister token if its balance is zero and account prefers emptied tokens
+    /// to be unregistered automatically
+    pub(crate) fn auto_unregister_if_empty(&mut self, token_id: &TokenId) {
+        if self.auto_unregister_empty
+            && self.token_balances.inspect(token_id, |balance| *balance) == Some(Amount::zero())
+        {
+            self.token_balances.remove(token_id);
+        }
+    }
+
     fn unregister_token(&mut self, token_id: &TokenId) -> Result<()> {
         if let Some(balance) = self.token_balances.inspect(token_id, |balance| *balance) {
             ensure_here!(balance == Amount::zero(), ErrorKind::NonZeroTokenBalance);
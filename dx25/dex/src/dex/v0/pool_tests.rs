@@ -10,15 +10,17 @@ mod tests {
     use crate::dex::latest::{EffSqrtprices, FeeLevelsArray, NUM_FEE_LEVELS};
 
     use crate::dex::pool::Pool as _;
+    use crate::dex::pool::PoolState as _;
     use crate::dex::pool::{
         fee_liquidity_from_net_liquidity, fee_rate, gross_liquidity_from_net_liquidity,
-        one_over_sqrt_one_minus_fee_rate,
+        one_over_sqrt_one_minus_fee_rate, SWAP_MAX_UNDERPAY,
     };
     use crate::dex::test_utils::{ItemFactory, Types};
     use crate::dex::traits::ItemFactory as _;
     use crate::dex::{
-        errors, BasisPoints, FeeLevel, Float, PairExt, Pool, PoolId, PoolV0, PositionClosedInfo,
-        PositionInit, PositionOpenedInfo, Range, Side, Tick, BASIS_POINT_DIVISOR,
+        errors, BasisPoints, FeeLevel, Float, PairExt, Pool, PoolId, PoolLatest,
+        PositionClosedInfo, PositionInit, PositionOpenedInfo, Range, Side, Tick,
+        BASIS_POINT_DIVISOR,
     };
     use crate::test_utils::{new_amount, new_token_id};
     use crate::{assert_eq_rel_tol, Amount, NetLiquidityUFP};
@@ -41,14 +43,16 @@ mod tests {
     }
 
     #[fixture]
-    fn empty_pool(#[default(&mut factory())] factory: &mut ItemFactory) -> PoolV0<Types> {
-        let Pool::V0(pool) = factory.new_pool().unwrap();
+    fn empty_pool(#[default(&mut factory())] factory: &mut ItemFactory) -> PoolLatest<Types> {
+        let Pool::V1(pool) = factory.new_pool().unwrap() else {
+            unreachable!()
+        };
         pool
     }
 
     #[rstest]
     fn test_price_is_zero_at_all_levels_in_empty_pool(
-        empty_pool: PoolV0<Types>,
+        empty_pool: PoolLatest<Types>,
         #[values(Side::Left, Side::Right)] side: Side,
         #[values(0, 1, 2, 3, 4, 5, 6, 7)] fee_level: FeeLevel,
     ) {
@@ -127,6 +131,7 @@ mod tests {
             net_liquidity: actual_net_liquidity,
             low_tick_liquidity_change,
             high_tick_liquidity_change,
+            ..
         } = result.unwrap();
 
         let (actual_x_amount, actual_y_amount) = deposited_amounts;
@@ -279,6 +284,7 @@ mod tests {
             net_liquidity: actual_net_liquidity,
             low_tick_liquidity_change,
             high_tick_liquidity_change,
+            ..
         } = result;
 
         let (actual_x_amount, actual_y_amount) = deposited_amounts;
@@ -535,6 +541,7 @@ mod tests {
             net_liquidity: actual_net_liquidity,
             low_tick_liquidity_change,
             high_tick_liquidity_change,
+            ..
         } = result.unwrap();
 
         let (actual_x_amount, actual_y_amount) = deposited_amounts;
@@ -783,6 +790,7 @@ mod tests {
             net_liquidity: actual_net_liquidity,
             low_tick_liquidity_change,
             high_tick_liquidity_change,
+            ..
         } = result.unwrap();
 
         let (actual_x_amount, actual_y_amount) = deposited_amounts;
@@ -984,7 +992,7 @@ mod tests {
     }
 
     #[rstest]
-    fn test_close_position_for_non_existed_position(mut empty_pool: PoolV0<Types>) {
+    fn test_close_position_for_non_existed_position(mut empty_pool: PoolLatest<Types>) {
         let non_existed_position_id = 0_u64;
 
         let result = empty_pool.withdraw_fee_and_close_position(non_existed_position_id);
@@ -1000,7 +1008,7 @@ mod tests {
 
     #[rstest]
     fn test_close_position_right_after_open_position(
-        mut empty_pool: PoolV0<Types>,
+        mut empty_pool: PoolLatest<Types>,
         #[values(Side::Left, Side::Right)] side: Side,
         #[values(0)] fee_level: FeeLevel,
         mut factory: ItemFactory,
@@ -1065,7 +1073,7 @@ mod tests {
     }
 
     #[rstest]
-    fn test_withdraw_fee_with_non_existed_position_id(mut empty_pool: PoolV0<Types>) {
+    fn test_withdraw_fee_with_non_existed_position_id(mut empty_pool: PoolLatest<Types>) {
         let position_id = 0_u64;
         let result = empty_pool.withdraw_fee(position_id);
 
@@ -1080,7 +1088,7 @@ mod tests {
 
     #[rstest]
     fn test_close_position_after_one_swap(
-        mut empty_pool: PoolV0<Types>,
+        mut empty_pool: PoolLatest<Types>,
         #[values(Side::Left, Side::Right)] side: Side,
         #[values(0, 1, 2, 3, 4, 5, 6, 7)] fee_level: FeeLevel,
         #[values(1235)] protocol_fee_fraction: BasisPoints,
@@ -1123,7 +1131,7 @@ mod tests {
 
         let amount_in = amount;
         empty_pool
-            .swap_exact_in(side, amount_in, protocol_fee_fraction)
+            .swap_exact_in(side, amount_in, protocol_fee_fraction, SWAP_MAX_UNDERPAY)
             .unwrap();
 
         let PositionClosedInfo {
@@ -1174,7 +1182,10 @@ mod tests {
     }
 
     #[rstest]
-    fn test_get_position_info_for_non_existed_position(pool_id: PoolId, empty_pool: PoolV0<Types>) {
+    fn test_get_position_info_for_non_existed_position(
+        pool_id: PoolId,
+        empty_pool: PoolLatest<Types>,
+    ) {
         let position_id = 0;
         let result = empty_pool.get_position_info(&pool_id, position_id);
         assert_matches!(
@@ -1189,7 +1200,7 @@ mod tests {
     #[rstest]
     fn test_get_position_info_for_existed_position(
         pool_id: PoolId,
-        mut empty_pool: PoolV0<Types>,
+        mut empty_pool: PoolLatest<Types>,
         #[values(0, 1, 2, 3, 4, 5, 6, 7)] fee_level: FeeLevel,
         #[values(Side::Left, Side::Right)] side: Side,
         mut factory: ItemFactory,
@@ -1273,11 +1284,53 @@ mod tests {
         );
     }
 
+    /// Swap cross-checks use underpay tolerance passed by caller
+    #[rstest]
+    fn test_swap_underpay_tolerance(
+        #[values(Side::Left, Side::Right)] side: Side,
+        mut factory: ItemFactory,
+    ) {
+        let mut new_pool = || {
+            let Pool::V1(mut pool) = factory.new_pool().unwrap() else {
+                unreachable!()
+            };
+            let position = PositionInit::new_full_range(
+                new_amount(0),
+                new_amount(1_u128 << 70),
+                new_amount(0),
+                new_amount(1_u128 << 70),
+            );
+            pool.open_position(position, 0, 0, &mut factory).unwrap();
+            pool
+        };
+        let amount_in = new_amount(1_u128 << 60);
+
+        assert_matches!(
+            new_pool().swap_exact_in(side, amount_in, 0, SWAP_MAX_UNDERPAY),
+            Ok(_)
+        );
+        // Negative tolerance requires trader to overpay, so even a regular swap fails
+        assert_matches!(
+            new_pool().swap_exact_in(side, amount_in, 0, -Float::one()),
+            Err(errors::Error {
+                kind: errors::ErrorKind::InternalLogicError,
+                ..
+            })
+        );
+        assert_matches!(
+            new_pool().swap_exact_out(side, amount_in, 0, -Float::one()),
+            Err(errors::Error {
+                kind: errors::ErrorKind::InternalLogicError,
+                ..
+            })
+        );
+    }
+
     /// Perform two swaps with different protocol fee fraction
     /// and check the accumulated protocol fee.
     #[rstest]
     fn test_change_protocol_fee_fraction(
-        mut empty_pool: PoolV0<Types>,
+        mut empty_pool: PoolLatest<Types>,
         #[values(Side::Left, Side::Right)] side: Side,
         #[values(0, 1, 2, 3, 4, 5, 6, 7)] fee_level: FeeLevel,
         mut factory: ItemFactory,
@@ -1312,14 +1365,24 @@ mod tests {
         let swap1_protocol_fee_fraction = 2000;
 
         empty_pool
-            .swap_exact_in(side, swap1_amount, swap1_protocol_fee_fraction)
+            .swap_exact_in(
+                side,
+                swap1_amount,
+                swap1_protocol_fee_fraction,
+                SWAP_MAX_UNDERPAY,
+            )
             .unwrap();
 
         let swap2_amount = new_amount(3_u128 << 70);
         let swap2_protocol_fee_fraction = 3000;
 
         empty_pool
-            .swap_exact_in(side, swap2_amount, swap2_protocol_fee_fraction)
+            .swap_exact_in(
+                side,
+                swap2_amount,
+                swap2_protocol_fee_fraction,
+                SWAP_MAX_UNDERPAY,
+            )
             .unwrap();
 
         let protocol_fee = empty_pool.withdraw_protocol_fee().unwrap()[side];
@@ -1339,4 +1402,44 @@ mod tests {
         assert!(Float::from(protocol_fee) > Float::from(1.0 - 1e-10) * expected_protocol_fee);
         assert!(Float::from(protocol_fee) < Float::from(1.0 + 1e-6) * expected_protocol_fee);
     }
+
+    /// Withdrawal of protocol fee reports accounting anomaly, when reserves of positions
+    /// exceed total reserves, instead of underflowing.
+    #[rstest]
+    fn test_negative_protocol_fee_is_error(
+        mut empty_pool: PoolLatest<Types>,
+        #[values(Side::Left, Side::Right)] side: Side,
+        mut factory: ItemFactory,
+    ) {
+        let amount = new_amount(1_u128 << 70);
+        let position = PositionInit {
+            amount_ranges: (
+                Range {
+                    min: new_amount(0_u128).into(),
+                    max: amount.into(),
+                },
+                Range {
+                    min: new_amount(0_u128).into(),
+                    max: amount.into(),
+                },
+            ),
+            ticks_range: (None, None),
+        };
+        empty_pool
+            .open_position(position, 0, 0, &mut factory)
+            .unwrap();
+
+        // Simulate accounting bug which loses part of reserves
+        let mut lost_reserves = (new_amount(0_u128), new_amount(0_u128));
+        lost_reserves[side] = new_amount(1_u128);
+        empty_pool.dec_total_reserves(lost_reserves).unwrap();
+
+        assert_matches!(
+            empty_pool.withdraw_protocol_fee(),
+            Err(errors::Error {
+                kind: errors::ErrorKind::NegativeProtocolFee,
+                ..
+            })
+        );
+    }
 }
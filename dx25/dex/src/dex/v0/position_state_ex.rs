@@ -3,14 +3,14 @@ use itertools::multizip;
 use num_traits::Zero;
 
 use crate::dex::pool::{fee_liquidity_from_net_liquidity, gross_liquidity_from_net_liquidity};
-use crate::dex::{Error, FeeLevel, PositionV0, Side, Tick, Types};
+use crate::dex::{Error, FeeLevel, PositionLatest, Side, Tick, Types};
 use crate::{
     error_here, fp, AmountUFP, FeeLiquidityUFP, GrossLiquidityUFP, LongestUFP, NetLiquidityUFP,
 };
 
 use super::EffSqrtprices;
 
-impl<T: Types> PositionV0<T> {
+impl<T: Types> PositionLatest<T> {
     pub fn net_liquidity(&self) -> NetLiquidityUFP {
         self.net_liquidity
     }
@@ -94,10 +94,10 @@ pub fn eval_position_balance_ufp(
 
 #[cfg(test)]
 mod tests {
-    use super::PositionV0;
+    use super::PositionLatest;
     use crate::dex::tick::Tick;
     use crate::dex::FeeLevel;
-    use crate::{assert_eq_rel_tol, Float, LPFeePerFeeLiquidity, Liquidity, TestTypes};
+    use crate::{assert_eq_rel_tol, Amount, Float, LPFeePerFeeLiquidity, Liquidity, TestTypes};
     use assert_matches::assert_matches;
     use num_traits::Zero;
     use rstest::rstest;
@@ -110,7 +110,7 @@ mod tests {
         let liquidity = Float::from(50);
         let eff_net_liqiudity = liquidity / one_over_sqrt_one_minus_fee_rate;
 
-        let position = PositionV0::<TestTypes> {
+        let position = PositionLatest::<TestTypes> {
             fee_level,
             net_liquidity: eff_net_liqiudity.try_into().unwrap(),
             init_acc_lp_fees_per_fee_liquidity: (
@@ -119,6 +119,7 @@ mod tests {
             ),
             tick_bounds: (Tick::MIN, Tick::MAX),
             init_sqrtprice: 0f64.into(),
+            init_amounts: (Amount::zero(), Amount::zero()),
             unwithdrawn_acc_lp_fees_per_fee_liquidity: (
                 LPFeePerFeeLiquidity::zero(),
                 LPFeePerFeeLiquidity::zero(),
@@ -10,6 +10,7 @@ use dx25::{
     dex::{PositionInit, SwapAction},
     Dx25Contract, EgldOrTokenId, TokenId,
 };
+use multiversx_sc::types::OptionalValue;
 use multiversx_sc_codec::TopDecode;
 use multiversx_sc_scenario::rust_biguint;
 
@@ -38,7 +39,7 @@ fn success_deposit_withdraw_egld() {
             &cf_setup.cf_wrapper,
             &rust_biguint!(deposit_amount),
             |sc| {
-                sc.deposit(ApiVec::default());
+                sc.deposit(ApiVec::default(), OptionalValue::None);
             },
         )
         .assert_ok();
@@ -128,7 +129,7 @@ fn success_open_swap_fee_close_batch_egld() {
         ESDT_TOKEN_ID,
         liq_deposit_amount,
         |sc: Dx25ContractObj| {
-            sc.deposit(ApiVec::default());
+            sc.deposit(ApiVec::default(), OptionalValue::None);
         }
     )
     .assert_ok();
@@ -155,6 +156,7 @@ fn success_open_swap_fee_close_batch_egld() {
                     Action::Withdraw(EgldOrTokenId::esdt(ESDT_TOKEN_ID), 0u64.into(), None),
                 ]
                 .into(),
+                OptionalValue::None,
             );
         }
     );
@@ -227,6 +229,7 @@ fn success_open_swap_fee_close_batch_egld() {
                     Action::Withdraw(EgldOrTokenId::esdt(ESDT_TOKEN_ID), 0u64.into(), None),
                 ]
                 .into(),
+                OptionalValue::None,
             );
         }
     );
@@ -291,6 +294,7 @@ fn success_open_swap_fee_close_batch_egld() {
                 Action::Withdraw(EgldOrTokenId::esdt(ESDT_TOKEN_ID), 0u64.into(), None),
             ]
             .into(),
+            OptionalValue::None,
         );
     });
     result.assert_ok();
@@ -354,6 +358,7 @@ fn success_open_swap_fee_close_batch_egld() {
                 Action::Withdraw(EgldOrTokenId::esdt(ESDT_TOKEN_ID), 0u64.into(), None),
             ]
             .into(),
+            OptionalValue::None,
         );
     });
     result.assert_ok();
@@ -14,6 +14,7 @@ use dx25::{
 };
 
 use contract_builder::{Dx25Setup, BTC_TOKEN_ID, ESDT_TOKEN_ID};
+use multiversx_sc::types::OptionalValue;
 use multiversx_sc_codec::TopDecode;
 use multiversx_sc_scenario::{
     rust_biguint,
@@ -88,7 +89,7 @@ fn success_deposit_batch_open_position() {
                 },
             ],
             |sc| {
-                sc.deposit(ApiVec(actions));
+                sc.deposit(ApiVec(actions), OptionalValue::None);
                 tx_logs = TxContextStack::static_peek().extract_result().result_logs;
             },
         )
@@ -208,7 +209,7 @@ fn success_batch_close_position() {
                 },
             ],
             |sc| {
-                sc.deposit(ApiVec(actions));
+                sc.deposit(ApiVec(actions), OptionalValue::None);
                 tx_logs = TxContextStack::static_peek().extract_result().result_logs;
             },
         )
@@ -233,7 +234,7 @@ fn success_batch_close_position() {
     ];
 
     transaction!(cf_setup, first_user_address, |sc: ContractObj<DebugApi>| {
-        sc.execute_actions(actions.into());
+        sc.execute_actions(actions.into(), OptionalValue::None);
     })
     .assert_ok();
 
@@ -336,7 +337,7 @@ fn success_deposit_batch_swap_in() {
                 },
             ],
             |sc| {
-                sc.deposit(ApiVec(actions));
+                sc.deposit(ApiVec(actions), OptionalValue::None);
             },
         )
         .assert_ok();
@@ -375,7 +376,7 @@ fn success_deposit_batch_swap_in() {
         ESDT_TOKEN_ID,
         swap_amount,
         |sc: ContractObj<DebugApi>| {
-            sc.deposit(actions.into());
+            sc.deposit(actions.into(), OptionalValue::None);
             tx_logs = TxContextStack::static_peek().extract_result().result_logs;
         }
     )
@@ -491,7 +492,7 @@ fn success_batch_withdraw_fee() {
                 },
             ],
             |sc| {
-                sc.deposit(ApiVec(actions));
+                sc.deposit(ApiVec(actions), OptionalValue::None);
                 tx_logs = TxContextStack::static_peek().extract_result().result_logs;
             },
         )
@@ -541,7 +542,7 @@ fn success_batch_withdraw_fee() {
         ESDT_TOKEN_ID,
         swap_amount,
         |sc: ContractObj<DebugApi>| {
-            sc.deposit(actions.into());
+            sc.deposit(actions.into(), OptionalValue::None);
         }
     )
     .assert_ok();
@@ -555,7 +556,7 @@ fn success_batch_withdraw_fee() {
 
     let mut tx_logs = vec![];
     transaction!(cf_setup, first_user_address, |sc: ContractObj<DebugApi>| {
-        sc.execute_actions(actions.into());
+        sc.execute_actions(actions.into(), OptionalValue::None);
         tx_logs = TxContextStack::static_peek().extract_result().result_logs;
     })
     .assert_ok();
@@ -3,7 +3,7 @@ mod contract_builder;
 
 use std::collections::{HashMap, HashSet};
 
-use multiversx_sc::types::BigUint;
+use multiversx_sc::types::{BigUint, OptionalValue};
 
 use multiversx_sc_scenario::{rust_biguint, testing_framework::TxTokenTransfer, DebugApi};
 
@@ -31,7 +31,7 @@ fn test_deposit_withdraw() {
         ESDT_TOKEN_ID,
         1000,
         |sc: ContractObj<DebugApi>| {
-            sc.deposit(ApiVec::default());
+            sc.deposit(ApiVec::default(), OptionalValue::None);
             let deposit = sc.get_deposit(
                 cf_setup.first_user_address.clone().into(),
                 TokenId::from_bytes(ESDT_TOKEN_ID),
@@ -205,7 +205,7 @@ fn test_multiple_deposits() {
                 },
             ],
             |sc| {
-                sc.deposit(ApiVec::default());
+                sc.deposit(ApiVec::default(), OptionalValue::None);
             },
         )
         .assert_ok();
@@ -239,7 +239,7 @@ fn test_multiple_deposits() {
                     value: rust_biguint!(500),
                 },
             ],
-            |sc| sc.deposit(vec![Action::Deposit].into()),
+            |sc| sc.deposit(vec![Action::Deposit].into(), OptionalValue::None),
         )
         .assert_ok();
 
@@ -256,3 +256,60 @@ fn test_multiple_deposits() {
     })
     .assert_ok();
 }
+
+#[test]
+fn test_sweep_untracked() {
+    let mut cf_setup = Dx25Setup::setup();
+
+    transfer!(
+        cf_setup,
+        first_user_address,
+        ESDT_TOKEN_ID,
+        1000,
+        |sc: ContractObj<DebugApi>| {
+            sc.deposit(ApiVec::default(), OptionalValue::None);
+        }
+    )
+    .assert_ok();
+
+    // Credit contract directly, bypassing deposit
+    cf_setup.blockchain_wrapper.set_esdt_balance(
+        cf_setup.cf_wrapper.address_ref(),
+        ESDT_TOKEN_ID,
+        &rust_biguint!(1300),
+    );
+
+    transaction!(cf_setup, first_user_address, |sc: ContractObj<DebugApi>| {
+        sc.sweep_untracked(TokenId::from_bytes(ESDT_TOKEN_ID), None);
+    })
+    .assert_failed("Permission denied");
+
+    transaction!(cf_setup, owner_address, |sc: ContractObj<DebugApi>| {
+        let swept = sc.sweep_untracked(TokenId::from_bytes(ESDT_TOKEN_ID), None);
+        assert_eq!(swept, 300);
+    })
+    .assert_ok();
+
+    // Only surplus is swept, user deposit stays intact
+    cf_setup.blockchain_wrapper.check_esdt_balance(
+        &cf_setup.owner_address,
+        ESDT_TOKEN_ID,
+        &rust_biguint!(300),
+    );
+    cf_setup.blockchain_wrapper.check_esdt_balance(
+        cf_setup.cf_wrapper.address_ref(),
+        ESDT_TOKEN_ID,
+        &rust_biguint!(1000),
+    );
+
+    transaction!(cf_setup, owner_address, |sc: ContractObj<DebugApi>| {
+        let swept = sc.sweep_untracked(TokenId::from_bytes(ESDT_TOKEN_ID), None);
+        assert_eq!(swept, 0);
+    })
+    .assert_ok();
+
+    transaction!(cf_setup, first_user_address, |sc: ContractObj<DebugApi>| {
+        sc.withdraw(EgldOrTokenId::esdt(ESDT_TOKEN_ID), 1000u32.into(), None);
+    })
+    .assert_ok();
+}
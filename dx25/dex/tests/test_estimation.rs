@@ -1,7 +1,7 @@
 #[macro_use]
 mod contract_builder;
 
-use multiversx_sc::types::BigUint;
+use multiversx_sc::types::{BigUint, OptionalValue};
 use multiversx_sc_scenario::{rust_biguint, DebugApi};
 
 use dx25::{api_types::ApiVec, dex::PositionInit, ContractObj, Dx25Contract, TokenId};
@@ -19,7 +19,7 @@ fn test_estimation() {
         ESDT_TOKEN_ID,
         1000,
         |sc: ContractObj<DebugApi>| {
-            sc.deposit(ApiVec::default());
+            sc.deposit(ApiVec::default(), OptionalValue::None);
             let deposit = sc.get_deposit(
                 cf_setup.first_user_address.clone().into(),
                 TokenId::from_bytes(ESDT_TOKEN_ID),
@@ -36,7 +36,7 @@ fn test_estimation() {
         BTC_TOKEN_ID,
         1000,
         |sc: ContractObj<DebugApi>| {
-            sc.deposit(ApiVec::default());
+            sc.deposit(ApiVec::default(), OptionalValue::None);
             let deposit = sc.get_deposit(
                 cf_setup.first_user_address.clone().into(),
                 TokenId::from_bytes(BTC_TOKEN_ID),
@@ -1,7 +1,7 @@
 #[macro_use]
 mod contract_builder;
 
-use multiversx_sc::types::TokenIdentifier;
+use multiversx_sc::types::{OptionalValue, TokenIdentifier};
 use multiversx_sc_codec::TopDecode;
 use multiversx_sc_scenario::{rust_biguint, DebugApi};
 
@@ -52,7 +52,7 @@ fn test_positions() {
         ESDT_TOKEN_ID,
         1000,
         |sc: ContractObj<DebugApi>| {
-            sc.deposit(ApiVec::default());
+            sc.deposit(ApiVec::default(), OptionalValue::None);
             let deposit = sc.get_deposit(
                 cf_setup.first_user_address.clone().into(),
                 TokenId::from_bytes(ESDT_TOKEN_ID),
@@ -68,7 +68,7 @@ fn test_positions() {
         BTC_TOKEN_ID,
         1000,
         |sc: ContractObj<DebugApi>| {
-            sc.deposit(ApiVec::default());
+            sc.deposit(ApiVec::default(), OptionalValue::None);
             let deposit = sc.get_deposit(
                 cf_setup.first_user_address.clone().into(),
                 TokenId::from_bytes(BTC_TOKEN_ID),
@@ -297,7 +297,7 @@ fn test_liqudity_fee_level_distribution() {
         ESDT_TOKEN_ID,
         1000,
         |sc: ContractObj<DebugApi>| {
-            sc.deposit(ApiVec::default());
+            sc.deposit(ApiVec::default(), OptionalValue::None);
             let deposit = sc.get_deposit(
                 cf_setup.first_user_address.clone().into(),
                 TokenId::from_bytes(ESDT_TOKEN_ID),
@@ -314,7 +314,7 @@ fn test_liqudity_fee_level_distribution() {
         BTC_TOKEN_ID,
         1000,
         |sc: ContractObj<DebugApi>| {
-            sc.deposit(ApiVec::default());
+            sc.deposit(ApiVec::default(), OptionalValue::None);
             let deposit = sc.get_deposit(
                 cf_setup.first_user_address.clone().into(),
                 TokenId::from_bytes(BTC_TOKEN_ID),
@@ -396,6 +396,7 @@ fn test_single_token_positions() {
                     position: PositionInit::new_full_range(0u32, 0u32, 100u32, 900u32),
                 }]
                 .into(),
+                OptionalValue::None,
             );
         }
     )
@@ -3,7 +3,7 @@ mod contract_builder;
 
 use std::collections::HashMap;
 
-use multiversx_sc::types::BigUint;
+use multiversx_sc::types::{BigUint, OptionalValue};
 use multiversx_sc_scenario::{rust_biguint, DebugApi};
 
 use dx25_client_sc::{ContractObj as ClientContractObj, Dx25ClientContract as _};
@@ -26,7 +26,7 @@ fn test_sc_withdraw() {
     transfer_egld!(cf_setup, client_address, 1000, |sc: ContractObj<
         DebugApi,
     >| {
-        sc.deposit(ApiVec::default());
+        sc.deposit(ApiVec::default(), OptionalValue::None);
         let deposit = sc.get_deposit(
             cf_setup.client_address.clone().into(),
             TokenId::from_bytes(WEGLD_TOKEN_ID),
@@ -42,7 +42,7 @@ fn test_sc_withdraw() {
         ESDT_TOKEN_ID,
         1000,
         |sc: ContractObj<DebugApi>| {
-            sc.deposit(ApiVec::default());
+            sc.deposit(ApiVec::default(), OptionalValue::None);
             let deposit = sc.get_deposit(
                 cf_setup.client_address.clone().into(),
                 TokenId::from_bytes(ESDT_TOKEN_ID),
@@ -3,6 +3,7 @@ mod contract_builder;
 
 use std::collections::HashMap;
 
+use multiversx_sc::types::OptionalValue;
 use multiversx_sc_scenario::{rust_biguint, DebugApi};
 
 use dx25::{
@@ -25,7 +26,7 @@ fn test_swaps() {
         ESDT_TOKEN_ID,
         1000,
         |sc: ContractObj<DebugApi>| {
-            sc.deposit(ApiVec::default());
+            sc.deposit(ApiVec::default(), OptionalValue::None);
             let deposit = sc.get_deposit(
                 cf_setup.first_user_address.clone().into(),
                 TokenId::from_bytes(ESDT_TOKEN_ID),
@@ -41,7 +42,7 @@ fn test_swaps() {
         BTC_TOKEN_ID,
         1000,
         |sc: ContractObj<DebugApi>| {
-            sc.deposit(ApiVec::default());
+            sc.deposit(ApiVec::default(), OptionalValue::None);
             let deposit = sc.get_deposit(
                 cf_setup.first_user_address.clone().into(),
                 TokenId::from_bytes(BTC_TOKEN_ID),
@@ -68,7 +69,7 @@ fn test_swaps() {
         BTC_TOKEN_ID,
         1000,
         |sc: ContractObj<DebugApi>| {
-            sc.deposit(ApiVec::default());
+            sc.deposit(ApiVec::default(), OptionalValue::None);
             let deposit = sc.get_deposit(
                 cf_setup.second_user_address.clone().into(),
                 TokenId::from_bytes(BTC_TOKEN_ID),
@@ -285,7 +286,7 @@ fn test_actions() {
         ESDT_TOKEN_ID,
         1000,
         |sc: ContractObj<DebugApi>| {
-            sc.deposit(ApiVec::default());
+            sc.deposit(ApiVec::default(), OptionalValue::None);
             let deposit = sc.get_deposit(
                 cf_setup.first_user_address.clone().into(),
                 TokenId::from_bytes(ESDT_TOKEN_ID),
@@ -301,7 +302,7 @@ fn test_actions() {
         BTC_TOKEN_ID,
         1000,
         |sc: ContractObj<DebugApi>| {
-            sc.deposit(ApiVec::default());
+            sc.deposit(ApiVec::default(), OptionalValue::None);
             let deposit = sc.get_deposit(
                 cf_setup.first_user_address.clone().into(),
                 TokenId::from_bytes(BTC_TOKEN_ID),
@@ -328,7 +329,7 @@ fn test_actions() {
         BTC_TOKEN_ID,
         1000,
         |sc: ContractObj<DebugApi>| {
-            sc.deposit(ApiVec::default());
+            sc.deposit(ApiVec::default(), OptionalValue::None);
             let deposit = sc.get_deposit(
                 cf_setup.second_user_address.clone().into(),
                 TokenId::from_bytes(BTC_TOKEN_ID),
@@ -355,7 +356,7 @@ fn test_actions() {
     transaction!(cf_setup, second_user_address, |sc: ContractObj<
         DebugApi,
     >| {
-        sc.execute_actions(vec![action1, action2].into());
+        sc.execute_actions(vec![action1, action2].into(), OptionalValue::None);
     })
     .assert_ok();
 
@@ -391,7 +392,7 @@ fn test_invalid_swap_in() {
         ESDT_TOKEN_ID,
         1000,
         |sc: ContractObj<DebugApi>| {
-            sc.deposit(ApiVec::default());
+            sc.deposit(ApiVec::default(), OptionalValue::None);
             let deposit = sc.get_deposit(
                 cf_setup.first_user_address.clone().into(),
                 TokenId::from_bytes(ESDT_TOKEN_ID),
@@ -407,7 +408,7 @@ fn test_invalid_swap_in() {
         BTC_TOKEN_ID,
         1000,
         |sc: ContractObj<DebugApi>| {
-            sc.deposit(ApiVec::default());
+            sc.deposit(ApiVec::default(), OptionalValue::None);
             let deposit = sc.get_deposit(
                 cf_setup.first_user_address.clone().into(),
                 TokenId::from_bytes(BTC_TOKEN_ID),
@@ -434,7 +435,7 @@ fn test_invalid_swap_in() {
         BTC_TOKEN_ID,
         1000,
         |sc: ContractObj<DebugApi>| {
-            sc.deposit(ApiVec::default());
+            sc.deposit(ApiVec::default(), OptionalValue::None);
             let deposit = sc.get_deposit(
                 cf_setup.second_user_address.clone().into(),
                 TokenId::from_bytes(BTC_TOKEN_ID),
@@ -495,3 +496,115 @@ fn test_invalid_swap_in() {
     })
     .assert_ok();
 }
+
+#[test]
+fn test_swap_and_send() {
+    let mut cf_setup = Dx25Setup::setup();
+    let recipient = cf_setup
+        .blockchain_wrapper
+        .create_user_account(&rust_biguint!(0));
+
+    transfer!(
+        cf_setup,
+        first_user_address,
+        ESDT_TOKEN_ID,
+        1000,
+        |sc: ContractObj<DebugApi>| {
+            sc.deposit(ApiVec::default(), OptionalValue::None);
+        }
+    )
+    .assert_ok();
+
+    transfer!(
+        cf_setup,
+        first_user_address,
+        BTC_TOKEN_ID,
+        1000,
+        |sc: ContractObj<DebugApi>| {
+            sc.deposit(ApiVec::default(), OptionalValue::None);
+        }
+    )
+    .assert_ok();
+
+    transaction!(cf_setup, first_user_address, |sc: ContractObj<DebugApi>| {
+        let _ = sc.open_position(
+            &TokenId::from_bytes(ESDT_TOKEN_ID),
+            &TokenId::from_bytes(BTC_TOKEN_ID),
+            16,
+            PositionInit::new_full_range(0u32, 1000u32, 0u32, 1000u32),
+        );
+    })
+    .assert_ok();
+
+    transfer!(
+        cf_setup,
+        second_user_address,
+        BTC_TOKEN_ID,
+        1000,
+        |sc: ContractObj<DebugApi>| {
+            sc.deposit(ApiVec::default(), OptionalValue::None);
+        }
+    )
+    .assert_ok();
+
+    // Slippage check is the same as for regular swap
+    transaction!(cf_setup, second_user_address, |sc: ContractObj<
+        DebugApi,
+    >| {
+        sc.swap_and_send(
+            vec![
+                TokenId::from_bytes(BTC_TOKEN_ID),
+                TokenId::from_bytes(ESDT_TOKEN_ID),
+            ]
+            .into(),
+            1000u32.into(),
+            1000u32.into(),
+            recipient.clone().into(),
+            None,
+        );
+    })
+    .assert_failed("Slippage error");
+
+    transaction!(cf_setup, second_user_address, |sc: ContractObj<
+        DebugApi,
+    >| {
+        sc.swap_and_send(
+            vec![
+                TokenId::from_bytes(BTC_TOKEN_ID),
+                TokenId::from_bytes(ESDT_TOKEN_ID),
+            ]
+            .into(),
+            1000u32.into(),
+            100u32.into(),
+            recipient.clone().into(),
+            None,
+        );
+    })
+    .assert_ok();
+
+    // Output is delivered to recipient, not to the swapper's deposit
+    cf_setup
+        .blockchain_wrapper
+        .check_esdt_balance(&recipient, ESDT_TOKEN_ID, &rust_biguint!(499));
+    cf_setup.blockchain_wrapper.check_esdt_balance(
+        &cf_setup.second_user_address,
+        ESDT_TOKEN_ID,
+        &rust_biguint!(0),
+    );
+
+    query!(cf_setup, |sc: ContractObj<DebugApi>| {
+        let second_user = cf_setup.second_user_address.clone();
+        assert_eq!(
+            sc.get_deposit(
+                second_user.clone().into(),
+                TokenId::from_bytes(BTC_TOKEN_ID)
+            ),
+            0
+        );
+        assert_eq!(
+            sc.get_deposit(second_user.into(), TokenId::from_bytes(ESDT_TOKEN_ID)),
+            0
+        );
+    })
+    .assert_ok();
+}
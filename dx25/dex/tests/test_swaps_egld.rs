@@ -3,7 +3,7 @@ mod contract_builder;
 
 use std::collections::HashMap;
 
-use multiversx_sc::types::BigUint;
+use multiversx_sc::types::{BigUint, OptionalValue};
 use multiversx_sc_scenario::{rust_biguint, DebugApi};
 
 use dx25::{
@@ -22,7 +22,7 @@ fn test_swaps_egld() {
     transfer_egld!(cf_setup, first_user_address, 1000, |sc: ContractObj<
         DebugApi,
     >| {
-        sc.deposit(ApiVec::default());
+        sc.deposit(ApiVec::default(), OptionalValue::None);
         let deposit = sc.get_deposit(
             cf_setup.first_user_address.clone().into(),
             TokenId::from_bytes(WEGLD_TOKEN_ID),
@@ -38,7 +38,7 @@ fn test_swaps_egld() {
         BTC_TOKEN_ID,
         1000,
         |sc: ContractObj<DebugApi>| {
-            sc.deposit(ApiVec::default());
+            sc.deposit(ApiVec::default(), OptionalValue::None);
             let deposit = sc.get_deposit(
                 cf_setup.first_user_address.clone().into(),
                 TokenId::from_bytes(BTC_TOKEN_ID),
@@ -70,7 +70,7 @@ fn test_swaps_egld() {
         BTC_TOKEN_ID,
         1000,
         |sc: ContractObj<DebugApi>| {
-            sc.deposit(ApiVec::default());
+            sc.deposit(ApiVec::default(), OptionalValue::None);
             let deposit = sc.get_deposit(
                 cf_setup.second_user_address.clone().into(),
                 TokenId::from_bytes(BTC_TOKEN_ID),